@@ -0,0 +1,209 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Benchmarks for the per-frame hot paths. Run with:
+//
+//     cargo bench
+//
+// A single benchmark can be selected by passing a part of its name:
+//
+//     cargo bench -- image_to_ansi
+
+use color_cycle::{image_to_ansi, palette};
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use color_cycle::color::Rgb;
+use color_cycle::image::{IndexedImage, LinearPalette, RgbImage};
+use color_cycle::palette::{BlendCurve, Cycle, Palette};
+
+const MEASURE_TIME: Duration = Duration::from_secs(2);
+const WARMUP_TIME: Duration = Duration::from_millis(500);
+
+const SIZES: &[(u32, u32)] = &[
+    (320, 200),
+    (640, 480),
+    (1280, 720),
+];
+
+struct Bencher {
+    filter: Option<String>,
+}
+
+impl Bencher {
+    fn bench<F>(&self, name: &str, mut f: F)
+    where F: FnMut() {
+        if let Some(filter) = &self.filter && !name.contains(filter.as_str()) {
+            return;
+        }
+
+        let warmup_start = Instant::now();
+        while warmup_start.elapsed() < WARMUP_TIME {
+            f();
+        }
+
+        let mut iterations = 0u64;
+        let start = Instant::now();
+        let mut elapsed;
+        loop {
+            f();
+            iterations += 1;
+            elapsed = start.elapsed();
+            if elapsed >= MEASURE_TIME {
+                break;
+            }
+        }
+
+        let nanos = elapsed.as_nanos() as f64 / iterations as f64;
+        println!("{name:<40} {:>14.0} ns/iter ({iterations} iterations)", nanos);
+    }
+}
+
+fn make_palette() -> Palette {
+    let mut palette = Palette::default();
+    for index in 0..=255u8 {
+        palette[index] = Rgb([index, index.wrapping_mul(3), 255 - index]);
+    }
+    palette
+}
+
+fn make_cycles() -> Box<[Cycle]> {
+    Box::new([
+        Cycle::new(16, 31, 1200, false),
+        Cycle::new(32, 63, 2400, true),
+        Cycle::new(64, 71, 600, false),
+        Cycle::new(128, 191, 3600, false),
+    ])
+}
+
+fn make_image(width: u32, height: u32) -> IndexedImage {
+    let mut image = IndexedImage::new(width, height, make_palette());
+    for y in 0..height {
+        for x in 0..width {
+            // a mix of flat areas and noise, like typical scenes
+            let index = if y < height / 2 {
+                (y * 32 / height) as u8
+            } else {
+                ((x * 7 + y * 13) ^ (x >> 2)) as u8
+            };
+            image.set_index(x, y, index);
+        }
+    }
+    image
+}
+
+fn main() {
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let bencher = Bencher { filter };
+
+    let palette = make_palette();
+    let other_palette = {
+        let mut other = palette.clone();
+        other.rotate_left(0, 255, 17);
+        other
+    };
+    let cycles = make_cycles();
+
+    let mut output = Palette::default();
+    let mut now = 0.0;
+    bencher.bench("apply_cycles_from", || {
+        now += 1.0 / 60.0;
+        output.apply_cycles_from(black_box(&palette), &cycles, now, None);
+    });
+
+    let mut now = 0.0;
+    bencher.bench("apply_cycles_from/blend", || {
+        now += 1.0 / 60.0;
+        output.apply_cycles_from(black_box(&palette), &cycles, now, Some(BlendCurve::Linear));
+    });
+
+    let mut now = 0.0;
+    bencher.bench("apply_cycles_from/blend/cosine", || {
+        now += 1.0 / 60.0;
+        output.apply_cycles_from(black_box(&palette), &cycles, now, Some(BlendCurve::Cosine));
+    });
+
+    bencher.bench("palette::blend", || {
+        palette::blend(black_box(&palette), black_box(&other_palette), 0.25, &mut output);
+    });
+
+    for &(width, height) in SIZES {
+        let image = make_image(width, height);
+        let mut frame = RgbImage::new(width, height);
+
+        bencher.bench(&format!("apply_with_palette/{width}x{height}"), || {
+            image.apply_with_palette(&mut frame, black_box(&other_palette));
+        });
+
+        // per-pixel lookup through the palette, for comparison
+        bencher.bench(&format!("apply_with_palette/{width}x{height}/per_pixel"), || {
+            let palette = black_box(&other_palette);
+            for y in 0..height {
+                for x in 0..width {
+                    frame.set_pixel(x, y, palette[image.get_index(x, y)]);
+                }
+            }
+        });
+    }
+
+    for &(width, height) in SIZES {
+        let image = make_image(width, height);
+        let mut frame = RgbImage::new(width, height);
+        let mut thumbnail = RgbImage::new(width / 4, height / 4);
+        let mut linear = LinearPalette::new();
+        image.apply_with_palette(&mut frame, &palette);
+
+        bencher.bench(&format!("resize_into/{width}x{height}"), || {
+            black_box(&frame).resize_into(&mut thumbnail);
+        });
+
+        // a new palette every frame, like with color cycling
+        let mut now = 0.0;
+        bencher.bench(&format!("draw_resized_indexed_image/{width}x{height}"), || {
+            now += 1.0 / 60.0;
+            output.apply_cycles_from(&palette, &cycles, now, None);
+            thumbnail.draw_resized_indexed_image(black_box(&image), &output, &mut linear);
+        });
+    }
+
+    for &(width, height) in SIZES {
+        let image = make_image(width, height);
+        let mut prev_frame = RgbImage::new(width, height);
+        let mut frame = RgbImage::new(width, height);
+        let mut lines = String::new();
+
+        image.apply_with_palette(&mut prev_frame, &palette);
+        image.apply_with_palette(&mut frame, &other_palette);
+
+        bencher.bench(&format!("image_to_ansi_into/{width}x{height}"), || {
+            image_to_ansi::image_to_ansi_into(black_box(&prev_frame), black_box(&frame), true, &mut lines);
+        });
+
+        // only the cycling pixels changed
+        let mut cycled = palette.clone();
+        cycled.apply_cycles(&cycles, 1.0);
+        image.apply_with_palette(&mut frame, &cycled);
+
+        bencher.bench(&format!("image_to_ansi_into/{width}x{height}/cycled"), || {
+            image_to_ansi::image_to_ansi_into(black_box(&prev_frame), black_box(&frame), true, &mut lines);
+        });
+
+        bencher.bench(&format!("simple_image_to_ansi_into/{width}x{height}"), || {
+            image_to_ansi::simple_image_to_ansi_into(black_box(&frame), &mut lines);
+        });
+    }
+}
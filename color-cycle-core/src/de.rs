@@ -0,0 +1,164 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Deserialization of colors, palettes, and cycles as they are written in
+//! Canvas Cycle JSON files.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use serde::{de::{Error, IgnoredAny, Visitor}, Deserializer};
+
+use crate::color::Rgb;
+use crate::palette::{Cycle, Palette};
+
+struct RgbVisitor;
+
+impl<'de> Visitor<'de> for RgbVisitor {
+    type Value = Rgb;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("RGB value as list of 3 numbers, each in the range of 0 to 255")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where A: serde::de::SeqAccess<'de>, {
+        let Some(r) = seq.next_element()? else {
+            return Err(Error::missing_field("r"));
+        };
+
+        let Some(g) = seq.next_element()? else {
+            return Err(Error::missing_field("g"));
+        };
+
+        let Some(b) = seq.next_element()? else {
+            return Err(Error::missing_field("b"));
+        };
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(Error::custom("superfluous elements in RGB value"));
+        };
+
+        Ok(Rgb([r, g, b]))
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for Rgb {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        deserializer.deserialize_seq(RgbVisitor)
+    }
+}
+
+struct PaletteVisitor;
+
+impl<'de> Visitor<'de> for PaletteVisitor {
+    type Value = Palette;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a list of 256 RGB values")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where A: serde::de::SeqAccess<'de>, {
+        let mut colors = Vec::with_capacity(256);
+
+        while let Some(rgb) = seq.next_element()? {
+            colors.push(rgb);
+        }
+
+        let colors: Box<[Rgb; 256]> = match colors.try_into() {
+            Ok(colors) => colors,
+            Err(_) => return Err(Error::custom("the color palette needs to have exactly 256 color values"))
+        };
+
+        Ok(Palette(colors))
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for Palette {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        deserializer.deserialize_seq(PaletteVisitor)
+    }
+}
+
+struct CycleVisitor;
+
+impl<'de> Visitor<'de> for CycleVisitor {
+    type Value = Cycle;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a color cycle definition")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: serde::de::MapAccess<'de>, {
+        let mut reverse = false;
+        let mut rate = 0;
+        let mut low = None;
+        let mut high = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "reverse" => {
+                    let value: i32 = map.next_value()?;
+                    if value == 0 {
+                        reverse = false;
+                    } else if value == 2 {
+                        reverse = true;
+                    } else {
+                        return Err(Error::invalid_value(
+                            serde::de::Unexpected::Signed(value as i64),
+                            &"0 or 2"));
+                    }
+                }
+                "rate" => {
+                    rate = map.next_value()?;
+                }
+                "low" => {
+                    low = Some(map.next_value()?);
+                }
+                "high" => {
+                    high = Some(map.next_value()?);
+                }
+                _ => {
+                    let _ = map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let Some(low) = low else {
+            return Err(Error::missing_field("low"));
+        };
+
+        let Some(high) = high else {
+            return Err(Error::missing_field("high"));
+        };
+
+        Ok(Cycle::new(low, high, rate, reverse))
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for Cycle {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        deserializer.deserialize_map(CycleVisitor)
+    }
+}
@@ -0,0 +1,42 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The palette cycling of color-cycle without file formats and terminal
+//! output: palettes, color cycles, blending, and the time of day timeline.
+//! Only `core` and `alloc` are needed, so microcontroller projects like LED
+//! badges can animate exactly like the `color-cycle` crate does.
+//!
+//! The `clap` feature makes [`palette::BlendCurve`] usable as a command line
+//! argument, the `serde` feature reads colors, palettes, and cycles from
+//! Canvas Cycle JSON.
+
+#![no_std]
+#![allow(clippy::manual_range_contains)]
+
+extern crate alloc;
+
+// the ValueEnum derive refers to std
+#[cfg(feature = "clap")]
+extern crate std;
+
+pub mod color;
+pub mod palette;
+pub mod timeline;
+
+mod math;
+#[cfg(feature = "serde")]
+mod de;
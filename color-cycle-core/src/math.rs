@@ -0,0 +1,53 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Float functions `core` doesn't have, for the value ranges used here.
+
+/// Like `f64::round()` for values of less than 2^52.
+#[inline]
+pub fn round(value: f64) -> f64 {
+    let whole = value as i64 as f64;
+    let fraction = value - whole;
+    if fraction >= 0.5 {
+        whole + 1.0
+    } else if fraction <= -0.5 {
+        whole - 1.0
+    } else {
+        whole
+    }
+}
+
+/// Like `f64::floor()` for values of less than 2^52.
+#[inline]
+pub fn floor(value: f64) -> f64 {
+    let whole = value as i64 as f64;
+    if whole > value { whole - 1.0 } else { whole }
+}
+
+/// `cos(PI * value)` for `value` from 0 to 1, accurate to about 1e-15.
+pub fn cos_pi(value: f64) -> f64 {
+    // cos(PI * value) = sin(PI * (0.5 - value)), as a Taylor series
+    let x = core::f64::consts::PI * (0.5 - value);
+    let x2 = x * x;
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..12 {
+        term *= -x2 / ((2 * n) * (2 * n + 1)) as f64;
+        sum += term;
+    }
+    sum
+}
@@ -0,0 +1,111 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Time of day timelines, which blend from one palette to the next.
+
+/// Length of a day in milliseconds.
+pub const DAY_DURATION: u64 = 24 * 60 * 60 * 1000;
+
+/// Where `time_of_day` (in milliseconds since midnight) is in the timeline
+/// `events`, sorted by the time of day `time_of()` returns for each of them.
+/// Returns the index of the event to blend from, of the event to blend to,
+/// and how far to blend from 0 to 1. From midnight to the first event the
+/// last event is blended into the first one, and from the last event to
+/// midnight the last event into the first one. `None` if there are no
+/// events.
+pub fn timeline_span<T>(events: &[T], time_of_day: u64, time_of: impl Fn(&T) -> u64) -> Option<(usize, usize, f64)> {
+    let last = events.len().checked_sub(1)?;
+    let to = events.partition_point(|event| time_of(event) <= time_of_day);
+    let (from, to) = if to == 0 || to > last { (last, 0) } else { (to - 1, to) };
+
+    // spans and times over midnight wrap around to the next day
+    let prev_time_of_day = time_of(&events[from]) % DAY_DURATION;
+    let next_time_of_day = time_of(&events[to]) % DAY_DURATION;
+    let current_span = (next_time_of_day + DAY_DURATION - prev_time_of_day) % DAY_DURATION;
+    let time_in_span = (time_of_day % DAY_DURATION + DAY_DURATION - prev_time_of_day) % DAY_DURATION;
+
+    if current_span == 0 {
+        // a single event, or all of them at the same time
+        return Some((from, to, 0.0));
+    }
+
+    Some((from, to, time_in_span as f64 / current_span as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 60 * 60 * 1000;
+
+    fn span(events: &[u64], time_of_day: u64) -> Option<(usize, usize, f64)> {
+        timeline_span(events, time_of_day, |&time| time)
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(span(&[], 0), None);
+    }
+
+    #[test]
+    fn between_events() {
+        let events = [6 * HOUR, 18 * HOUR];
+        assert_eq!(span(&events, 6 * HOUR), Some((0, 1, 0.0)));
+        assert_eq!(span(&events, 12 * HOUR), Some((0, 1, 0.5)));
+        assert_eq!(span(&events, 15 * HOUR), Some((0, 1, 0.75)));
+    }
+
+    #[test]
+    fn before_first_event() {
+        // blends the last event into the first one across midnight
+        let events = [6 * HOUR, 18 * HOUR];
+        assert_eq!(span(&events, 0), Some((1, 0, 0.5)));
+        assert_eq!(span(&events, 3 * HOUR), Some((1, 0, 0.75)));
+    }
+
+    #[test]
+    fn after_last_event() {
+        let events = [6 * HOUR, 18 * HOUR];
+        assert_eq!(span(&events, 18 * HOUR), Some((1, 0, 0.0)));
+        assert_eq!(span(&events, 21 * HOUR), Some((1, 0, 0.25)));
+        assert_eq!(span(&events, DAY_DURATION - 1), Some((1, 0, (6 * HOUR - 1) as f64 / (12 * HOUR) as f64)));
+    }
+
+    #[test]
+    fn single_event() {
+        for time_of_day in [0, 6 * HOUR, 12 * HOUR, DAY_DURATION - 1] {
+            assert_eq!(span(&[12 * HOUR], time_of_day), Some((0, 0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn events_at_the_same_time() {
+        let events = [12 * HOUR, 12 * HOUR];
+        for time_of_day in [0, 12 * HOUR, 18 * HOUR] {
+            assert_eq!(span(&events, time_of_day), Some((1, 0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn event_at_end_of_day_is_midnight() {
+        let events = [6 * HOUR, DAY_DURATION];
+        assert_eq!(span(&events, 0), Some((1, 0, 0.0)));
+        assert_eq!(span(&events, 3 * HOUR), Some((1, 0, 0.5)));
+        assert_eq!(span(&events, 15 * HOUR), Some((0, 1, 0.5)));
+        assert_eq!(span(&events, DAY_DURATION - 1), Some((0, 1, (18 * HOUR - 1) as f64 / (18 * HOUR) as f64)));
+    }
+}
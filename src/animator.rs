@@ -0,0 +1,349 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use color_cycle_core::timeline::timeline_span;
+
+use crate::clock::CycleClock;
+use crate::image::{CycleImage, IndexedImage, LivingWorld};
+use crate::palette::{BlendCurve, Cycle, Palette};
+
+pub use color_cycle_core::timeline::DAY_DURATION;
+
+/// Computes the palette of a frame from the time of day and the cycle time,
+/// and composes the sprites of a scene.
+#[derive(Debug, Clone)]
+pub struct Animator {
+    cycled_palette1: Palette,
+    cycled_palette2: Palette,
+    blended_palette: Palette,
+    loop_duration: f64,
+    phase: f64,
+    blend_curve: BlendCurve,
+    cache: Option<PaletteCache>,
+    smoothing: Option<Smoothing>,
+    /// seconds added to the cycle time of each cycle by index
+    cycle_offsets: Vec<f64>,
+    /// whether each cycle is applied, by index
+    cycle_mask: Vec<bool>,
+}
+
+/// State of the interpolation between the cycle states of consecutive
+/// frames, see [`Animator::set_smoothing()`].
+#[derive(Debug, Clone)]
+struct Smoothing {
+    /// Seconds per frame at the requested frame rate.
+    frame_duration: f64,
+    /// Cycle time and palette of the previous frame.
+    previous: Option<(f64, Palette)>,
+    output: Palette,
+}
+
+/// Frames that take longer than this many times the requested frame
+/// duration are interpolated.
+const SMOOTHING_THRESHOLD: f64 = 1.5;
+
+/// Blended time of day palettes (before color cycling), one per time slot
+/// of `resolution` milliseconds. Holds at most `max_entries` palettes and
+/// starts over when it is full.
+#[derive(Debug, Clone)]
+pub struct PaletteCache {
+    resolution: u64,
+    max_entries: usize,
+    entries: HashMap<u64, Palette>,
+}
+
+impl PaletteCache {
+    /// `resolution` is clamped to at least 1 millisecond.
+    pub fn new(resolution: u64, max_entries: usize) -> Self {
+        Self {
+            resolution: resolution.max(1),
+            max_entries,
+            entries: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn resolution(&self) -> u64 {
+        self.resolution
+    }
+
+    #[inline]
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn get_or_insert_with(&mut self, slot: u64, init: impl FnOnce(&mut Palette)) -> &Palette {
+        if self.entries.len() >= self.max_entries && !self.entries.contains_key(&slot) {
+            self.entries.clear();
+        }
+
+        self.entries.entry(slot).or_insert_with(|| {
+            let mut palette = Palette::default();
+            init(&mut palette);
+            palette
+        })
+    }
+}
+
+impl Animator {
+    pub fn new(living_world: &LivingWorld) -> Self {
+        let palette = living_world.base().palette();
+        let loop_duration = std::iter::once(living_world.base())
+            .chain(living_world.palettes())
+            .flat_map(|image| image.cycles())
+            .filter_map(|cycle| cycle.period())
+            .fold(0.0, f64::max);
+
+        Self {
+            cycled_palette1: palette.clone(),
+            cycled_palette2: palette.clone(),
+            blended_palette: palette.clone(),
+            loop_duration,
+            phase: 0.0,
+            blend_curve: BlendCurve::default(),
+            cache: None,
+            smoothing: None,
+            cycle_offsets: Vec::new(),
+            cycle_mask: Vec::new(),
+        }
+    }
+
+    /// Seconds of the longest color cycle, which is what the phase is
+    /// relative to.
+    #[inline]
+    pub fn loop_duration(&self) -> f64 {
+        self.loop_duration
+    }
+
+    #[inline]
+    pub fn phase(&self) -> f64 {
+        self.phase
+    }
+
+    /// Sets the phase offset of the color cycles as a fraction (0 to 1) of
+    /// the loop duration. Values outside of that range wrap around.
+    #[inline]
+    pub fn set_phase(&mut self, phase: f64) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    #[inline]
+    pub fn blend_curve(&self) -> BlendCurve {
+        self.blend_curve
+    }
+
+    /// How the colors fade into each other when `palette()` is called with
+    /// `blend`.
+    #[inline]
+    pub fn set_blend_curve(&mut self, curve: BlendCurve) {
+        self.blend_curve = curve;
+    }
+
+    #[inline]
+    pub fn palette_cache(&self) -> Option<&PaletteCache> {
+        self.cache.as_ref()
+    }
+
+    /// Replaces the palette cache, dropping everything cached so far. With
+    /// a cache timeline scenes whose palettes share the same cycles only
+    /// need to cycle one palette per frame.
+    #[inline]
+    pub fn set_palette_cache(&mut self, cache: Option<PaletteCache>) {
+        self.cache = cache;
+    }
+
+    #[inline]
+    pub fn smoothing(&self) -> Option<f64> {
+        self.smoothing.as_ref().map(|smoothing| smoothing.frame_duration)
+    }
+
+    /// Enables interpolation of the color cycles for frame rates below the
+    /// requested one, `frame_duration` being the seconds per frame at the
+    /// requested frame rate. When a frame comes late, its palette is blended
+    /// half way with the one of the previous frame, so that the cycles don't
+    /// jump as much. This is independent of the blending of `palette()`.
+    pub fn set_smoothing(&mut self, frame_duration: Option<f64>) {
+        match (frame_duration, &mut self.smoothing) {
+            (Some(frame_duration), Some(smoothing)) => smoothing.frame_duration = frame_duration,
+            (Some(frame_duration), None) => {
+                self.smoothing = Some(Smoothing {
+                    frame_duration,
+                    previous: None,
+                    output: Palette::default(),
+                });
+            }
+            (None, _) => self.smoothing = None,
+        }
+    }
+
+    #[inline]
+    pub fn cycle_offsets(&self) -> &[f64] {
+        &self.cycle_offsets
+    }
+
+    /// Shifts the cycle time of single cycles by the seconds at their index
+    /// in `offsets`, on top of the phase offset. Used to speed up cycles
+    /// independently of each other.
+    #[inline]
+    pub fn set_cycle_offsets(&mut self, offsets: &[f64]) {
+        self.cycle_offsets.clear();
+        self.cycle_offsets.extend_from_slice(offsets);
+    }
+
+    #[inline]
+    pub fn cycle_mask(&self) -> &[bool] {
+        &self.cycle_mask
+    }
+
+    /// Only applies the cycles that are `true` at their index in `mask`,
+    /// cycles past its end are applied. Used to mute single cycles.
+    #[inline]
+    pub fn set_cycle_mask(&mut self, mask: &[bool]) {
+        self.cycle_mask.clear();
+        self.cycle_mask.extend_from_slice(mask);
+    }
+
+    /// Phase offset in seconds that is added to the cycle time.
+    #[inline]
+    pub fn phase_offset(&self) -> f64 {
+        self.phase * self.loop_duration
+    }
+
+    /// The palette at `clock`. The phase offset is added to the cycle time.
+    pub fn palette(&mut self, living_world: &LivingWorld, clock: CycleClock, blend: bool) -> &Palette {
+        let time_of_day = clock.time_of_day;
+        let blend_cycle = clock.cycle_time();
+        let Some(mut smoothing) = self.smoothing.take() else {
+            return self.cycled_palette(living_world, time_of_day, blend_cycle, blend);
+        };
+
+        let current = self.cycled_palette(living_world, time_of_day, blend_cycle, blend);
+        let late = match &smoothing.previous {
+            Some((previous_cycle, previous)) => {
+                let elapsed = blend_cycle - previous_cycle;
+                let late = elapsed > smoothing.frame_duration * SMOOTHING_THRESHOLD;
+                if late {
+                    crate::palette::blend(previous, current, 0.5, &mut smoothing.output);
+                }
+                late
+            }
+            None => false,
+        };
+
+        match &mut smoothing.previous {
+            Some((previous_cycle, previous)) => {
+                *previous_cycle = blend_cycle;
+                previous.clone_from(current);
+            }
+            None => smoothing.previous = Some((blend_cycle, current.clone())),
+        }
+
+        if !late {
+            smoothing.output.clone_from(current);
+        }
+        &self.smoothing.insert(smoothing).output
+    }
+
+    fn cycled_palette(&mut self, living_world: &LivingWorld, time_of_day: u64, blend_cycle: f64, blend: bool) -> &Palette {
+        let blend_cycle = blend_cycle + self.phase_offset();
+        if living_world.timeline().is_empty() {
+            self.cycled_palette1.apply_cycles_masked_from(living_world.base().palette(), living_world.base().cycles(), blend_cycle, &self.cycle_offsets, &self.cycle_mask, blend.then_some(self.blend_curve));
+            return &self.cycled_palette1;
+        }
+
+        if let Some(cache) = &mut self.cache {
+            let slot = time_of_day / cache.resolution;
+            let (palette1, palette2, blend_palettes) = timeline_palettes(living_world, slot * cache.resolution);
+
+            // blending and cycling can only be swapped if both use the same cycles
+            if palette1.cycles() == palette2.cycles() {
+                let blended = cache.get_or_insert_with(slot, |output| {
+                    crate::palette::blend(palette1.palette(), palette2.palette(), blend_palettes, output);
+                });
+                self.cycled_palette1.apply_cycles_masked_from(blended, palette1.cycles(), blend_cycle, &self.cycle_offsets, &self.cycle_mask, blend.then_some(self.blend_curve));
+                return &self.cycled_palette1;
+            }
+        }
+
+        let (palette1, palette2, blend_palettes) = timeline_palettes(living_world, time_of_day);
+        self.cycled_palette1.apply_cycles_masked_from(palette1.palette(), palette1.cycles(), blend_cycle, &self.cycle_offsets, &self.cycle_mask, blend.then_some(self.blend_curve));
+        self.cycled_palette2.apply_cycles_masked_from(palette2.palette(), palette2.cycles(), blend_cycle, &self.cycle_offsets, &self.cycle_mask, blend.then_some(self.blend_curve));
+
+        crate::palette::blend(&self.cycled_palette1, &self.cycled_palette2, blend_palettes, &mut self.blended_palette);
+
+        &self.blended_palette
+    }
+
+    /// Returns `image` with the parallax layers and sprites of `living_world`
+    /// drawn over it as they are at `clock`, using `buf` for the result.
+    /// `image` is the part of the base image that starts at `x`/`y`. Without
+    /// layers and sprites `image` itself is returned.
+    pub fn compose<'a>(&self, living_world: &LivingWorld, image: &'a IndexedImage, x: u32, y: u32, clock: CycleClock, buf: &'a mut IndexedImage) -> &'a IndexedImage {
+        if living_world.layers().is_empty() && living_world.sprites().is_empty() {
+            return image;
+        }
+
+        buf.get_rect_from(0, 0, image.width(), image.height(), image);
+        for layer in living_world.layers() {
+            layer.draw(buf, x, y);
+        }
+
+        for sprite in living_world.sprites() {
+            sprite.draw(buf, x, y, clock.cycle_time());
+        }
+
+        buf
+    }
+}
+
+/// The color cycles active at `time_of_day` (in milliseconds since
+/// midnight): those of the base image if there is no timeline, or else
+/// those of the timeline palette that is blended from.
+pub fn active_cycles(living_world: &LivingWorld, time_of_day: u64) -> &[Cycle] {
+    if living_world.timeline().is_empty() {
+        living_world.base().cycles()
+    } else {
+        timeline_palettes(living_world, time_of_day).0.cycles()
+    }
+}
+
+/// The two palettes of the timeline of `living_world` that are active at
+/// `time_of_day` (in milliseconds since midnight) and how far to blend from
+/// the first to the second.
+fn timeline_palettes(living_world: &LivingWorld, time_of_day: u64) -> (&CycleImage, &CycleImage, f64) {
+    let timeline = living_world.timeline();
+    let (from, to, blend_palettes) = timeline_span(timeline, time_of_day, |event| event.time_of_day() as u64 * 1000).unwrap();
+    let palettes = living_world.palettes();
+
+    (&palettes[timeline[from].palette_index()], &palettes[timeline[to].palette_index()], blend_palettes)
+}
@@ -0,0 +1,176 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Two-key chords: a leader key followed by another key, optionally
+//! followed by a number that is typed in and confirmed with Enter.
+
+use crate::i18n::{self, Msg};
+use crate::input::{Key, Modifiers};
+
+pub const DEFAULT_LEADER: char = '\\';
+
+/// Longest number that can be typed after a chord.
+const MAX_DIGITS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChordAction {
+    /// go to a time of day typed as `HMM` or `HHMM`
+    GotoTime,
+    /// open the file with the typed number
+    GotoFile,
+}
+
+impl ChordAction {
+    #[inline]
+    fn prompt(self) -> Msg {
+        match self {
+            ChordAction::GotoTime => Msg::PromptTime,
+            ChordAction::GotoFile => Msg::PromptFile,
+        }
+    }
+}
+
+/// The second key of each chord and what it does.
+pub const CHORDS: &[(char, ChordAction, Msg)] = &[
+    ('t', ChordAction::GotoTime, Msg::ChordGotoTime),
+    ('f', ChordAction::GotoFile, Msg::ChordGotoFile),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+enum Pending {
+    #[default]
+    None,
+    Leader,
+    Number(ChordAction, String),
+}
+
+/// What a key did to the chord state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordEvent {
+    /// not part of a chord, handle the key as usual
+    Ignored,
+    /// the chord continues, show the new hint
+    Pending,
+    /// the chord was aborted with Escape or an unknown key
+    Cancelled,
+    /// the chord is complete with the number that was typed
+    Complete(ChordAction, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Chords {
+    leader: char,
+    pending: Pending,
+}
+
+impl Chords {
+    #[inline]
+    pub fn new(leader: char) -> Self {
+        Self { leader, pending: Pending::None }
+    }
+
+    #[inline]
+    pub fn leader(&self) -> char {
+        self.leader
+    }
+
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.pending != Pending::None
+    }
+
+    pub fn handle_key(&mut self, key: Key, modifiers: Modifiers) -> ChordEvent {
+        match std::mem::take(&mut self.pending) {
+            Pending::None => {
+                if key == Key::Char(self.leader) && modifiers == Modifiers::NONE {
+                    self.pending = Pending::Leader;
+                    ChordEvent::Pending
+                } else {
+                    ChordEvent::Ignored
+                }
+            }
+            Pending::Leader => {
+                let Key::Char(ch) = key else {
+                    return ChordEvent::Cancelled;
+                };
+                match CHORDS.iter().find(|(chord_key, _, _)| *chord_key == ch) {
+                    Some(&(_, action, _)) => {
+                        self.pending = Pending::Number(action, String::new());
+                        ChordEvent::Pending
+                    }
+                    None => ChordEvent::Cancelled,
+                }
+            }
+            Pending::Number(action, mut number) => match key {
+                Key::Enter => ChordEvent::Complete(action, number),
+                Key::Escape => ChordEvent::Cancelled,
+                Key::Backspace => {
+                    number.pop();
+                    self.pending = Pending::Number(action, number);
+                    ChordEvent::Pending
+                }
+                Key::Char(ch @ '0'..='9') => {
+                    if number.len() < MAX_DIGITS {
+                        number.push(ch);
+                    }
+                    self.pending = Pending::Number(action, number);
+                    ChordEvent::Pending
+                }
+                _ => {
+                    // ignore other keys while typing
+                    self.pending = Pending::Number(action, number);
+                    ChordEvent::Pending
+                }
+            }
+        }
+    }
+
+    /// Text for the On Screen Display that shows what can be typed next.
+    pub fn hint(&self) -> Option<String> {
+        match &self.pending {
+            Pending::None => None,
+            Pending::Leader => {
+                let mut hint = String::new();
+                hint.push(self.leader);
+                for &(key, _, msg) in CHORDS {
+                    hint.push_str("  ");
+                    hint.push(key);
+                    hint.push_str(": ");
+                    hint.push_str(i18n::tr(msg));
+                }
+                Some(hint)
+            }
+            Pending::Number(action, number) => Some(i18n::format(action.prompt(), &[number])),
+        }
+    }
+}
+
+/// Parses a time typed as `H`, `HH`, `HMM`, or `HHMM` into milliseconds
+/// since midnight.
+pub fn parse_time(number: &str) -> Option<u64> {
+    let (hours, mins) = match number.len() {
+        1 | 2 => (number, "0"),
+        3 | 4 => number.split_at(number.len() - 2),
+        _ => return None,
+    };
+    let hours: u64 = hours.parse().ok()?;
+    let mins: u64 = mins.parse().ok()?;
+    if hours >= 24 || mins >= 60 {
+        return None;
+    }
+    Some((hours * 60 + mins) * 60 * 1000)
+}
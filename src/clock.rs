@@ -0,0 +1,87 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The time a frame is rendered at.
+//!
+//! A [`CycleClock`] is the time in milliseconds since the scene started,
+//! which drives the color cycles and sprites, together with the time of day
+//! that selects the palette of a timeline. It is what [`Animator`] takes to
+//! render a frame, and since it is plain integers it serializes exactly.
+//!
+//! [`Animator`]: crate::animator::Animator
+
+use serde::{Deserialize, Serialize};
+
+use crate::animator::DAY_DURATION;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct CycleClock {
+    /// milliseconds since the start of the scene
+    pub elapsed: u64,
+    /// milliseconds since midnight
+    pub time_of_day: u64,
+}
+
+impl CycleClock {
+    /// `time_of_day` wraps around at midnight.
+    #[inline]
+    pub fn new(elapsed: u64, time_of_day: u64) -> Self {
+        Self {
+            elapsed,
+            time_of_day: time_of_day % DAY_DURATION,
+        }
+    }
+
+    /// The start of a scene at `time_of_day`.
+    #[inline]
+    pub fn at_time_of_day(time_of_day: u64) -> Self {
+        Self::new(0, time_of_day)
+    }
+
+    /// Converts `seconds` since the start of the scene, rounded to the
+    /// millisecond. Negative values are clamped to 0.
+    #[inline]
+    pub fn from_secs(seconds: f64, time_of_day: u64) -> Self {
+        Self::new((seconds.max(0.0) * 1000.0).round() as u64, time_of_day)
+    }
+
+    /// Seconds since the start of the scene, as used by the color cycles.
+    #[inline]
+    pub fn cycle_time(&self) -> f64 {
+        self.elapsed as f64 / 1000.0
+    }
+
+    /// The clock `msec` milliseconds later. The time of day runs `time_speed`
+    /// times as fast, or stands still if `time_speed` is 0.
+    pub fn advance(&self, msec: u64, time_speed: u64) -> Self {
+        let day_msec = ((msec as u128 * time_speed as u128) % DAY_DURATION as u128) as u64;
+        Self::new(self.elapsed + msec, self.time_of_day + day_msec)
+    }
+
+    /// The same elapsed time at another time of day.
+    #[inline]
+    pub fn with_time_of_day(&self, time_of_day: u64) -> Self {
+        Self::new(self.elapsed, time_of_day)
+    }
+
+    /// Hours and minutes of the time of day.
+    #[inline]
+    pub fn hours_mins(&self) -> (u32, u32) {
+        let mins = (self.time_of_day / (60 * 1000)) as u32;
+        (mins / 60, mins % 60)
+    }
+}
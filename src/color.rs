@@ -14,68 +14,359 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{fmt::Display, ops::{Index, IndexMut}};
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
-#[repr(transparent)]
-pub struct Rgb(pub [u8; 3]);
+use clap::ValueEnum;
 
-impl Index<usize> for Rgb {
-    type Output = u8;
+pub use color_cycle_core::color::{blend, Rgb};
 
+/// Conversions of [`Rgb`] that need the float math of `std`.
+pub trait RgbExt: Sized {
+    /// Relative luminance of the linear light in the range 0.0 to 1.0, as
+    /// used for contrast ratios.
+    fn relative_luminance(self) -> f64;
+
+    /// Linear light of each channel in the range 0.0 to 1.0.
+    fn to_linear(self) -> [f64; 3];
+
+    /// Inverse of [`RgbExt::to_linear()`]. Values are clamped.
+    fn from_linear(linear: [f64; 3]) -> Self;
+
+    /// Hue in degrees (0.0 to 360.0), saturation and value (0.0 to 1.0).
+    fn to_hsv(self) -> [f64; 3];
+
+    /// Inverse of [`RgbExt::to_hsv()`]. The hue wraps around, saturation and
+    /// value are clamped.
+    fn from_hsv(hsv: [f64; 3]) -> Self;
+
+    /// CIE L*a*b* coordinates (D65 white point).
+    fn to_lab(self) -> [f64; 3];
+
+    /// Oklab coordinates: lightness (0.0 to 1.0) and the green-red and
+    /// blue-yellow axes. Good for mixing and for evenly spaced gradients.
+    fn to_oklab(self) -> [f64; 3];
+
+    /// Inverse of [`RgbExt::to_oklab()`]. Colors outside of sRGB are clamped.
+    fn from_oklab(oklab: [f64; 3]) -> Self;
+}
+
+impl RgbExt for Rgb {
     #[inline]
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.0[index]
+    fn relative_luminance(self) -> f64 {
+        let [r, g, b] = self.to_linear();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
     }
-}
 
-impl IndexMut<usize> for Rgb {
     #[inline]
-    fn index_mut(&mut self, index: usize) -> &mut u8 {
-        &mut self.0[index]
+    fn to_linear(self) -> [f64; 3] {
+        self.0.map(srgb_to_linear)
     }
-}
 
-impl From<[u8; 3]> for Rgb {
     #[inline]
-    fn from(value: [u8; 3]) -> Self {
-        Self(value)
+    fn from_linear(linear: [f64; 3]) -> Self {
+        Self(linear.map(linear_to_srgb))
+    }
+
+    fn to_hsv(self) -> [f64; 3] {
+        let [r, g, b] = self.0.map(|value| value as f64 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+        [hue, saturation, max]
+    }
+
+    fn from_hsv(hsv: [f64; 3]) -> Self {
+        let [hue, saturation, value] = hsv;
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let min = value - chroma;
+
+        Self([r, g, b].map(|channel| quantize_channel(channel + min)))
+    }
+
+    fn to_lab(self) -> [f64; 3] {
+        let [r, g, b] = self.to_linear();
+
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y =  0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+        let fx = lab_f(x);
+        let fy = lab_f(y);
+        let fz = lab_f(z);
+
+        [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+    }
+
+    fn to_oklab(self) -> [f64; 3] {
+        let [r, g, b] = self.to_linear();
+
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+        [
+            0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        ]
+    }
+
+    fn from_oklab(oklab: [f64; 3]) -> Self {
+        let [lightness, a, b] = oklab;
+
+        let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+        Self::from_linear([
+             4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ])
     }
 }
 
-impl Display for Rgb {
-    #[inline]
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Rgb([r, g, b]) = *self;
-        write!(f, "#{r:02X}{g:02X}{b:02X}")
+/// Like [`blend()`], but mixes the light instead of the sRGB values, like
+/// physically overlapping the two colors. Use this for averaging pixels.
+pub fn blend_linear(c1: Rgb, c2: Rgb, mid: f64) -> Rgb {
+    let linear1 = c1.to_linear();
+    let linear2 = c2.to_linear();
+    let inv_mid = 1.0 - mid;
+    Rgb::from_linear(std::array::from_fn(|channel| linear1[channel] * inv_mid + linear2[channel] * mid))
+}
+
+/// Like [`blend()`], but mixes in Oklab, which keeps perceived lightness
+/// and hue steady along the way.
+pub fn blend_oklab(c1: Rgb, c2: Rgb, mid: f64) -> Rgb {
+    let oklab1 = c1.to_oklab();
+    let oklab2 = c2.to_oklab();
+    let inv_mid = 1.0 - mid;
+    Rgb::from_oklab(std::array::from_fn(|channel| oklab1[channel] * inv_mid + oklab2[channel] * mid))
+}
+
+/// Color gamuts of historic hardware that palettes can be snapped to.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gamut {
+    /// EGA, 2 bits per channel (64 colors)
+    Ega,
+    /// Amiga OCS, 4 bits per channel (4096 colors)
+    Ocs,
+    /// Commodore 64, a fixed palette of 16 colors
+    C64,
+}
+
+/// The C64 palette as measured by Pepto.
+pub const C64_PALETTE: [Rgb; 16] = [
+    Rgb([0x00, 0x00, 0x00]), Rgb([0xFF, 0xFF, 0xFF]), Rgb([0x68, 0x37, 0x2B]), Rgb([0x70, 0xA4, 0xB2]),
+    Rgb([0x6F, 0x3D, 0x86]), Rgb([0x58, 0x8D, 0x43]), Rgb([0x35, 0x28, 0x79]), Rgb([0xB8, 0xC7, 0x6F]),
+    Rgb([0x6F, 0x4F, 0x25]), Rgb([0x43, 0x39, 0x00]), Rgb([0x9A, 0x67, 0x59]), Rgb([0x44, 0x44, 0x44]),
+    Rgb([0x6C, 0x6C, 0x6C]), Rgb([0x9A, 0xD2, 0x84]), Rgb([0x6C, 0x5E, 0xB5]), Rgb([0x95, 0x95, 0x95]),
+];
+
+/// Bits per channel of the lookup table of the nearest C64 colors.
+const C64_LUT_BITS: u32 = 5;
+
+static EGA_LUT: [u8; 256] = channel_lut(2);
+static OCS_LUT: [u8; 256] = channel_lut(4);
+
+/// Maps every channel value to the nearest one representable with `bits`.
+const fn channel_lut(bits: u32) -> [u8; 256] {
+    let max = (1u32 << bits) - 1;
+    let mut lut = [0u8; 256];
+    let mut value = 0;
+    while value < 256 {
+        let level = (value as u32 * max + 127) / 255;
+        lut[value] = (level * 255 / max) as u8;
+        value += 1;
     }
+    lut
 }
 
-impl Rgb {
-    #[inline]
-    pub fn r(&self) -> u8 {
-        self.0[0]
+/// Indices into [`C64_PALETTE`] of the perceptually nearest color for every
+/// color quantized to [`C64_LUT_BITS`] per channel.
+fn c64_lut() -> &'static [u8] {
+    static LUT: OnceLock<Box<[u8]>> = OnceLock::new();
+    LUT.get_or_init(|| {
+        let size = 1usize << C64_LUT_BITS;
+        let shift = 8 - C64_LUT_BITS;
+        let labs = C64_PALETTE.map(Rgb::to_lab);
+        let mut lut = Vec::with_capacity(size * size * size);
+        for r in 0..size {
+            for g in 0..size {
+                for b in 0..size {
+                    // the center of the range of colors that end up in this entry
+                    let lab = Rgb([r, g, b].map(|value| ((value << shift) | (1 << (shift - 1))) as u8)).to_lab();
+                    let (index, _) = labs.iter()
+                        .map(|other| lab_distance(lab, *other))
+                        .enumerate()
+                        .fold((0, f64::INFINITY), |best, (index, dist)| if dist < best.1 { (index, dist) } else { best });
+                    lut.push(index as u8);
+                }
+            }
+        }
+        lut.into()
+    })
+}
+
+impl Gamut {
+    /// The nearest color this hardware can display.
+    pub fn snap(self, color: Rgb) -> Rgb {
+        match self {
+            Gamut::Ega => Rgb(color.0.map(|value| EGA_LUT[value as usize])),
+            Gamut::Ocs => Rgb(color.0.map(|value| OCS_LUT[value as usize])),
+            Gamut::C64 => {
+                let shift = 8 - C64_LUT_BITS;
+                let [r, g, b] = color.0.map(|value| (value >> shift) as usize);
+                let index = (r << (2 * C64_LUT_BITS)) | (g << C64_LUT_BITS) | b;
+                C64_PALETTE[c64_lut()[index] as usize]
+            }
+        }
     }
+}
 
-    #[inline]
-    pub fn g(&self) -> u8 {
-        self.0[1]
+/// Perceptual distance of two colors (CIE76 ΔE). Around 2.3 is the smallest
+/// difference most people can notice.
+pub fn delta_e(c1: Rgb, c2: Rgb) -> f64 {
+    lab_distance(c1.to_lab(), c2.to_lab())
+}
+
+/// Like [`delta_e()`] for colors that are already converted with
+/// [`RgbExt::to_lab()`].
+#[inline]
+pub fn lab_distance(lab1: [f64; 3], lab2: [f64; 3]) -> f64 {
+    let [l1, a1, b1] = lab1;
+    let [l2, a2, b2] = lab2;
+    ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+}
+
+/// Converts an sRGB channel value to linear light (0.0 to 1.0).
+#[inline]
+pub fn srgb_to_linear(value: u8) -> f64 {
+    let value = value as f64 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
     }
+}
 
-    #[inline]
-    pub fn b(&self) -> u8 {
-        self.0[2]
+/// Converts linear light (0.0 to 1.0, clamped) to an sRGB channel value.
+#[inline]
+pub fn linear_to_srgb(value: f64) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    quantize_channel(value)
+}
+
+/// Rounds `value` (0.0 to 1.0) to the nearest of `levels` (2 to 256) evenly
+/// spaced channel values.
+#[inline]
+pub fn posterize(value: f64, levels: u32) -> u8 {
+    let steps = (levels.clamp(2, 256) - 1) as f64;
+    quantize_channel((value.clamp(0.0, 1.0) * steps).round() / steps)
+}
+
+#[inline]
+fn quantize_channel(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[inline]
+fn lab_f(t: f64) -> f64 {
+    const DELTA: f64 = 6.0 / 29.0;
+    if t > DELTA * DELTA * DELTA {
+        t.cbrt()
+    } else {
+        t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
     }
 }
 
-pub fn blend(c1: Rgb, c2: Rgb, mid: f64) -> Rgb {
-    let Rgb([r1, g1, b1]) = c1;
-    let Rgb([r2, g2, b2]) = c2;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let inv_mid = 1.0 - mid;
-    let r = (r1 as f64 * inv_mid + r2 as f64 * mid).round();
-    let g = (g1 as f64 * inv_mid + g2 as f64 * mid).round();
-    let b = (b1 as f64 * inv_mid + b2 as f64 * mid).round();
+    /// A grid through the RGB cube, including black, white and the
+    /// primaries.
+    fn sample_colors() -> impl Iterator<Item = Rgb> {
+        const STEPS: [u8; 14] = [0, 1, 2, 17, 34, 64, 100, 127, 128, 160, 200, 230, 254, 255];
+        STEPS.into_iter().flat_map(|r| STEPS.into_iter().flat_map(move |g| STEPS.into_iter().map(move |b| Rgb([r, g, b]))))
+    }
+
+    fn assert_close(c1: Rgb, c2: Rgb, tolerance: u8) {
+        for (a, b) in c1.0.into_iter().zip(c2.0) {
+            assert!(a.abs_diff(b) <= tolerance, "{c1:?} != {c2:?}");
+        }
+    }
 
-    Rgb([r as u8, g as u8, b as u8])
+    #[test]
+    fn srgb_linear_round_trip() {
+        for value in 0..=255u8 {
+            assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
+        }
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert_eq!(srgb_to_linear(255), 1.0);
+    }
+
+    #[test]
+    fn hsv_round_trip() {
+        for color in sample_colors() {
+            assert_close(Rgb::from_hsv(color.to_hsv()), color, 1);
+        }
+    }
+
+    #[test]
+    fn oklab_round_trip() {
+        for color in sample_colors() {
+            assert_close(Rgb::from_oklab(color.to_oklab()), color, 1);
+        }
+    }
+
+    #[test]
+    fn delta_e_of_same_color_is_zero() {
+        for color in sample_colors() {
+            assert_eq!(delta_e(color, color), 0.0);
+        }
+        assert!(delta_e(Rgb([0, 0, 0]), Rgb([255, 255, 255])) > 99.0);
+    }
+
+    #[test]
+    fn blend_endpoints() {
+        let c1 = Rgb([10, 200, 30]);
+        let c2 = Rgb([250, 0, 128]);
+        for blend in [blend, blend_linear, blend_oklab] {
+            assert_close(blend(c1, c2, 0.0), c1, 1);
+            assert_close(blend(c1, c2, 1.0), c2, 1);
+        }
+        assert_eq!(blend_linear(c1, c2, 0.0), c1);
+        assert_eq!(blend_linear(c1, c2, 1.0), c2);
+    }
 }
@@ -0,0 +1,108 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Converts ILBM files into Canvas Cycle JSON files and back, see
+//! `color-cycle convert`.
+
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::error::Error;
+use crate::ilbm::{self, ILBM};
+use crate::image::{CycleImage, LivingWorld};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConvertFormat {
+    /// Canvas Cycle JSON (.json), from ILBM files
+    #[default]
+    Json,
+    /// ILBM (.lbm), from any supported file
+    Ilbm,
+}
+
+impl ConvertFormat {
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConvertFormat::Json => "json",
+            ConvertFormat::Ilbm => "lbm",
+        }
+    }
+
+    /// Decodes a file for conversion into this format. Only the base image
+    /// of a file ends up in an ILBM file, there is no place for timelines.
+    pub fn decode(&self, data: &[u8], filename: &str) -> Result<CycleImage, Error> {
+        match self {
+            ConvertFormat::Json => decode_ilbm(data, filename),
+            ConvertFormat::Ilbm => Ok(LivingWorld::from_bytes(data)?.into_base()),
+        }
+    }
+
+    pub fn write(&self, out: &mut impl Write, image: &CycleImage) -> Result<(), Error> {
+        match self {
+            ConvertFormat::Json => write_json(out, image)?,
+            ConvertFormat::Ilbm => ilbm::write_ilbm(out, image)?,
+        }
+        Ok(())
+    }
+}
+
+/// Decodes an ILBM (or PBM) file. `filename` is kept in the JSON file.
+pub fn decode_ilbm(data: &[u8], filename: &str) -> Result<CycleImage, Error> {
+    let ilbm = ILBM::read(&mut Cursor::new(data))?;
+    let image: CycleImage = ilbm.try_into()?;
+    Ok(CycleImage::new(Some(filename.to_owned()), image.indexed_image().clone(), image.cycles().into()))
+}
+
+/// Writes `image` in the Canvas Cycle JSON format, as read by this program
+/// and the original Canvas Cycle viewer.
+pub fn write_json(out: &mut impl Write, image: &CycleImage) -> std::io::Result<()> {
+    let indexed_image = image.indexed_image();
+    let cycles: Vec<_> = image.cycles().iter()
+        .map(|cycle| serde_json::json!({
+            "reverse": if cycle.reverse() { 2 } else { 0 },
+            "rate": cycle.rate(),
+            "low": cycle.low(),
+            "high": cycle.high(),
+        }))
+        .collect();
+    let mut json = serde_json::json!({
+        "width": image.width(),
+        "height": image.height(),
+        "colors": indexed_image.palette().0.iter().map(|color| color.0).collect::<Vec<_>>(),
+        "cycles": cycles,
+        "pixels": indexed_image.data(),
+    });
+    if let Some(filename) = image.filename() {
+        json["filename"] = filename.into();
+    }
+
+    serde_json::to_writer(&mut *out, &json)?;
+    writeln!(out)
+}
+
+/// Where the converted file of `path` goes: next to it, or into
+/// `output_dir`, with the extension of `format`.
+pub fn output_path(path: &Path, output_dir: Option<&Path>, format: ConvertFormat) -> PathBuf {
+    let path = path.with_extension(format.extension());
+    match (output_dir, path.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => path,
+    }
+}
@@ -0,0 +1,242 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Textual descriptions of a scene for `--describe`, so that the day of a
+//! scene can be followed with a screen reader or a braille display.
+
+use crate::color::RgbExt;
+use crate::i18n::{self, Msg};
+use crate::palette::Palette;
+
+/// Seconds between two descriptions when nothing else happens.
+pub const DEFAULT_DESCRIBE_INTERVAL: u64 = 60;
+
+/// Below this share of (weighted) colorful pixels a scene counts as gray.
+const MIN_COLORFULNESS: f64 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartOfDay {
+    Night,
+    Dawn,
+    Morning,
+    Midday,
+    Afternoon,
+    Evening,
+}
+
+impl PartOfDay {
+    /// `time_of_day` in milliseconds since midnight.
+    pub fn from_time_of_day(time_of_day: u64) -> Self {
+        match time_of_day / (60 * 60 * 1000) {
+            5..=6   => PartOfDay::Dawn,
+            7..=10  => PartOfDay::Morning,
+            11..=13 => PartOfDay::Midday,
+            14..=17 => PartOfDay::Afternoon,
+            18..=21 => PartOfDay::Evening,
+            _       => PartOfDay::Night,
+        }
+    }
+
+    pub fn msg(self) -> Msg {
+        match self {
+            PartOfDay::Night     => Msg::PartNight,
+            PartOfDay::Dawn      => Msg::PartDawn,
+            PartOfDay::Morning   => Msg::PartMorning,
+            PartOfDay::Midday    => Msg::PartMidday,
+            PartOfDay::Afternoon => Msg::PartAfternoon,
+            PartOfDay::Evening   => Msg::PartEvening,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Brightness {
+    Dark,
+    Dim,
+    Bright,
+}
+
+impl Brightness {
+    pub fn msg(self) -> Msg {
+        match self {
+            Brightness::Dark   => Msg::Dark,
+            Brightness::Dim    => Msg::Dim,
+            Brightness::Bright => Msg::Bright,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hue {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+    Pink,
+    Gray,
+}
+
+impl Hue {
+    const COLORFUL: [Hue; 8] = [
+        Hue::Red, Hue::Orange, Hue::Yellow, Hue::Green,
+        Hue::Cyan, Hue::Blue, Hue::Purple, Hue::Pink,
+    ];
+
+    /// `degrees` as returned by [`crate::color::RgbExt::to_hsv()`].
+    pub fn from_degrees(degrees: f64) -> Self {
+        match degrees.rem_euclid(360.0) {
+            degrees if degrees <  15.0 => Hue::Red,
+            degrees if degrees <  45.0 => Hue::Orange,
+            degrees if degrees <  70.0 => Hue::Yellow,
+            degrees if degrees < 160.0 => Hue::Green,
+            degrees if degrees < 200.0 => Hue::Cyan,
+            degrees if degrees < 260.0 => Hue::Blue,
+            degrees if degrees < 300.0 => Hue::Purple,
+            degrees if degrees < 345.0 => Hue::Pink,
+            _ => Hue::Red,
+        }
+    }
+
+    pub fn msg(self) -> Msg {
+        match self {
+            Hue::Red    => Msg::Red,
+            Hue::Orange => Msg::Orange,
+            Hue::Yellow => Msg::Yellow,
+            Hue::Green  => Msg::Green,
+            Hue::Cyan   => Msg::Cyan,
+            Hue::Blue   => Msg::Blue,
+            Hue::Purple => Msg::Purple,
+            Hue::Pink   => Msg::Pink,
+            Hue::Gray   => Msg::Gray,
+        }
+    }
+}
+
+/// The overall impression of the colors of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colors {
+    pub brightness: Brightness,
+    pub hue: Hue,
+}
+
+impl Colors {
+    /// `usage` is the number of pixels per palette index, as returned by
+    /// [`crate::image::IndexedImage::index_usage()`].
+    pub fn new(usage: &[usize; 256], palette: &Palette) -> Self {
+        let mut pixels = 0.0;
+        let mut luminance = 0.0;
+        let mut hues = [0.0; Hue::COLORFUL.len()];
+
+        for (&uses, &color) in usage.iter().zip(palette.0.iter()) {
+            if uses == 0 {
+                continue;
+            }
+            let uses = uses as f64;
+            let [hue, saturation, value] = color.to_hsv();
+            let hue = Hue::from_degrees(hue);
+
+            pixels += uses;
+            luminance += uses * color.luminance();
+            if let Some(index) = Hue::COLORFUL.iter().position(|&other| other == hue) {
+                hues[index] += uses * saturation * value;
+            }
+        }
+
+        let pixels = if pixels > 0.0 { pixels } else { 1.0 };
+        let luminance = luminance / pixels;
+        let brightness = if luminance < 0.2 {
+            Brightness::Dark
+        } else if luminance < 0.5 {
+            Brightness::Dim
+        } else {
+            Brightness::Bright
+        };
+
+        let colorfulness: f64 = hues.iter().sum::<f64>() / pixels;
+        let hue = if colorfulness < MIN_COLORFULNESS {
+            Hue::Gray
+        } else {
+            hues.iter()
+                .zip(Hue::COLORFUL)
+                .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                .map_or(Hue::Gray, |(_, hue)| hue)
+        };
+
+        Self { brightness, hue }
+    }
+
+    /// E.g. "Mostly green, bright."
+    pub fn describe(&self) -> String {
+        i18n::format(Msg::DescribeColors, &[&i18n::tr(self.hue.msg()), &i18n::tr(self.brightness.msg())])
+    }
+}
+
+/// E.g. "14:05, afternoon."
+pub fn describe_time(time_of_day: u64) -> String {
+    let (hours, mins) = hours_mins(time_of_day);
+    let part = i18n::tr(PartOfDay::from_time_of_day(time_of_day).msg());
+    i18n::format(Msg::DescribeTime, &[&hours, &format!("{mins:02}"), &part])
+}
+
+/// The first description of a scene: what it is, the time of day, the
+/// colors, and when the palette changes next. `next_event` is in seconds
+/// since midnight.
+pub fn describe_scene(title: &str, author: Option<&str>, month: Option<&str>, time_of_day: u64, colors: &Colors, next_event: Option<u32>) -> String {
+    let mut text = match author {
+        Some(author) => i18n::format(Msg::DescribeBy, &[&title, &author]),
+        None => title.to_owned(),
+    };
+    if let Some(month) = month {
+        text.push_str(&format!(" ({month})"));
+    }
+    text.push_str(". ");
+    text.push_str(&describe_update(time_of_day, colors, next_event));
+    text
+}
+
+/// The time of day, the colors, and when the palette changes next.
+pub fn describe_update(time_of_day: u64, colors: &Colors, next_event: Option<u32>) -> String {
+    let mut text = format!("{} {}", describe_time(time_of_day), colors.describe());
+    if let Some(next_event) = next_event {
+        let (hours, mins) = hours_mins(next_event as u64 * 1000);
+        text.push(' ');
+        text.push_str(&i18n::format(Msg::DescribeNext, &[&hours, &format!("{mins:02}")]));
+    }
+    text
+}
+
+/// A timeline event was reached.
+pub fn describe_change(event_time: u32, colors: &Colors) -> String {
+    let (hours, mins) = hours_mins(event_time as u64 * 1000);
+    format!("{} {}", i18n::format(Msg::DescribeChange, &[&hours, &format!("{mins:02}")]), colors.describe())
+}
+
+/// A short line for a braille display, e.g. "Jungle 14:05 afternoon, green bright".
+pub fn status_line(title: &str, time_of_day: u64, colors: &Colors) -> String {
+    let (hours, mins) = hours_mins(time_of_day);
+    let part = i18n::tr(PartOfDay::from_time_of_day(time_of_day).msg());
+    format!("{title} {hours}:{mins:02} {part}, {} {}", i18n::tr(colors.hue.msg()), i18n::tr(colors.brightness.msg()))
+}
+
+#[inline]
+fn hours_mins(time_of_day: u64) -> (u64, u64) {
+    let mins = time_of_day / (60 * 1000);
+    (mins / 60 % 24, mins % 60)
+}
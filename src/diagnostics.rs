@@ -0,0 +1,260 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Short codes for the errors that files can cause, with explanations of
+//! their likely causes and workarounds, printed by `--explain <CODE>`.
+
+use std::io::{IsTerminal, Write};
+
+use crate::error::Error;
+
+macro_rules! codes {
+    ($($code:ident = $id:literal, $summary:literal, $explanation:literal;)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Code {
+            $($code,)*
+        }
+
+        impl Code {
+            pub const ALL: &[Code] = &[$(Code::$code,)*];
+
+            /// The code as shown to the user, like `E0003`.
+            pub fn id(self) -> &'static str {
+                match self {
+                    $(Code::$code => $id,)*
+                }
+            }
+
+            /// One line summary of the error.
+            pub fn summary(self) -> &'static str {
+                match self {
+                    $(Code::$code => $summary,)*
+                }
+            }
+
+            /// Causes and workarounds of the error.
+            pub fn explanation(self) -> &'static str {
+                match self {
+                    $(Code::$code => $explanation,)*
+                }
+            }
+        }
+    };
+}
+
+codes! {
+    NotAnImage = "E0001", "file is not in a supported format",
+"The file doesn't start like any of the supported formats: ILBM or PBM
+files (IFF, as written by Deluxe Paint) or Canvas Cycle JSON files.
+
+Causes:
+- The file is something else, e.g. an HTML error page that was saved
+  instead of the actual file.
+- The file is an IFF file of another type, like an ANIM or an 8SVX sound.
+- The JSON file is wrapped in JavaScript, like the files of the Canvas
+  Cycle web page (`CanvasCycle.processImage({...})`).
+
+Workarounds:
+- Check what the file actually is with `file <FILE>`.
+- Strip any JavaScript around the JSON object.";
+
+    TruncatedChunk = "E0002", "chunk shorter than expected",
+"A chunk of an ILBM file (BMHD, CAMG, CRNG, or CCRT) is shorter than its
+fixed size.
+
+Causes:
+- The file was written by a buggy program.
+- The file was cut off or corrupted while copying or downloading.
+
+Workarounds:
+- Download or extract the file again.
+- Load the file in an image editor that is more lenient and save it again.";
+
+    TruncatedBody = "E0003", "BODY chunk shorter than expected",
+"The BODY chunk of an ILBM file, which holds the pixels, has less data than
+the size of the image given in the BMHD chunk needs.
+
+Causes:
+- The file was cut off, e.g. by an incomplete download, or the last
+  sectors of a disk image were lost.
+- The compression flag in the BMHD chunk is wrong, so compressed data is
+  read as if it was uncompressed.
+
+Workarounds:
+- Download or extract the file again.
+- If the file loads with other tools but looks scrambled in 8 pixel wide
+  stripes here, try `--ilbm-column-swap`.";
+
+    BrokenCompression = "E0004", "broken BODY compression",
+"The compressed pixel data of an ILBM file doesn't decompress to the size
+of the image. This is checked for the ByteRun1 compression and for the
+VDAT chunks of the Atari ST compression.
+
+Causes:
+- The file is corrupted.
+- The file uses a compression that is marked with a flag that means
+  something else in the program that wrote it.
+
+Workarounds:
+- Load the file in an image editor that is more lenient and save it again
+  uncompressed or with ByteRun1 compression.
+- If the image loads but looks scrambled in 8 pixel wide stripes, try
+  `--ilbm-column-swap`.";
+
+    MissingHeader = "E0005", "BMHD chunk missing",
+"An ILBM file has no BMHD chunk (the image header with the size and the
+number of bit planes) before its BODY chunk.
+
+Causes:
+- The file is corrupted.
+- The file only holds a palette or color cycles and no image.
+
+Workarounds:
+- Load the file in an image editor and save it again.";
+
+    Unsupported = "E0006", "unsupported image feature",
+"The file uses a number of bit planes or a compression that isn't
+supported. Supported are 1 to 8 bit planes for ILBM files, 1, 4, or 8 bit
+planes for PBM files, and no compression, ByteRun1, or the Atari ST VDAT
+compression.
+
+Workarounds:
+- Convert the image to 256 colors or less with an image editor that can
+  read it.";
+
+    InvalidCycle = "E0007", "invalid color cycle",
+"A CCRT chunk (Graphicraft color cycling) has a direction other than -1, 0,
+or 1.
+
+Causes:
+- The file is corrupted.
+- The file was written by a program that uses the CCRT chunk differently.
+
+Workarounds:
+- Remove the CCRT chunk with an IFF editor. CRNG chunks are read instead.";
+
+    ImageTooLarge = "E0008", "image too large",
+"The size declared by the file exceeds the pixel limit, or the pixel data
+is smaller than the declared size.
+
+Causes:
+- The file is corrupted and declares a nonsense size.
+- The image is really that big.
+
+Workarounds:
+- Raise the limit with `--max-pixels` if the image is really that big.";
+
+    InvalidJson = "E0009", "invalid JSON file",
+"A JSON file isn't valid JSON or doesn't have the structure of a Canvas
+Cycle or Living Worlds file. The error message includes the line and
+column.
+
+Causes:
+- A field is missing or has the wrong type, e.g. a palette that doesn't
+  have exactly 256 colors.
+- The file was edited by hand and has a syntax error, like a trailing
+  comma.
+- The timeline references a palette that doesn't exist.
+
+Workarounds:
+- Fix the file at the given line and column.";
+
+    Io = "E0010", "IO error",
+"The file couldn't be read.
+
+Causes:
+- The file doesn't exist or the path is misspelled.
+- Missing permissions.
+- The file ends in the middle of the data (unexpected end of file).
+
+Workarounds:
+- Check the path and permissions.
+- Download or extract the file again if it is cut off.";
+
+    Network = "E0011", "network error",
+"A file couldn't be downloaded.
+
+Causes:
+- Only plain http:// URLs are supported, TLS (https://) is not.
+- The server responded with an error status, too many redirects, an HTML
+  page instead of a file, or something that isn't HTTP.
+- The download took longer than --network-timeout or was bigger than
+  --max-download-size.
+- Network access is disabled with --offline.
+
+Workarounds:
+- Raise --network-timeout or --max-download-size if the server is just
+  slow or the file really is that big.
+- Download the file with another tool and open the local file.";
+}
+
+impl Code {
+    /// Parses a code like `E0003`. The `E` and the leading zeros are
+    /// optional, so `e3` and `3` work too.
+    pub fn parse(value: &str) -> Option<Code> {
+        let value = value.trim();
+        let number = value.strip_prefix(['E', 'e']).unwrap_or(value);
+        let number: u32 = number.parse().ok()?;
+        Code::ALL.iter().copied().find(|code| code.id()[1..].parse() == Ok(number))
+    }
+}
+
+impl std::fmt::Display for Code {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.id().fmt(f)
+    }
+}
+
+/// Prints the explanation of `code` for `--explain`.
+pub fn explain(out: &mut impl Write, code: Code) -> std::io::Result<()> {
+    writeln!(out, "{code}: {}\n", code.summary())?;
+    writeln!(out, "{}", code.explanation())
+}
+
+/// Prints the list of all codes.
+pub fn list(out: &mut impl Write) -> std::io::Result<()> {
+    for code in Code::ALL {
+        writeln!(out, "{code}  {}", code.summary())?;
+    }
+    Ok(())
+}
+
+/// Whether to color the diagnostics on stderr: only if it is a terminal and
+/// `NO_COLOR` isn't set.
+pub fn use_color() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// Prints `err` to stderr, with its code and a hint to `--explain` it if
+/// it has one.
+pub fn report(err: &Error) {
+    let color = use_color();
+    let mut stderr = std::io::stderr().lock();
+    let _ = write_report(&mut stderr, err, color);
+}
+
+pub fn write_report(out: &mut impl Write, err: &Error, color: bool) -> std::io::Result<()> {
+    let (red, bold, reset) = if color { ("\x1B[1;31m", "\x1B[1m", "\x1B[0m") } else { ("", "", "") };
+    match err.code() {
+        Some(code) => {
+            writeln!(out, "{red}{code}{reset}: {err}")?;
+            writeln!(out, "{bold}hint{reset}: run `color-cycle --explain {code}` for possible causes and workarounds")
+        }
+        None => writeln!(out, "{red}error{reset}: {err}"),
+    }
+}
@@ -0,0 +1,408 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Probes the terminal for the features this program uses or might use and
+//! prints a report, see `color-cycle doctor`.
+
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::latency::{CURSOR_POSITION_QUERY, Link};
+
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Primary Device Attributes. Every terminal answers this, so it is sent
+/// after each probe to know when the answer to the probe (if any) is complete.
+pub const DA1: &str = "\x1B[c";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Support {
+    Yes,
+    No,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Default)]
+struct Features {
+    round_trip: Option<Duration>,
+    truecolor: Support,
+    half_blocks: Option<u32>,
+    sync_output: Support,
+    mouse: Support,
+    sgr_mouse: Support,
+    focus: Support,
+    sixel: Support,
+    kitty: Support,
+    /// width and height of a character cell in pixels
+    cell_size: Option<(u32, u32)>,
+    /// width and height of the text area in pixels
+    window_size: Option<(u32, u32)>,
+}
+
+impl std::fmt::Display for Support {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Support::Yes     => "yes".fmt(f),
+            Support::No      => "no".fmt(f),
+            Support::Unknown => "unknown".fmt(f),
+        }
+    }
+}
+
+fn report(label: &str, value: impl std::fmt::Display) {
+    println!("{label:<28} {value}");
+}
+
+#[cfg(not(windows))]
+fn isatty(fd: libc::c_int) -> bool {
+    unsafe { libc::isatty(fd) != 0 }
+}
+
+#[cfg(windows)]
+pub fn run() -> Result<(), Error> {
+    println!("Terminal probing is not supported on Windows.");
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn run() -> Result<(), Error> {
+    println!("color-cycle doctor");
+    println!("==================");
+    println!();
+
+    let stdin_tty = isatty(libc::STDIN_FILENO);
+    let stdout_tty = isatty(libc::STDOUT_FILENO);
+    let env = |name: &str| std::env::var(name).unwrap_or_else(|_| "(not set)".to_owned());
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+
+    report("stdin is a terminal", if stdin_tty { Support::Yes } else { Support::No });
+    report("stdout is a terminal", if stdout_tty { Support::Yes } else { Support::No });
+    report("TERM", env("TERM"));
+    report("COLORTERM", env("COLORTERM"));
+    if let Some((columns, rows)) = term_size::dimensions() {
+        report("Terminal size", format!("{columns}x{rows} cells, {columns}x{} pixels", rows * 2));
+    } else {
+        report("Terminal size", "unknown");
+    }
+
+    if !stdin_tty || !stdout_tty {
+        println!();
+        println!("stdin and stdout need to be a terminal for probing the terminal features.");
+        return Ok(());
+    }
+
+    let mut prober = Prober::new()?;
+
+    let da1 = prober.query("")?;
+    let da1_attrs = da1.as_deref().and_then(parse_da1);
+
+    // Without an answer to DA1 every probe would just run into the timeout.
+    let features = if da1.is_some() {
+        prober.probe(&colorterm, da1_attrs.as_deref())?
+    } else {
+        Features::default()
+    };
+
+    drop(prober);
+
+    match &da1 {
+        Some(da1) => report("Device attributes", escape(da1)),
+        None => report("Device attributes", "no answer"),
+    }
+    match features.round_trip {
+        Some(rtt) => report("Round-trip time", format!("{} ms ({} link)", rtt.as_millis(), Link::from_rtt(rtt))),
+        None => report("Round-trip time", "unknown"),
+    }
+    report("24-bit color", features.truecolor);
+    report("Half-block glyphs (▀▄█)", match features.half_blocks {
+        Some(3) => "yes".to_owned(),
+        Some(width) => format!("no, rendered {width} cells wide instead of 3"),
+        None => "unknown".to_owned(),
+    });
+    report("Synchronized output", features.sync_output);
+    report("Mouse reporting", features.mouse);
+    report("SGR mouse reporting", features.sgr_mouse);
+    report("Focus events", features.focus);
+    report("Sixel graphics", features.sixel);
+    report("Kitty graphics", features.kitty);
+    report("Cell size", match features.cell_size {
+        Some((width, height)) => format!("{width}x{height} pixels"),
+        None => "unknown".to_owned(),
+    });
+    report("Window size", match features.window_size {
+        Some((width, height)) => format!("{width}x{height} pixels"),
+        None => "unknown".to_owned(),
+    });
+
+    println!();
+    println!("Renderer: 24-bit ANSI colors with half-block characters");
+
+    if features.truecolor == Support::No {
+        println!("Warning: This terminal doesn't seem to support 24-bit colors. Colors will be wrong.");
+    }
+
+    if let Some(width) = features.half_blocks && width != 3 {
+        println!("Warning: Half-block characters are not rendered single width. The image will be distorted.");
+    }
+
+    if let Some(rtt) = features.round_trip {
+        let link = Link::from_rtt(rtt);
+        let mut defaults = Vec::new();
+        if let Some(fps) = link.fps() {
+            defaults.push(format!("--fps {fps}"));
+        }
+        if link.sync_output() {
+            defaults.push("--sync-output".to_owned());
+        }
+        if let Some(max_bandwidth) = link.max_bandwidth() {
+            defaults.push(format!("--max-bandwidth {max_bandwidth}"));
+        }
+        if !defaults.is_empty() {
+            println!("Slow connection: defaults to {}", defaults.join(" "));
+        }
+    }
+
+    Ok(())
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+fn escape(data: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &byte in data {
+        match byte {
+            0x1B => escaped.push_str("ESC"),
+            0x20..=0x7E => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\x{byte:02X}")),
+        }
+    }
+    escaped
+}
+
+/// Parses the attributes out of `CSI ? Ps ; ... c`.
+pub fn parse_da1(answer: &[u8]) -> Option<Vec<u32>> {
+    let start = answer.windows(3).rposition(|window| window == b"\x1B[?")?;
+    let answer = &answer[start + 3..];
+    let end = answer.iter().position(|&byte| byte == b'c')?;
+    let attrs = std::str::from_utf8(&answer[..end]).ok()?;
+
+    Some(attrs.split(';').filter_map(|attr| attr.parse().ok()).collect())
+}
+
+/// Parses the XTWINOPS report `CSI kind ; height ; width t` and returns
+/// width and height.
+pub fn parse_window_report(answer: &[u8], kind: u32) -> Option<(u32, u32)> {
+    let prefix = format!("\x1B[{kind};");
+    let prefix = prefix.as_bytes();
+    let start = answer.windows(prefix.len()).position(|window| window == prefix)?;
+    let answer = &answer[start + prefix.len()..];
+    let end = answer.iter().position(|&byte| byte == b't')?;
+    let size = std::str::from_utf8(&answer[..end]).ok()?;
+    let (height, width) = size.split_once(';')?;
+
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Parses `CSI row ; column R`.
+pub fn parse_cursor_position(answer: &[u8]) -> Option<(u32, u32)> {
+    let start = answer.windows(2).position(|window| window == b"\x1B[")?;
+    let answer = &answer[start + 2..];
+    let end = answer.iter().position(|&byte| byte == b'R')?;
+    let position = std::str::from_utf8(&answer[..end]).ok()?;
+    let (row, column) = position.split_once(';')?;
+
+    Some((row.parse().ok()?, column.parse().ok()?))
+}
+
+#[cfg(not(windows))]
+struct Prober {
+    stdin: std::io::StdinLock<'static>,
+    stdout: std::io::StdoutLock<'static>,
+    orig_ttystate: libc::termios,
+}
+
+#[cfg(not(windows))]
+impl Prober {
+    fn new() -> Result<Self, Error> {
+        unsafe {
+            let mut ttystate = std::mem::MaybeUninit::<libc::termios>::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, ttystate.as_mut_ptr()) == -1 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let orig_ttystate = ttystate.assume_init();
+            let mut ttystate = orig_ttystate;
+
+            ttystate.c_lflag &= !(libc::ICANON | libc::ECHO);
+            ttystate.c_cc[libc::VMIN] = 0;
+            ttystate.c_cc[libc::VTIME] = 0;
+
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &ttystate) == -1 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+
+            Ok(Self {
+                stdin: std::io::stdin().lock(),
+                stdout: std::io::stdout().lock(),
+                orig_ttystate,
+            })
+        }
+    }
+
+    /// Sends `probe` followed by DA1 and returns everything the terminal
+    /// answered, including the DA1 answer. Returns `None` on timeout.
+    fn query(&mut self, probe: &str) -> Result<Option<Vec<u8>>, Error> {
+        write!(self.stdout, "{probe}{DA1}")?;
+        self.stdout.flush()?;
+
+        let start = Instant::now();
+        let mut answer = Vec::new();
+        let mut buf = [0u8; 256];
+
+        while start.elapsed() < PROBE_TIMEOUT {
+            let count = match self.stdin.read(&mut buf) {
+                Ok(count) => count,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock || err.kind() == std::io::ErrorKind::Interrupted => 0,
+                Err(err) => return Err(err.into()),
+            };
+
+            if count == 0 {
+                std::thread::sleep(Duration::from_millis(5));
+                continue;
+            }
+
+            answer.extend_from_slice(&buf[..count]);
+            if parse_da1(&answer).is_some() {
+                return Ok(Some(answer));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn probe(&mut self, colorterm: &str, da1_attrs: Option<&[u32]>) -> Result<Features, Error> {
+        let truecolor = match self.query("\x1B[38;2;1;2;3m\x1BP$qm\x1B\\\x1B[0m")? {
+            Some(answer) => {
+                let answer = String::from_utf8_lossy(&answer).into_owned();
+                if answer.contains("1:2:3") || answer.contains("1;2;3") {
+                    Support::Yes
+                } else if answer.contains("\x1BP") {
+                    Support::No
+                } else if colorterm == "truecolor" || colorterm == "24bit" {
+                    Support::Yes
+                } else {
+                    Support::Unknown
+                }
+            }
+            None => Support::Unknown,
+        };
+
+        let sixel = match da1_attrs {
+            Some(attrs) => if attrs.contains(&4) { Support::Yes } else { Support::No },
+            None => Support::Unknown,
+        };
+
+        let kitty = match self.query("\x1B_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1B\\")? {
+            Some(answer) => if contains(&answer, b"_Gi=31;OK") { Support::Yes } else { Support::No },
+            None => Support::Unknown,
+        };
+
+        Ok(Features {
+            round_trip: self.round_trip()?,
+            truecolor,
+            half_blocks: self.glyph_width("▀▄█")?,
+            sync_output: self.mode(2026)?,
+            mouse: self.mode(1000)?,
+            sgr_mouse: self.mode(1006)?,
+            focus: self.mode(1004)?,
+            sixel,
+            kitty,
+            cell_size: self.window_report(16, 6)?,
+            window_size: self.window_report(14, 4)?,
+        })
+    }
+
+    /// Sends the XTWINOPS query `CSI query t`, which is answered with
+    /// `CSI kind ; height ; width t`.
+    fn window_report(&mut self, query: u32, kind: u32) -> Result<Option<(u32, u32)>, Error> {
+        let Some(answer) = self.query(&format!("\x1B[{query}t"))? else {
+            return Ok(None);
+        };
+
+        Ok(parse_window_report(&answer, kind).filter(|&(width, height)| width > 0 && height > 0))
+    }
+
+    /// Measures how long a cursor position query takes to be answered.
+    fn round_trip(&mut self) -> Result<Option<Duration>, Error> {
+        let start = Instant::now();
+        let Some(answer) = self.query(CURSOR_POSITION_QUERY)? else {
+            return Ok(None);
+        };
+        let elapsed = start.elapsed();
+
+        Ok(parse_cursor_position(&answer).map(|_| elapsed))
+    }
+
+    /// Queries a DEC private mode via DECRQM.
+    fn mode(&mut self, mode: u32) -> Result<Support, Error> {
+        let Some(answer) = self.query(&format!("\x1B[?{mode}$p"))? else {
+            return Ok(Support::Unknown);
+        };
+
+        let prefix = format!("\x1B[?{mode};");
+        let prefix = prefix.as_bytes();
+        let Some(start) = answer.windows(prefix.len()).position(|window| window == prefix) else {
+            // DECRQM itself is not supported
+            return Ok(Support::Unknown);
+        };
+
+        Ok(match answer.get(start + prefix.len()) {
+            Some(b'1') | Some(b'2') | Some(b'3') => Support::Yes,
+            Some(b'0') => Support::Unknown,
+            _ => Support::No,
+        })
+    }
+
+    /// Prints `text` and measures how far the cursor moved.
+    fn glyph_width(&mut self, text: &str) -> Result<Option<u32>, Error> {
+        let Some(before) = self.query("\r\x1B[6n")? else {
+            return Ok(None);
+        };
+        let Some(after) = self.query(&format!("{text}\x1B[6n"))? else {
+            return Ok(None);
+        };
+        write!(self.stdout, "\r\x1B[2K")?;
+        self.stdout.flush()?;
+
+        let (Some((_, column_before)), Some((_, column_after))) = (parse_cursor_position(&before), parse_cursor_position(&after)) else {
+            return Ok(None);
+        };
+
+        Ok(Some(column_after.saturating_sub(column_before)))
+    }
+}
+
+#[cfg(not(windows))]
+impl Drop for Prober {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.orig_ttystate);
+        }
+    }
+}
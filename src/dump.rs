@@ -0,0 +1,133 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::image::RgbImage;
+
+/// Writer that passes everything through to the inner writer and, while
+/// capturing, also keeps a copy of it.
+#[derive(Debug)]
+pub struct Tee<W> {
+    inner: W,
+    capture: Option<Vec<u8>>,
+}
+
+impl<W> Tee<W> {
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self { inner, capture: None }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Starts a new capture, discarding any previous one.
+    #[inline]
+    pub fn start_capture(&mut self) {
+        self.capture = Some(Vec::new());
+    }
+
+    /// Ends the current capture and returns everything written since
+    /// [`Tee::start_capture()`].
+    #[inline]
+    pub fn take_capture(&mut self) -> Option<Vec<u8>> {
+        self.capture.take()
+    }
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        if let Some(capture) = &mut self.capture {
+            capture.extend_from_slice(&buf[..count]);
+        }
+        Ok(count)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes the output of rendered frames to numbered files for bug reports.
+///
+/// For every frame `frame-NNNNNN.ansi` contains the exact bytes written to
+/// the terminal, `frame-NNNNNN-prev.ppm` the frame before (what the
+/// differential renderer compared against), and `frame-NNNNNN.ppm` the
+/// frame itself.
+#[derive(Debug)]
+pub struct FrameDump {
+    dir: PathBuf,
+    index: u32,
+    count: u32,
+}
+
+impl FrameDump {
+    pub fn new(dir: impl Into<PathBuf>, count: u32) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir, index: 0, count })
+    }
+
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of frames written so far.
+    #[inline]
+    pub fn written(&self) -> u32 {
+        self.index
+    }
+
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.index >= self.count
+    }
+
+    pub fn write(&mut self, payload: &[u8], prev_frame: &RgbImage, frame: &RgbImage) -> std::io::Result<()> {
+        if self.is_done() {
+            return Ok(());
+        }
+
+        let index = self.index + 1;
+        std::fs::write(self.dir.join(format!("frame-{index:06}.ansi")), payload)?;
+        write_ppm(&self.dir.join(format!("frame-{index:06}-prev.ppm")), prev_frame)?;
+        write_ppm(&self.dir.join(format!("frame-{index:06}.ppm")), frame)?;
+        self.index = index;
+
+        Ok(())
+    }
+}
+
+/// Writes the image as binary PPM (P6).
+pub fn write_ppm(path: &Path, image: &RgbImage) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let (width, height) = image.size();
+    write!(writer, "P6\n{width} {height}\n255\n")?;
+    for y in 0..height {
+        for x in 0..width {
+            writer.write_all(&image.get_pixel(x, y).0)?;
+        }
+    }
+    writer.flush()
+}
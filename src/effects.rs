@@ -0,0 +1,215 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use std::f64::consts::PI;
+use std::time::Instant;
+
+use crate::color::{blend, Rgb};
+use crate::image::{RgbImage, Weather};
+use crate::rng::Rng;
+
+const HOUR: f64 = 60.0 * 60.0 * 1000.0;
+const DAWN: f64 = 6.0 * HOUR;
+
+const RAIN_COLOR: Rgb = Rgb([170, 190, 220]);
+const RAIN_BLEND: f64 = 0.45;
+const RAIN_LENGTH: u32 = 3;
+/// pixels per second
+const RAIN_SPEED: f64 = 90.0;
+/// horizontal movement per pixel of falling
+const RAIN_SLANT: f64 = -0.25;
+/// one rain drop per this many pixels
+const RAIN_DENSITY: u32 = 120;
+
+const SNOW_COLOR: Rgb = Rgb([245, 248, 255]);
+const SNOW_BLEND: f64 = 0.8;
+/// pixels per second
+const SNOW_SPEED: f64 = 12.0;
+/// pixels per second of sideways drift
+const SNOW_DRIFT: f64 = 4.0;
+/// one snow flake per this many pixels
+const SNOW_DENSITY: u32 = 90;
+
+const FOG_COLOR: Rgb = Rgb([200, 204, 212]);
+const FOG_BLEND: f64 = 0.6;
+
+/// Longest time step of a particle update, so particles don't jump after a
+/// stall.
+const MAX_TIME_STEP: f64 = 0.25;
+
+#[derive(Debug, Clone)]
+struct Particle {
+    x: f64,
+    y: f64,
+    /// factor of the speed of the particle, for some depth
+    speed: f64,
+    /// offset of the sideways drift of snow flakes
+    phase: f64,
+}
+
+/// Renders procedural weather into frames. Particles live in frame (not
+/// image) coordinates and are re-spawned whenever the frame size changes.
+#[derive(Debug, Clone)]
+pub struct WeatherEffect {
+    weather: Weather,
+    rng: Rng,
+    particles: Vec<Particle>,
+    size: (u32, u32),
+    last_update: Option<Instant>,
+}
+
+impl WeatherEffect {
+    #[inline]
+    pub fn new(weather: Weather, rng: Rng) -> Self {
+        Self {
+            weather,
+            rng,
+            particles: Vec::new(),
+            size: (0, 0),
+            last_update: None,
+        }
+    }
+
+    #[inline]
+    pub fn weather(&self) -> Weather {
+        self.weather
+    }
+
+    /// Advances the particles to `now` and draws the weather into `frame`.
+    /// `time_of_day` (in milliseconds since midnight) makes fog thicker at
+    /// dawn and rain and snow fainter at night.
+    pub fn draw(&mut self, frame: &mut RgbImage, time_of_day: u64, now: Instant) {
+        if frame.size() != self.size {
+            self.spawn(frame.size());
+        }
+
+        let dt = self.last_update.map_or(0.0, |last| (now - last).as_secs_f64().min(MAX_TIME_STEP));
+        self.last_update = Some(now);
+
+        let time_of_day = time_of_day as f64;
+        match self.weather {
+            Weather::Rain => self.draw_rain(frame, dt, daylight(time_of_day)),
+            Weather::Snow => self.draw_snow(frame, dt, daylight(time_of_day)),
+            Weather::Fog  => draw_fog(frame, 0.35 + 0.65 * dawn(time_of_day)),
+        }
+    }
+
+    fn spawn(&mut self, size: (u32, u32)) {
+        let (width, height) = size;
+        let density = match self.weather {
+            Weather::Rain => RAIN_DENSITY,
+            Weather::Snow => SNOW_DENSITY,
+            Weather::Fog  => 0,
+        };
+        let count = if density == 0 { 0 } else { (width as usize * height as usize) / density as usize };
+
+        self.size = size;
+        self.particles.clear();
+        for _ in 0..count {
+            self.particles.push(Particle {
+                x: self.rng.next_f64() * width as f64,
+                y: self.rng.next_f64() * height as f64,
+                speed: 0.6 + 0.4 * self.rng.next_f64(),
+                phase: self.rng.next_f64() * 2.0 * PI,
+            });
+        }
+    }
+
+    fn respawn(rng: &mut Rng, particle: &mut Particle, width: u32) {
+        particle.x = rng.next_f64() * width as f64;
+        particle.y = -(rng.next_f64() * RAIN_LENGTH as f64);
+    }
+
+    fn draw_rain(&mut self, frame: &mut RgbImage, dt: f64, daylight: f64) {
+        let (width, height) = frame.size();
+        let amount = RAIN_BLEND * (0.5 + 0.5 * daylight);
+        for particle in &mut self.particles {
+            let distance = RAIN_SPEED * particle.speed * dt;
+            particle.y += distance;
+            particle.x += distance * RAIN_SLANT;
+            if particle.y - RAIN_LENGTH as f64 >= height as f64 {
+                Self::respawn(&mut self.rng, particle, width);
+            }
+            particle.x = particle.x.rem_euclid(width as f64);
+
+            for index in 0..RAIN_LENGTH {
+                let x = particle.x - index as f64 * RAIN_SLANT;
+                let y = particle.y - index as f64;
+                blend_pixel(frame, x, y, RAIN_COLOR, amount);
+            }
+        }
+    }
+
+    fn draw_snow(&mut self, frame: &mut RgbImage, dt: f64, daylight: f64) {
+        let (width, height) = frame.size();
+        let amount = SNOW_BLEND * (0.5 + 0.5 * daylight);
+        for particle in &mut self.particles {
+            particle.phase += dt * particle.speed;
+            particle.y += SNOW_SPEED * particle.speed * dt;
+            particle.x += SNOW_DRIFT * particle.phase.sin() * dt;
+            if particle.y >= height as f64 {
+                Self::respawn(&mut self.rng, particle, width);
+            }
+            particle.x = particle.x.rem_euclid(width as f64);
+
+            blend_pixel(frame, particle.x, particle.y, SNOW_COLOR, amount);
+        }
+    }
+}
+
+/// Fog that gets thicker towards the bottom of the frame.
+fn draw_fog(frame: &mut RgbImage, density: f64) {
+    let (width, height) = frame.size();
+    if height == 0 {
+        return;
+    }
+
+    for y in 0..height {
+        let amount = FOG_BLEND * density * (y as f64 / height as f64).powf(1.5);
+        for x in 0..width {
+            let color = frame.get_pixel(x, y);
+            frame.set_pixel(x, y, blend(color, FOG_COLOR, amount));
+        }
+    }
+}
+
+#[inline]
+fn blend_pixel(frame: &mut RgbImage, x: f64, y: f64, color: Rgb, amount: f64) {
+    if x < 0.0 || y < 0.0 {
+        return;
+    }
+
+    let (x, y) = (x as u32, y as u32);
+    if x < frame.width() && y < frame.height() {
+        let old = frame.get_pixel(x, y);
+        frame.set_pixel(x, y, blend(old, color, amount));
+    }
+}
+
+/// 1.0 at noon, 0.0 from 18:00 to 6:00.
+#[inline]
+fn daylight(time_of_day: f64) -> f64 {
+    (PI * (time_of_day - DAWN) / (12.0 * HOUR)).sin().max(0.0)
+}
+
+/// 1.0 at 6:00, falling to 0.0 three hours before and after.
+#[inline]
+fn dawn(time_of_day: f64) -> f64 {
+    let distance = (time_of_day - DAWN).abs();
+    let distance = distance.min(24.0 * HOUR - distance);
+    (1.0 - distance / (3.0 * HOUR)).max(0.0)
+}
@@ -16,28 +16,58 @@
 
 use std::fmt::Display;
 
+use crate::diagnostics::Code;
+
 #[derive(Debug)]
 pub struct Error {
+    code: Option<Code>,
     message: String,
-    source: Option<Box<dyn std::error::Error>>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
 }
 
 impl Error {
     pub fn new<S>(message: S) -> Self
     where S: Into<String> {
         Self {
+            code: None,
             message: message.into(),
             source: None,
         }
     }
 
-    pub fn with_cause<S>(message: S, source: Box<dyn std::error::Error>) -> Self
+    /// The code of the error is taken from `source`, if it has one.
+    pub fn with_cause<S>(message: S, source: Box<dyn std::error::Error + Send + Sync>) -> Self
     where S: Into<String> {
+        let code = if let Some(err) = source.downcast_ref::<Error>() {
+            err.code
+        } else if let Some(err) = source.downcast_ref::<crate::ilbm::Error>() {
+            err.code()
+        } else if source.is::<std::io::Error>() {
+            Some(Code::Io)
+        } else if source.is::<serde_json::Error>() {
+            Some(Code::InvalidJson)
+        } else {
+            None
+        };
+
         Self {
+            code,
             message: message.into(),
             source: Some(source),
         }
     }
+
+    #[inline]
+    pub fn with_code(mut self, code: Code) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Short code of the error for `--explain`, if there is one.
+    #[inline]
+    pub fn code(&self) -> Option<Code> {
+        self.code
+    }
 }
 
 impl Display for Error {
@@ -54,7 +84,7 @@ impl Display for Error {
 impl std::error::Error for Error {
     #[inline]
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        self.source.as_deref()
+        self.source.as_deref().map(|source| source as _)
     }
 }
 
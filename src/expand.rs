@@ -0,0 +1,230 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Expands directories and glob patterns given on the command line into the
+//! files they stand for.
+//!
+//! Shells on Windows don't expand glob patterns, so this is done here for
+//! arguments that contain `*`, `?`, or `[` and don't name an existing file.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::Error;
+use crate::source::is_url;
+
+/// Extensions of the files collected from directories, compared ignoring
+/// case.
+pub const IMAGE_EXTENSIONS: &[&str] = &["json", "lbm", "ilbm"];
+
+/// The files `path` stands for: the image files in a directory and all its
+/// subdirectories, the files matching a glob pattern, or else just `path`.
+/// Both are sorted by path. Hidden files and directories are skipped.
+pub fn expand(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    if path.to_str().is_some_and(is_url) {
+        return Ok(vec![path.to_owned()]);
+    }
+
+    if path.is_dir() {
+        let mut files = Vec::new();
+        collect_images(path, &mut files)?;
+        if files.is_empty() {
+            return Err(Error::new(format!("no {} files in directory", IMAGE_EXTENSIONS.join("/"))));
+        }
+        files.sort();
+        return Ok(files);
+    }
+
+    if !path.exists() && is_pattern(&path.to_string_lossy()) {
+        let mut files = glob(path)?;
+        if !files.is_empty() {
+            files.sort();
+            return Ok(files);
+        }
+    }
+
+    Ok(vec![path.to_owned()])
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.iter().any(|image_ext| ext.eq_ignore_ascii_case(image_ext)))
+}
+
+#[inline]
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+fn collect_images(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_str().is_none_or(is_hidden) {
+            continue;
+        }
+        let path = entry.path();
+        // symbolic links to directories aren't followed, they could loop
+        if entry.file_type()?.is_dir() {
+            collect_images(&path, files)?;
+        } else if is_image(&path) && path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+fn is_pattern(value: &str) -> bool {
+    value.contains(['*', '?', '['])
+}
+
+/// Matches the components of `pattern` one by one against the directory
+/// tree. Components without wildcards are taken as they are.
+fn glob(pattern: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let Component::Normal(name) = component else {
+            for path in &mut paths {
+                path.push(component);
+            }
+            continue;
+        };
+
+        let Some(name) = name.to_str().filter(|name| is_pattern(name)) else {
+            for path in &mut paths {
+                path.push(name);
+            }
+            continue;
+        };
+
+        let mut matches = Vec::new();
+        for dir in &paths {
+            let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir.as_path() };
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries {
+                let entry = entry?;
+                let Some(entry_name) = entry.file_name().to_str().map(str::to_owned) else {
+                    continue;
+                };
+                // like shells, wildcards only match hidden files if the pattern starts with a dot
+                if is_hidden(&entry_name) && !is_hidden(name) {
+                    continue;
+                }
+                if glob_match(name, &entry_name) {
+                    matches.push(if dir == Path::new(".") && !pattern.starts_with(".") {
+                        PathBuf::from(entry_name)
+                    } else {
+                        dir.join(entry_name)
+                    });
+                }
+            }
+        }
+        paths = matches;
+    }
+    paths.retain(|path| path.exists());
+    Ok(paths)
+}
+
+/// Whether `name` matches the glob `pattern`: `*` matches any number of
+/// characters, `?` one character, `[abc]` or `[a-z]` one of the given
+/// characters, and `[!abc]` any other character. Ignores case on Windows.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let fold = |ch: char| if cfg!(windows) { ch.to_ascii_lowercase() } else { ch };
+    let pattern: Vec<char> = pattern.chars().map(fold).collect();
+    let name: Vec<char> = name.chars().map(fold).collect();
+
+    // backtracking to the last `*`, which is enough without `**`
+    let mut pattern_index = 0;
+    let mut name_index = 0;
+    let mut star: Option<(usize, usize)> = None;
+    while name_index < name.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                star = Some((pattern_index, name_index));
+                pattern_index += 1;
+                continue;
+            }
+            Some('?') => {
+                pattern_index += 1;
+                name_index += 1;
+                continue;
+            }
+            Some('[') => {
+                if let Some((matched, len)) = match_class(&pattern[pattern_index..], name[name_index]) {
+                    if matched {
+                        pattern_index += len;
+                        name_index += 1;
+                        continue;
+                    }
+                } else if name[name_index] == '[' {
+                    // unclosed bracket, taken literally
+                    pattern_index += 1;
+                    name_index += 1;
+                    continue;
+                }
+            }
+            Some(&ch) if ch == name[name_index] => {
+                pattern_index += 1;
+                name_index += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some((star_index, star_name_index)) = star else {
+            return false;
+        };
+        // let the `*` match one more character
+        pattern_index = star_index + 1;
+        name_index = star_name_index + 1;
+        star = Some((star_index, name_index));
+    }
+
+    pattern[pattern_index..].iter().all(|&ch| ch == '*')
+}
+
+/// Matches `ch` against the character class at the start of `class`.
+/// Returns whether it matched and the length of the class, or `None` if
+/// the class isn't closed.
+fn match_class(class: &[char], ch: char) -> Option<(bool, usize)> {
+    let mut index = 1;
+    let negated = matches!(class.get(index), Some('!' | '^'));
+    if negated {
+        index += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let &start = class.get(index)?;
+        if start == ']' && !first {
+            return Some((matched != negated, index + 1));
+        }
+        first = false;
+
+        if class.get(index + 1) == Some(&'-') && class.get(index + 2).is_some_and(|&end| end != ']') {
+            let end = class[index + 2];
+            matched |= (start..=end).contains(&ch);
+            index += 3;
+        } else {
+            matched |= start == ch;
+            index += 1;
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::image::LivingWorld;
+use crate::image::format::{self, Decoded};
+
+pub use crate::image::format::FileFormat;
+
+/// Everything known about a loaded file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileInfo {
+    /// Path or URL.
+    pub source: String,
+    /// Size of the file in bytes.
+    pub size: usize,
+    pub format: FileFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Number of distinct palette indices used by the pixels.
+    pub colors_used: usize,
+    pub cycle_count: usize,
+    /// Number of time of day palettes.
+    pub palette_count: usize,
+    pub timeline_event_count: usize,
+    pub layer_count: usize,
+    pub sprite_count: usize,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub copyright: Option<String>,
+    pub annotations: Vec<String>,
+    /// Month of the scene, from a scene index.
+    pub month: Option<String>,
+    /// Ambient sound of the scene, from a scene index.
+    pub sound: Option<String>,
+    /// Time it took to read and decode the file.
+    pub load_time: Duration,
+}
+
+impl FileInfo {
+    /// Decodes a file like [`LivingWorld::from_bytes()`] and collects its
+    /// metadata. `load_time` is left at zero for the caller to fill in.
+    pub fn decode(source: impl Into<String>, data: &[u8]) -> Result<(LivingWorld, FileInfo), Error> {
+        let Decoded { living_world, format, title, author, copyright, annotations } = format::builtin().decode(data)?;
+
+        let base = living_world.base();
+        let usage = base.indexed_image().index_usage();
+
+        let info = FileInfo {
+            source: source.into(),
+            size: data.len(),
+            format,
+            width: base.width(),
+            height: base.height(),
+            colors_used: usage.iter().filter(|&&uses| uses > 0).count(),
+            cycle_count: base.cycles().len(),
+            palette_count: living_world.palettes().len(),
+            timeline_event_count: living_world.timeline().len(),
+            layer_count: living_world.layers().len(),
+            sprite_count: living_world.sprites().len(),
+            title,
+            author,
+            copyright,
+            annotations,
+            month: None,
+            sound: None,
+            load_time: Duration::ZERO,
+        };
+
+        Ok((living_world, info))
+    }
+}
@@ -0,0 +1,128 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A tiny 5x7 pixel font for text drawn into images, like title cards.
+//! Only ASCII letters, digits, and some punctuation have glyphs. Lower case
+//! letters are drawn as upper case and everything else as `?`.
+
+use crate::color::Rgb;
+use crate::image::RgbImage;
+
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/// Empty columns between two glyphs.
+const SPACING: u32 = 1;
+
+/// One byte per row, the lowest 5 bits are the pixels (MSB first).
+type Glyph = [u8; GLYPH_HEIGHT as usize];
+
+const GLYPHS: &[(char, Glyph)] = &[
+    (' ',  [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('!',  [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04]),
+    ('&',  [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D]),
+    ('\'', [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00]),
+    ('(',  [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')',  [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('+',  [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    (',',  [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    ('-',  [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('.',  [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    ('/',  [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('0',  [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1',  [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2',  [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3',  [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4',  [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5',  [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6',  [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7',  [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8',  [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9',  [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    (':',  [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    ('?',  [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+    ('A',  [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('B',  [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C',  [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D',  [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E',  [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F',  [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G',  [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H',  [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I',  [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J',  [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K',  [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L',  [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M',  [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N',  [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O',  [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P',  [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q',  [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R',  [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S',  [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T',  [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U',  [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V',  [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W',  [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X',  [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y',  [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z',  [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('_',  [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F]),
+];
+
+fn glyph(ch: char) -> &'static Glyph {
+    let ch = ch.to_ascii_uppercase();
+    let index = GLYPHS.binary_search_by_key(&ch, |&(glyph_ch, _)| glyph_ch)
+        .or_else(|_| GLYPHS.binary_search_by_key(&'?', |&(glyph_ch, _)| glyph_ch))
+        .unwrap_or(0);
+    &GLYPHS[index].1
+}
+
+/// Width in pixels of `text` drawn with `scale`.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    let count = text.chars().count() as u32;
+    if count == 0 {
+        return 0;
+    }
+    (count * (GLYPH_WIDTH + SPACING) - SPACING) * scale
+}
+
+/// Draws `text` with its top left corner at `x`/`y`, each font pixel
+/// `scale` by `scale` image pixels. Pixels outside of `image` are skipped.
+pub fn draw_text(image: &mut RgbImage, x: i64, y: i64, text: &str, scale: u32, color: Rgb) {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let scale = scale as i64;
+    let advance = (GLYPH_WIDTH + SPACING) as i64 * scale;
+
+    for (index, ch) in text.chars().enumerate() {
+        let glyph_x = x + index as i64 * advance;
+        for (row, bits) in glyph(ch).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH as i64 {
+                if bits & (1 << (GLYPH_WIDTH as i64 - 1 - column)) == 0 {
+                    continue;
+                }
+                let pixel_x = glyph_x + column * scale;
+                let pixel_y = y + row as i64 * scale;
+                for dy in pixel_y.max(0)..(pixel_y + scale).min(height) {
+                    for dx in pixel_x.max(0)..(pixel_x + scale).min(width) {
+                        image.set_pixel(dx as u32, dy as u32, color);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,302 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Disk cache for the rendered frames of exports, so that exporting the
+//! same scenes again (e.g. with another encoder) skips rendering them.
+//!
+//! Every source file gets a directory of its own that records the size and
+//! modification time of the file. When the file changes, all its frames are
+//! dropped. When the cache grows over its size limit, the least recently
+//! used frames are deleted.
+//!
+//! Several instances can share a cache directory: files are only ever
+//! replaced by renaming a completely written temporary file, dropping and
+//! trimming happen under an exclusive lock of the cache, and every frame
+//! records the version of the source file it was rendered from.
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::color::Rgb;
+use crate::image::RgbImage;
+
+const FRAME_EXT: &str = "rgb";
+const TEMP_EXT: &str = "tmp";
+const SOURCE_FILE: &str = "source";
+const LOCK_FILE: &str = "lock";
+
+/// Start of every cached frame, changed whenever their format changes.
+const FRAME_MAGIC: &[u8; 4] = b"CCF1";
+/// Magic, version of the source file, width and height.
+const FRAME_HEADER_SIZE: usize = 4 + 8 + 4 + 4;
+
+/// Makes the names of temporary files unique within this process.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Share of the size limit the cache is trimmed down to when it is full, so
+/// it isn't trimmed again for every new frame.
+const TRIM_TARGET: f64 = 0.9;
+
+#[derive(Debug)]
+pub struct FrameCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    total_bytes: u64,
+}
+
+/// The directory of the frames of one source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceCache {
+    dir: PathBuf,
+    /// hash of the size and modification time of the file
+    version: u64,
+}
+
+impl FrameCache {
+    /// Opens (or creates) the cache in `dir`. `max_bytes` is the size limit
+    /// of all cached frames together.
+    pub fn open(dir: impl Into<PathBuf>, max_bytes: u64) -> std::io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let total_bytes = frame_files(&dir)?.iter().map(|frame| frame.size).sum();
+        Ok(Self { dir, max_bytes, total_bytes })
+    }
+
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    #[inline]
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    #[inline]
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// The cache directory for the frames of the file at `path`. Cached
+    /// frames are dropped if the file changed since they were stored.
+    /// Returns `None` if `path` isn't a local file.
+    pub fn source(&mut self, path: &Path) -> Option<SourceCache> {
+        let path = path.canonicalize().ok()?;
+        let metadata = fs::metadata(&path).ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        let modified = metadata.modified().ok()
+            .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .unwrap_or_default();
+
+        let path_str = path.to_string_lossy();
+        let fingerprint = format!("{}\n{}\n{}.{:09}\n", path_str, metadata.len(), modified.as_secs(), modified.subsec_nanos());
+        let dir = self.dir.join(format!("{:016x}", hash(path_str.as_bytes())));
+        let version = hash(fingerprint.as_bytes());
+
+        if fs::read_to_string(dir.join(SOURCE_FILE)).ok().as_deref() != Some(fingerprint.as_str()) {
+            let _lock = self.lock();
+
+            // another instance might have done it while waiting for the lock
+            if fs::read_to_string(dir.join(SOURCE_FILE)).ok().as_deref() != Some(fingerprint.as_str()) {
+                if dir.exists() {
+                    let dropped: u64 = frame_files(&dir).ok()?.iter().map(|frame| frame.size).sum();
+                    fs::remove_dir_all(&dir).ok()?;
+                    self.total_bytes = self.total_bytes.saturating_sub(dropped);
+                }
+                fs::create_dir_all(&dir).ok()?;
+                write_atomic(&dir.join(SOURCE_FILE), fingerprint.as_bytes()).ok()?;
+            }
+        }
+
+        Some(SourceCache { dir, version })
+    }
+
+    /// Locks the cache against changes by other instances until the
+    /// returned file is dropped. Without write access to the cache there is
+    /// nothing to lock and `None` is returned.
+    fn lock(&self) -> Option<File> {
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.dir.join(LOCK_FILE))
+            .ok()?;
+        file.lock().ok()?;
+        Some(file)
+    }
+
+    /// Reads the frame stored under `key` into `out`. Returns `false` if
+    /// there is no such frame.
+    pub fn load(&mut self, source: &SourceCache, key: u64, out: &mut RgbImage) -> bool {
+        let path = source.frame_path(key);
+        let Ok(mut file) = File::open(&path) else {
+            return false;
+        };
+
+        let mut header = [0u8; FRAME_HEADER_SIZE];
+        if file.read_exact(&mut header).is_err() {
+            return false;
+        }
+        let (magic, rest) = header.split_at(4);
+        let (version, rest) = rest.split_at(8);
+        let (width, height) = rest.split_at(4);
+        // frames of another format or rendered from an older version of the
+        // file (stored by an instance that still had it open)
+        if magic != FRAME_MAGIC || version != source.version.to_le_bytes() {
+            return false;
+        }
+        let width = u32::from_le_bytes([width[0], width[1], width[2], width[3]]);
+        let height = u32::from_le_bytes([height[0], height[1], height[2], height[3]]);
+
+        let mut data = Vec::new();
+        if file.read_to_end(&mut data).is_err() || data.len() != width as usize * height as usize * 3 {
+            return false;
+        }
+
+        let pixels: Vec<Rgb> = data.chunks_exact(3).map(|pixel| Rgb([pixel[0], pixel[1], pixel[2]])).collect();
+        let Some(image) = RgbImage::from_buffer(width, height, &pixels) else {
+            return false;
+        };
+        *out = image;
+
+        // the modification time is used to find the least recently used frames
+        let _ = file.set_modified(SystemTime::now());
+        true
+    }
+
+    /// Stores `image` under `key`, trimming the cache if it gets too big.
+    /// Errors are ignored, the frame just won't be cached.
+    pub fn store(&mut self, source: &SourceCache, key: u64, image: &RgbImage) {
+        let (width, height) = image.size();
+        let mut data = Vec::with_capacity(FRAME_HEADER_SIZE + width as usize * height as usize * 3);
+        data.extend_from_slice(FRAME_MAGIC);
+        data.extend_from_slice(&source.version.to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        for y in 0..height {
+            for x in 0..width {
+                data.extend_from_slice(&image.get_pixel(x, y).0);
+            }
+        }
+
+        if data.len() as u64 > self.max_bytes {
+            return;
+        }
+
+        if write_atomic(&source.frame_path(key), &data).is_err() {
+            return;
+        }
+
+        self.total_bytes += data.len() as u64;
+        if self.total_bytes > self.max_bytes {
+            let _ = self.trim();
+        }
+    }
+
+    /// Deletes the least recently used frames until the cache is well below
+    /// its size limit.
+    pub fn trim(&mut self) -> std::io::Result<()> {
+        let _lock = self.lock();
+        let mut frames = frame_files(&self.dir)?;
+        frames.sort_by_key(|frame| frame.modified);
+
+        let mut total_bytes: u64 = frames.iter().map(|frame| frame.size).sum();
+        let target = (self.max_bytes as f64 * TRIM_TARGET) as u64;
+        for frame in &frames {
+            if total_bytes <= target {
+                break;
+            }
+            if fs::remove_file(&frame.path).is_ok() {
+                total_bytes -= frame.size;
+            }
+        }
+        self.total_bytes = total_bytes;
+
+        Ok(())
+    }
+}
+
+impl SourceCache {
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn frame_path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{key:016x}.{FRAME_EXT}"))
+    }
+}
+
+/// Writes a temporary file next to `path` first and then renames it, so
+/// that no other instance ever finds a half written file. The name of the
+/// temporary file is unique, so that instances don't write into the same.
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(".{}.{counter}.{TEMP_EXT}", std::process::id()));
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let res = File::create(&tmp_path)
+        .and_then(|mut file| file.write_all(data))
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    res
+}
+
+/// Hashes everything a cached frame depends on into a cache key (FNV-1a,
+/// so keys stay the same between builds).
+pub fn hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+struct FrameFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// All cached frames of all sources below `dir`.
+fn frame_files(dir: &Path) -> std::io::Result<Vec<FrameFile>> {
+    let mut frames = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            frames.extend(frame_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == FRAME_EXT) {
+            let metadata = fs::metadata(&path)?;
+            frames.push(FrameFile {
+                path,
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+    Ok(frames)
+}
@@ -0,0 +1,95 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Feeds the rendered frames to a user command, see `--pipe-frames`.
+//!
+//! Each frame is a line `RGBA WIDTH HEIGHT FPS` followed by the pixels, row
+//! by row, 4 bytes each. The header repeats for every frame, because the
+//! size changes with the terminal and with each file. Frames are written
+//! straight into the pipe, so a command that reads slowly blocks the render
+//! loop and slows the animation down instead of frames piling up.
+
+use std::io::Write;
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+
+use crate::image::RgbImage;
+
+#[derive(Debug)]
+pub struct FramePipe {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    buf: Vec<u8>,
+}
+
+impl FramePipe {
+    /// Runs `command` with the shell. Its output is discarded, so it
+    /// doesn't end up on the terminal.
+    pub fn spawn(command: &str) -> std::io::Result<Self> {
+        #[cfg(not(windows))]
+        let mut shell = {
+            let mut shell = Command::new("sh");
+            shell.arg("-c").arg(command);
+            shell
+        };
+
+        #[cfg(windows)]
+        let mut shell = {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C").arg(command);
+            shell
+        };
+
+        let mut child = shell
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        Ok(Self {
+            stdin: child.stdin.take(),
+            child,
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: &RgbImage, fps: u32) -> std::io::Result<()> {
+        let Some(stdin) = &mut self.stdin else {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        };
+
+        let (width, height) = frame.size();
+        self.buf.clear();
+        writeln!(self.buf, "RGBA {width} {height} {fps}")?;
+        self.buf.reserve(width as usize * height as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let [r, g, b] = frame.get_pixel(x, y).0;
+                self.buf.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+
+        stdin.write_all(&self.buf)?;
+        stdin.flush()
+    }
+
+    /// Closes the pipe, so that the command sees the end of the input, and
+    /// waits for it to exit.
+    pub fn finish(mut self) -> std::io::Result<ExitStatus> {
+        drop(self.stdin.take());
+        self.child.wait()
+    }
+}
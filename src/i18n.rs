@@ -0,0 +1,641 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Message catalog for the text shown to the user (OSD messages and the
+//! hotkey help), so it can be translated.
+//!
+//! The locale is taken from `LC_ALL`, `LC_MESSAGES` or `LANG`. Translations
+//! that aren't built in can be loaded from a file with one `key = text`
+//! entry per line. Texts use `{}` or `{0}`, `{1}`, ... as placeholders for
+//! their arguments.
+
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::file_info::{FileFormat, FileInfo};
+use crate::keymap::KeyMap;
+
+macro_rules! messages {
+    ($($name:ident => $key:literal, $text:literal;)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Msg {
+            $($name),*
+        }
+
+        impl Msg {
+            pub const ALL: &[Msg] = &[$(Msg::$name),*];
+
+            /// Key used in translation files.
+            pub fn key(self) -> &'static str {
+                match self {
+                    $(Msg::$name => $key),*
+                }
+            }
+
+            /// The English text, which is used if there is no translation.
+            pub fn english(self) -> &'static str {
+                match self {
+                    $(Msg::$name => $text),*
+                }
+            }
+        }
+    };
+}
+
+messages! {
+    Enabled            => "enabled", "Enabled";
+    Disabled           => "disabled", "Disabled";
+    BlendMode          => "blend_mode", "Blend Mode: {}";
+    Shuffle            => "shuffle", "Shuffle: {}";
+    BlendCurve         => "blend_curve", "Blend Curve: {}";
+    OsdEnabled         => "osd_enabled", "OSD: Enabled";
+    OsdDisabled        => "osd_disabled", "OSD: Disabled";
+    Clock              => "clock", "{0}:{1}";
+    ClockEvent         => "clock_event", "{0}:{1} {2}";
+    Fps                => "fps", "FPS: {}";
+    AtLastFile         => "at_last_file", "Already at last file.";
+    AtFirstFile        => "at_first_file", "Already at first file.";
+    OnlyFilesOpened    => "only_files_opened", "Only {} files opened!";
+    CyclePhase         => "cycle_phase", "Cycle Phase: {}";
+    CycleSpeed         => "cycle_speed", "Cycle Speed: {}x";
+    PanLock            => "pan_lock", "Pan Lock: {}";
+    PanLocked          => "pan_locked", "Pan Lock: Enabled (L to unlock)";
+    Zoom               => "zoom", "Zoom: {}x";
+    Paused             => "paused", "Paused (Space to resume)";
+    Resumed            => "resumed", "Resumed";
+    NoCameraPath       => "no_camera_path", "No camera path";
+    CameraFollow       => "camera_follow", "Camera: Follow Path";
+    CameraManual       => "camera_manual", "Camera: Manual";
+    CameraManualHint   => "camera_manual_hint", "Camera: Manual (C to follow)";
+    ExportedPalette    => "exported_palette", "Exported palette to {}";
+    ExportError        => "export_error", "{}: {}";
+    FastForwardOn      => "fast_forward_on", "Fast Forward: ON";
+    FastForwardOff     => "fast_forward_off", "Fast Forward: OFF";
+    DiffDebug          => "diff_debug", "Diff Debug: {}";
+    TimeBar            => "time_bar", "Time Bar: {}";
+    RendererFull       => "renderer_full", "Renderer: Full Redraw";
+    RendererDiff       => "renderer_diff", "Renderer: Differential";
+    ReachedEdge        => "reached_edge", "Reached {} edge";
+    EdgeTop            => "edge_top", "top";
+    EdgeBottom         => "edge_bottom", "bottom";
+    EdgeLeft           => "edge_left", "left";
+    EdgeRight          => "edge_right", "right";
+    DumpedFrames       => "dumped_frames", "Dumped {} frames to {}";
+    DumpError          => "dump_error", "Error dumping frames: {}";
+    ChordGotoTime      => "chord_goto_time", "Go to time";
+    ChordGotoFile      => "chord_goto_file", "Go to file";
+    PromptTime         => "prompt_time", "Go to time (HHMM): {}_";
+    PromptFile         => "prompt_file", "Go to file: {}_";
+    InvalidTime        => "invalid_time", "Invalid time: {}";
+    LoadError          => "load_error", "Error loading file";
+    LoadErrorHotkeys   => "load_error_hotkeys", "R: Retry   N: Next file   P: Previous file   Q: Quit";
+    OutputStalled      => "output_stalled", "Terminal not responding, reduced to {} FPS";
+    JournalError       => "journal_error", "Error writing journal: {}";
+    FramePipeClosed    => "frame_pipe_closed", "Frame pipe closed: {}";
+    PerformOn          => "perform_on", "Performance mode: 1-0 boost cycles, A-L flash, Escape leaves";
+    PerformOff         => "perform_off", "Performance mode: Off";
+    PaletteLegend      => "palette_legend", "≈ {0} similar  × {1} unused";
+    CycleListTitle     => "cycle_list_title", " Cycles: ↑/↓ select, M mute, S solo";
+    NoCycles           => "no_cycles", "No color cycles";
+    CycleMuted         => "cycle_muted", "Cycle {}: Muted";
+    CycleUnmuted       => "cycle_unmuted", "Cycle {}: Unmuted";
+    CycleSolo          => "cycle_solo", "Cycle {}: Solo";
+    CycleSoloOff       => "cycle_solo_off", "Solo: Off";
+    DescribeTime       => "describe_time", "{0}:{1}, {2}.";
+    DescribeColors     => "describe_colors", "Mostly {0}, {1}.";
+    DescribeNext       => "describe_next", "Next change at {0}:{1}.";
+    DescribeChange     => "describe_change", "{0}:{1}: The light changes.";
+    DescribeBy         => "describe_by", "{0} by {1}";
+    PartNight          => "part_night", "night";
+    PartDawn           => "part_dawn", "dawn";
+    PartMorning        => "part_morning", "morning";
+    PartMidday         => "part_midday", "midday";
+    PartAfternoon      => "part_afternoon", "afternoon";
+    PartEvening        => "part_evening", "evening";
+    Dark               => "dark", "dark";
+    Dim                => "dim", "dim";
+    Bright             => "bright", "bright";
+    Red                => "red", "red";
+    Orange             => "orange", "orange";
+    Yellow             => "yellow", "yellow";
+    Green              => "green", "green";
+    Cyan               => "cyan", "cyan";
+    Blue               => "blue", "blue";
+    Purple             => "purple", "purple";
+    Pink               => "pink", "pink";
+    Gray               => "gray", "gray";
+    ExplainHint        => "explain_hint", "Run `color-cycle --explain {0}` for possible causes and workarounds.";
+    TooSmall           => "too_small", "Terminal too small";
+    Hotkeys            => "hotkeys", "Hotkeys";
+    HelpBlend          => "help_blend", "Toggle blend mode";
+    HelpShuffle        => "help_shuffle", "Toggle playing the files in random order";
+    HelpBlendCurve     => "help_blend_curve", "Next blend curve (linear, cosine, shift-fade)";
+    HelpQuit           => "help_quit", "Quit program";
+    HelpOsd            => "help_osd", "Toggle On Screen Display";
+    HelpNextFile       => "help_next_file", "Open next file";
+    HelpPrevFile       => "help_prev_file", "Open previous file";
+    HelpFileIndex      => "help_file_index", "Open file by number, after a second or with Enter\nor G";
+    HelpCount          => "help_count", "Typed before a command repeats it, e.g. 20 and\nCursor Right moves 20 pixels, 3 and N skips 3 files.\nEscape cancels the number.";
+    HelpLastFile       => "help_last_file", "Open last file";
+    HelpPaste          => "help_paste", "Open the pasted path or URL";
+    HelpFpsUp          => "help_fps_up", "Increase frames per second by 1";
+    HelpFpsDown        => "help_fps_down", "Decrease frames per second by 1";
+    HelpFastForward    => "help_fast_forward", "Toogle fast forward ({}x speed)";
+    HelpBack5          => "help_back_5", "Go back in time by 5 minutes";
+    HelpBack1          => "help_back_1", "Go back in time by 1 minute";
+    HelpForward5       => "help_forward_5", "Go forward in time by 5 minutes";
+    HelpForward1       => "help_forward_1", "Go forward in time by 1 minute";
+    HelpNow            => "help_now", "Go to current time and continue normal progression";
+    HelpPause          => "help_pause", "Pause/resume the color cycling";
+    HelpPhase1         => "help_phase_1", "Shift the phase of the color cycles by 1%";
+    HelpPhase10        => "help_phase_10", "Shift the phase of the color cycles by 10%";
+    HelpCycleSpeed     => "help_cycle_speed", "Halve/double the speed of the color cycling";
+    HelpGrid           => "help_grid", "Toggle grid overlay";
+    HelpHideAll        => "help_hide_all", "Hide all overlays, press again to restore them";
+    HelpChordTime      => "help_chord_time", "Go to a time of day, type it as HHMM and press Enter";
+    HelpChordFile      => "help_chord_file", "Go to a file, type its number and press Enter";
+    HelpExport         => "help_export", "Export the current palette as a color theme to the\ncurrent directory (see --theme-format)";
+    HelpPanLock        => "help_pan_lock", "Toggle pan lock (ignore keys that move the view-port)";
+    HelpZoomIn         => "help_zoom_in", "Zoom in";
+    HelpZoomOut        => "help_zoom_out", "Zoom out";
+    HelpCamera         => "help_camera", "Follow the camera path of the file again after moving\nthe view-port manually";
+    HelpScrub          => "help_scrub", "Toggle time scrub mode. In scrub mode Cursor Left/Right\nmove through the day, Shift for bigger steps.\nEnter or Escape leave scrub mode.";
+    HelpTimeBar        => "help_time_bar", "Toggle time of day bar with the keyframes of the\ntimeline, in scenes with a timeline";
+    HelpReverseColumns => "help_reverse_columns", "Reverse pixels in columns of 8.\nThis is a hack fix for images that appear to be\nbroken like that.";
+    HelpFileInfo       => "help_file_info", "Toggle file information panel";
+    HelpDiffDebug      => "help_diff_debug", "Toggle highlighting of re-drawn cells (debug)";
+    HelpPalette        => "help_palette", "Toggle palette inspector, marking indistinguishable (≈)\nand unused (×) colors";
+    HelpCycleList      => "help_cycle_list", "Toggle cycle list. In the cycle list Cursor Up/Down\nselect a cycle, M mutes it and S solos it.\nEscape closes the cycle list.";
+    HelpPerform        => "help_perform", "Toggle performance mode. In performance mode 1 to 9\nand 0 speed up the cycles 1 to 10 while held and A, S,\nD, F, G, H, J, K, L flash the palette.\nEscape leaves performance mode.";
+    HelpRenderer       => "help_renderer", "Toggle between differential and full redraw\nrenderer (debug)";
+    HelpUp             => "help_up", "Move view-port up by 1 pixel";
+    HelpDown           => "help_down", "Move view-port down by 1 pixel";
+    HelpLeft           => "help_left", "Move view-port left by 1 pixel";
+    HelpRight          => "help_right", "Move view-port right by 1 pixel";
+    HelpLeftEdge       => "help_left_edge", "Move view-port to left edge";
+    HelpRightEdge      => "help_right_edge", "Move view-port to right edge";
+    HelpTop            => "help_top", "Move view-port to top";
+    HelpBottom         => "help_bottom", "Move view-port to bottom";
+    HelpPageUp         => "help_page_up", "Move view-port up by half a screen";
+    HelpPageDown       => "help_page_down", "Move view-port down by half a screen";
+    HelpPageLeft       => "help_page_left", "Move view-port left by half a screen";
+    HelpPageRight      => "help_page_right", "Move view-port right by half a screen";
+    HelpWheel          => "help_wheel", "Move view-port up or down (with --mouse)";
+    HelpShiftWheel     => "help_shift_wheel", "Move view-port left or right (with --mouse)";
+    HelpCtrlWheel      => "help_ctrl_wheel", "Zoom, or go forward or back in time by 1 minute in\nscenes with a timeline (with --mouse)";
+    InfoSource         => "info_source", "Source";
+    InfoSize           => "info_size", "Size";
+    InfoBytes          => "info_bytes", "{} bytes";
+    InfoFormat         => "info_format", "Format";
+    InfoDimensions     => "info_dimensions", "Dimensions";
+    InfoDimensionsText => "info_dimensions_text", "{0} x {1}";
+    InfoColors         => "info_colors", "Colors";
+    InfoColorsUsed     => "info_colors_used", "{} used";
+    InfoCycles         => "info_cycles", "Cycles";
+    InfoPalettes       => "info_palettes", "Palettes";
+    InfoTimeline       => "info_timeline", "Timeline";
+    InfoEvents         => "info_events", "{} events";
+    InfoLayers         => "info_layers", "Layers";
+    InfoSprites        => "info_sprites", "Sprites";
+    InfoTitle          => "info_title", "Title";
+    InfoAuthor         => "info_author", "Author";
+    InfoCopyright      => "info_copyright", "Copyright";
+    InfoAnnotation     => "info_annotation", "Annotation";
+    InfoMonth          => "info_month", "Month";
+    InfoSound          => "info_sound", "Sound";
+    InfoLoadTime       => "info_load_time", "Load time";
+    InfoMillis         => "info_millis", "{} ms";
+    InfoSeed           => "info_seed", "Seed";
+    InfoLatency        => "info_latency", "Latency";
+    InfoLatencyText    => "info_latency_text", "{0} ms ({1})";
+}
+
+const GERMAN: &[(Msg, &str)] = &[
+    (Msg::Enabled,            "Ein"),
+    (Msg::Disabled,           "Aus"),
+    (Msg::BlendMode,          "Überblenden: {}"),
+    (Msg::Shuffle,            "Zufällige Reihenfolge: {}"),
+    (Msg::BlendCurve,         "Überblendkurve: {}"),
+    (Msg::OsdEnabled,         "Bildschirmanzeige: Ein"),
+    (Msg::OsdDisabled,        "Bildschirmanzeige: Aus"),
+    (Msg::Clock,              "{0}:{1} Uhr"),
+    (Msg::ClockEvent,         "{0}:{1} Uhr {2}"),
+    (Msg::Fps,                "Bilder/s: {}"),
+    (Msg::AtLastFile,         "Bereits bei der letzten Datei."),
+    (Msg::AtFirstFile,        "Bereits bei der ersten Datei."),
+    (Msg::OnlyFilesOpened,    "Nur {} Dateien geöffnet!"),
+    (Msg::CyclePhase,         "Zyklus-Phase: {}"),
+    (Msg::CycleSpeed,         "Zyklus-Geschwindigkeit: {}x"),
+    (Msg::PanLock,            "Ansicht gesperrt: {}"),
+    (Msg::PanLocked,          "Ansicht gesperrt (L zum Entsperren)"),
+    (Msg::Zoom,               "Zoom: {}x"),
+    (Msg::Paused,             "Angehalten (Leertaste zum Fortsetzen)"),
+    (Msg::Resumed,            "Fortgesetzt"),
+    (Msg::NoCameraPath,       "Kein Kamerapfad"),
+    (Msg::CameraFollow,       "Kamera: Pfad folgen"),
+    (Msg::CameraManual,       "Kamera: Manuell"),
+    (Msg::CameraManualHint,   "Kamera: Manuell (C zum Folgen)"),
+    (Msg::ExportedPalette,    "Palette nach {} exportiert"),
+    (Msg::FastForwardOn,      "Schnellvorlauf: Ein"),
+    (Msg::FastForwardOff,     "Schnellvorlauf: Aus"),
+    (Msg::DiffDebug,          "Diff-Debug: {}"),
+    (Msg::TimeBar,            "Tageszeitleiste: {}"),
+    (Msg::RendererFull,       "Darstellung: Alles neu zeichnen"),
+    (Msg::RendererDiff,       "Darstellung: Nur Änderungen"),
+    (Msg::ReachedEdge,        "Rand erreicht: {}"),
+    (Msg::EdgeTop,            "oben"),
+    (Msg::EdgeBottom,         "unten"),
+    (Msg::EdgeLeft,           "links"),
+    (Msg::EdgeRight,          "rechts"),
+    (Msg::DumpedFrames,       "{} Bilder nach {} gespeichert"),
+    (Msg::DumpError,          "Fehler beim Speichern der Bilder: {}"),
+    (Msg::ChordGotoTime,      "Zu Uhrzeit"),
+    (Msg::ChordGotoFile,      "Zu Datei"),
+    (Msg::PromptTime,         "Zu Uhrzeit (HHMM): {}_"),
+    (Msg::PromptFile,         "Zu Datei: {}_"),
+    (Msg::InvalidTime,        "Ungültige Uhrzeit: {}"),
+    (Msg::LoadError,          "Fehler beim Laden der Datei"),
+    (Msg::LoadErrorHotkeys,   "R: Erneut versuchen   N: Nächste Datei   P: Vorherige Datei   Q: Beenden"),
+    (Msg::OutputStalled,      "Terminal reagiert nicht, auf {} Bilder/s reduziert"),
+    (Msg::JournalError,       "Fehler beim Schreiben des Journals: {}"),
+    (Msg::FramePipeClosed,    "Bild-Pipe geschlossen: {}"),
+    (Msg::PerformOn,          "Performance-Modus: 1-0 beschleunigen Zyklen, A-L blitzen, Escape beendet"),
+    (Msg::PerformOff,         "Performance-Modus: Aus"),
+    (Msg::PaletteLegend,      "≈ {0} ähnlich  × {1} unbenutzt"),
+    (Msg::CycleListTitle,     " Zyklen: ↑/↓ auswählen, M stumm, S solo"),
+    (Msg::NoCycles,           "Keine Farbzyklen"),
+    (Msg::CycleMuted,         "Zyklus {}: Stumm"),
+    (Msg::CycleUnmuted,       "Zyklus {}: Aktiv"),
+    (Msg::CycleSolo,          "Zyklus {}: Solo"),
+    (Msg::CycleSoloOff,       "Solo: Aus"),
+    (Msg::DescribeTime,       "{0}:{1} Uhr, {2}."),
+    (Msg::DescribeColors,     "Überwiegend {0}, {1}."),
+    (Msg::DescribeNext,       "Nächste Änderung um {0}:{1} Uhr."),
+    (Msg::DescribeChange,     "{0}:{1} Uhr: Das Licht ändert sich."),
+    (Msg::DescribeBy,         "{0} von {1}"),
+    (Msg::PartNight,          "Nacht"),
+    (Msg::PartDawn,           "Morgendämmerung"),
+    (Msg::PartMorning,        "Vormittag"),
+    (Msg::PartMidday,         "Mittag"),
+    (Msg::PartAfternoon,      "Nachmittag"),
+    (Msg::PartEvening,        "Abend"),
+    (Msg::Dark,               "dunkel"),
+    (Msg::Dim,                "gedämpft"),
+    (Msg::Bright,             "hell"),
+    (Msg::Red,                "Rot"),
+    (Msg::Orange,             "Orange"),
+    (Msg::Yellow,             "Gelb"),
+    (Msg::Green,              "Grün"),
+    (Msg::Cyan,               "Türkis"),
+    (Msg::Blue,               "Blau"),
+    (Msg::Purple,             "Violett"),
+    (Msg::Pink,               "Rosa"),
+    (Msg::Gray,               "Grau"),
+    (Msg::ExplainHint,        "`color-cycle --explain {0}` zeigt mögliche Ursachen und Abhilfen."),
+    (Msg::TooSmall,           "Terminal zu klein"),
+    (Msg::Hotkeys,            "Tastenkürzel"),
+    (Msg::HelpBlend,          "Überblenden ein/aus"),
+    (Msg::HelpShuffle,        "Dateien in zufälliger Reihenfolge abspielen ein/aus"),
+    (Msg::HelpBlendCurve,     "Nächste Überblendkurve (linear, cosine, shift-fade)"),
+    (Msg::HelpQuit,           "Programm beenden"),
+    (Msg::HelpOsd,            "Bildschirmanzeige ein/aus"),
+    (Msg::HelpNextFile,       "Nächste Datei öffnen"),
+    (Msg::HelpPrevFile,       "Vorherige Datei öffnen"),
+    (Msg::HelpFileIndex,      "Datei nach Nummer öffnen, nach einer Sekunde oder\nmit Enter oder G"),
+    (Msg::HelpCount,          "Vor einem Befehl getippt wiederholt sie ihn, z.B.\nbewegen 20 und Cursor Rechts um 20 Pixel, 3 und N\nspringen 3 Dateien weiter. Escape verwirft die Zahl."),
+    (Msg::HelpLastFile,       "Letzte Datei öffnen"),
+    (Msg::HelpPaste,          "Eingefügten Pfad oder URL öffnen"),
+    (Msg::HelpFpsUp,          "Bilder pro Sekunde um 1 erhöhen"),
+    (Msg::HelpFpsDown,        "Bilder pro Sekunde um 1 verringern"),
+    (Msg::HelpFastForward,    "Schnellvorlauf ein/aus ({}-fache Geschwindigkeit)"),
+    (Msg::HelpBack5,          "5 Minuten in der Zeit zurück"),
+    (Msg::HelpBack1,          "1 Minute in der Zeit zurück"),
+    (Msg::HelpForward5,       "5 Minuten in der Zeit vor"),
+    (Msg::HelpForward1,       "1 Minute in der Zeit vor"),
+    (Msg::HelpNow,            "Zur aktuellen Uhrzeit springen und normal weiterlaufen"),
+    (Msg::HelpPause,          "Farbzyklen anhalten/fortsetzen"),
+    (Msg::HelpPhase1,         "Phase der Farbzyklen um 1% verschieben"),
+    (Msg::HelpPhase10,        "Phase der Farbzyklen um 10% verschieben"),
+    (Msg::HelpCycleSpeed,     "Geschwindigkeit der Farbzyklen halbieren/verdoppeln"),
+    (Msg::HelpGrid,           "Gitter ein/aus"),
+    (Msg::HelpHideAll,        "Alle Einblendungen verstecken, erneut drücken zum\nWiederherstellen"),
+    (Msg::HelpChordTime,      "Zu einer Uhrzeit springen, als HHMM eingeben und\nmit Enter bestätigen"),
+    (Msg::HelpChordFile,      "Zu einer Datei springen, Nummer eingeben und mit\nEnter bestätigen"),
+    (Msg::HelpExport,         "Aktuelle Palette als Farbschema in das aktuelle\nVerzeichnis exportieren (siehe --theme-format)"),
+    (Msg::HelpPanLock,        "Ansicht sperren (Tasten zum Verschieben ignorieren)"),
+    (Msg::HelpZoomIn,         "Vergrößern"),
+    (Msg::HelpZoomOut,        "Verkleinern"),
+    (Msg::HelpCamera,         "Nach manuellem Verschieben wieder dem Kamerapfad\nder Datei folgen"),
+    (Msg::HelpScrub,          "Zeitleisten-Modus ein/aus. Darin bewegen Cursor\nLinks/Rechts durch den Tag, mit Shift in größeren\nSchritten. Enter oder Escape beenden den Modus."),
+    (Msg::HelpTimeBar,        "Tageszeitleiste mit den Schlüsselbildern der Zeitleiste\nein/aus, in Szenen mit Zeitleiste"),
+    (Msg::HelpReverseColumns, "Pixel in Spalten zu je 8 umkehren.\nBehelf für Bilder, die so fehlerhaft aussehen."),
+    (Msg::HelpFileInfo,       "Dateiinformationen ein/aus"),
+    (Msg::HelpDiffDebug,      "Neu gezeichnete Zellen hervorheben (Debug)"),
+    (Msg::HelpPalette,        "Palettenansicht ein/aus, markiert ununterscheidbare (≈)\nund unbenutzte (×) Farben"),
+    (Msg::HelpCycleList,      "Zyklenliste ein/aus. Darin wählen Cursor Hoch/Runter\neinen Zyklus, M schaltet ihn stumm und S solo.\nEscape schließt die Zyklenliste."),
+    (Msg::HelpPerform,        "Performance-Modus ein/aus. Darin beschleunigen 1 bis 9\nund 0 die Zyklen 1 bis 10 solange sie gedrückt sind,\nA, S, D, F, G, H, J, K, L lassen die Palette aufblitzen.\nEscape beendet den Modus."),
+    (Msg::HelpRenderer,       "Zwischen Darstellung nur der Änderungen und\nvollständigem Neuzeichnen wechseln (Debug)"),
+    (Msg::HelpUp,             "Ansicht um 1 Pixel nach oben"),
+    (Msg::HelpDown,           "Ansicht um 1 Pixel nach unten"),
+    (Msg::HelpLeft,           "Ansicht um 1 Pixel nach links"),
+    (Msg::HelpRight,          "Ansicht um 1 Pixel nach rechts"),
+    (Msg::HelpLeftEdge,       "Ansicht an den linken Rand"),
+    (Msg::HelpRightEdge,      "Ansicht an den rechten Rand"),
+    (Msg::HelpTop,            "Ansicht ganz nach oben"),
+    (Msg::HelpBottom,         "Ansicht ganz nach unten"),
+    (Msg::HelpPageUp,         "Ansicht um eine halbe Bildschirmhöhe nach oben"),
+    (Msg::HelpPageDown,       "Ansicht um eine halbe Bildschirmhöhe nach unten"),
+    (Msg::HelpPageLeft,       "Ansicht um eine halbe Bildschirmbreite nach links"),
+    (Msg::HelpPageRight,      "Ansicht um eine halbe Bildschirmbreite nach rechts"),
+    (Msg::HelpWheel,          "Ansicht nach oben oder unten (mit --mouse)"),
+    (Msg::HelpShiftWheel,     "Ansicht nach links oder rechts (mit --mouse)"),
+    (Msg::HelpCtrlWheel,      "Zoomen, oder in Szenen mit Zeitleiste 1 Minute vor\noder zurück in der Zeit (mit --mouse)"),
+    (Msg::InfoSource,         "Quelle"),
+    (Msg::InfoSize,           "Größe"),
+    (Msg::InfoBytes,          "{} Bytes"),
+    (Msg::InfoFormat,         "Format"),
+    (Msg::InfoDimensions,     "Abmessungen"),
+    (Msg::InfoDimensionsText, "{0} x {1}"),
+    (Msg::InfoColors,         "Farben"),
+    (Msg::InfoColorsUsed,     "{} benutzt"),
+    (Msg::InfoCycles,         "Zyklen"),
+    (Msg::InfoPalettes,       "Paletten"),
+    (Msg::InfoTimeline,       "Zeitleiste"),
+    (Msg::InfoEvents,         "{} Ereignisse"),
+    (Msg::InfoLayers,         "Ebenen"),
+    (Msg::InfoSprites,        "Sprites"),
+    (Msg::InfoTitle,          "Titel"),
+    (Msg::InfoAuthor,         "Autor"),
+    (Msg::InfoCopyright,      "Copyright"),
+    (Msg::InfoAnnotation,     "Anmerkung"),
+    (Msg::InfoMonth,          "Monat"),
+    (Msg::InfoSound,          "Ton"),
+    (Msg::InfoLoadTime,       "Ladezeit"),
+    (Msg::InfoMillis,         "{} ms"),
+    (Msg::InfoSeed,           "Startwert"),
+    (Msg::InfoLatency,        "Latenz"),
+    (Msg::InfoLatencyText,    "{0} ms ({1})"),
+];
+
+/// Built in translations by language code.
+const BUILTIN: &[(&str, &[(Msg, &str)])] = &[
+    ("de", GERMAN),
+];
+
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    texts: Box<[Box<str>]>,
+}
+
+impl Default for Catalog {
+    #[inline]
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl Catalog {
+    pub fn english() -> Self {
+        Self {
+            texts: Msg::ALL.iter().map(|msg| msg.english().into()).collect(),
+        }
+    }
+
+    /// The built in translation for `locale` (e.g. `de_AT.UTF-8`), falling
+    /// back to English for unknown locales and missing messages.
+    pub fn for_locale(locale: &str) -> Self {
+        let mut catalog = Self::english();
+        let lang = language(locale);
+        if let Some((_, messages)) = BUILTIN.iter().find(|(code, _)| *code == lang) {
+            for &(msg, text) in messages.iter() {
+                catalog.set(msg, text);
+            }
+        }
+        catalog
+    }
+
+    /// The catalog for the locale of the environment.
+    pub fn from_env() -> Self {
+        match env_locale() {
+            Some(locale) => Self::for_locale(&locale),
+            None => Self::english(),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, msg: Msg) -> &str {
+        &self.texts[msg as usize]
+    }
+
+    #[inline]
+    pub fn set(&mut self, msg: Msg, text: impl Into<Box<str>>) {
+        self.texts[msg as usize] = text.into();
+    }
+
+    /// Overrides messages with the `key = text` lines of `source`. Empty
+    /// lines and lines starting with `#` are ignored, `\n` in a text is a
+    /// line break.
+    pub fn parse(&mut self, source: &str) -> Result<(), String> {
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, text)) = line.split_once('=') else {
+                return Err(format!("line {}: expected key = text", index + 1));
+            };
+            let key = key.trim();
+            let Some(&msg) = Msg::ALL.iter().find(|msg| msg.key() == key) else {
+                return Err(format!("line {}: unknown message: {key}", index + 1));
+            };
+
+            self.set(msg, text.trim().replace("\\n", "\n"));
+        }
+        Ok(())
+    }
+
+    /// Overrides messages with the ones in the file at `path`.
+    pub fn load(&mut self, path: &Path) -> Result<(), String> {
+        let source = std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.to_string_lossy()))?;
+        self.parse(&source).map_err(|err| format!("{}: {err}", path.to_string_lossy()))
+    }
+
+    /// Appends the text of `msg` to `out`, with the placeholders replaced
+    /// by `args`. Placeholders without argument are left empty, `{{` and
+    /// `}}` are literal braces.
+    pub fn write(&self, out: &mut String, msg: Msg, args: &[&dyn Display]) {
+        use std::fmt::Write;
+
+        let mut next_arg = 0;
+        let mut chars = self.get(msg).chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' => {
+                    let rest = chars.as_str();
+                    if let Some(rest) = rest.strip_prefix('{') {
+                        out.push('{');
+                        chars = rest.chars();
+                    } else if let Some((index, rest)) = rest.split_once('}') {
+                        let index = if index.is_empty() {
+                            next_arg += 1;
+                            next_arg - 1
+                        } else if let Ok(index) = index.parse() {
+                            index
+                        } else {
+                            out.push('{');
+                            continue;
+                        };
+                        if let Some(arg) = args.get(index) {
+                            let _ = write!(out, "{arg}");
+                        }
+                        chars = rest.chars();
+                    } else {
+                        out.push('{');
+                    }
+                }
+                '}' => {
+                    out.push('}');
+                    if let Some(rest) = chars.as_str().strip_prefix('}') {
+                        chars = rest.chars();
+                    }
+                }
+                _ => out.push(ch),
+            }
+        }
+    }
+
+    #[inline]
+    pub fn format(&self, msg: Msg, args: &[&dyn Display]) -> String {
+        let mut out = String::new();
+        self.write(&mut out, msg, args);
+        out
+    }
+
+    /// The hotkey help of the bindings of `keymap`, with descriptions that
+    /// span several lines indented under the first line.
+    pub fn hotkey_help(&self, keymap: &KeyMap, fast_forward_speed: u64) -> String {
+        let title = self.get(Msg::Hotkeys);
+        let rows = keymap.help_rows();
+        let key_width = rows.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0) + 2;
+        let mut help = format!("{title}\n{}\n", "=".repeat(title.chars().count()));
+
+        for (keys, msg) in rows {
+            let text = self.format(msg, &[&fast_forward_speed]);
+            let mut lines = text.lines();
+            help.push_str(&format!("{keys:<key_width$}{}\n", lines.next().unwrap_or("")));
+            for line in lines {
+                help.push_str(&format!("{:key_width$}{line}\n", ""));
+            }
+        }
+
+        help.pop();
+        help
+    }
+
+    /// Label and value pairs of the file information panel.
+    pub fn file_info_fields(&self, info: &FileInfo) -> Vec<(String, String)> {
+        let field = |label: Msg, value: String| (self.get(label).to_owned(), value);
+        let mut fields = vec![
+            field(Msg::InfoSource,     info.source.clone()),
+            field(Msg::InfoSize,       self.format(Msg::InfoBytes, &[&info.size])),
+            field(Msg::InfoFormat,     info.format.to_string()),
+            field(Msg::InfoDimensions, self.format(Msg::InfoDimensionsText, &[&info.width, &info.height])),
+            field(Msg::InfoColors,     self.format(Msg::InfoColorsUsed, &[&info.colors_used])),
+            field(Msg::InfoCycles,     info.cycle_count.to_string()),
+        ];
+
+        if info.format == FileFormat::LivingWorld {
+            fields.push(field(Msg::InfoPalettes, info.palette_count.to_string()));
+            fields.push(field(Msg::InfoTimeline, self.format(Msg::InfoEvents, &[&info.timeline_event_count])));
+        }
+
+        if info.layer_count > 0 {
+            fields.push(field(Msg::InfoLayers, info.layer_count.to_string()));
+        }
+
+        if info.sprite_count > 0 {
+            fields.push(field(Msg::InfoSprites, info.sprite_count.to_string()));
+        }
+
+        if let Some(title) = &info.title {
+            fields.push(field(Msg::InfoTitle, title.clone()));
+        }
+
+        if let Some(author) = &info.author {
+            fields.push(field(Msg::InfoAuthor, author.clone()));
+        }
+
+        if let Some(copyright) = &info.copyright {
+            fields.push(field(Msg::InfoCopyright, copyright.clone()));
+        }
+
+        for annotation in &info.annotations {
+            fields.push(field(Msg::InfoAnnotation, annotation.clone()));
+        }
+
+        if let Some(month) = &info.month {
+            fields.push(field(Msg::InfoMonth, month.clone()));
+        }
+
+        if let Some(sound) = &info.sound {
+            fields.push(field(Msg::InfoSound, sound.clone()));
+        }
+
+        let load_time = format!("{:.1}", info.load_time.as_secs_f64() * 1000.0);
+        fields.push(field(Msg::InfoLoadTime, self.format(Msg::InfoMillis, &[&load_time])));
+
+        fields
+    }
+}
+
+/// First set of `LC_ALL`, `LC_MESSAGES` and `LANG`.
+pub fn env_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Language code of a locale like `de_AT.UTF-8@euro`.
+fn language(locale: &str) -> &str {
+    let end = locale.find(['_', '.', '@']).unwrap_or(locale.len());
+    &locale[..end]
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Sets the catalog used by [`tr`] and [`format`]. Only the first call has
+/// an effect.
+pub fn init(catalog: Catalog) {
+    let _ = CATALOG.set(catalog);
+}
+
+/// The catalog set by [`init`], or the one of the locale of the environment.
+pub fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(Catalog::from_env)
+}
+
+#[inline]
+pub fn tr(msg: Msg) -> &'static str {
+    catalog().get(msg)
+}
+
+#[inline]
+pub fn format(msg: Msg, args: &[&dyn Display]) -> String {
+    catalog().format(msg, args)
+}
@@ -16,9 +16,9 @@
 
 // See: https://moddingwiki.shikadi.net/wiki/LBM_Format
 
-use std::{fmt::Display, io::{Read, Seek}, mem::MaybeUninit};
+use std::{fmt::Display, io::{Read, Seek, Write}, mem::MaybeUninit};
 
-use crate::{bitvec::BitVec, color::Rgb, image::{CycleImage, IndexedImage}, palette::{Cycle, Palette}};
+use crate::{bitvec::BitVec, color::Rgb, diagnostics::Code, image::{self, CycleImage, IndexedImage}, palette::{Cycle, Palette}};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ErrorKind {
@@ -27,11 +27,22 @@ pub enum ErrorKind {
     IO,
 }
 
+impl ErrorKind {
+    fn default_code(self) -> Option<Code> {
+        match self {
+            ErrorKind::UnsupportedFileFormat => Some(Code::NotAnImage),
+            ErrorKind::BrokenFile => None,
+            ErrorKind::IO => Some(Code::Io),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
+    code: Option<Code>,
     message: String,
-    cause: Option<Box<dyn std::error::Error>>
+    cause: Option<Box<dyn std::error::Error + Send + Sync>>
 }
 
 impl Error {
@@ -40,6 +51,11 @@ impl Error {
         self.kind
     }
 
+    #[inline]
+    pub fn code(&self) -> Option<Code> {
+        self.code
+    }
+
     #[inline]
     pub fn message(&self) -> &str {
         &self.message
@@ -50,20 +66,28 @@ impl Error {
     where S: Into<String> {
         Self {
             kind,
+            code: kind.default_code(),
             message: message.into(),
             cause: None
         }
     }
 
     #[inline]
-    pub fn with_cause<S>(kind: ErrorKind, message: S, cause: Box<dyn std::error::Error>) -> Self
+    pub fn with_cause<S>(kind: ErrorKind, message: S, cause: Box<dyn std::error::Error + Send + Sync>) -> Self
     where S: Into<String> {
         Self {
             kind,
+            code: kind.default_code(),
             message: message.into(),
             cause: Some(cause)
         }
     }
+
+    #[inline]
+    pub fn with_code(mut self, code: Code) -> Self {
+        self.code = Some(code);
+        self
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -82,7 +106,7 @@ impl Display for Error {
 impl std::error::Error for Error {
     #[inline]
     fn cause(&self) -> Option<&dyn std::error::Error> {
-        self.cause.as_deref()
+        self.cause.as_deref().map(|cause| cause as _)
     }
 }
 
@@ -182,7 +206,7 @@ impl BMHD {
     where R: Read + Seek {
         if chunk_len < Self::SIZE {
             return Err(Error::new(ErrorKind::BrokenFile,
-                format!("truncated BMHD chunk: {} < {}", chunk_len, Self::SIZE)));
+                format!("truncated BMHD chunk: {} < {}", chunk_len, Self::SIZE)).with_code(Code::TruncatedChunk));
         }
 
         let width = read_u16be(reader)?;
@@ -247,10 +271,15 @@ pub struct ILBM {
     cmap: Option<CMAP>,
     crngs: Vec<CRNG>,
     ccrts: Vec<CCRT>,
+    name: Option<String>,
+    author: Option<String>,
+    copyright: Option<String>,
+    annotations: Vec<String>,
 }
 
 impl ILBM {
     pub const MIN_SIZE: u32 = BMHD::SIZE + 12;
+    pub const MAGIC: &[u8] = b"FORM";
 
     #[inline]
     pub fn file_type(&self) -> FileType {
@@ -287,6 +316,30 @@ impl ILBM {
         &self.ccrts
     }
 
+    /// Content of the `NAME` chunk.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Content of the `AUTH` chunk.
+    #[inline]
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Content of the `(c) ` chunk.
+    #[inline]
+    pub fn copyright(&self) -> Option<&str> {
+        self.copyright.as_deref()
+    }
+
+    /// Contents of all `ANNO` chunks.
+    #[inline]
+    pub fn annotations(&self) -> &[String] {
+        &self.annotations
+    }
+
     pub fn can_read<R>(reader: &mut R) -> bool
     where R: Read + Seek {
         let mut fourcc = [0u8; 4];
@@ -294,7 +347,7 @@ impl ILBM {
             return false;
         }
 
-        if fourcc != *b"FORM" {
+        if fourcc != Self::MAGIC {
             return false;
         }
 
@@ -322,7 +375,7 @@ impl ILBM {
         let mut fourcc = [0u8; 4];
         reader.read_exact(&mut fourcc)?;
 
-        if fourcc != *b"FORM" {
+        if fourcc != Self::MAGIC {
             return Err(Error::new(ErrorKind::UnsupportedFileFormat,
                 format!("illegal FOURCC: {:?} {:?}", &fourcc, String::from_utf8_lossy(&fourcc))));
         }
@@ -352,6 +405,10 @@ impl ILBM {
         let mut crngs = Vec::new();
         let mut ccrts = Vec::new();
         let mut camg = None;
+        let mut name = None;
+        let mut author = None;
+        let mut copyright = None;
+        let mut annotations = Vec::new();
 
         // eprintln!("type: {file_type}");
         let mut pos = 4;
@@ -362,13 +419,17 @@ impl ILBM {
 
             match &fourcc {
                 b"BMHD" => {
-                    header = Some(BMHD::read(reader, chunk_len)?);
+                    let bmhd = BMHD::read(reader, chunk_len)?;
+                    if let Err(err) = image::check_dimensions(bmhd.width().into(), bmhd.height().into()) {
+                        return Err(Error::new(ErrorKind::UnsupportedFileFormat, err.to_string()).with_code(Code::ImageTooLarge));
+                    }
+                    header = Some(bmhd);
                     // eprintln!("{:?}", header.as_ref().unwrap());
                 }
                 b"BODY" => {
                     let Some(header) = &header else {
                         return Err(Error::new(ErrorKind::BrokenFile,
-                            "BMHD chunk not found before BODY chunk"));
+                            "BMHD chunk not found before BODY chunk").with_code(Code::MissingHeader));
                     };
                     body = Some(BODY::read(reader, chunk_len, file_type, header)?);
                 }
@@ -385,6 +446,18 @@ impl ILBM {
                     camg = Some(CAMG::read(reader, chunk_len)?);
                     // eprintln!("{:?}", camg.as_ref().unwrap());
                 }
+                b"NAME" => {
+                    name = Some(read_text(reader, chunk_len)?);
+                }
+                b"AUTH" => {
+                    author = Some(read_text(reader, chunk_len)?);
+                }
+                b"(c) " => {
+                    copyright = Some(read_text(reader, chunk_len)?);
+                }
+                b"ANNO" => {
+                    annotations.push(read_text(reader, chunk_len)?);
+                }
                 _ => {
                     // skip unknown chunk
                     // eprintln!("skip unsupported chunk: {:?} {:?}", &fourcc, String::from_utf8_lossy(&fourcc));
@@ -403,7 +476,7 @@ impl ILBM {
         }
 
         let Some(header) = header else {
-            return Err(Error::new(ErrorKind::BrokenFile, "BMHD chunk missing"));
+            return Err(Error::new(ErrorKind::BrokenFile, "BMHD chunk missing").with_code(Code::MissingHeader));
         };
 
         if let Some(camg) = &camg && camg.viewport_mode() & CAMG::EHB != 0 {
@@ -434,6 +507,10 @@ impl ILBM {
             cmap,
             crngs,
             ccrts,
+            name,
+            author,
+            copyright,
+            annotations,
         })
     }
 
@@ -483,7 +560,7 @@ impl BODY {
             _ => {
                 if file_type != FileType::ILBM || num_planes > 8 {
                     return Err(Error::new(ErrorKind::BrokenFile,
-                        format!("unsupported number of bit planes: {num_planes}")));
+                        format!("unsupported number of bit planes: {num_planes}")).with_code(Code::Unsupported));
                 }
             }
         }
@@ -573,7 +650,7 @@ impl BODY {
                 // uncompressed
                 if data_len > chunk_len as usize {
                     return Err(Error::new(ErrorKind::BrokenFile,
-                        format!("truncated BODY chunk: {} < {}", chunk_len, data_len)));
+                        format!("truncated BODY chunk: {} < {}", chunk_len, data_len)).with_code(Code::TruncatedBody));
                 }
 
                 for _y in 0..header.height() {
@@ -604,7 +681,7 @@ impl BODY {
                                 //eprintln!("broken BODY compression, more data than fits into row: {} > {}", next_pos, line_len);
                                 //break;
                                 return Err(Error::new(ErrorKind::BrokenFile,
-                                    format!("broken BODY compression, more data than fits into row: {} > {}", next_pos, line_len)));
+                                    format!("broken BODY compression, more data than fits into row: {} > {}", next_pos, line_len)).with_code(Code::BrokenCompression));
                             }
                             reader.read_exact(&mut line[pos..next_pos])?;
                             read_len += count;
@@ -621,7 +698,7 @@ impl BODY {
                                 //eprintln!("broken BODY compression, more data than fits into row: {} > {}", next_pos, line_len);
                                 //break;
                                 return Err(Error::new(ErrorKind::BrokenFile,
-                                    format!("broken BODY compression, more data than fits into row: {} > {}", next_pos, line_len)));
+                                    format!("broken BODY compression, more data than fits into row: {} > {}", next_pos, line_len)).with_code(Code::BrokenCompression));
                             }
                             line[pos..next_pos].fill(value);
                             pos = next_pos;
@@ -640,7 +717,7 @@ impl BODY {
 
                 if read_len > chunk_len as usize {
                     return Err(Error::new(ErrorKind::BrokenFile,
-                        format!("truncated compressed BODY chunk: {} < {}", chunk_len, read_len)));
+                        format!("truncated compressed BODY chunk: {} < {}", chunk_len, read_len)).with_code(Code::TruncatedBody));
                 }
 
                 if read_len < chunk_len as usize {
@@ -670,7 +747,7 @@ impl BODY {
                             ErrorKind::BrokenFile,
                             format!("expected \"VDAT\" chunk but got {:?} {:?}",
                                 String::from_utf8_lossy(&fourcc), &fourcc)
-                        ));
+                        ).with_code(Code::BrokenCompression));
                     }
 
                     let sub_chunk_len = read_u32be(reader)?;
@@ -680,7 +757,7 @@ impl BODY {
                         return Err(Error::new(
                             ErrorKind::BrokenFile,
                             format!("truncated compressed BODY chunk {} < {}", chunk_len, read_len)
-                        ));
+                        ).with_code(Code::TruncatedBody));
                     }
 
                     buf.resize(sub_chunk_len as usize, 0u8);
@@ -691,7 +768,7 @@ impl BODY {
                         return Err(Error::new(
                             ErrorKind::BrokenFile,
                             format!("error in VDAT, cmd_cnt < 2: {cmd_cnt}")
-                        ));
+                        ).with_code(Code::BrokenCompression));
                     }
                     let mut data_offset = cmd_cnt as usize;
 
@@ -758,7 +835,7 @@ impl BODY {
             _ => {
                 return Err(Error::new(
                     ErrorKind::UnsupportedFileFormat,
-                    format!("unsupported compression flag: {}", header.compression())));
+                    format!("unsupported compression flag: {}", header.compression())).with_code(Code::Unsupported));
             }
         }
 
@@ -830,7 +907,7 @@ impl CAMG {
     where R: Read + Seek {
         if chunk_len < Self::SIZE {
             return Err(Error::new(ErrorKind::BrokenFile,
-                format!("truncated CAMG chunk: {} < {}", chunk_len, Self::SIZE)));
+                format!("truncated CAMG chunk: {} < {}", chunk_len, Self::SIZE)).with_code(Code::TruncatedChunk));
         }
 
         let viewport_mode = read_u32be(reader)?;
@@ -880,7 +957,7 @@ impl CRNG {
     where R: Read + Seek {
         if chunk_len < Self::SIZE {
             return Err(Error::new(ErrorKind::BrokenFile,
-                format!("truncated CRNG chunk: {} < {}", chunk_len, Self::SIZE)));
+                format!("truncated CRNG chunk: {} < {}", chunk_len, Self::SIZE)).with_code(Code::TruncatedChunk));
         }
 
         let _padding = read_u16be(reader)?;
@@ -943,13 +1020,13 @@ impl CCRT {
     where R: Read + Seek {
         if chunk_len < Self::SIZE {
             return Err(Error::new(ErrorKind::BrokenFile,
-                format!("truncated CCRT chunk: {} < {}", chunk_len, Self::SIZE)));
+                format!("truncated CCRT chunk: {} < {}", chunk_len, Self::SIZE)).with_code(Code::TruncatedChunk));
         }
 
         let direction = read_i16be(reader)?;
         if direction < -1 || direction > 1 {
             return Err(Error::new(ErrorKind::BrokenFile,
-                format!("invalid CCRT direction: {}", direction)));
+                format!("invalid CCRT direction: {}", direction)).with_code(Code::InvalidCycle));
         }
 
         let low = read_u8(reader)?;
@@ -992,7 +1069,7 @@ impl TryFrom<ILBM> for CycleImage {
             if let Some(indexed_image) = IndexedImage::from_buffer(width, height, body.pixels().into(), palette) {
                 indexed_image
             } else {
-                return Err(Error::new(ErrorKind::BrokenFile, "image buffer is too small for given width/height"));
+                return Err(Error::new(ErrorKind::BrokenFile, "image buffer is too small for given width/height").with_code(Code::ImageTooLarge));
             }
         } else {
             IndexedImage::new(width, height, palette)
@@ -1054,6 +1131,136 @@ impl TryFrom<ILBM> for CycleImage {
     }
 }
 
+/// Writes `image` as an ILBM file with BMHD, CMAP, CRNG and BODY chunks.
+/// The body is compressed with ByteRun1 and uses as few bit planes as the
+/// pixels, the color cycles and the palette need, so that classic tools
+/// like Deluxe Paint can load the file.
+pub fn write_ilbm(writer: &mut impl Write, image: &CycleImage) -> Result<()> {
+    let (Ok(width), Ok(height)) = (u16::try_from(image.width()), u16::try_from(image.height())) else {
+        return Err(Error::new(ErrorKind::UnsupportedFileFormat,
+            format!("image too large for ILBM: {}x{}", image.width(), image.height())).with_code(Code::ImageTooLarge));
+    };
+    let indexed_image = image.indexed_image();
+    let pixels = indexed_image.data();
+
+    let palette = indexed_image.palette();
+    let last_color = palette.0.iter().rposition(|color| color.0 != [0, 0, 0]).unwrap_or(0) as u8;
+    let max_index = pixels.iter().copied()
+        .chain(image.cycles().iter().map(Cycle::high))
+        .fold(last_color, u8::max);
+    let num_planes = (u8::BITS - max_index.leading_zeros()).max(1) as usize;
+
+    let mut data = Vec::new();
+    data.extend_from_slice(b"ILBM");
+
+    let mut chunk = Vec::with_capacity(BMHD::SIZE as usize);
+    chunk.extend_from_slice(&width.to_be_bytes());
+    chunk.extend_from_slice(&height.to_be_bytes());
+    chunk.extend_from_slice(&0i16.to_be_bytes()); // x_origin
+    chunk.extend_from_slice(&0i16.to_be_bytes()); // y_origin
+    chunk.push(num_planes as u8);
+    chunk.push(0); // mask
+    chunk.push(1); // compression
+    chunk.push(0); // flags
+    chunk.extend_from_slice(&0u16.to_be_bytes()); // trans_color
+    chunk.push(1); // x_aspect
+    chunk.push(1); // y_aspect
+    chunk.extend_from_slice(&(width as i16).to_be_bytes());
+    chunk.extend_from_slice(&(height as i16).to_be_bytes());
+    write_chunk(&mut data, b"BMHD", &chunk);
+
+    chunk.clear();
+    for color in &palette.0[..1 << num_planes] {
+        chunk.extend_from_slice(&color.0);
+    }
+    write_chunk(&mut data, b"CMAP", &chunk);
+
+    for cycle in image.cycles() {
+        chunk.clear();
+        chunk.extend_from_slice(&0u16.to_be_bytes()); // padding
+        chunk.extend_from_slice(&(cycle.rate().min(u16::MAX.into()) as u16).to_be_bytes());
+        let flags: u16 = if cycle.reverse() { 3 } else { 1 };
+        chunk.extend_from_slice(&flags.to_be_bytes());
+        chunk.push(cycle.low());
+        chunk.push(cycle.high());
+        write_chunk(&mut data, b"CRNG", &chunk);
+    }
+
+    chunk.clear();
+    let plane_len = (width as usize).div_ceil(16) * 2;
+    let mut plane = vec![0u8; plane_len];
+    for row in pixels.chunks_exact(width as usize) {
+        for plane_index in 0..num_planes {
+            plane.fill(0);
+            for (x, &value) in row.iter().enumerate() {
+                plane[x / 8] |= ((value >> plane_index) & 1) << (7 - x % 8);
+            }
+            byte_run1(&mut chunk, &plane);
+        }
+    }
+    write_chunk(&mut data, b"BODY", &chunk);
+
+    writer.write_all(ILBM::MAGIC)?;
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(&data)?;
+    Ok(())
+}
+
+/// Appends a chunk with its header and padding byte to `data`.
+fn write_chunk(data: &mut Vec<u8>, fourcc: &[u8; 4], chunk: &[u8]) {
+    data.extend_from_slice(fourcc);
+    data.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+    data.extend_from_slice(chunk);
+    if chunk.len() & 1 != 0 {
+        data.push(0);
+    }
+}
+
+/// Compresses `input` with ByteRun1 (PackBits). Runs of three or more
+/// bytes are repeated, everything else is copied literally.
+fn byte_run1(output: &mut Vec<u8>, input: &[u8]) {
+    let mut pos = 0;
+    while pos < input.len() {
+        let value = input[pos];
+        let run = input[pos..].iter().take(128).take_while(|&&byte| byte == value).count();
+        if run >= 3 {
+            output.push((257 - run) as u8);
+            output.push(value);
+            pos += run;
+        } else {
+            let start = pos;
+            pos += run;
+            while pos < input.len() && pos - start < 128 {
+                if input[pos..].len() >= 3 && input[pos] == input[pos + 1] && input[pos] == input[pos + 2] {
+                    break;
+                }
+                pos += 1;
+            }
+            output.push((pos - start - 1) as u8);
+            output.extend_from_slice(&input[start..pos]);
+        }
+    }
+}
+
+/// Reads a text chunk. The text is not necessarily NUL terminated and
+/// might not be valid UTF-8 (it's usually ISO-8859-1 on the Amiga).
+pub fn read_text(reader: &mut impl Read, chunk_len: u32) -> Result<String> {
+    let mut buf = Vec::new();
+    reader.take(chunk_len.into()).read_to_end(&mut buf)?;
+    if buf.len() < chunk_len as usize {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+
+    if let Some(index) = buf.iter().position(|&byte| byte == 0) {
+        buf.truncate(index);
+    }
+
+    Ok(match String::from_utf8(buf) {
+        Ok(text) => text,
+        Err(err) => err.into_bytes().into_iter().map(char::from).collect(),
+    })
+}
+
 #[inline]
 pub fn read_u8(reader: &mut impl Read) -> Result<u8> {
     let mut buf = MaybeUninit::<[u8; 1]>::uninit();
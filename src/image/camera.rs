@@ -0,0 +1,79 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+/// Length of a day in milliseconds.
+const DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Where the camera looks at a certain time of day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKeyframe {
+    /// time of day in seconds since midnight
+    time_of_day: u32,
+    /// center of the view-port in image coordinates
+    x: f64,
+    y: f64,
+}
+
+impl CameraKeyframe {
+    #[inline]
+    pub fn new(time_of_day: u32, x: f64, y: f64) -> Self {
+        Self { time_of_day, x, y }
+    }
+
+    #[inline]
+    pub fn time_of_day(&self) -> u32 {
+        self.time_of_day
+    }
+
+    #[inline]
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    #[inline]
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+/// Center of the view-port at `time_of_day` (in milliseconds since
+/// midnight), linearly interpolated between the `keyframes` (sorted by time
+/// of day), wrapping around at midnight. Returns `None` if there are no
+/// keyframes.
+pub fn camera_position(keyframes: &[CameraKeyframe], time_of_day: u64) -> Option<(f64, f64)> {
+    let first = keyframes.first()?;
+    let last = keyframes.last()?;
+
+    let next_index = keyframes.iter().position(|keyframe| keyframe.time_of_day as u64 * 1000 > time_of_day);
+    let (prev, next, prev_time, next_time) = match next_index {
+        Some(0) | None => (last, first, last.time_of_day as u64 * 1000, first.time_of_day as u64 * 1000 + DAY),
+        Some(index) => {
+            let prev = &keyframes[index - 1];
+            let next = &keyframes[index];
+            (prev, next, prev.time_of_day as u64 * 1000, next.time_of_day as u64 * 1000)
+        }
+    };
+
+    // before the first keyframe the time is measured from the day before
+    let time_of_day = if time_of_day < prev_time { time_of_day + DAY } else { time_of_day };
+    let span = next_time - prev_time;
+    let mid = if span > 0 { (time_of_day - prev_time) as f64 / span as f64 } else { 0.0 };
+
+    Some((
+        prev.x + (next.x - prev.x) * mid,
+        prev.y + (next.y - prev.y) * mid,
+    ))
+}
@@ -14,9 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::error::Error;
 use crate::palette::{Cycle, Palette};
 
-use super::IndexedImage;
+use super::{IndexedImage, LivingWorld};
 
 #[derive(Debug, Clone)]
 pub struct CycleImage {
@@ -35,6 +36,13 @@ impl CycleImage {
         }
     }
 
+    /// Loads an ILBM or JSON file from memory, keeping only the base image
+    /// of Living Worlds files.
+    #[inline]
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        LivingWorld::from_bytes(data).map(LivingWorld::into_base)
+    }
+
     #[inline]
     pub fn filename(&self) -> Option<&str> {
         self.filename.as_deref()
@@ -0,0 +1,176 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use std::fmt::Display;
+use std::io::Cursor;
+use std::sync::OnceLock;
+
+use crate::diagnostics::Code;
+use crate::error::Error;
+use crate::ilbm::{FileType, ILBM};
+
+use super::{CycleImage, LivingWorld};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    ILBM,
+    PBM,
+    /// Single image Canvas Cycle JSON file.
+    CanvasCycle,
+    /// Canvas Cycle JSON file with palettes for different times of day.
+    LivingWorld,
+}
+
+impl Display for FileFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileFormat::ILBM        => "ILBM".fmt(f),
+            FileFormat::PBM         => "PBM".fmt(f),
+            FileFormat::CanvasCycle => "Canvas Cycle JSON".fmt(f),
+            FileFormat::LivingWorld => "Living Worlds JSON".fmt(f),
+        }
+    }
+}
+
+/// A decoded file and the metadata found in it.
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub living_world: LivingWorld,
+    pub format: FileFormat,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub copyright: Option<String>,
+    pub annotations: Vec<String>,
+}
+
+impl Decoded {
+    #[inline]
+    pub fn new(living_world: LivingWorld, format: FileFormat) -> Self {
+        Self {
+            title: living_world.name().map(str::to_owned),
+            living_world,
+            format,
+            author: None,
+            copyright: None,
+            annotations: Vec::new(),
+        }
+    }
+}
+
+/// A file format that is detected by looking at the start of the file.
+#[derive(Debug, Clone, Copy)]
+pub struct Decoder {
+    /// Name used in error messages.
+    pub name: &'static str,
+    /// Returns `true` if the data looks like this format.
+    pub sniff: fn(&[u8]) -> bool,
+    pub decode: fn(&[u8]) -> Result<Decoded, Error>,
+}
+
+/// The decoders that are tried, in order, when loading a file.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    decoders: Vec<Decoder>,
+}
+
+impl Registry {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All formats supported by this program.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Decoder { name: "ILBM", sniff: sniff_ilbm, decode: decode_ilbm });
+        registry.register(Decoder { name: "JSON", sniff: sniff_json, decode: decode_json });
+        registry
+    }
+
+    /// Decoders registered earlier take precedence.
+    #[inline]
+    pub fn register(&mut self, decoder: Decoder) {
+        self.decoders.push(decoder);
+    }
+
+    #[inline]
+    pub fn decoders(&self) -> &[Decoder] {
+        &self.decoders
+    }
+
+    /// The first decoder that recognizes `data`.
+    pub fn detect(&self, data: &[u8]) -> Option<&Decoder> {
+        self.decoders.iter().find(|decoder| (decoder.sniff)(data))
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Decoded, Error> {
+        let Some(decoder) = self.detect(data) else {
+            let names: Vec<_> = self.decoders.iter().map(|decoder| decoder.name).collect();
+            return Err(Error::new(format!("unknown file format, tried: {}", names.join(", "))).with_code(Code::NotAnImage));
+        };
+
+        (decoder.decode)(data)
+    }
+}
+
+/// The registry of all built-in formats.
+pub fn builtin() -> &'static Registry {
+    static BUILTIN: OnceLock<Registry> = OnceLock::new();
+    BUILTIN.get_or_init(Registry::with_builtin)
+}
+
+fn sniff_ilbm(data: &[u8]) -> bool {
+    data.starts_with(ILBM::MAGIC)
+}
+
+fn decode_ilbm(data: &[u8]) -> Result<Decoded, Error> {
+    let ilbm = ILBM::read(&mut Cursor::new(data))?;
+    let format = match ilbm.file_type() {
+        FileType::ILBM => FileFormat::ILBM,
+        FileType::PBM  => FileFormat::PBM,
+    };
+    let title = ilbm.name().map(str::to_owned);
+    let author = ilbm.author().map(str::to_owned);
+    let copyright = ilbm.copyright().map(str::to_owned);
+    let annotations = ilbm.annotations().to_vec();
+
+    let image: CycleImage = ilbm.try_into()?;
+    Ok(Decoded {
+        living_world: image.into(),
+        format,
+        title,
+        author,
+        copyright,
+        annotations,
+    })
+}
+
+fn sniff_json(data: &[u8]) -> bool {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    data.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{')
+}
+
+fn decode_json(data: &[u8]) -> Result<Decoded, Error> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let living_world: LivingWorld = serde_json::from_slice(data)?;
+    let format = if living_world.timeline().is_empty() && living_world.palettes().is_empty() {
+        FileFormat::CanvasCycle
+    } else {
+        FileFormat::LivingWorld
+    };
+    Ok(Decoded::new(living_world, format))
+}
@@ -16,7 +16,7 @@
 
 use crate::palette::Palette;
 
-use super::RgbImage;
+use super::{pixel_count, RgbImage, Scale};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IndexedImage {
@@ -38,7 +38,7 @@ impl IndexedImage {
         Self {
             width,
             height,
-            data: vec![0; width as usize * height as usize].into(),
+            data: vec![0; pixel_count(width, height).expect("image dimensions overflow")].into(),
             palette,
         }
     }
@@ -47,13 +47,13 @@ impl IndexedImage {
         Self {
             width,
             height,
-            data: vec![index; width as usize * height as usize].into(),
+            data: vec![index; pixel_count(width, height).expect("image dimensions overflow")].into(),
             palette,
         }
     }
 
     pub fn from_buffer(width: u32, height: u32, image: Box<[u8]>, palette: Palette) -> Option<Self> {
-        let size = width as usize * height as usize;
+        let size = pixel_count(width, height)?;
         if image.len() < size {
             return None;
         }
@@ -96,6 +96,15 @@ impl IndexedImage {
         &self.data
     }
 
+    /// Number of pixels per palette index.
+    pub fn index_usage(&self) -> [usize; 256] {
+        let mut usage = [0usize; 256];
+        for &index in self.data.iter() {
+            usage[index as usize] += 1;
+        }
+        usage
+    }
+
     #[inline]
     pub fn get_index(&self, x: u32, y: u32) -> u8 {
         let offset = self.width as usize * y as usize + x as usize;
@@ -120,7 +129,7 @@ impl IndexedImage {
 
         let width = width.min(self.width - x);
         let height = height.min(self.height - y);
-        let size = width as usize * height as usize;
+        let size = pixel_count(width, height).expect("image dimensions overflow");
 
         let mut data = unsafe { Box::new_uninit_slice(size).assume_init() };
 
@@ -146,8 +155,8 @@ impl IndexedImage {
 
     #[inline]
     pub fn get_rect_from(&mut self, x: u32, y: u32, width: u32, height: u32, other: &IndexedImage) {
-        let width = width.min(other.width - x);
-        let height = height.min(other.height - y);
+        let width = width.min(other.width.saturating_sub(x));
+        let height = height.min(other.height.saturating_sub(y));
         self.width = width;
         self.height = height;
         self.data = other.get_rect_data(x, y, width, height);
@@ -158,6 +167,14 @@ impl IndexedImage {
         image.draw_indexed_image_with_palette(self, palette);
     }
 
+    /// Updates only the pixels of `image` whose color differs between
+    /// `old_palette` and `palette`. `image` has to hold this image drawn with
+    /// `old_palette`. Returns `false` if nothing changed.
+    #[inline]
+    pub fn apply_palette_changes(&self, image: &mut RgbImage, palette: &Palette, old_palette: &Palette) -> bool {
+        image.draw_indexed_image_with_palette_changes(self, palette, old_palette)
+    }
+
     #[inline]
     pub fn apply(&self, image: &mut RgbImage) {
         image.draw_indexed_image(self);
@@ -168,7 +185,7 @@ impl IndexedImage {
             return;
         }
 
-        let size = width as usize * height as usize;
+        let size = pixel_count(width, height).expect("image dimensions overflow");
         let mut data: Box<[u8]> = vec![index; size].into();
 
         for new_y in 0..height {
@@ -182,6 +199,37 @@ impl IndexedImage {
         self.data = data;
     }
 
+    /// Scales this image into `output` by `scale` horizontally and
+    /// vertically, repeating or dropping pixels. See [`Scale::to_area()`]
+    /// for the size of the result.
+    pub fn scale_into(&self, scale: (Scale, Scale), output: &mut IndexedImage) {
+        let (scale_x, scale_y) = scale;
+        let width = scale_x.to_area(self.width);
+        let height = scale_y.to_area(self.height);
+        let size = pixel_count(width, height).expect("image dimensions overflow");
+        if output.data.len() != size {
+            output.data = vec![0; size].into();
+        }
+        output.width = width;
+        output.height = height;
+        output.palette.clone_from(&self.palette);
+
+        let width = width as usize;
+        let src_width = self.width as usize;
+        for y in 0..height {
+            let first = y as usize * width;
+            if !scale_y.is_start(y) {
+                output.data.copy_within(first - width..first, first);
+                continue;
+            }
+            let src = scale_y.to_image(y) as usize * src_width;
+            let row = &self.data[src..src + src_width];
+            for (x, pixel) in output.data[first..first + width].iter_mut().enumerate() {
+                *pixel = row[scale_x.to_image(x as u32) as usize];
+            }
+        }
+    }
+
     pub fn column_swap(&mut self) {
         let columns = (self.width / 8) as usize;
         for y in 0..self.height {
@@ -206,3 +254,51 @@ impl From<IndexedImage> for RgbImage {
         RgbImage::from_indexed_image(&value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Rgb;
+
+    /// A palette where every index has a distinct color.
+    fn test_palette() -> Palette {
+        let mut palette = Palette::default();
+        for index in 0..=255u8 {
+            palette[index] = Rgb([index, index.wrapping_mul(7), 255 - index]);
+        }
+        palette
+    }
+
+    fn assert_same_as_naive(width: u32, height: u32, data: Vec<u8>) {
+        let palette = test_palette();
+        let indexed_image = IndexedImage::from_buffer(width, height, data.into(), Palette::default()).unwrap();
+        let mut image = RgbImage::new(width, height);
+        indexed_image.apply_with_palette(&mut image, &palette);
+
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(image.get_pixel(x, y), palette[indexed_image.get_index(x, y)], "pixel {x}/{y} of {width}x{height}");
+            }
+        }
+    }
+
+    #[test]
+    fn apply_with_palette_odd_widths() {
+        for width in [1, 3, 7, 9, 13, 17] {
+            let height = 5;
+            let data = (0..width * height).map(|index| (index * 37 % 256) as u8).collect();
+            assert_same_as_naive(width, height, data);
+        }
+    }
+
+    #[test]
+    fn apply_with_palette_single_pixel() {
+        assert_same_as_naive(1, 1, vec![200]);
+    }
+
+    #[test]
+    fn apply_with_palette_all_indices() {
+        assert_same_as_naive(16, 16, (0..=255).collect());
+        assert_same_as_naive(256, 1, (0..=255).rev().collect());
+    }
+}
@@ -0,0 +1,92 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::IndexedImage;
+
+/// An image drawn over the base image that scrolls at a different speed
+/// than the view-port (parallax). Its pixels index into the palette of the
+/// scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    width: u32,
+    height: u32,
+    pixels: Box<[u8]>,
+    /// position in image coordinates when the view-port is at 0/0
+    x: i32,
+    y: i32,
+    /// how far the layer moves per pixel the view-port moves, 1.0 moves
+    /// with the base image, less is farther away, more is closer
+    scroll: f64,
+    /// palette index that isn't drawn
+    transparent: Option<u8>,
+}
+
+impl Layer {
+    /// Returns `None` if `pixels` is too small for `width` and `height`.
+    pub fn new(width: u32, height: u32, pixels: Box<[u8]>, x: i32, y: i32, scroll: f64, transparent: Option<u8>) -> Option<Self> {
+        let size = super::pixel_count(width, height)?;
+        if pixels.len() < size {
+            return None;
+        }
+
+        let pixels = if pixels.len() > size { pixels[..size].into() } else { pixels };
+        Some(Self { width, height, pixels, x, y, scroll, transparent })
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn scroll(&self) -> f64 {
+        self.scroll
+    }
+
+    #[inline]
+    pub fn transparent(&self) -> Option<u8> {
+        self.transparent
+    }
+
+    /// Draws the layer into `image`, which shows the part of the scene that
+    /// starts at `origin_x`/`origin_y`.
+    pub fn draw(&self, image: &mut IndexedImage, origin_x: u32, origin_y: u32) {
+        let x = self.x as i64 - (origin_x as f64 * self.scroll).round() as i64;
+        let y = self.y as i64 - (origin_y as f64 * self.scroll).round() as i64;
+
+        let x_start = x.max(0);
+        let x_end = (x + self.width as i64).min(image.width() as i64);
+        if x_start >= x_end {
+            return;
+        }
+
+        for dest_y in y.max(0)..(y + self.height as i64).min(image.height() as i64) {
+            let src_y = (dest_y - y) as usize;
+            for dest_x in x_start..x_end {
+                let src_x = (dest_x - x) as usize;
+                let index = self.pixels[src_y * self.width as usize + src_x];
+                if Some(index) != self.transparent {
+                    image.set_index(dest_x as u32, dest_y as u32, index);
+                }
+            }
+        }
+    }
+}
@@ -14,22 +14,64 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::CycleImage;
+use std::io::Read;
+
+use crate::error::Error;
+
+use super::camera::{camera_position, CameraKeyframe};
+use super::{CycleImage, Layer, Sprite, Weather};
 
 // render files from http://www.effectgames.com/demos/worlds/
 
+/// Half a day in milliseconds.
+const HALF_DAY: u64 = 12 * 60 * 60 * 1000;
+
 #[derive(Debug, Clone)]
 pub struct LivingWorld {
     name: Option<String>,
     base: CycleImage,
     palettes: Box<[CycleImage]>,
     timeline: Box<[TimedEvent]>,
+    layers: Box<[Layer]>,
+    sprites: Box<[Sprite]>,
+    camera: Box<[CameraKeyframe]>,
+    weather: Option<Weather>,
 }
 
 impl LivingWorld {
     #[inline]
     pub fn new(name: Option<String>, base: CycleImage, palettes: Box<[CycleImage]>, timeline: Box<[TimedEvent]>) -> Self {
-        Self { name, base, palettes, timeline }
+        Self { name, base, palettes, timeline, layers: Box::new([]), sprites: Box::new([]), camera: Box::new([]), weather: None }
+    }
+
+    /// Parallax layers drawn over the base image, in drawing order.
+    #[inline]
+    pub fn with_layers(mut self, layers: Box<[Layer]>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Sprites drawn over the base image and the layers, in drawing order.
+    #[inline]
+    pub fn with_sprites(mut self, sprites: Box<[Sprite]>) -> Self {
+        self.sprites = sprites;
+        self
+    }
+
+    /// Loads a file in any of the [built-in formats](super::format::builtin)
+    /// from memory.
+    ///
+    /// The file format is detected by its magic bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        super::format::builtin().decode(data).map(|decoded| decoded.living_world)
+    }
+
+    /// Reads the whole input and loads it via [`LivingWorld::from_bytes()`].
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where R: Read {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::from_bytes(&data)
     }
 
     #[inline]
@@ -39,9 +81,33 @@ impl LivingWorld {
             base,
             palettes: Box::new([]),
             timeline: Box::new([]),
+            layers: Box::new([]),
+            sprites: Box::new([]),
+            camera: Box::new([]),
+            weather: None,
         }
     }
 
+    /// Camera path the view-port follows, sorted by time of day.
+    #[inline]
+    pub fn with_camera(mut self, camera: Box<[CameraKeyframe]>) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    /// Replaces the name of the scene, e.g. by the title from a scene index.
+    #[inline]
+    pub fn with_name(mut self, name: Option<String>) -> Self {
+        self.name = name;
+        self
+    }
+
+    #[inline]
+    pub fn with_weather(mut self, weather: Option<Weather>) -> Self {
+        self.weather = weather;
+        self
+    }
+
     #[inline]
     pub fn name(&self) -> Option<&str> {
         self.name.as_deref()
@@ -62,6 +128,72 @@ impl LivingWorld {
         &self.timeline
     }
 
+    #[inline]
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    #[inline]
+    pub fn sprites(&self) -> &[Sprite] {
+        &self.sprites
+    }
+
+    #[inline]
+    pub fn camera(&self) -> &[CameraKeyframe] {
+        &self.camera
+    }
+
+    #[inline]
+    pub fn weather(&self) -> Option<Weather> {
+        self.weather
+    }
+
+    /// Center of the view-port according to the camera path at
+    /// `time_of_day` (in milliseconds since midnight), or `None` if there is
+    /// no camera path.
+    #[inline]
+    pub fn camera_position(&self, time_of_day: u64) -> Option<(f64, f64)> {
+        camera_position(&self.camera, time_of_day)
+    }
+
+    /// Returns the last timeline event that lies after `prev_time_of_day`
+    /// and not after `time_of_day` (both in milliseconds since midnight),
+    /// wrapping around at midnight. Going backwards in time by less than
+    /// half a day doesn't cross any events.
+    pub fn crossed_event(&self, prev_time_of_day: u64, time_of_day: u64) -> Option<&TimedEvent> {
+        let wrapped = time_of_day < prev_time_of_day;
+        if wrapped && prev_time_of_day - time_of_day < HALF_DAY {
+            return None;
+        }
+
+        let crossed = |event: &&TimedEvent| {
+            let event_time = event.time_of_day() as u64 * 1000;
+            if wrapped {
+                event_time > prev_time_of_day || event_time <= time_of_day
+            } else {
+                event_time > prev_time_of_day && event_time <= time_of_day
+            }
+        };
+
+        if wrapped {
+            // events after midnight come last
+            self.timeline.iter().filter(|event| event.time_of_day() as u64 * 1000 <= time_of_day).rfind(crossed)
+                .or_else(|| self.timeline.iter().rfind(crossed))
+        } else {
+            self.timeline.iter().rfind(crossed)
+        }
+    }
+
+    /// Returns the first timeline event after `time_of_day` (in milliseconds
+    /// since midnight), wrapping around at midnight.
+    pub fn next_event(&self, time_of_day: u64) -> Option<&TimedEvent> {
+        let event_time = |event: &&TimedEvent| event.time_of_day() as u64 * 1000;
+        self.timeline.iter()
+            .filter(|event| event_time(event) > time_of_day)
+            .min_by_key(event_time)
+            .or_else(|| self.timeline.iter().min_by_key(event_time))
+    }
+
     #[inline]
     pub fn into_base(self) -> CycleImage {
         self.base
@@ -1,9 +1,75 @@
+pub mod camera;
 pub mod cycle_image;
+pub mod format;
 pub mod indexed_image;
+pub mod layer;
 pub mod living_world;
+pub mod resample;
 pub mod rgb_image;
+pub mod sprite;
+pub mod viewport;
+pub mod weather;
 
+pub use self::camera::CameraKeyframe;
 pub use self::cycle_image::CycleImage;
 pub use self::indexed_image::IndexedImage;
+pub use self::layer::Layer;
 pub use self::living_world::LivingWorld;
+pub use self::resample::LinearPalette;
 pub use self::rgb_image::RgbImage;
+pub use self::sprite::Sprite;
+pub use self::viewport::{Scale, Viewport};
+pub use self::weather::Weather;
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Default for [`max_pixels()`], 8192 x 8192 pixels.
+pub const DEFAULT_MAX_PIXELS: u64 = 8192 * 8192;
+
+static MAX_PIXELS: AtomicU64 = AtomicU64::new(DEFAULT_MAX_PIXELS);
+
+/// Maximum number of pixels of a loaded image. Files declaring bigger
+/// images are rejected before any memory is allocated for them.
+#[inline]
+pub fn max_pixels() -> u64 {
+    MAX_PIXELS.load(Ordering::Relaxed)
+}
+
+#[inline]
+pub fn set_max_pixels(max_pixels: u64) {
+    MAX_PIXELS.store(max_pixels, Ordering::Relaxed);
+}
+
+/// Number of pixels of an image with the given size, or `None` if that
+/// doesn't fit into `usize`.
+#[inline]
+pub fn pixel_count(width: u32, height: u32) -> Option<usize> {
+    (width as usize).checked_mul(height as usize)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageTooLarge {
+    pub width: u32,
+    pub height: u32,
+    pub max_pixels: u64,
+}
+
+impl Display for ImageTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "image of size {} x {} exceeds the limit of {} pixels",
+            self.width, self.height, self.max_pixels)
+    }
+}
+
+impl std::error::Error for ImageTooLarge {}
+
+/// Checks the dimensions declared by a file against [`max_pixels()`] and
+/// returns the number of pixels.
+pub fn check_dimensions(width: u32, height: u32) -> Result<usize, ImageTooLarge> {
+    let max_pixels = max_pixels();
+    match pixel_count(width, height) {
+        Some(count) if count as u64 <= max_pixels => Ok(count),
+        _ => Err(ImageTooLarge { width, height, max_pixels }),
+    }
+}
@@ -0,0 +1,198 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Area-averaging resampler shared by everything that scales images.
+//!
+//! Averaging sRGB values directly makes dithered and fine detailed areas
+//! darker than they look at full size, so pixels are converted to linear
+//! light, averaged weighted by how much of each source pixel an output pixel
+//! covers, and converted back.
+
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use crate::color::{srgb_to_linear, Rgb};
+use crate::palette::Palette;
+
+use super::RgbImage;
+
+pub type Linear = [f32; 3];
+
+/// Linear light value of every sRGB channel value.
+pub fn srgb_lut() -> &'static [f32; 256] {
+    static LUT: OnceLock<[f32; 256]> = OnceLock::new();
+    LUT.get_or_init(|| std::array::from_fn(|value| srgb_to_linear(value as u8) as f32))
+}
+
+#[inline]
+pub fn rgb_to_linear(color: Rgb) -> Linear {
+    let lut = srgb_lut();
+    color.0.map(|value| lut[value as usize])
+}
+
+/// Linear light values halfway between two neighbouring sRGB values.
+fn srgb_thresholds() -> &'static [f32; 255] {
+    static THRESHOLDS: OnceLock<[f32; 255]> = OnceLock::new();
+    THRESHOLDS.get_or_init(|| {
+        let lut = srgb_lut();
+        std::array::from_fn(|value| (lut[value] + lut[value + 1]) * 0.5)
+    })
+}
+
+/// The sRGB value nearest to `value` in linear light. A binary search is a
+/// lot cheaper than a `powf()` per channel.
+#[inline]
+pub fn linear_to_srgb(value: f32) -> u8 {
+    srgb_thresholds().partition_point(|&threshold| threshold <= value) as u8
+}
+
+#[inline]
+pub fn linear_to_rgb(linear: Linear) -> Rgb {
+    Rgb(linear.map(linear_to_srgb))
+}
+
+/// A palette expanded to linear light. The expansion is only recomputed
+/// when the palette changed, so with color cycling it is done at most once
+/// per frame instead of once per pixel.
+#[derive(Debug, Clone)]
+pub struct LinearPalette {
+    palette: Option<Palette>,
+    linear: Box<[Linear; 256]>,
+}
+
+impl Default for LinearPalette {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinearPalette {
+    pub fn new() -> Self {
+        Self {
+            palette: None,
+            linear: Box::new([[0.0; 3]; 256]),
+        }
+    }
+
+    pub fn update(&mut self, palette: &Palette) -> &[Linear; 256] {
+        if self.palette.as_ref() != Some(palette) {
+            for (linear, &color) in self.linear.iter_mut().zip(palette.0.iter()) {
+                *linear = rgb_to_linear(color);
+            }
+
+            match &mut self.palette {
+                Some(cached) => cached.clone_from(palette),
+                None => self.palette = Some(palette.clone()),
+            }
+        }
+
+        &self.linear
+    }
+}
+
+/// The source pixels along one axis that make up one output pixel.
+#[derive(Debug, Clone)]
+struct Span {
+    start: usize,
+    weights: Range<usize>,
+}
+
+/// Spans of all output pixels along one axis and their weights, which add
+/// up to 1.0 per span.
+fn axis(src_len: u32, dest_len: u32) -> (Vec<Span>, Vec<f32>) {
+    let scale = src_len as f64 / dest_len as f64;
+    let mut spans = Vec::with_capacity(dest_len as usize);
+    let mut weights = Vec::new();
+
+    for index in 0..dest_len {
+        let low = index as f64 * scale;
+        let high = ((index + 1) as f64 * scale).min(src_len as f64);
+        let start = low.floor() as usize;
+        let end = (high.ceil() as usize).clamp(start + 1, src_len as usize);
+
+        let first = weights.len();
+        for src_index in start..end {
+            let covered = high.min(src_index as f64 + 1.0) - low.max(src_index as f64);
+            weights.push((covered.max(0.0) / scale) as f32);
+        }
+        spans.push(Span { start, weights: first..weights.len() });
+    }
+
+    (spans, weights)
+}
+
+/// Scales an image of `src_width` by `src_height` pixels to the size of
+/// `dest`. `read_row` fills in one row of the source image in linear light.
+pub(super) fn resample(src_width: u32, src_height: u32, dest: &mut RgbImage, mut read_row: impl FnMut(u32, &mut [Linear])) {
+    let (dest_width, dest_height) = dest.size();
+    if src_width == 0 || src_height == 0 || dest_width == 0 || dest_height == 0 {
+        return;
+    }
+
+    let (columns, column_weights) = axis(src_width, dest_width);
+    let (rows, row_weights) = axis(src_height, dest_height);
+    let mut src_row = vec![[0.0; 3]; src_width as usize];
+    let mut sum_row = vec![[0.0; 3]; src_width as usize];
+
+    for (y, row) in rows.iter().enumerate() {
+        sum_row.fill([0.0; 3]);
+        for (src_y, &weight) in (row.start..).zip(&row_weights[row.weights.clone()]) {
+            read_row(src_y as u32, &mut src_row);
+            for (sum, value) in sum_row.iter_mut().zip(src_row.iter()) {
+                for (sum, value) in sum.iter_mut().zip(value) {
+                    *sum += value * weight;
+                }
+            }
+        }
+
+        for (x, column) in columns.iter().enumerate() {
+            let mut sum = [0.0f32; 3];
+            for (value, &weight) in sum_row[column.start..].iter().zip(&column_weights[column.weights.clone()]) {
+                for (sum, value) in sum.iter_mut().zip(value) {
+                    *sum += value * weight;
+                }
+            }
+            dest.set_pixel(x as u32, y as u32, linear_to_rgb(sum));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_weights_sum_to_one() {
+        for (src_len, dest_len) in [(1, 1), (2, 1), (3, 2), (7, 3), (640, 123), (10, 10), (2, 5), (3, 640)] {
+            let (spans, weights) = axis(src_len, dest_len);
+            assert_eq!(spans.len(), dest_len as usize);
+            for span in &spans {
+                let sum: f32 = weights[span.weights.clone()].iter().sum();
+                assert!((sum - 1.0).abs() < 1e-5, "{src_len} -> {dest_len}: {sum}");
+                assert!(span.start + span.weights.len() <= src_len as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn linear_srgb_round_trip() {
+        for value in 0..=255u8 {
+            assert_eq!(linear_to_srgb(srgb_lut()[value as usize]), value);
+        }
+    }
+}
@@ -15,9 +15,12 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::color::Rgb;
-use super::IndexedImage;
+use super::{pixel_count, resample, IndexedImage, LinearPalette};
 use crate::palette::Palette;
 
+/// Pixels converted per iteration of the palette lookup loop.
+const LUT_CHUNK_SIZE: usize = 8;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RgbImage {
     width: u32,
@@ -37,7 +40,7 @@ impl RgbImage {
         Self {
             width,
             height,
-            data: vec![Rgb::default(); width as usize * height as usize].into(),
+            data: vec![Rgb::default(); pixel_count(width, height).expect("image dimensions overflow")].into(),
         }
     }
 
@@ -45,12 +48,12 @@ impl RgbImage {
         Self {
             width,
             height,
-            data: vec![color; width as usize * height as usize].into(),
+            data: vec![color; pixel_count(width, height).expect("image dimensions overflow")].into(),
         }
     }
 
     pub fn from_buffer(width: u32, height: u32, image: &[Rgb]) -> Option<Self> {
-        let size = width as usize * height as usize;
+        let size = pixel_count(width, height)?;
         if image.len() < size {
             return None;
         }
@@ -109,17 +112,81 @@ impl RgbImage {
         self.data.fill(color);
     }
 
+    #[inline]
     pub fn draw_indexed_image(&mut self, indexed_image: &IndexedImage) {
-        let palette = indexed_image.palette();
-        for (index, pixel) in indexed_image.data().iter().cloned().zip(self.data.iter_mut()) {
-            *pixel = palette[index];
-        }
+        self.draw_indexed_image_with_palette(indexed_image, indexed_image.palette());
     }
 
     pub fn draw_indexed_image_with_palette(&mut self, indexed_image: &IndexedImage, palette: &Palette) {
+        // A copy of the palette on the stack and fixed size chunks let the
+        // compiler drop all bounds checks and unroll the loop.
+        let lut: [Rgb; 256] = *palette.0;
+        let len = indexed_image.data().len().min(self.data.len());
+        let mut src = indexed_image.data()[..len].chunks_exact(LUT_CHUNK_SIZE);
+        let mut dest = self.data[..len].chunks_exact_mut(LUT_CHUNK_SIZE);
+
+        for (src, dest) in (&mut src).zip(&mut dest) {
+            for (&index, pixel) in src.iter().zip(dest.iter_mut()) {
+                *pixel = lut[index as usize];
+            }
+        }
+
+        for (&index, pixel) in src.remainder().iter().zip(dest.into_remainder()) {
+            *pixel = lut[index as usize];
+        }
+    }
+
+    /// Like [`RgbImage::draw_indexed_image_with_palette()`], but this image
+    /// already holds `indexed_image` drawn with `old_palette`, so only the
+    /// pixels whose color changed are updated. Returns `false` if no color
+    /// changed.
+    pub fn draw_indexed_image_with_palette_changes(&mut self, indexed_image: &IndexedImage, palette: &Palette, old_palette: &Palette) -> bool {
+        let mut changed = [false; 256];
+        let mut any_changed = false;
+        for (index, (new, old)) in palette.0.iter().zip(old_palette.0.iter()).enumerate() {
+            if new != old {
+                changed[index] = true;
+                any_changed = true;
+            }
+        }
+
+        if !any_changed {
+            return false;
+        }
+
         for (index, pixel) in indexed_image.data().iter().cloned().zip(self.data.iter_mut()) {
-            *pixel = palette[index];
+            if changed[index as usize] {
+                *pixel = palette[index];
+            }
         }
+
+        true
+    }
+
+    /// Scales this image to the size of `dest`, averaging the colors in
+    /// linear light. Works for upscaling too, but is meant for making
+    /// images smaller.
+    pub fn resize_into(&self, dest: &mut RgbImage) {
+        resample::resample(self.width, self.height, dest, |y, row| {
+            let offset = y as usize * self.width as usize;
+            for (linear, &color) in row.iter_mut().zip(&self.data[offset..offset + self.width as usize]) {
+                *linear = resample::rgb_to_linear(color);
+            }
+        });
+    }
+
+    /// Like [`RgbImage::resize_into()`], but draws `indexed_image` with
+    /// `palette` scaled to the size of this image, without the full size
+    /// image in between. `linear` caches the palette in linear light.
+    pub fn draw_resized_indexed_image(&mut self, indexed_image: &IndexedImage, palette: &Palette, linear: &mut LinearPalette) {
+        let lut = linear.update(palette);
+        let width = indexed_image.width() as usize;
+        resample::resample(indexed_image.width(), indexed_image.height(), self, |y, row| {
+            let offset = y as usize * width;
+            for (linear, &index) in row.iter_mut().zip(&indexed_image.data()[offset..offset + width]) {
+                *linear = lut[index as usize];
+            }
+        });
     }
 
     pub fn get_rect_data(&self, x: u32, y: u32, width: u32, height: u32) -> Box<[Rgb]> {
@@ -129,7 +196,7 @@ impl RgbImage {
 
         let width = width.min(self.width - x);
         let height = height.min(self.height - y);
-        let size = width as usize * height as usize;
+        let size = pixel_count(width, height).expect("image dimensions overflow");
 
         let mut data = unsafe { Box::new_uninit_slice(size).assume_init() };
 
@@ -153,8 +220,8 @@ impl RgbImage {
 
     #[inline]
     pub fn get_rect_from(&mut self, x: u32, y: u32, width: u32, height: u32, other: &RgbImage) {
-        let width = width.min(other.width - x);
-        let height = height.min(other.height - y);
+        let width = width.min(other.width.saturating_sub(x));
+        let height = height.min(other.height.saturating_sub(y));
         self.width = width;
         self.height = height;
         self.data = other.get_rect_data(x, y, width, height);
@@ -165,7 +232,7 @@ impl RgbImage {
             return;
         }
 
-        let size = width as usize * height as usize;
+        let size = pixel_count(width, height).expect("image dimensions overflow");
         let mut data: Box<[Rgb]> = vec![color; size].into();
 
         for new_y in 0..height.min(self.height) {
@@ -180,3 +247,56 @@ impl RgbImage {
         self.data = data;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resized(image: &RgbImage, width: u32, height: u32) -> RgbImage {
+        let mut dest = RgbImage::new(width, height);
+        image.resize_into(&mut dest);
+        dest
+    }
+
+    fn test_image(width: u32, height: u32) -> RgbImage {
+        let data: Vec<Rgb> = (0..width * height)
+            .map(|index| Rgb([(index * 13 % 256) as u8, (index * 71 % 256) as u8, (255 - index % 256) as u8]))
+            .collect();
+        RgbImage::from_buffer(width, height, &data).unwrap()
+    }
+
+    #[test]
+    fn resize_to_same_size_is_identity() {
+        for (width, height) in [(1, 1), (5, 3), (16, 9)] {
+            let image = test_image(width, height);
+            assert_eq!(resized(&image, width, height), image);
+        }
+    }
+
+    #[test]
+    fn resize_averages_in_linear_light() {
+        let black = Rgb([0, 0, 0]);
+        let white = Rgb([255, 255, 255]);
+        let image = RgbImage::from_buffer(2, 2, &[black, white, white, black]).unwrap();
+        // half the light is 188 in sRGB, not 128
+        assert_eq!(resized(&image, 1, 1).get_pixel(0, 0), Rgb([188, 188, 188]));
+    }
+
+    #[test]
+    fn resize_keeps_constant_color() {
+        let color = Rgb([12, 150, 201]);
+        let image = RgbImage::from_color(13, 7, color);
+        for (width, height) in [(1, 1), (5, 3), (6, 4), (13, 7)] {
+            assert_eq!(resized(&image, width, height), RgbImage::from_color(width, height, color));
+        }
+    }
+
+    #[test]
+    fn upscale_solid_image() {
+        let color = Rgb([200, 16, 99]);
+        let image = RgbImage::from_color(3, 2, color);
+        for (width, height) in [(6, 4), (7, 5), (640, 480)] {
+            assert_eq!(resized(&image, width, height), RgbImage::from_color(width, height, color));
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::IndexedImage;
+
+/// A point of the path of a sprite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathPoint {
+    /// milliseconds since the start of the path
+    time: u32,
+    x: i32,
+    y: i32,
+}
+
+impl PathPoint {
+    #[inline]
+    pub fn new(time: u32, x: i32, y: i32) -> Self {
+        Self { time, x, y }
+    }
+
+    #[inline]
+    pub fn time(&self) -> u32 {
+        self.time
+    }
+
+    #[inline]
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    #[inline]
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+}
+
+/// A small animated image that moves along a path over the base image, like
+/// a bird or a boat. Its pixels index into the palette of the scene, so it
+/// is color cycled just like the base image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite {
+    width: u32,
+    height: u32,
+    /// all frames, one after another
+    frames: Box<[u8]>,
+    /// milliseconds each frame is shown
+    frame_duration: u32,
+    /// palette index that isn't drawn
+    transparent: Option<u8>,
+    /// sorted by time, the path loops at the time of the last point
+    path: Box<[PathPoint]>,
+}
+
+impl Sprite {
+    /// `frames` holds `width * height` pixels per frame. Returns `None` if
+    /// there are no frames, the size of `frames` isn't a multiple of the
+    /// frame size, or the path is empty or not sorted by time.
+    pub fn new(width: u32, height: u32, frames: Box<[u8]>, frame_duration: u32, transparent: Option<u8>, path: Box<[PathPoint]>) -> Option<Self> {
+        let frame_size = super::pixel_count(width, height)?;
+        if frame_size == 0 || frames.is_empty() || !frames.len().is_multiple_of(frame_size) {
+            return None;
+        }
+
+        if path.is_empty() || path.windows(2).any(|pair| pair[0].time > pair[1].time) {
+            return None;
+        }
+
+        Some(Self { width, height, frames, frame_duration, transparent, path })
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len() / (self.width as usize * self.height as usize)
+    }
+
+    #[inline]
+    pub fn frame_duration(&self) -> u32 {
+        self.frame_duration
+    }
+
+    #[inline]
+    pub fn transparent(&self) -> Option<u8> {
+        self.transparent
+    }
+
+    #[inline]
+    pub fn path(&self) -> &[PathPoint] {
+        &self.path
+    }
+
+    /// Pixels of the frame shown at `time` (in seconds).
+    pub fn frame(&self, time: f64) -> &[u8] {
+        let frame_count = self.frame_count();
+        let index = if frame_count > 1 && self.frame_duration > 0 {
+            (time * 1000.0 / self.frame_duration as f64).rem_euclid(frame_count as f64) as usize
+        } else {
+            0
+        };
+        let frame_size = self.width as usize * self.height as usize;
+        let offset = index.min(frame_count - 1) * frame_size;
+        &self.frames[offset..offset + frame_size]
+    }
+
+    /// Position of the top left corner in image coordinates at `time` (in
+    /// seconds), linearly interpolated between the points of the path.
+    pub fn position(&self, time: f64) -> (i32, i32) {
+        let first = self.path[0];
+        let last = self.path[self.path.len() - 1];
+        let duration = last.time as f64;
+        if duration <= 0.0 {
+            return (last.x, last.y);
+        }
+
+        let time = (time * 1000.0).rem_euclid(duration);
+        if time < first.time as f64 {
+            return (first.x, first.y);
+        }
+
+        for pair in self.path.windows(2) {
+            let [from, to] = [pair[0], pair[1]];
+            if time < to.time as f64 {
+                let span = (to.time - from.time) as f64;
+                let mid = if span > 0.0 { (time - from.time as f64) / span } else { 1.0 };
+                let x = from.x as f64 + (to.x - from.x) as f64 * mid;
+                let y = from.y as f64 + (to.y - from.y) as f64 * mid;
+                return (x.round() as i32, y.round() as i32);
+            }
+        }
+
+        (last.x, last.y)
+    }
+
+    /// Draws the sprite as it is at `time` (in seconds) into `image`, which
+    /// shows the part of the scene that starts at `origin_x`/`origin_y`.
+    pub fn draw(&self, image: &mut IndexedImage, origin_x: u32, origin_y: u32, time: f64) {
+        let (x, y) = self.position(time);
+        let x = x as i64 - origin_x as i64;
+        let y = y as i64 - origin_y as i64;
+        let frame = self.frame(time);
+
+        let image_width = image.width() as i64;
+        let image_height = image.height() as i64;
+        let x_start = x.max(0);
+        let x_end = (x + self.width as i64).min(image_width);
+        if x_start >= x_end {
+            return;
+        }
+
+        for dest_y in y.max(0)..(y + self.height as i64).min(image_height) {
+            let src_y = (dest_y - y) as usize;
+            for dest_x in x_start..x_end {
+                let src_x = (dest_x - x) as usize;
+                let index = frame[src_y * self.width as usize + src_x];
+                if Some(index) != self.transparent {
+                    image.set_index(dest_x as u32, dest_y as u32, index);
+                }
+            }
+        }
+    }
+}
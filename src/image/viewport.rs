@@ -0,0 +1,408 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use super::CycleImage;
+
+/// Pixels of the area per image pixel along one axis, as a fraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scale {
+    num: u32,
+    den: u32,
+}
+
+impl Scale {
+    pub const ONE: Scale = Scale { num: 1, den: 1 };
+
+    /// Zeros are treated as 1.
+    #[inline]
+    pub fn new(num: u32, den: u32) -> Self {
+        Self { num: num.max(1), den: den.max(1) }
+    }
+
+    #[inline]
+    pub fn num(&self) -> u32 {
+        self.num
+    }
+
+    #[inline]
+    pub fn den(&self) -> u32 {
+        self.den
+    }
+
+    #[inline]
+    pub fn times(self, factor: u32) -> Self {
+        Self::new(self.num * factor, self.den)
+    }
+
+    /// Pixels of the area covered by `size` image pixels, rounded down.
+    #[inline]
+    pub fn to_area(self, size: u32) -> u32 {
+        (size as u64 * self.num as u64 / self.den as u64) as u32
+    }
+
+    /// The image pixel shown at the pixel `pos` of the area, which is also
+    /// how many whole image pixels fit into `pos` pixels of the area.
+    #[inline]
+    pub fn to_image(self, pos: u32) -> u32 {
+        (pos as u64 * self.den as u64 / self.num as u64) as u32
+    }
+
+    /// The first pixel of the area that shows the image pixel `pos`.
+    #[inline]
+    pub fn start_of(self, pos: u32) -> u32 {
+        (pos as u64 * self.num as u64).div_ceil(self.den as u64) as u32
+    }
+
+    /// Whether the pixel `pos` of the area is the first one of an image
+    /// pixel.
+    #[inline]
+    pub fn is_start(self, pos: u32) -> bool {
+        pos == 0 || self.to_image(pos) != self.to_image(pos - 1)
+    }
+}
+
+/// The part of an image that is shown in an area (the terminal) of a given
+/// size. An image smaller than the area is centered in it, otherwise the
+/// position of the view-port is kept within the image.
+///
+/// With a zoom factor above 1 every image pixel covers that many pixels of
+/// the area in both directions, so less of the image fits. On top of that
+/// the pixels can be stretched along one axis, see [`Viewport::set_stretch()`].
+/// Positions are always in image coordinates, sizes of the area in pixels of
+/// the area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    image_width: u32,
+    image_height: u32,
+    width: u32,
+    height: u32,
+    zoom: u32,
+    /// horizontal and vertical stretch of the pixels before zooming
+    stretch: (Scale, Scale),
+    /// image coordinates of the top left corner
+    x: u32,
+    y: u32,
+}
+
+impl Viewport {
+    /// Creates a view-port of `width` x `height` pixels centered on the
+    /// image.
+    pub fn new(image_width: u32, image_height: u32, width: u32, height: u32) -> Self {
+        let mut viewport = Self { image_width, image_height, width, height, zoom: 1, stretch: (Scale::ONE, Scale::ONE), x: 0, y: 0 };
+        viewport.center();
+        viewport
+    }
+
+    #[inline]
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    #[inline]
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    #[inline]
+    pub fn position(&self) -> (u32, u32) {
+        (self.x, self.y)
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn image_size(&self) -> (u32, u32) {
+        (self.image_width, self.image_height)
+    }
+
+    #[inline]
+    pub fn zoom(&self) -> u32 {
+        self.zoom
+    }
+
+    /// Changes the zoom factor, keeping the same image coordinates in the
+    /// center of the area. Returns `false` if it didn't change.
+    pub fn set_zoom(&mut self, zoom: u32) -> bool {
+        let zoom = zoom.max(1);
+        if zoom == self.zoom {
+            return false;
+        }
+        self.keep_center(|viewport| viewport.zoom = zoom);
+        true
+    }
+
+    #[inline]
+    pub fn stretch(&self) -> (Scale, Scale) {
+        self.stretch
+    }
+
+    /// Stretches the pixels horizontally and vertically, e.g. to make up for
+    /// terminal cells that aren't exactly twice as high as wide. Keeps the
+    /// same image coordinates in the center of the area.
+    pub fn set_stretch(&mut self, stretch: (Scale, Scale)) {
+        self.keep_center(|viewport| viewport.stretch = stretch);
+    }
+
+    fn keep_center(&mut self, change: impl FnOnce(&mut Self)) {
+        let (view_width, view_height) = self.view_size();
+        let center_x = self.x as f64 + view_width.min(self.image_width) as f64 / 2.0;
+        let center_y = self.y as f64 + view_height.min(self.image_height) as f64 / 2.0;
+        change(self);
+        self.center_on(center_x, center_y);
+    }
+
+    /// Pixels of the area per image pixel, horizontally and vertically.
+    #[inline]
+    pub fn scale(&self) -> (Scale, Scale) {
+        (self.stretch.0.times(self.zoom), self.stretch.1.times(self.zoom))
+    }
+
+    /// Size of the whole image in pixels of the area when not zoomed.
+    #[inline]
+    pub fn stretched_image_size(&self) -> (u32, u32) {
+        (self.stretch.0.to_area(self.image_width), self.stretch.1.to_area(self.image_height))
+    }
+
+    /// Size of the area in image pixels.
+    #[inline]
+    pub fn view_size(&self) -> (u32, u32) {
+        let (scale_x, scale_y) = self.scale();
+        (scale_x.to_image(self.width), scale_y.to_image(self.height))
+    }
+
+    /// Size of the shown part of the image, in image pixels.
+    #[inline]
+    pub fn visible_size(&self) -> (u32, u32) {
+        let (view_width, view_height) = self.view_size();
+        (self.image_width.min(view_width), self.image_height.min(view_height))
+    }
+
+    /// Size of the shown part of the image, in pixels of the area.
+    #[inline]
+    pub fn shown_size(&self) -> (u32, u32) {
+        let (width, height) = self.visible_size();
+        let (scale_x, scale_y) = self.scale();
+        (scale_x.to_area(width), scale_y.to_area(height))
+    }
+
+    #[inline]
+    pub fn max_x(&self) -> u32 {
+        self.image_width.saturating_sub(self.view_size().0)
+    }
+
+    #[inline]
+    pub fn max_y(&self) -> u32 {
+        self.image_height.saturating_sub(self.view_size().1)
+    }
+
+    /// Where the image starts in the area, which is not 0 if the image is
+    /// smaller than the area.
+    #[inline]
+    pub fn offset(&self) -> (u32, u32) {
+        let (shown_width, shown_height) = self.shown_size();
+        (self.width.saturating_sub(shown_width) / 2, self.height.saturating_sub(shown_height) / 2)
+    }
+
+    /// Changes the size of the area, keeping the position within the image.
+    #[inline]
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.clamp();
+    }
+
+    /// Keeps the position within the image.
+    #[inline]
+    pub fn clamp(&mut self) {
+        self.x = self.x.min(self.max_x());
+        self.y = self.y.min(self.max_y());
+    }
+
+    /// Moves the view-port to `x`/`y`, or as close as possible. Returns
+    /// `false` if it didn't move at all.
+    pub fn set_position(&mut self, x: u32, y: u32) -> bool {
+        let old = self.position();
+        self.x = x.min(self.max_x());
+        self.y = y.min(self.max_y());
+        old != self.position()
+    }
+
+    /// Moves the view-port by `dx`/`dy` pixels, stopping at the edges of
+    /// the image. Returns `false` if it didn't move at all.
+    pub fn pan(&mut self, dx: i64, dy: i64) -> bool {
+        let x = (self.x as i64 + dx).clamp(0, self.max_x() as i64);
+        let y = (self.y as i64 + dy).clamp(0, self.max_y() as i64);
+        self.set_position(x as u32, y as u32)
+    }
+
+    /// Moves the view-port to the center of the image.
+    #[inline]
+    pub fn center(&mut self) {
+        self.x = self.max_x() / 2;
+        self.y = self.max_y() / 2;
+    }
+
+    /// Moves the view-port so that the image coordinates `x`/`y` are in its
+    /// center, or as close as possible.
+    pub fn center_on(&mut self, x: f64, y: f64) {
+        let (view_width, view_height) = self.view_size();
+        let x = (x - view_width as f64 / 2.0).round().clamp(0.0, self.max_x() as f64);
+        let y = (y - view_height as f64 / 2.0).round().clamp(0.0, self.max_y() as f64);
+        self.set_position(x as u32, y as u32);
+    }
+
+    /// The shown part of `image`.
+    #[inline]
+    pub fn rect(&self, image: &CycleImage) -> CycleImage {
+        let (width, height) = self.visible_size();
+        image.get_rect(self.x, self.y, width, height)
+    }
+
+    /// Copies the shown part of `image` into `output`.
+    #[inline]
+    pub fn rect_into(&self, image: &CycleImage, output: &mut CycleImage) {
+        let (width, height) = self.view_size();
+        output.get_rect_from(self.x, self.y, width, height, image);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::IndexedImage;
+    use crate::palette::Palette;
+
+    #[test]
+    fn pan_clamps_at_each_edge() {
+        let mut viewport = Viewport::new(100, 60, 40, 20);
+        assert_eq!(viewport.position(), (30, 20));
+
+        assert!(viewport.pan(-1000, 0));
+        assert_eq!(viewport.x(), 0);
+        assert!(!viewport.pan(-1, 0));
+
+        assert!(viewport.pan(1000, 0));
+        assert_eq!(viewport.x(), 60);
+        assert!(!viewport.pan(1, 0));
+
+        assert!(viewport.pan(0, -1000));
+        assert_eq!(viewport.y(), 0);
+        assert!(!viewport.pan(0, -1));
+
+        assert!(viewport.pan(0, 1000));
+        assert_eq!(viewport.y(), 40);
+        assert!(!viewport.pan(0, 1));
+
+        assert!(viewport.set_position(0, 0));
+        assert!(!viewport.set_position(0, 0));
+        viewport.set_position(u32::MAX, u32::MAX);
+        assert_eq!(viewport.position(), (viewport.max_x(), viewport.max_y()));
+    }
+
+    #[test]
+    fn resize_keeps_position_within_image() {
+        let mut viewport = Viewport::new(100, 60, 40, 20);
+        viewport.set_position(60, 40);
+        viewport.resize(80, 50);
+        assert_eq!(viewport.position(), (20, 10));
+        viewport.resize(200, 100);
+        assert_eq!(viewport.position(), (0, 0));
+    }
+
+    #[test]
+    fn small_image_is_centered() {
+        let viewport = Viewport::new(20, 10, 80, 50);
+        assert_eq!(viewport.position(), (0, 0));
+        assert_eq!(viewport.max_x(), 0);
+        assert_eq!(viewport.max_y(), 0);
+        assert_eq!(viewport.visible_size(), (20, 10));
+        assert_eq!(viewport.shown_size(), (20, 10));
+        assert_eq!(viewport.offset(), (30, 20));
+    }
+
+    #[test]
+    fn odd_height_starts_in_lower_half_of_cell() {
+        // 2 pixels per terminal row, so an odd offset means phase 1
+        let viewport = Viewport::new(20, 9, 80, 50);
+        let (_, offset_y) = viewport.offset();
+        assert_eq!(offset_y, 20);
+        assert_eq!(offset_y % 2, 0);
+
+        let viewport = Viewport::new(20, 11, 80, 50);
+        let (_, offset_y) = viewport.offset();
+        assert_eq!(offset_y, 19);
+        assert_eq!(offset_y % 2, 1);
+        // the image still ends within the area
+        assert!(offset_y + viewport.shown_size().1 <= viewport.height());
+    }
+
+    #[test]
+    fn zoom_keeps_center() {
+        let mut viewport = Viewport::new(100, 100, 20, 20);
+        assert_eq!(viewport.position(), (40, 40));
+        assert!(viewport.set_zoom(2));
+        assert!(!viewport.set_zoom(2));
+        assert_eq!(viewport.view_size(), (10, 10));
+        assert_eq!(viewport.position(), (45, 45));
+        assert_eq!(viewport.max_x(), 90);
+        assert_eq!(viewport.shown_size(), (20, 20));
+
+        // 0 is treated as 1
+        viewport.set_zoom(0);
+        assert_eq!(viewport.zoom(), 1);
+        assert_eq!(viewport.position(), (40, 40));
+    }
+
+    #[test]
+    fn center_on_clamps() {
+        let mut viewport = Viewport::new(100, 60, 40, 20);
+        viewport.center_on(0.0, 0.0);
+        assert_eq!(viewport.position(), (0, 0));
+        viewport.center_on(50.0, 30.0);
+        assert_eq!(viewport.position(), (30, 20));
+        viewport.center_on(1000.0, 1000.0);
+        assert_eq!(viewport.position(), (60, 40));
+    }
+
+    #[test]
+    fn rect_at_bottom_right_corner() {
+        let (width, height) = (7, 5);
+        let data: Box<[u8]> = (0..width * height).map(|index| index as u8).collect();
+        let indexed_image = IndexedImage::from_buffer(width, height, data, Palette::default()).unwrap();
+        let image = CycleImage::new(None, indexed_image, Box::new([]));
+
+        let mut viewport = Viewport::new(width, height, 3, 2);
+        viewport.set_position(u32::MAX, u32::MAX);
+        assert_eq!(viewport.position(), (4, 3));
+
+        let rect = viewport.rect(&image);
+        assert_eq!(rect.indexed_image().size(), (3, 2));
+        assert_eq!(rect.indexed_image().data(), &[25, 26, 27, 32, 33, 34]);
+
+        let mut output = CycleImage::new(None, IndexedImage::new(0, 0, Palette::default()), Box::new([]));
+        viewport.rect_into(&image, &mut output);
+        assert_eq!(output.indexed_image().data(), rect.indexed_image().data());
+    }
+}
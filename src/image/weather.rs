@@ -0,0 +1,52 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use std::{fmt::Display, str::FromStr};
+
+/// Procedural weather drawn over a scene.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weather {
+    /// Falling rain streaks
+    Rain,
+    /// Drifting snow flakes
+    Snow,
+    /// Fog that rises from the bottom, thickest at dawn
+    Fog,
+}
+
+impl Display for Weather {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Weather::Rain => "rain".fmt(f),
+            Weather::Snow => "snow".fmt(f),
+            Weather::Fog  => "fog".fmt(f),
+        }
+    }
+}
+
+impl FromStr for Weather {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "rain" => Ok(Weather::Rain),
+            "snow" => Ok(Weather::Snow),
+            "fog"  => Ok(Weather::Fog),
+            _ => Err(format!("unknown weather: {value:?}, expected one of: rain, snow, fog")),
+        }
+    }
+}
@@ -65,7 +65,28 @@ fn move_cursor(curr_x: u32, curr_line_y: u32, x: u32, line_y: u32, lines: &mut S
     }
 }
 
+#[inline]
 pub fn image_to_ansi_into(prev_frame: &RgbImage, image: &RgbImage, full_width: bool, lines: &mut String) {
+    image_to_ansi_phased_into(prev_frame, image, full_width, 0, lines);
+}
+
+/// Pixel at row `y` of the cell grid when the image starts `phase` pixel
+/// rows down. The pixel above the image is black.
+#[inline]
+fn phased_pixel(image: &RgbImage, x: u32, y: u32, phase: u32) -> Rgb {
+    if y < phase {
+        Rgb([0, 0, 0])
+    } else {
+        image.get_pixel(x, y - phase)
+    }
+}
+
+/// Like [`image_to_ansi_into()`], but shifts the image down by `phase`
+/// (0 or 1) pixel rows, so that the image can start in the lower half of
+/// a cell. The upper half of the first line is drawn black then.
+pub fn image_to_ansi_phased_into(prev_frame: &RgbImage, image: &RgbImage, full_width: bool, phase: u32, lines: &mut String) {
+    debug_assert!(phase < 2);
+
     if prev_frame.width() < image.width() {
         panic!("prev_frame.width() < image.width(): {:?} < {:?}", prev_frame.width(), image.width());
     }
@@ -74,7 +95,8 @@ pub fn image_to_ansi_into(prev_frame: &RgbImage, image: &RgbImage, full_width: b
         panic!("prev_frame.height() < image.height(): {:?} < {:?}", prev_frame.height(), image.height());
     }
 
-    let row_count = image.height().div_ceil(2);
+    let height = image.height() + phase;
+    let row_count = height.div_ceil(2);
 
     lines.clear();
 
@@ -93,11 +115,11 @@ pub fn image_to_ansi_into(prev_frame: &RgbImage, image: &RgbImage, full_width: b
     for line_y in 0..row_count {
         let y = line_y * 2;
         let mut line_start = true;
-        if y + 1 == image.height() {
+        if y + 1 == height {
             let mut prev_color = Rgb([0, 0, 0]);
             for x in 0..image.width() {
-                let color = image.get_pixel(x, y);
-                if color != prev_frame.get_pixel(x, y) {
+                let color = phased_pixel(image, x, y, phase);
+                if color != phased_pixel(prev_frame, x, y, phase) {
                     move_cursor(curr_x, curr_line_y, x, line_y, lines);
                     let Rgb([r, g, b]) = color;
                     if !line_start && color == prev_color {
@@ -121,10 +143,10 @@ pub fn image_to_ansi_into(prev_frame: &RgbImage, image: &RgbImage, full_width: b
             let mut prev_bg = Rgb([0, 0, 0]);
             let mut prev_fg = Rgb([0, 0, 0]);
             for x in 0..image.width() {
-                let color_top    = image.get_pixel(x, y);
-                let color_bottom = image.get_pixel(x, y + 1);
+                let color_top    = phased_pixel(image, x, y, phase);
+                let color_bottom = phased_pixel(image, x, y + 1, phase);
 
-                if color_top != prev_frame.get_pixel(x, y) || color_bottom != prev_frame.get_pixel(x, y + 1) {
+                if color_top != phased_pixel(prev_frame, x, y, phase) || color_bottom != phased_pixel(prev_frame, x, y + 1, phase) {
                     move_cursor(curr_x, curr_line_y, x, line_y, lines);
                     let Rgb([r1, g1, b1]) = color_top;
 
@@ -175,9 +197,14 @@ pub fn image_to_ansi_into(prev_frame: &RgbImage, image: &RgbImage, full_width: b
         }
     }
 
-    // Just to ensure that the cursor is at the correct position after
-    // the image is rendered or when hitting Ctrl+C during sleep.
-    let dx = image.width() - curr_x;
+    move_cursor_to_end(curr_x, curr_line_y, image.width(), row_count, lines);
+}
+
+/// Just to ensure that the cursor is at the correct position after
+/// the image is rendered or when hitting Ctrl+C during sleep.
+#[inline]
+fn move_cursor_to_end(curr_x: u32, curr_line_y: u32, width: u32, row_count: u32, lines: &mut String) {
+    let dx = width - curr_x;
     if dx > 0 {
         if dx == 1 {
             lines.push_str("\x1B[C");
@@ -196,8 +223,29 @@ pub fn image_to_ansi_into(prev_frame: &RgbImage, image: &RgbImage, full_width: b
     }
 }
 
+#[inline]
 pub fn simple_image_to_ansi_into(image: &RgbImage, lines: &mut String) {
-    let row_count = image.height().div_ceil(2);
+    simple_image_to_ansi_impl(image, 0, false, lines);
+}
+
+/// Like [`simple_image_to_ansi_into()`], but shifts the image down by
+/// `phase` pixel rows, see [`image_to_ansi_phased_into()`].
+#[inline]
+pub fn simple_image_to_ansi_phased_into(image: &RgbImage, phase: u32, lines: &mut String) {
+    debug_assert!(phase < 2);
+    simple_image_to_ansi_impl(image, phase, false, lines);
+}
+
+/// Like [`simple_image_to_ansi_into()`], but separates the lines with
+/// newlines instead of cursor movements. For output that isn't a terminal.
+#[inline]
+pub fn image_to_ansi_text_into(image: &RgbImage, lines: &mut String) {
+    simple_image_to_ansi_impl(image, 0, true, lines);
+}
+
+fn simple_image_to_ansi_impl(image: &RgbImage, phase: u32, newlines: bool, lines: &mut String) {
+    let height = image.height() + phase;
+    let row_count = height.div_ceil(2);
 
     lines.clear();
 
@@ -212,13 +260,17 @@ pub fn simple_image_to_ansi_into(image: &RgbImage, lines: &mut String) {
 
     for line_y in 0..row_count {
         if line_y > 0 {
-            let _ = write!(lines, "\x1B[{}D\x1B[1B", width);
+            if newlines {
+                lines.push_str("\x1B[0m\n");
+            } else {
+                let _ = write!(lines, "\x1B[{}D\x1B[1B", width);
+            }
         }
         let y = line_y * 2;
-        if y + 1 == image.height() {
+        if y + 1 == height {
             let mut prev_color = Rgb([0, 0, 0]);
             for x in 0..image.width() {
-                let color = image.get_pixel(x, y);
+                let color = phased_pixel(image, x, y, phase);
                 let Rgb([r, g, b]) = color;
                 if x > 0 && color == prev_color {
                     lines.push('▀');
@@ -231,8 +283,8 @@ pub fn simple_image_to_ansi_into(image: &RgbImage, lines: &mut String) {
             let mut prev_bg = Rgb([0, 0, 0]);
             let mut prev_fg = Rgb([0, 0, 0]);
             for x in 0..image.width() {
-                let color_top    = image.get_pixel(x, y);
-                let color_bottom = image.get_pixel(x, y + 1);
+                let color_top    = phased_pixel(image, x, y, phase);
+                let color_bottom = phased_pixel(image, x, y + 1, phase);
 
                 let Rgb([r1, g1, b1]) = color_top;
 
@@ -275,3 +327,112 @@ pub fn simple_image_to_ansi_into(image: &RgbImage, lines: &mut String) {
 
     lines.push_str("\x1B[0m");
 }
+
+/// Characters used by [`blocks_image_to_ansi_into()`], from dark to bright.
+pub const BLOCK_RAMP: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Index into [`BLOCK_RAMP`] for the cell at pixel row `y` of the cell grid.
+#[inline]
+fn block_index(image: &RgbImage, x: u32, y: u32, phase: u32) -> usize {
+    let top = phased_pixel(image, x, y, phase).luminance();
+    let bottom = if y + 1 < image.height() + phase {
+        phased_pixel(image, x, y + 1, phase).luminance()
+    } else {
+        top
+    };
+    let value = (top + bottom) * 0.5;
+    (value * (BLOCK_RAMP.len() - 1) as f64).round() as usize
+}
+
+/// Renders the image without any colors, using one character of
+/// [`BLOCK_RAMP`] per cell for the mean luminance of its two pixels.
+///
+/// Only cells that differ from `prev_frame` are emitted. Pass `None` to
+/// draw all cells.
+pub fn blocks_image_to_ansi_into(prev_frame: Option<&RgbImage>, image: &RgbImage, full_width: bool, phase: u32, lines: &mut String) {
+    let width = image.width();
+    let row_count = (image.height() + phase).div_ceil(2);
+
+    lines.clear();
+
+    if row_count == 0 {
+        return;
+    }
+
+    lines.push_str("\x1B[0m");
+
+    let mut curr_line_y = 0;
+    let mut curr_x = 0;
+
+    for line_y in 0..row_count {
+        let y = line_y * 2;
+        for x in 0..width {
+            let index = block_index(image, x, y, phase);
+            if let Some(prev_frame) = prev_frame && block_index(prev_frame, x, y, phase) == index {
+                continue;
+            }
+
+            move_cursor(curr_x, curr_line_y, x, line_y, lines);
+            lines.push(BLOCK_RAMP[index]);
+
+            // NOTE: Cursor location doesn't update at the end of the screen.
+            if full_width && (x + 1) == width {
+                curr_x = x;
+            } else {
+                curr_x = x + 1;
+            }
+            curr_line_y = line_y;
+        }
+    }
+
+    move_cursor_to_end(curr_x, curr_line_y, width, row_count, lines);
+}
+
+/// Like [`blocks_image_to_ansi_into()`], but produces plain text lines.
+pub fn blocks_image_to_text_into(image: &RgbImage, lines: &mut String) {
+    let row_count = image.height().div_ceil(2);
+
+    lines.clear();
+
+    for line_y in 0..row_count {
+        if line_y > 0 {
+            lines.push('\n');
+        }
+        for x in 0..image.width() {
+            lines.push(BLOCK_RAMP[block_index(image, x, line_y * 2, 0)]);
+        }
+    }
+}
+
+/// Copies `image` into `output` and paints every terminal cell that differs
+/// from `prev_frame` in `color`.
+///
+/// These are exactly the cells that [`image_to_ansi_phased_into()`] would
+/// re-emit for the same `phase`.
+pub fn highlight_changes(prev_frame: &RgbImage, image: &RgbImage, phase: u32, color: Rgb, output: &mut RgbImage) {
+    output.clone_from(image);
+
+    let width = image.width();
+    let height = image.height();
+
+    for cell_y in (0..height + phase).step_by(2) {
+        // pixel rows of the image that are covered by this line of cells
+        let top = cell_y.checked_sub(phase);
+        let bottom = cell_y + 1 - phase;
+        let has_bottom = bottom < height;
+        for x in 0..width {
+            let changed =
+                top.is_some_and(|y| image.get_pixel(x, y) != prev_frame.get_pixel(x, y)) ||
+                (has_bottom && image.get_pixel(x, bottom) != prev_frame.get_pixel(x, bottom));
+
+            if changed {
+                if let Some(y) = top {
+                    output.set_pixel(x, y, color);
+                }
+                if has_bottom {
+                    output.set_pixel(x, bottom, color);
+                }
+            }
+        }
+    }
+}
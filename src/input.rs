@@ -0,0 +1,843 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_ESCAPE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Sent by the terminal after pasted text when bracketed paste mode
+/// (DECSET 2004) is enabled. The text is preceded by `CSI 200 ~`.
+const PASTE_END: &[u8] = b"\x1B[201~";
+
+/// Pasted text beyond this is discarded.
+const MAX_PASTE_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE:  Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(1);
+    pub const ALT:   Modifiers = Modifiers(2);
+    pub const CTRL:  Modifiers = Modifiers(4);
+
+    #[inline]
+    pub fn contains(&self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Decodes the modifier parameter of xterm style escape sequences,
+    /// e.g. the `5` in `CSI 1 ; 5 H`.
+    #[inline]
+    fn from_xterm(param: u32) -> Self {
+        Modifiers((param.saturating_sub(1) & 7) as u8)
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Modifiers;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    #[inline]
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self { key, modifiers }
+    }
+
+    #[inline]
+    pub fn plain(key: Key) -> Self {
+        Self { key, modifiers: Modifiers::NONE }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WheelDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Key(KeyEvent),
+    /// Text pasted in bracketed paste mode.
+    Paste(String),
+    /// Answer to a cursor position query, see [`crate::latency`].
+    CursorPosition { row: u32, column: u32 },
+    /// Mouse wheel, reported when SGR mouse reporting (DECSET 1000 and
+    /// 1006) is enabled. Other mouse events are ignored.
+    Wheel { direction: WheelDirection, modifiers: Modifiers },
+}
+
+enum Parsed {
+    Event(Event, usize),
+    /// Start of bracketed paste. Skip that many bytes.
+    PasteStart(usize),
+    /// Not a valid or not a supported input sequence. Skip that many bytes.
+    Ignore(usize),
+    /// Need more bytes to decide.
+    Incomplete,
+}
+
+/// Reassembles key presses from the raw bytes read from the terminal.
+///
+/// Escape sequences might arrive split over several reads on slow
+/// connections. Incomplete sequences are kept until the rest arrives or
+/// the escape timeout passed. Only then a lone `ESC` is reported as the
+/// escape key.
+///
+/// Text between the bracketed paste markers is reported as a single
+/// [`Event::Paste`] instead of as individual key presses.
+#[derive(Debug)]
+pub struct InputParser {
+    buf: Vec<u8>,
+    escape_timeout: Duration,
+    pending_since: Option<Instant>,
+    paste: Option<Vec<u8>>,
+    /// First half of a UTF-16 surrogate pair read from the Windows console.
+    #[cfg(windows)]
+    high_surrogate: Option<u16>,
+}
+
+impl InputParser {
+    pub fn new(escape_timeout: Duration) -> Self {
+        Self {
+            buf: Vec::new(),
+            escape_timeout,
+            pending_since: None,
+            paste: None,
+            #[cfg(windows)]
+            high_surrogate: None,
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        if self.buf.is_empty() {
+            self.pending_since = None;
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Discards all buffered input.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.pending_since = None;
+        self.paste = None;
+    }
+
+    pub fn next_event(&mut self, now: Instant) -> Option<Event> {
+        loop {
+            if let Some(paste) = &mut self.paste {
+                // Pasted text can arrive in many reads and isn't subject to
+                // the escape timeout.
+                if let Some(index) = find(&self.buf, PASTE_END) {
+                    append_paste(paste, &self.buf[..index]);
+                    self.buf.drain(..index + PASTE_END.len());
+                    let text = String::from_utf8_lossy(paste).into_owned();
+                    self.paste = None;
+                    return Some(Event::Paste(text));
+                }
+
+                // keep what could be the start of the end marker
+                let keep = (1..PASTE_END.len()).rev()
+                    .find(|&len| self.buf.ends_with(&PASTE_END[..len]))
+                    .unwrap_or(0);
+                let len = self.buf.len() - keep;
+                append_paste(paste, &self.buf[..len]);
+                self.buf.drain(..len);
+                return None;
+            }
+
+            if self.buf.is_empty() {
+                self.pending_since = None;
+                return None;
+            }
+
+            match parse(&self.buf) {
+                Parsed::Event(event, len) => {
+                    self.buf.drain(..len);
+                    self.pending_since = None;
+                    return Some(event);
+                }
+                Parsed::Ignore(len) => {
+                    self.buf.drain(..len);
+                    self.pending_since = None;
+                }
+                Parsed::PasteStart(len) => {
+                    self.buf.drain(..len);
+                    self.pending_since = None;
+                    self.paste = Some(Vec::new());
+                }
+                Parsed::Incomplete => {
+                    let since = *self.pending_since.get_or_insert(now);
+                    if now.duration_since(since) < self.escape_timeout {
+                        return None;
+                    }
+
+                    self.pending_since = None;
+                    if self.buf == [0x1B] {
+                        // lone ESC
+                        self.buf.clear();
+                        return Some(Event::Key(KeyEvent::plain(Key::Escape)));
+                    }
+
+                    // Truncated escape sequence or UTF-8 character. Drop it
+                    // instead of interpreting its bytes as individual key
+                    // presses.
+                    self.buf.clear();
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[inline]
+fn append_paste(paste: &mut Vec<u8>, bytes: &[u8]) {
+    let len = bytes.len().min(MAX_PASTE_SIZE - paste.len());
+    paste.extend_from_slice(&bytes[..len]);
+}
+
+fn parse(buf: &[u8]) -> Parsed {
+    let byte = buf[0];
+
+    match byte {
+        0x1B => parse_escape(buf),
+        b'\r' | b'\n' => Parsed::Event(Event::Key(KeyEvent::plain(Key::Enter)), 1),
+        b'\t' => Parsed::Event(Event::Key(KeyEvent::plain(Key::Tab)), 1),
+        0x7F | 0x08 => Parsed::Event(Event::Key(KeyEvent::plain(Key::Backspace)), 1),
+        0x01..=0x1A => {
+            let ch = (b'a' + byte - 1) as char;
+            Parsed::Event(Event::Key(KeyEvent::new(Key::Char(ch), Modifiers::CTRL)), 1)
+        }
+        0x20..=0x7E => Parsed::Event(Event::Key(KeyEvent::plain(Key::Char(byte as char))), 1),
+        0xC2..=0xF4 => parse_utf8(buf),
+        _ => Parsed::Ignore(1),
+    }
+}
+
+/// Decodes a multi-byte UTF-8 character, e.g. typed with an IME or a
+/// compose key. Invalid bytes are skipped one at a time, so that no part of
+/// them is taken for a key press.
+fn parse_utf8(buf: &[u8]) -> Parsed {
+    let len = match buf[0] {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    };
+
+    match std::str::from_utf8(&buf[..len.min(buf.len())]) {
+        Ok(text) => match text.chars().next() {
+            Some(ch) => Parsed::Event(Event::Key(KeyEvent::plain(Key::Char(ch))), len),
+            None => Parsed::Ignore(1),
+        },
+        // the rest of the character didn't arrive yet
+        Err(err) if err.error_len().is_none() => Parsed::Incomplete,
+        Err(_) => Parsed::Ignore(1),
+    }
+}
+
+fn parse_escape(buf: &[u8]) -> Parsed {
+    let Some(&byte) = buf.get(1) else {
+        return Parsed::Incomplete;
+    };
+
+    match byte {
+        b'[' => parse_csi(buf),
+        b'O' => {
+            // SS3, sent for cursor keys in application mode
+            let Some(&byte) = buf.get(2) else {
+                return Parsed::Incomplete;
+            };
+            match cursor_key(byte) {
+                Some(key) => Parsed::Event(Event::Key(KeyEvent::plain(key)), 3),
+                None => Parsed::Ignore(3),
+            }
+        }
+        b']' | b'P' | b'_' | b'^' | b'X' => {
+            // OSC, e.g. a late answer to a color query, or DCS, APC, PM,
+            // SOS, e.g. a late answer to a version query. Ends with BEL
+            // (only OSC) or ST.
+            for index in 2..buf.len() {
+                match buf[index] {
+                    0x07 => return Parsed::Ignore(index + 1),
+                    0x1B => {
+                        let Some(&next) = buf.get(index + 1) else {
+                            return Parsed::Incomplete;
+                        };
+                        return Parsed::Ignore(if next == b'\\' { index + 2 } else { index });
+                    }
+                    _ => {}
+                }
+            }
+            Parsed::Incomplete
+        }
+        0x1B => Parsed::Event(Event::Key(KeyEvent::plain(Key::Escape)), 1),
+        _ => match parse(&buf[1..]) {
+            Parsed::Event(Event::Key(event), len) => {
+                Parsed::Event(Event::Key(KeyEvent::new(event.key, event.modifiers | Modifiers::ALT)), len + 1)
+            }
+            Parsed::Event(Event::Paste(_) | Event::CursorPosition { .. } | Event::Wheel { .. }, len) |
+            Parsed::Ignore(len) | Parsed::PasteStart(len) => Parsed::Ignore(len + 1),
+            Parsed::Incomplete => Parsed::Incomplete,
+        }
+    }
+}
+
+#[inline]
+fn cursor_key(byte: u8) -> Option<Key> {
+    match byte {
+        b'A' => Some(Key::Up),
+        b'B' => Some(Key::Down),
+        b'C' => Some(Key::Right),
+        b'D' => Some(Key::Left),
+        b'H' => Some(Key::Home),
+        b'F' => Some(Key::End),
+        _ => None,
+    }
+}
+
+fn parse_csi(buf: &[u8]) -> Parsed {
+    // CSI parameter bytes, intermediate bytes, final byte
+    let mut index = 2;
+    while let Some(&byte) = buf.get(index) {
+        if byte >= 0x40 && byte <= 0x7E {
+            break;
+        }
+        if byte < 0x20 || byte > 0x3F {
+            // not a valid CSI sequence
+            return Parsed::Ignore(index);
+        }
+        index += 1;
+    }
+
+    let Some(&final_byte) = buf.get(index) else {
+        return Parsed::Incomplete;
+    };
+    let len = index + 1;
+
+    let Ok(params) = std::str::from_utf8(&buf[2..index]) else {
+        return Parsed::Ignore(len);
+    };
+
+    if let Some(params) = params.strip_prefix('<') {
+        return parse_sgr_mouse(params, final_byte, len);
+    }
+
+    if params.starts_with(['?', '=', '>']) {
+        return Parsed::Ignore(len);
+    }
+
+    let params: Vec<_> = params.split(';').map(|param| param.parse::<u32>().ok()).collect();
+    if final_byte == b'R' && let [Some(row), Some(column)] = params[..] {
+        return Parsed::Event(Event::CursorPosition { row, column }, len);
+    }
+
+    let first = params.first().copied().flatten();
+    let modifiers = params.get(1).copied().flatten().map(Modifiers::from_xterm).unwrap_or_default();
+
+    let key = if final_byte == b'~' {
+        match first {
+            Some(1) | Some(7) => Key::Home,
+            Some(2) => Key::Insert,
+            Some(3) => Key::Delete,
+            Some(4) | Some(8) => Key::End,
+            Some(5) => Key::PageUp,
+            Some(6) => Key::PageDown,
+            Some(200) => return Parsed::PasteStart(len),
+            _ => return Parsed::Ignore(len),
+        }
+    } else if let Some(key) = cursor_key(final_byte) {
+        key
+    } else {
+        return Parsed::Ignore(len);
+    };
+
+    Parsed::Event(Event::Key(KeyEvent::new(key, modifiers)), len)
+}
+
+/// Parses the parameters of `CSI < button ; column ; row M`. Wheel
+/// events are button 64 to 67 plus 4 for Shift, 8 for Alt and 16 for Ctrl.
+fn parse_sgr_mouse(params: &str, final_byte: u8, len: usize) -> Parsed {
+    if final_byte != b'M' {
+        // button release
+        return Parsed::Ignore(len);
+    }
+    let Some(Ok(button)) = params.split(';').next().map(str::parse::<u32>) else {
+        return Parsed::Ignore(len);
+    };
+    if button & 0xE0 != 0x40 {
+        // not a wheel event or mouse motion
+        return Parsed::Ignore(len);
+    }
+
+    let direction = match button & 3 {
+        0 => WheelDirection::Up,
+        1 => WheelDirection::Down,
+        2 => WheelDirection::Left,
+        _ => WheelDirection::Right,
+    };
+    let modifiers = Modifiers(((button >> 2) & 7) as u8);
+
+    Parsed::Event(Event::Wheel { direction, modifiers }, len)
+}
+
+/// Reads all the currently available input without blocking and feeds
+/// it to the parser.
+///
+/// The Windows console doesn't send escape sequences for special keys
+/// (unless virtual terminal input is enabled), so the key events are read
+/// with `ReadConsoleInputW` and translated into the xterm sequences the
+/// parser understands, including modifiers. So are mouse wheel events, if
+/// mouse input is enabled.
+#[cfg(windows)]
+pub fn read_available(_reader: impl Read, parser: &mut InputParser) -> std::io::Result<()> {
+    use winapi::um::consoleapi::{GetNumberOfConsoleInputEvents, ReadConsoleInputW};
+    use winapi::um::processenv::GetStdHandle;
+    use winapi::um::winbase::STD_INPUT_HANDLE;
+    use winapi::um::wincontypes::{INPUT_RECORD, KEY_EVENT, MOUSE_EVENT};
+
+    let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    let mut records: [INPUT_RECORD; 64] = unsafe { std::mem::zeroed() };
+    loop {
+        let mut available = 0;
+        if unsafe { GetNumberOfConsoleInputEvents(handle, &mut available) } == 0 {
+            // not a console, e.g. redirected input
+            return Ok(());
+        }
+        if available == 0 {
+            return Ok(());
+        }
+
+        let mut count = 0;
+        if unsafe { ReadConsoleInputW(handle, records.as_mut_ptr(), records.len() as u32, &mut count) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        for record in &records[..count as usize] {
+            if record.EventType == MOUSE_EVENT {
+                feed_console_wheel(parser, unsafe { record.Event.MouseEvent() });
+                continue;
+            }
+            if record.EventType != KEY_EVENT {
+                continue;
+            }
+            let event = unsafe { record.Event.KeyEvent() };
+            if event.bKeyDown == 0 {
+                continue;
+            }
+            for _ in 0..event.wRepeatCount.max(1) {
+                feed_console_key(parser, event);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn feed_console_wheel(parser: &mut InputParser, event: &winapi::um::wincontypes::MOUSE_EVENT_RECORD) {
+    use winapi::um::wincontypes::{
+        LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, MOUSE_HWHEELED, MOUSE_WHEELED, RIGHT_ALT_PRESSED,
+        RIGHT_CTRL_PRESSED, SHIFT_PRESSED,
+    };
+
+    // the high word is the signed wheel delta, positive is up or right
+    let delta = (event.dwButtonState >> 16) as i16;
+    let mut button = match event.dwEventFlags {
+        MOUSE_WHEELED  => if delta > 0 { 64 } else { 65 },
+        MOUSE_HWHEELED => if delta > 0 { 67 } else { 66 },
+        _ => return,
+    };
+
+    let state = event.dwControlKeyState;
+    if state & SHIFT_PRESSED != 0 {
+        button |= 4;
+    }
+    if state & (LEFT_ALT_PRESSED | RIGHT_ALT_PRESSED) != 0 {
+        button |= 8;
+    }
+    if state & (LEFT_CTRL_PRESSED | RIGHT_CTRL_PRESSED) != 0 {
+        button |= 16;
+    }
+
+    let position = event.dwMousePosition;
+    parser.feed(format!("\x1B[<{button};{};{}M", position.X + 1, position.Y + 1).as_bytes());
+}
+
+#[cfg(windows)]
+fn feed_console_key(parser: &mut InputParser, event: &winapi::um::wincontypes::KEY_EVENT_RECORD) {
+    use winapi::um::wincontypes::{
+        LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, RIGHT_ALT_PRESSED, RIGHT_CTRL_PRESSED, SHIFT_PRESSED,
+    };
+    use winapi::um::winuser::{
+        VK_DELETE, VK_DOWN, VK_END, VK_HOME, VK_INSERT, VK_LEFT, VK_NEXT, VK_PRIOR, VK_RIGHT, VK_UP,
+    };
+
+    let state = event.dwControlKeyState;
+    let alt = state & (LEFT_ALT_PRESSED | RIGHT_ALT_PRESSED) != 0;
+    let ctrl = state & (LEFT_CTRL_PRESSED | RIGHT_CTRL_PRESSED) != 0;
+    let mut modifiers = Modifiers::NONE;
+    if state & SHIFT_PRESSED != 0 {
+        modifiers = modifiers | Modifiers::SHIFT;
+    }
+    if alt {
+        modifiers = modifiers | Modifiers::ALT;
+    }
+    if ctrl {
+        modifiers = modifiers | Modifiers::CTRL;
+    }
+    // xterm modifier parameter, see Modifiers::from_xterm()
+    let param = modifiers.0 as u32 + 1;
+
+    let sequence = match event.wVirtualKeyCode as i32 {
+        VK_UP    => Some(b'A'),
+        VK_DOWN  => Some(b'B'),
+        VK_RIGHT => Some(b'C'),
+        VK_LEFT  => Some(b'D'),
+        VK_HOME  => Some(b'H'),
+        VK_END   => Some(b'F'),
+        _ => None,
+    };
+    if let Some(final_byte) = sequence {
+        let sequence = if modifiers == Modifiers::NONE {
+            format!("\x1B[{}", final_byte as char)
+        } else {
+            format!("\x1B[1;{param}{}", final_byte as char)
+        };
+        parser.feed(sequence.as_bytes());
+        return;
+    }
+
+    let number = match event.wVirtualKeyCode as i32 {
+        VK_INSERT => Some(2),
+        VK_DELETE => Some(3),
+        VK_PRIOR  => Some(5),
+        VK_NEXT   => Some(6),
+        _ => None,
+    };
+    if let Some(number) = number {
+        let sequence = if modifiers == Modifiers::NONE {
+            format!("\x1B[{number}~")
+        } else {
+            format!("\x1B[{number};{param}~")
+        };
+        parser.feed(sequence.as_bytes());
+        return;
+    }
+
+    let unit = unsafe { *event.uChar.UnicodeChar() };
+    if unit == 0 {
+        // modifier keys on their own, function keys etc.
+        return;
+    }
+
+    let ch = if (0xD800..0xDC00).contains(&unit) {
+        parser.high_surrogate = Some(unit);
+        return;
+    } else if let Some(high) = parser.high_surrogate.take() {
+        char::decode_utf16([high, unit]).next().and_then(Result::ok)
+    } else {
+        char::from_u32(unit.into())
+    };
+    let Some(ch) = ch else {
+        return;
+    };
+
+    // Ctrl+Alt is AltGr on many keyboard layouts, which types characters
+    // like @ or { instead of being a shortcut.
+    if alt && !ctrl {
+        parser.feed(b"\x1B");
+    }
+    let mut buf = [0u8; 4];
+    parser.feed(ch.encode_utf8(&mut buf).as_bytes());
+}
+
+/// A number typed before a command, like in vim, e.g. `20` and then
+/// Cursor Right to move 20 pixels.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountPrefix {
+    count: Option<u32>,
+    last_digit_ts: Option<Instant>,
+}
+
+impl CountPrefix {
+    /// Largest count, more digits are ignored.
+    pub const MAX: u32 = 9999;
+
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn pending(&self) -> Option<u32> {
+        self.count
+    }
+
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.count.is_some()
+    }
+
+    pub fn push_digit(&mut self, digit: u32, now: Instant) {
+        let count = self.count.unwrap_or(0) * 10 + digit;
+        if count <= Self::MAX {
+            self.count = Some(count);
+        }
+        self.last_digit_ts = Some(now);
+    }
+
+    #[inline]
+    pub fn take(&mut self) -> Option<u32> {
+        self.last_digit_ts = None;
+        self.count.take()
+    }
+
+    /// Takes the count if no digit was typed for `timeout`.
+    pub fn take_expired(&mut self, now: Instant, timeout: Duration) -> Option<u32> {
+        match self.last_digit_ts {
+            Some(ts) if now.duration_since(ts) >= timeout => self.take(),
+            _ => None,
+        }
+    }
+}
+
+/// Makes the terminal the standard input, after a file piped in was read
+/// from it, so that hotkeys still work.
+#[cfg(not(windows))]
+pub fn reopen_terminal() -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let tty = std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDIN_FILENO) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Makes the console the standard input, after a file piped in was read
+/// from it, so that hotkeys still work.
+#[cfg(windows)]
+pub fn reopen_terminal() -> std::io::Result<()> {
+    use std::os::windows::io::IntoRawHandle;
+
+    let console = std::fs::OpenOptions::new().read(true).write(true).open("CONIN$")?;
+    let handle = console.into_raw_handle();
+    if unsafe { winapi::um::processenv::SetStdHandle(winapi::um::winbase::STD_INPUT_HANDLE, handle as _) } == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Reads all the currently available input without blocking and feeds
+/// it to the parser.
+#[cfg(not(windows))]
+pub fn read_available(mut reader: impl Read, parser: &mut InputParser) -> std::io::Result<()> {
+    let mut buf = [0u8; 256];
+    loop {
+        match reader.read(&mut buf) {
+            Err(err) => {
+                match err.kind() {
+                    std::io::ErrorKind::WouldBlock => return Ok(()),
+                    std::io::ErrorKind::Other if err.raw_os_error() == Some(libc::EAGAIN) => return Ok(()),
+                    std::io::ErrorKind::Interrupted => continue,
+                    _ => return Err(err)
+                }
+            }
+            Ok(0) => return Ok(()),
+            Ok(count) => {
+                parser.feed(&buf[..count]);
+                if count < buf.len() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds the chunks as separate reads, then lets the escape timeout
+    /// pass to flush whatever is left.
+    fn parse_chunks(chunks: &[&[u8]]) -> Vec<Event> {
+        let now = Instant::now();
+        let mut parser = InputParser::new(DEFAULT_ESCAPE_TIMEOUT);
+        let mut events = Vec::new();
+        for chunk in chunks {
+            parser.feed(chunk);
+            while let Some(event) = parser.next_event(now) {
+                events.push(event);
+            }
+        }
+        let later = now + DEFAULT_ESCAPE_TIMEOUT * 2;
+        while let Some(event) = parser.next_event(later) {
+            events.push(event);
+        }
+        events
+    }
+
+    fn parse_bytes(bytes: &[u8]) -> Vec<Event> {
+        parse_chunks(&[bytes])
+    }
+
+    /// xorshift64, so the fuzz input is the same on every run
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn invalid_utf8_produces_no_key() {
+        let inputs: &[&[u8]] = &[
+            // stray continuation bytes
+            b"\x80", b"\xBF", b"\x80\x80\x80",
+            // overlong encodings
+            b"\xC0\xAF", b"\xC1\xBF", b"\xE0\x80\xAF", b"\xF0\x80\x80\xAF",
+            // beyond U+10FFFF
+            b"\xF4\x90\x80\x80", b"\xF5\x80\x80\x80", b"\xF8\x88\x80\x80\x80", b"\xFF", b"\xFE",
+            // UTF-16 surrogates
+            b"\xED\xA0\x80",
+        ];
+        for input in inputs {
+            assert_eq!(parse_bytes(input), [], "{input:X?}");
+        }
+    }
+
+    #[test]
+    fn truncated_sequences_produce_no_key() {
+        let sequences: &[&[u8]] = &[
+            "é".as_bytes(), "€".as_bytes(), "😀".as_bytes(),
+            b"\x1B[A", b"\x1B[1;5H", b"\x1B[3~", b"\x1B[200~", b"\x1B[<64;10;5M", b"\x1B[12;40R",
+            b"\x1BOA",
+            b"\x1B]11;rgb:ffff/0000/0000\x07", b"\x1B]11;rgb:ffff/0000/0000\x1B\\", b"\x1BP>|xterm\x1B\\",
+        ];
+        for sequence in sequences {
+            // a lone ESC is the escape key
+            let start = if sequence[0] == 0x1B { 2 } else { 1 };
+            for len in start..sequence.len() {
+                let prefix = &sequence[..len];
+                assert_eq!(parse_bytes(prefix), [], "{prefix:X?}");
+            }
+        }
+    }
+
+    #[test]
+    fn split_sequences_are_reassembled() {
+        assert_eq!(
+            parse_chunks(&[b"\x1B", b"[1;", b"5", b"A"]),
+            [Event::Key(KeyEvent::new(Key::Up, Modifiers::CTRL))]);
+        assert_eq!(
+            parse_chunks(&[b"\x1B]11;rgb:ff", b"ff/0000/0000\x1B", b"\\x"]),
+            [Event::Key(KeyEvent::plain(Key::Char('x')))]);
+        assert_eq!(
+            parse_chunks(&[b"\x1B[200~he", b"llo\x1B[20", b"1~"]),
+            [Event::Paste("hello".to_string())]);
+        for ch in ['é', '€', '😀'] {
+            let mut buf = [0u8; 4];
+            let bytes = ch.encode_utf8(&mut buf).as_bytes();
+            let chunks: Vec<&[u8]> = bytes.chunks(1).collect();
+            assert_eq!(parse_chunks(&chunks), [Event::Key(KeyEvent::plain(Key::Char(ch)))], "{ch}");
+        }
+    }
+
+    #[test]
+    fn multi_byte_chars_produce_one_key() {
+        for ch in ['ß', 'é', 'ÿ', 'ſ', '\u{7FF}', 'ह', '€', '\u{FFFD}', '\u{FFFF}', '😀', '\u{10FFFF}'] {
+            let mut buf = [0u8; 4];
+            let bytes = ch.encode_utf8(&mut buf).as_bytes();
+            assert_eq!(parse_bytes(bytes), [Event::Key(KeyEvent::plain(Key::Char(ch)))], "{ch:?}");
+        }
+    }
+
+    #[test]
+    fn random_bytes_dont_panic() {
+        let mut state = 0x9E37_79B9_7F4A_7C15;
+        for _ in 0..2000 {
+            let len = (next_random(&mut state) % 64) as usize;
+            let bytes: Vec<u8> = (0..len).map(|_| {
+                let value = next_random(&mut state);
+                // bias towards bytes that start or continue sequences
+                match value % 4 {
+                    0 => 0x1B,
+                    1 => 0x80 | (value >> 8) as u8 & 0x7F,
+                    _ => (value >> 8) as u8,
+                }
+            }).collect();
+
+            let mut chunks = Vec::new();
+            let mut rest = &bytes[..];
+            while !rest.is_empty() {
+                let len = 1 + (next_random(&mut state) as usize) % rest.len();
+                let (chunk, tail) = rest.split_at(len);
+                chunks.push(chunk);
+                rest = tail;
+            }
+
+            for event in parse_chunks(&chunks) {
+                if let Event::Key(KeyEvent { key: Key::Char(ch), .. }) = event && !ch.is_ascii() {
+                    // every decoded multi-byte char was present in the input
+                    let mut buf = [0u8; 4];
+                    let encoded = ch.encode_utf8(&mut buf).as_bytes();
+                    assert!(find(&bytes, encoded).is_some(), "{ch:?} not in {bytes:X?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Crash-safe journal of the playback state for unattended installations.
+//!
+//! The state is written every few seconds so that after a crash or a power
+//! loss `--resume-journal` continues with the same file, time of day and
+//! settings. A snapshot is written to a temporary file and synced before it
+//! replaces the journal, and the previous journal is kept as a backup. Each
+//! journal carries a checksum, so a torn or corrupted file is detected and
+//! the backup is used instead.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::frame_cache;
+
+pub const DEFAULT_JOURNAL_INTERVAL: u64 = 5;
+
+const MAGIC: &str = "color-cycle journal 1";
+const TEMP_EXT: &str = "tmp";
+const BACKUP_EXT: &str = "bak";
+
+/// Everything needed to resume playback.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub file_index: usize,
+    /// name of the file at `file_index`, to notice a changed playlist
+    pub source: String,
+    pub current_time: Option<u64>,
+    pub time_speed: u64,
+    pub fps: u32,
+    pub blend: bool,
+    pub cycle_phase: f64,
+    /// None if the view follows the camera
+    pub position: Option<(u32, u32)>,
+    pub seed: u64,
+}
+
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    interval: Duration,
+    last_write: Option<Instant>,
+    last: Option<Snapshot>,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            interval,
+            last_write: None,
+            last: None,
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the journal, or its backup if the journal is missing or
+    /// corrupted. Returns None if neither holds an intact snapshot.
+    pub fn recover(&mut self) -> Option<Snapshot> {
+        if let Some(snapshot) = read(&self.path) {
+            self.last = Some(snapshot.clone());
+            return Some(snapshot);
+        }
+        // the broken journal gets replaced with the next write
+        read(&with_ext(&self.path, BACKUP_EXT))
+    }
+
+    /// Whether the next snapshot should be taken.
+    #[inline]
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.last_write.is_none_or(|last_write| now >= last_write + self.interval)
+    }
+
+    /// Writes `snapshot` unless it didn't change since the last write.
+    /// Failures aren't retried before the next interval.
+    pub fn update(&mut self, snapshot: Snapshot, now: Instant) -> std::io::Result<()> {
+        self.last_write = Some(now);
+        if self.last.as_ref() == Some(&snapshot) {
+            return Ok(());
+        }
+        self.write(&snapshot)?;
+        self.last = Some(snapshot);
+        Ok(())
+    }
+
+    /// Durably replaces the journal with `snapshot`.
+    fn write(&self, snapshot: &Snapshot) -> std::io::Result<()> {
+        let temp_path = with_ext(&self.path, TEMP_EXT);
+        {
+            let mut file = File::create(&temp_path)?;
+            file.write_all(encode(snapshot)?.as_bytes())?;
+            file.sync_all()?;
+        }
+
+        // keep the last good journal in case the new one gets torn anyway,
+        // e.g. by a file system that doesn't order the rename after the data,
+        // but don't replace a good backup with a broken journal
+        if self.last.is_some() && self.path.exists() {
+            fs::rename(&self.path, with_ext(&self.path, BACKUP_EXT))?;
+        }
+        fs::rename(&temp_path, &self.path)?;
+
+        sync_dir(&self.path);
+
+        Ok(())
+    }
+}
+
+fn encode(snapshot: &Snapshot) -> std::io::Result<String> {
+    let json = serde_json::to_string(snapshot)?;
+    Ok(format!("{MAGIC}\n{:016x}\n{json}\n", frame_cache::hash(json.as_bytes())))
+}
+
+fn decode(data: &str) -> Option<Snapshot> {
+    let mut lines = data.splitn(3, '\n');
+    if lines.next()? != MAGIC {
+        return None;
+    }
+    let checksum = u64::from_str_radix(lines.next()?, 16).ok()?;
+    let json = lines.next()?.strip_suffix('\n')?;
+    if frame_cache::hash(json.as_bytes()) != checksum {
+        return None;
+    }
+    serde_json::from_str(json).ok()
+}
+
+fn read(path: &Path) -> Option<Snapshot> {
+    decode(&fs::read_to_string(path).ok()?)
+}
+
+fn with_ext(path: &Path, ext: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(ext);
+    path.into()
+}
+
+/// Makes the renames durable. Directories can't be opened as files on all
+/// platforms, so failures are ignored.
+fn sync_dir(path: &Path) {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
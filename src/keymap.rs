@@ -0,0 +1,437 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Key bindings: which key runs which [`Command`]. The defaults can be
+//! changed with a file of `key = command` lines, see [`KeyMap::parse`].
+//! The hotkey help is generated from the bindings in effect.
+
+use std::path::Path;
+
+use crate::i18n::Msg;
+use crate::input::{Key, KeyEvent, Modifiers};
+
+macro_rules! commands {
+    ($($name:ident => $key:literal;)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Command {
+            $($name),*
+        }
+
+        impl Command {
+            pub const ALL: &[Command] = &[$(Command::$name),*];
+
+            /// Name used in key binding files.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Command::$name => $key),*
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    Quit           => "quit";
+    Pause          => "pause";
+    Blend          => "blend";
+    BlendCurve     => "blend-curve";
+    Osd            => "osd";
+    HideAll        => "hide-all";
+    FpsUp          => "fps-up";
+    FpsDown        => "fps-down";
+    NextFile       => "next-file";
+    PrevFile       => "prev-file";
+    Shuffle        => "shuffle";
+    LastFile       => "last-file";
+    Back           => "back";
+    BackMinute     => "back-minute";
+    Forward        => "forward";
+    ForwardMinute  => "forward-minute";
+    Now            => "now";
+    FastForward    => "fast-forward";
+    PhaseDown      => "phase-down";
+    PhaseUp        => "phase-up";
+    BigPhaseDown   => "big-phase-down";
+    BigPhaseUp     => "big-phase-up";
+    CycleSlower    => "cycle-slower";
+    CycleFaster    => "cycle-faster";
+    ZoomIn         => "zoom-in";
+    ZoomOut        => "zoom-out";
+    PanLock        => "pan-lock";
+    Camera         => "camera";
+    Export         => "export";
+    Grid           => "grid";
+    Scrub          => "scrub";
+    TimeBar        => "time-bar";
+    ReverseColumns => "reverse-columns";
+    FileInfo       => "file-info";
+    Perform        => "perform";
+    Palette        => "palette";
+    CycleList      => "cycle-list";
+    DiffDebug      => "diff-debug";
+    Renderer       => "renderer";
+    Up             => "up";
+    Down           => "down";
+    Left           => "left";
+    Right          => "right";
+    LeftEdge       => "left-edge";
+    RightEdge      => "right-edge";
+    Top            => "top";
+    Bottom         => "bottom";
+    PageUp         => "page-up";
+    PageDown       => "page-down";
+    PageLeft       => "page-left";
+    PageRight      => "page-right";
+}
+
+impl Command {
+    /// Commands that move the view-port, which Pan Lock blocks.
+    #[inline]
+    pub fn is_pan(self) -> bool {
+        matches!(self,
+            Command::Up | Command::Down | Command::Left | Command::Right |
+            Command::LeftEdge | Command::RightEdge | Command::Top | Command::Bottom |
+            Command::PageUp | Command::PageDown | Command::PageLeft | Command::PageRight)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Command::ALL.iter().copied().find(|command| command.name() == name)
+    }
+}
+
+/// Keys written like in the hotkey help, see [`parse_key`].
+pub const DEFAULT_BINDINGS: &[(&str, Command)] = &[
+    ("Q",             Command::Quit),
+    ("Escape",        Command::Quit),
+    ("Space",         Command::Pause),
+    ("B",             Command::Blend),
+    ("Shift+B",       Command::BlendCurve),
+    ("O",             Command::Osd),
+    ("H",             Command::HideAll),
+    ("+",             Command::FpsUp),
+    ("-",             Command::FpsDown),
+    ("N",             Command::NextFile),
+    ("P",             Command::PrevFile),
+    ("Shift+S",       Command::Shuffle),
+    ("0",             Command::LastFile),
+    ("A",             Command::Back),
+    ("Shift+A",       Command::BackMinute),
+    ("D",             Command::Forward),
+    ("Shift+D",       Command::ForwardMinute),
+    ("S",             Command::Now),
+    ("W",             Command::FastForward),
+    ("[",             Command::PhaseDown),
+    ("]",             Command::PhaseUp),
+    ("{",             Command::BigPhaseDown),
+    ("}",             Command::BigPhaseUp),
+    ("<",             Command::CycleSlower),
+    (">",             Command::CycleFaster),
+    ("Z",             Command::ZoomIn),
+    ("Shift+Z",       Command::ZoomOut),
+    ("L",             Command::PanLock),
+    ("C",             Command::Camera),
+    ("E",             Command::Export),
+    ("G",             Command::Grid),
+    ("T",             Command::Scrub),
+    ("Shift+T",       Command::TimeBar),
+    ("I",             Command::ReverseColumns),
+    ("Shift+I",       Command::FileInfo),
+    ("Shift+V",       Command::Perform),
+    ("Shift+P",       Command::Palette),
+    ("Shift+C",       Command::CycleList),
+    ("Shift+X",       Command::DiffDebug),
+    ("Shift+R",       Command::Renderer),
+    ("Cursor Up",     Command::Up),
+    ("Cursor Down",   Command::Down),
+    ("Cursor Left",   Command::Left),
+    ("Cursor Right",  Command::Right),
+    ("Home",          Command::LeftEdge),
+    ("End",           Command::RightEdge),
+    ("Ctrl+Home",     Command::Top),
+    ("Ctrl+End",      Command::Bottom),
+    ("Page Up",       Command::PageUp),
+    ("Page Down",     Command::PageDown),
+    ("Alt+Page Up",   Command::PageLeft),
+    ("Alt+Page Down", Command::PageRight),
+];
+
+/// What the keys column of a row of the hotkey help shows.
+#[derive(Debug, Clone, Copy)]
+pub enum HelpKeys {
+    /// input that isn't bound through the key map
+    Fixed(&'static str),
+    /// the keys bound to these commands, joined with "and"
+    Commands(&'static [Command]),
+}
+
+use HelpKeys::{Commands, Fixed};
+
+/// Rows of the hotkey help. Key names are the same in every language.
+pub const HELP: &[(HelpKeys, Msg)] = &[
+    (Commands(&[Command::Blend]),                        Msg::HelpBlend),
+    (Commands(&[Command::BlendCurve]),                   Msg::HelpBlendCurve),
+    (Commands(&[Command::Quit]),                         Msg::HelpQuit),
+    (Commands(&[Command::Osd]),                          Msg::HelpOsd),
+    (Commands(&[Command::NextFile]),                     Msg::HelpNextFile),
+    (Commands(&[Command::PrevFile]),                     Msg::HelpPrevFile),
+    (Commands(&[Command::Shuffle]),                      Msg::HelpShuffle),
+    (Fixed("Number"),                                    Msg::HelpFileIndex),
+    (Fixed("Number"),                                    Msg::HelpCount),
+    (Commands(&[Command::LastFile]),                     Msg::HelpLastFile),
+    (Fixed("Paste path"),                                Msg::HelpPaste),
+    (Commands(&[Command::FpsUp]),                        Msg::HelpFpsUp),
+    (Commands(&[Command::FpsDown]),                      Msg::HelpFpsDown),
+    (Commands(&[Command::FastForward]),                  Msg::HelpFastForward),
+    (Commands(&[Command::Back]),                         Msg::HelpBack5),
+    (Commands(&[Command::BackMinute]),                   Msg::HelpBack1),
+    (Commands(&[Command::Forward]),                      Msg::HelpForward5),
+    (Commands(&[Command::ForwardMinute]),                Msg::HelpForward1),
+    (Commands(&[Command::Now]),                          Msg::HelpNow),
+    (Commands(&[Command::Pause]),                        Msg::HelpPause),
+    (Commands(&[Command::PhaseDown, Command::PhaseUp]),  Msg::HelpPhase1),
+    (Commands(&[Command::BigPhaseDown, Command::BigPhaseUp]), Msg::HelpPhase10),
+    (Commands(&[Command::CycleSlower, Command::CycleFaster]), Msg::HelpCycleSpeed),
+    (Commands(&[Command::Grid]),                         Msg::HelpGrid),
+    (Commands(&[Command::HideAll]),                      Msg::HelpHideAll),
+    (Fixed("\\ then T"),                                 Msg::HelpChordTime),
+    (Fixed("\\ then F"),                                 Msg::HelpChordFile),
+    (Commands(&[Command::Export]),                       Msg::HelpExport),
+    (Commands(&[Command::PanLock]),                      Msg::HelpPanLock),
+    (Commands(&[Command::ZoomIn]),                       Msg::HelpZoomIn),
+    (Commands(&[Command::ZoomOut]),                      Msg::HelpZoomOut),
+    (Commands(&[Command::Camera]),                       Msg::HelpCamera),
+    (Commands(&[Command::Scrub]),                        Msg::HelpScrub),
+    (Commands(&[Command::TimeBar]),                      Msg::HelpTimeBar),
+    (Commands(&[Command::ReverseColumns]),               Msg::HelpReverseColumns),
+    (Commands(&[Command::FileInfo]),                     Msg::HelpFileInfo),
+    (Commands(&[Command::DiffDebug]),                    Msg::HelpDiffDebug),
+    (Commands(&[Command::Palette]),                      Msg::HelpPalette),
+    (Commands(&[Command::CycleList]),                    Msg::HelpCycleList),
+    (Commands(&[Command::Perform]),                      Msg::HelpPerform),
+    (Commands(&[Command::Renderer]),                     Msg::HelpRenderer),
+    (Commands(&[Command::Up]),                           Msg::HelpUp),
+    (Commands(&[Command::Down]),                         Msg::HelpDown),
+    (Commands(&[Command::Left]),                         Msg::HelpLeft),
+    (Commands(&[Command::Right]),                        Msg::HelpRight),
+    (Commands(&[Command::LeftEdge]),                     Msg::HelpLeftEdge),
+    (Commands(&[Command::RightEdge]),                    Msg::HelpRightEdge),
+    (Commands(&[Command::Top]),                          Msg::HelpTop),
+    (Commands(&[Command::Bottom]),                       Msg::HelpBottom),
+    (Commands(&[Command::PageUp]),                       Msg::HelpPageUp),
+    (Commands(&[Command::PageDown]),                     Msg::HelpPageDown),
+    (Commands(&[Command::PageLeft]),                     Msg::HelpPageLeft),
+    (Commands(&[Command::PageRight]),                    Msg::HelpPageRight),
+    (Fixed("Wheel"),                                     Msg::HelpWheel),
+    (Fixed("Shift+Wheel"),                               Msg::HelpShiftWheel),
+    (Fixed("Ctrl+Wheel"),                                Msg::HelpCtrlWheel),
+];
+
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("Space",        Key::Char(' ')),
+    ("Escape",       Key::Escape),
+    ("Enter",        Key::Enter),
+    ("Tab",          Key::Tab),
+    ("Backspace",    Key::Backspace),
+    ("Cursor Up",    Key::Up),
+    ("Cursor Down",  Key::Down),
+    ("Cursor Left",  Key::Left),
+    ("Cursor Right", Key::Right),
+    ("Home",         Key::Home),
+    ("End",          Key::End),
+    ("Insert",       Key::Insert),
+    ("Delete",       Key::Delete),
+    ("Page Up",      Key::PageUp),
+    ("Page Down",    Key::PageDown),
+];
+
+/// Parses a key like `B`, `Shift+B`, `Ctrl+Home` or `Alt+Page Up`. Letters
+/// are written in upper case like in the hotkey help, Shift+ selects the
+/// upper case letter. Names are case insensitive, `Up` and `PageUp` work
+/// as well as `Cursor Up` and `Page Up`.
+pub fn parse_key(spec: &str) -> Option<KeyEvent> {
+    let mut modifiers = Modifiers::NONE;
+    let mut rest = spec.trim();
+    // a trailing + is the + key itself, like in Ctrl++
+    while let Some((prefix, tail)) = rest.split_once('+') && !tail.is_empty() {
+        modifiers = modifiers | match prefix.trim().to_ascii_lowercase().as_str() {
+            "shift" => Modifiers::SHIFT,
+            "ctrl"  => Modifiers::CTRL,
+            "alt"   => Modifiers::ALT,
+            _ => return None,
+        };
+        rest = tail.trim();
+    }
+
+    let mut chars = rest.chars();
+    let key = match (chars.next(), chars.next()) {
+        (Some(ch), None) if ch.is_ascii_alphabetic() => {
+            if modifiers == Modifiers::SHIFT {
+                // terminals send Shift+B as a plain B
+                modifiers = Modifiers::NONE;
+                Key::Char(ch.to_ascii_uppercase())
+            } else {
+                Key::Char(ch.to_ascii_lowercase())
+            }
+        }
+        (Some(ch), None) => Key::Char(ch),
+        _ => {
+            let name: String = rest.chars().filter(|ch| !ch.is_whitespace()).collect::<String>().to_ascii_lowercase();
+            let name = name.strip_prefix("cursor").unwrap_or(&name);
+            let name = if name == "esc" { "escape" } else { name };
+            KEY_NAMES.iter()
+                .find(|(key_name, _)| {
+                    let key_name = key_name.strip_prefix("Cursor ").unwrap_or(key_name);
+                    key_name.replace(' ', "").eq_ignore_ascii_case(name)
+                })
+                .map(|&(_, key)| key)?
+        }
+    };
+
+    Some(KeyEvent::new(key, modifiers))
+}
+
+/// The name of a key as shown in the hotkey help, the inverse of
+/// [`parse_key`].
+pub fn key_name(event: KeyEvent) -> String {
+    let mut name = String::new();
+    if event.modifiers.contains(Modifiers::CTRL) {
+        name.push_str("Ctrl+");
+    }
+    if event.modifiers.contains(Modifiers::ALT) {
+        name.push_str("Alt+");
+    }
+    if event.modifiers.contains(Modifiers::SHIFT) {
+        name.push_str("Shift+");
+    }
+    match event.key {
+        Key::Char(ch) if ch.is_ascii_uppercase() => {
+            name.push_str("Shift+");
+            name.push(ch);
+        }
+        Key::Char(ch) if ch.is_ascii_lowercase() => name.push(ch.to_ascii_uppercase()),
+        key => match KEY_NAMES.iter().find(|&&(_, other)| other == key) {
+            Some((key_name, _)) => name.push_str(key_name),
+            None => if let Key::Char(ch) = key {
+                name.push(ch);
+            }
+        }
+    }
+    name
+}
+
+/// The key bindings in effect, in the order they were bound.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(KeyEvent, Command)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS.iter()
+            .map(|&(spec, command)| (parse_key(spec).expect("valid default key"), command))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    /// The command bound to a key, if any.
+    pub fn get(&self, key: Key, modifiers: Modifiers) -> Option<Command> {
+        let event = KeyEvent::new(key, modifiers);
+        self.bindings.iter().find(|(bound, _)| *bound == event).map(|&(_, command)| command)
+    }
+
+    /// Binds `event` to `command`, replacing what it was bound to before.
+    /// `None` unbinds the key.
+    pub fn bind(&mut self, event: KeyEvent, command: Option<Command>) {
+        self.bindings.retain(|(bound, _)| *bound != event);
+        if let Some(command) = command {
+            self.bindings.push((event, command));
+        }
+    }
+
+    /// The keys bound to `command`.
+    pub fn keys(&self, command: Command) -> impl Iterator<Item = KeyEvent> + '_ {
+        self.bindings.iter().filter(move |&&(_, bound)| bound == command).map(|&(event, _)| event)
+    }
+
+    /// Changes bindings with the `key = command` lines of `source`. Empty
+    /// lines and lines starting with `#` are ignored. The command `none`
+    /// unbinds the key, the key `clear = all` removes all default bindings.
+    pub fn parse(&mut self, source: &str) -> Result<(), String> {
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // command names never contain '=', but a key might
+            let Some((key, command)) = line.rsplit_once('=') else {
+                return Err(format!("line {}: expected key = command", index + 1));
+            };
+            let (key, command) = (key.trim(), command.trim());
+
+            if key == "clear" && command == "all" {
+                self.bindings.clear();
+                continue;
+            }
+
+            let Some(event) = parse_key(key) else {
+                return Err(format!("line {}: unknown key: {key}", index + 1));
+            };
+            let command = if command == "none" {
+                None
+            } else if let Some(command) = Command::from_name(command) {
+                Some(command)
+            } else {
+                return Err(format!("line {}: unknown command: {command}", index + 1));
+            };
+
+            self.bind(event, command);
+        }
+        Ok(())
+    }
+
+    /// Changes bindings with the ones in the file at `path`.
+    pub fn load(&mut self, path: &Path) -> Result<(), String> {
+        let source = std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.to_string_lossy()))?;
+        self.parse(&source).map_err(|err| format!("{}: {err}", path.to_string_lossy()))
+    }
+
+    /// The keys column and description of each row of the hotkey help.
+    /// Rows of commands that aren't bound to any key are left out.
+    pub fn help_rows(&self) -> Vec<(String, Msg)> {
+        let mut rows = Vec::with_capacity(HELP.len());
+        for &(keys, msg) in HELP {
+            let keys = match keys {
+                Fixed(keys) => keys.to_owned(),
+                Commands(commands) => {
+                    let names: Vec<String> = commands.iter()
+                        .map(|&command| self.keys(command).map(key_name).collect::<Vec<_>>().join(" or "))
+                        .filter(|names| !names.is_empty())
+                        .collect();
+                    if names.is_empty() {
+                        continue;
+                    }
+                    names.join(" and ")
+                }
+            };
+            rows.push((keys, msg));
+        }
+        rows
+    }
+}
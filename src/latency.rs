@@ -0,0 +1,193 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Measures the round-trip time to the terminal with cursor position
+//! queries (DSR 6), at startup and then every now and then, to pick
+//! defaults that suit slow links like ssh or mosh over mobile networks.
+
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use crate::doctor::parse_cursor_position;
+
+/// Device Status Report: asks the terminal for the cursor position.
+pub const CURSOR_POSITION_QUERY: &str = "\x1B[6n";
+
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Time between two measurements while running.
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A query without answer for this long is given up on and sent again.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(30);
+
+const REMOTE_RTT: Duration = Duration::from_millis(20);
+const SLOW_RTT: Duration = Duration::from_millis(150);
+
+/// How fast the connection to the terminal is, judged by its latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Link {
+    /// The terminal runs on the same machine.
+    Local,
+    /// Some network in between, e.g. ssh over a LAN or good internet.
+    Remote,
+    /// High latency, e.g. mosh or ssh over a mobile network.
+    Slow,
+}
+
+impl Link {
+    pub fn from_rtt(rtt: Duration) -> Self {
+        if rtt < REMOTE_RTT {
+            Link::Local
+        } else if rtt < SLOW_RTT {
+            Link::Remote
+        } else {
+            Link::Slow
+        }
+    }
+
+    /// Default for `--fps`, `None` to keep the regular default.
+    pub fn fps(self) -> Option<u32> {
+        match self {
+            Link::Local  => None,
+            Link::Remote => Some(30),
+            Link::Slow   => Some(10),
+        }
+    }
+
+    /// Default for `--sync-output`. Over a network a frame often arrives
+    /// in several parts, which would show as tearing.
+    #[inline]
+    pub fn sync_output(self) -> bool {
+        self != Link::Local
+    }
+
+    /// Default for `--max-bandwidth` in KiB per second.
+    pub fn max_bandwidth(self) -> Option<u32> {
+        match self {
+            Link::Local | Link::Remote => None,
+            Link::Slow => Some(256),
+        }
+    }
+}
+
+impl std::fmt::Display for Link {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Link::Local  => "local".fmt(f),
+            Link::Remote => "remote".fmt(f),
+            Link::Slow   => "slow".fmt(f),
+        }
+    }
+}
+
+/// Sends a cursor position query and waits for the answer. `reader` needs
+/// to be the terminal in non-canonical mode without blocking reads.
+/// Returns `None` if the terminal didn't answer in time.
+pub fn measure(reader: &mut impl Read, writer: &mut impl Write) -> std::io::Result<Option<Duration>> {
+    write!(writer, "{CURSOR_POSITION_QUERY}")?;
+    writer.flush()?;
+
+    let start = Instant::now();
+    let mut answer = Vec::new();
+    let mut buf = [0u8; 256];
+
+    while start.elapsed() < QUERY_TIMEOUT {
+        let count = match reader.read(&mut buf) {
+            Ok(count) => count,
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock || err.kind() == std::io::ErrorKind::Interrupted => 0,
+            Err(err) => return Err(err),
+        };
+
+        if count == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+            continue;
+        }
+
+        answer.extend_from_slice(&buf[..count]);
+        if parse_cursor_position(&answer).is_some() {
+            return Ok(Some(start.elapsed()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Settings that follow the measured latency, because they weren't given
+/// on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Adaptive {
+    pub fps: bool,
+    pub sync_output: bool,
+    pub max_bandwidth: bool,
+}
+
+/// Measures the latency while running. The answers arrive as
+/// [`crate::input::Event::CursorPosition`] among the key presses.
+#[derive(Debug, Clone)]
+pub struct LatencyProbe {
+    rtt: Duration,
+    sent: Option<Instant>,
+    next_probe: Instant,
+    pub adaptive: Adaptive,
+}
+
+impl LatencyProbe {
+    /// `rtt` is the initial measurement.
+    pub fn new(rtt: Duration, adaptive: Adaptive, now: Instant) -> Self {
+        Self {
+            rtt,
+            sent: None,
+            next_probe: now + PROBE_INTERVAL,
+            adaptive,
+        }
+    }
+
+    /// Smoothed round-trip time.
+    #[inline]
+    pub fn rtt(&self) -> Duration {
+        self.rtt
+    }
+
+    #[inline]
+    pub fn link(&self) -> Link {
+        Link::from_rtt(self.rtt)
+    }
+
+    /// Sends a query if one is due.
+    pub fn poll(&mut self, writer: &mut impl Write, now: Instant) {
+        let waiting = self.sent.is_some_and(|sent| now < sent + ANSWER_TIMEOUT);
+        if !waiting && now >= self.next_probe {
+            let _ = write!(writer, "{CURSOR_POSITION_QUERY}");
+            self.sent = Some(now);
+        }
+    }
+
+    /// Records the answer to a query. Returns the new link class if it
+    /// changed.
+    pub fn answer(&mut self, now: Instant) -> Option<Link> {
+        let sent = self.sent.take()?;
+        let link = self.link();
+
+        // smooth out single hiccups
+        self.rtt = (self.rtt * 3 + (now - sent)) / 4;
+        self.next_probe = now + PROBE_INTERVAL;
+
+        let new_link = self.link();
+        (new_link != link).then_some(new_link)
+    }
+}
@@ -0,0 +1,78 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Decoder and renderer of color cycle images, as used by the `color-cycle`
+//! binary.
+//!
+//! - [`image`] reads Canvas Cycle JSON, Living Worlds and ILBM/PBM files
+//!   into a [`LivingWorld`](image::LivingWorld) and holds the indexed and
+//!   RGB images.
+//! - [`palette`] cycles the colors of a [`Palette`](palette::Palette).
+//! - [`animator`] computes the palette of a frame at a time of day,
+//!   blending the palettes of a timeline, and composes sprites and layers.
+//! - [`clock`] holds the [`CycleClock`](clock::CycleClock) a frame is
+//!   rendered at: the cycle time and the time of day.
+//! - [`image_to_ansi`] turns an [`RgbImage`](image::RgbImage) into ANSI
+//!   escape sequences, two pixels per character cell.
+//! - [`ilbm`] is the low level ILBM reader and writer, [`color`] holds
+//!   [`Rgb`](color::Rgb) and color math.
+//! - [`file_info`] collects the metadata of a file while decoding it into a
+//!   [`FileInfo`](file_info::FileInfo).
+//!
+//! The palette cycling math itself lives in the `no_std` crate
+//! `color_cycle_core`, which [`color`] and [`palette`] re-export.
+//!
+//! Rendering a frame of a file at a given time:
+//!
+//! ```no_run
+//! use color_cycle::image::{LivingWorld, RgbImage};
+//! use color_cycle::image_to_ansi::simple_image_to_ansi_into;
+//! use color_cycle::palette::{BlendCurve, Palette};
+//!
+//! # fn main() -> Result<(), color_cycle::error::Error> {
+//! let data = std::fs::read("scene.json")?;
+//! let living_world = LivingWorld::from_bytes(&data)?;
+//! let base = living_world.base();
+//!
+//! // the cycled palette 2.5 seconds into the animation, blended
+//! let mut palette = Palette::default();
+//! palette.apply_cycles_from(base.palette(), base.cycles(), 2.5, Some(BlendCurve::Linear));
+//!
+//! let mut frame = RgbImage::new(base.width(), base.height());
+//! frame.draw_indexed_image_with_palette(base.indexed_image(), &palette);
+//!
+//! let mut lines = String::new();
+//! simple_image_to_ansi_into(&frame, &mut lines);
+//! print!("{lines}");
+//! # Ok(())
+//! # }
+//! ```
+
+#![allow(clippy::manual_range_contains)]
+
+pub mod animator;
+pub mod bitvec;
+pub mod clock;
+pub mod color;
+pub mod diagnostics;
+pub mod error;
+pub mod file_info;
+pub mod ilbm;
+pub mod image;
+pub mod image_to_ansi;
+pub mod palette;
+pub mod read;
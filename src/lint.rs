@@ -0,0 +1,172 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Palette checks for `color-cycle lint` and the palette inspector: palette
+//! entries that no pixel uses, and used entries whose colors can't be told
+//! apart, so artists can free up some of the 256 colors.
+
+use std::io::Write;
+
+use crate::color::{lab_distance, Rgb, RgbExt};
+use crate::palette::{Cycle, Palette};
+
+/// Default ΔE below which two colors count as indistinguishable.
+pub const DEFAULT_THRESHOLD: f64 = 2.3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteLint {
+    pub threshold: f64,
+    /// Palette indices no pixel shows, not even through a cycle.
+    pub unused: Vec<u8>,
+    /// Pairs of used palette indices whose colors are closer than the
+    /// threshold, with their ΔE. Colors of the same cycle are not
+    /// compared, since they are shown one after another.
+    pub similar: Vec<(u8, u8, f64)>,
+}
+
+impl PaletteLint {
+    /// `usage` is the number of pixels per palette index, as returned by
+    /// [`crate::image::IndexedImage::index_usage()`].
+    pub fn new(usage: &[usize; 256], palette: &Palette, cycles: &[Cycle], threshold: f64) -> Self {
+        // a pixel in a cycle shows all colors of the cycle over time
+        let mut cycle_of = [None; 256];
+        let mut used: [bool; 256] = std::array::from_fn(|index| usage[index] > 0);
+        for (cycle_index, cycle) in cycles.iter().enumerate() {
+            if cycle.low() > cycle.high() {
+                continue;
+            }
+            let range = cycle.low() as usize..=cycle.high() as usize;
+            let cycle_used = usage[range.clone()].iter().any(|&uses| uses > 0);
+            for index in range {
+                cycle_of[index] = Some(cycle_index);
+                used[index] |= cycle_used;
+            }
+        }
+
+        let unused = (0..=255u8).filter(|&index| !used[index as usize]).collect();
+
+        let labs: Vec<_> = palette.0.iter().map(|color| color.to_lab()).collect();
+        let mut similar = Vec::new();
+        for index1 in 0..256 {
+            if !used[index1] {
+                continue;
+            }
+            for index2 in index1 + 1..256 {
+                if !used[index2] || (cycle_of[index1].is_some() && cycle_of[index1] == cycle_of[index2]) {
+                    continue;
+                }
+                let distance = lab_distance(labs[index1], labs[index2]);
+                if distance < threshold {
+                    similar.push((index1 as u8, index2 as u8, distance));
+                }
+            }
+        }
+
+        Self { threshold, unused, similar }
+    }
+
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.unused.is_empty() && self.similar.is_empty()
+    }
+
+    /// The marker of each palette index for the palette inspector.
+    pub fn markers(&self) -> [Option<Marker>; 256] {
+        let mut markers = [None; 256];
+        for &index in &self.unused {
+            markers[index as usize] = Some(Marker::Unused);
+        }
+        for &(index1, index2, _) in &self.similar {
+            markers[index1 as usize] = Some(Marker::Similar);
+            markers[index2 as usize] = Some(Marker::Similar);
+        }
+        markers
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    Unused,
+    Similar,
+}
+
+impl Marker {
+    #[inline]
+    pub fn symbol(self) -> char {
+        match self {
+            Marker::Unused  => '×',
+            Marker::Similar => '≈',
+        }
+    }
+}
+
+pub fn write_lint(out: &mut impl Write, name: &str, palette: &Palette, lint: &PaletteLint) -> std::io::Result<()> {
+    writeln!(out, "File:      {name}")?;
+    writeln!(out, "Threshold: ΔE {:.1}", lint.threshold)?;
+
+    if lint.unused.is_empty() {
+        writeln!(out, "Unused:    none")?;
+    } else {
+        writeln!(out, "Unused:    {} of 256: {}", lint.unused.len(), format_ranges(&lint.unused))?;
+    }
+
+    if lint.similar.is_empty() {
+        writeln!(out, "Similar:   none")?;
+    } else {
+        writeln!(out, "Similar:   {} pairs", lint.similar.len())?;
+        for &(index1, index2, distance) in &lint.similar {
+            writeln!(out, "  {index1:>3} {} ~ {index2:>3} {}  ΔE {distance:.2}",
+                palette.0[index1 as usize], palette.0[index2 as usize])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats sorted indices as e.g. `3, 5-9, 200`.
+fn format_ranges(indices: &[u8]) -> String {
+    let mut ranges: Vec<(u8, u8)> = Vec::new();
+    for &index in indices {
+        match ranges.last_mut() {
+            Some((_, end)) if *end as u16 + 1 == index as u16 => *end = index,
+            _ => ranges.push((index, index)),
+        }
+    }
+
+    ranges.iter()
+        .map(|&(start, end)| if start == end { start.to_string() } else { format!("{start}-{end}") })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Width of the palette inspector in terminal cells.
+pub const INSPECTOR_WIDTH: u32 = 32;
+
+/// Draws the palette as a 16 by 16 grid of swatches with the palette index
+/// of the top left swatch at terminal cell `row`/`column`. Each swatch is
+/// two cells wide and shows the marker of its palette index, if any.
+pub fn write_inspector(out: &mut impl Write, palette: &Palette, markers: &[Option<Marker>; 256], row: u32, column: u32) {
+    for (index, &color) in palette.0.iter().enumerate() {
+        let Rgb([r, g, b]) = color;
+        if index % 16 == 0 {
+            let _ = write!(out, "\x1B[{};{}H", row + index as u32 / 16, column);
+        }
+        let symbol = markers[index].map_or(' ', Marker::symbol);
+        let Rgb([fg_r, fg_g, fg_b]) = color.contrasting();
+        let _ = write!(out, "\x1B[38;2;{fg_r};{fg_g};{fg_b}m\x1B[48;2;{r};{g};{b}m{symbol} ");
+    }
+}
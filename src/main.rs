@@ -16,41 +16,178 @@
 
 #![allow(clippy::manual_range_contains)]
 
-pub mod image_to_ansi;
-pub mod color;
-pub mod image;
-pub mod palette;
-pub mod read;
-pub mod ilbm;
-pub mod bitvec;
-pub mod error;
-
-use std::path::PathBuf;
+/// Profiles the rest of the enclosing block under the given name.
+/// Expands to nothing without the `profile` feature.
+macro_rules! profile_scope {
+    ($name:expr) => {
+        #[cfg(feature = "profile")]
+        let _profile_span = $crate::profile::Span::new($name);
+    };
+}
+
+// the decoder and renderer live in the library, this makes them available
+// under the same paths as the modules of the binary
+use color_cycle::{animator, clock, color, diagnostics, error, file_info, ilbm, image, image_to_ansi, palette};
+use color_cycle_core::timeline::timeline_span;
+
+pub mod source;
+pub mod doctor;
+pub mod input;
+pub mod rng;
+pub mod dump;
+pub mod overlay;
+pub mod pan;
+pub mod theme;
+pub mod term_colors;
+pub mod effects;
+pub mod pacing;
+pub mod i18n;
+pub mod chord;
+pub mod keymap;
+pub mod font;
+pub mod reel;
+pub mod frame_cache;
+pub mod png;
+pub mod stats;
+pub mod manifest;
+pub mod lint;
+pub mod latency;
+pub mod watchdog;
+pub mod describe;
+pub mod journal;
+pub mod video;
+pub mod perform;
+pub mod mixer;
+pub mod play_order;
+pub mod expand;
+pub mod watch;
+pub mod palette_file;
+pub mod frame_pipe;
+pub mod convert;
+pub mod panes;
+pub mod recording;
+
+#[cfg(feature = "embed-demo")]
+pub mod demo;
+
+#[cfg(feature = "profile")]
+pub mod profile;
+
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::fs::File;
-use std::io::{BufReader, Read, Seek, StdinLock, StdoutLock, Write};
+use std::io::{BufWriter, IsTerminal, StdinLock, StdoutLock, Write};
 
 #[cfg(not(windows))]
 use std::mem::MaybeUninit;
 
-use clap::Parser;
-use image::{CycleImage, IndexedImage, LivingWorld, RgbImage};
-use image_to_ansi::{image_to_ansi_into, simple_image_to_ansi_into};
-
-use palette::Palette;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use image::{IndexedImage, LivingWorld, RgbImage, Scale, Viewport, Weather};
+use image_to_ansi::{
+    blocks_image_to_ansi_into, blocks_image_to_text_into, highlight_changes, image_to_ansi_into,
+    image_to_ansi_phased_into, image_to_ansi_text_into, simple_image_to_ansi_into,
+    simple_image_to_ansi_phased_into,
+};
+
+use palette::{BlendCurve, Palette};
+use color::Gamut;
+use animator::{Animator, PaletteCache, DAY_DURATION};
+use clock::CycleClock;
+use source::{NetworkPolicy, Prefetcher, SceneTime, Source};
+use latency::{LatencyProbe, Link};
+use watchdog::Watchdog;
+use video::{VideoFormat, VideoWriter};
+use perform::{Flash, Performance};
+use mixer::CycleMixer;
+use play_order::PlayOrder;
+use watch::FileWatcher;
+use palette_file::PaletteFormat;
+use convert::ConvertFormat;
+use frame_pipe::FramePipe;
+use recording::TimingWriter;
+use journal::Journal;
+use rng::Rng;
+use dump::{FrameDump, Tee};
+use frame_cache::FrameCache;
+use pan::{Edge, PanAnimation};
+use theme::ThemeFormat;
+use term_colors::{ColorSync, SavedColors};
+use overlay::{OsdColors, Overlay, Overlays};
+use effects::WeatherEffect;
+use pacing::FramePacer;
+use i18n::{Catalog, Msg};
+use chord::{ChordAction, ChordEvent, Chords};
+use keymap::{Command as KeyCommand, KeyMap};
+use input::{CountPrefix, Event, InputParser, Key, KeyEvent, Modifiers, WheelDirection};
 
 const MAX_FPS: u32 = 10_000;
+const DEFAULT_FPS: u32 = 60;
+const BEGIN_SYNCHRONIZED_UPDATE: &str = "\x1B[?2026h";
+const END_SYNCHRONIZED_UPDATE: &str = "\x1B[?2026l";
 const TIME_STEP: u64 = 5 * 60 * 1000;
 const SMALL_TIME_STEP: u64 = 60 * 1000;
-const DAY_DURATION: u64 = 24 * 60 * 60 * 1000;
+const PHASE_STEP: f64 = 0.01;
+const BIG_PHASE_STEP: f64 = 0.1;
+const BOUNCE_DISTANCE: u32 = 2;
+const BOUNCE_DURATION: Duration = Duration::from_millis(150);
+const SCRUB_STEP: u64 = 30 * 1000;
+const MAX_SCRUB_ACCELERATION: u64 = 30;
+const SCRUB_REPEAT_INTERVAL: Duration = Duration::from_millis(200);
+const WHEEL_PAN_STEP: i64 = 3;
+const MAX_ZOOM: u32 = 8;
+const MAX_OSD_PADDING: u32 = 8;
+const MAX_CELL_SIZE: u32 = 1000;
+const MAX_CELL_STRETCH: u32 = 4;
+const MIN_CYCLE_SPEED: f64 = 0.25;
+const MAX_CYCLE_SPEED: f64 = 8.0;
+/// A number typed without a command after it opens that file.
+const COUNT_TIMEOUT: Duration = Duration::from_secs(1);
 const FAST_FORWARD_SPEED: u64 = 10_000;
+const DEBUG_DIFF_COLOR: color::Rgb = color::Rgb([255, 0, 255]);
+const MONO_SHADES: u32 = 16;
+const EINK_FPS: u32 = 1;
+/// Below this many terminal columns or rows nothing is rendered.
+const MIN_TERM_COLUMNS: u32 = 4;
+const MIN_TERM_ROWS: u32 = 2;
+
+pub struct NBTerm {
+    /// Whether mouse reporting was enabled and needs to be disabled again.
+    mouse: bool,
+    /// Modes of the Windows console before they were changed, restored on
+    /// drop.
+    #[cfg(windows)]
+    console_modes: ConsoleModes,
+}
+
+#[cfg(windows)]
+struct ConsoleModes {
+    input: Option<winapi::shared::minwindef::DWORD>,
+    output: winapi::shared::minwindef::DWORD,
+}
+
+/// Mode of a standard handle of the console, `None` if it isn't a console.
+#[cfg(windows)]
+unsafe fn console_mode(std_handle: winapi::shared::minwindef::DWORD) -> Option<(winapi::um::winnt::HANDLE, winapi::shared::minwindef::DWORD)> {
+    use winapi::shared::minwindef::{DWORD, FALSE};
+
+    let handle = unsafe { winapi::um::processenv::GetStdHandle(std_handle) };
+    if handle.is_null() || handle == winapi::um::handleapi::INVALID_HANDLE_VALUE {
+        return None;
+    }
+
+    let mut mode: DWORD = 0;
+    if unsafe { winapi::um::consoleapi::GetConsoleMode(handle, &mut mode as *mut DWORD) } == FALSE {
+        return None;
+    }
 
-pub struct NBTerm;
+    Some((handle, mode))
+}
 
 impl NBTerm {
-    pub fn new() -> Result<Self, error::Error> {
+    pub fn new(mouse: bool) -> Result<Self, error::Error> {
         #[cfg(not(windows))]
         unsafe {
             let mut ttystate = MaybeUninit::<libc::termios>::zeroed();
@@ -76,35 +213,65 @@ impl NBTerm {
             }
         }
 
-//        #[cfg(windows)]
-//        unsafe {
-//            use winapi::shared::minwindef::{DWORD, FALSE};
-//
-//            let handle = winapi::um::processenv::GetStdHandle(winapi::um::winbase::STD_INPUT_HANDLE);
-//            if handle == winapi::um::handleapi::INVALID_HANDLE_VALUE {
-//                let err = std::io::Error::last_os_error();
-//                return Err(err);
-//            }
-//
-//            let mut mode: DWORD = 0;
-//
-//            if winapi::um::consoleapi::GetConsoleMode(handle, &mut mode as *mut DWORD) == FALSE {
-//                let err = std::io::Error::last_os_error();
-//                return Err(err);
-//            }
-//
-//            if winapi::um::consoleapi::SetConsoleMode(handle, mode & !(winapi::um::wincon::ENABLE_ECHO_INPUT | winapi::um::wincon::ENABLE_LINE_INPUT)) == FALSE {
-//                let err = std::io::Error::last_os_error();
-//                return Err(err);
-//            }
-//        }
+        #[cfg(windows)]
+        let console_modes = unsafe {
+            use winapi::shared::minwindef::FALSE;
+            use winapi::um::consoleapi::SetConsoleMode;
+            use winapi::um::winbase::{STD_INPUT_HANDLE, STD_OUTPUT_HANDLE};
+            use winapi::um::wincon::{
+                ENABLE_ECHO_INPUT, ENABLE_EXTENDED_FLAGS, ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT,
+                ENABLE_PROCESSED_INPUT, ENABLE_PROCESSED_OUTPUT, ENABLE_QUICK_EDIT_MODE,
+                ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+            };
+
+            let Some((output, output_mode)) = console_mode(STD_OUTPUT_HANDLE) else {
+                let err = std::io::Error::last_os_error();
+                return Err(err.into());
+            };
+
+            // The legacy console only understands escape sequences with
+            // virtual terminal processing (Windows 10 and later).
+            if SetConsoleMode(output, output_mode | ENABLE_PROCESSED_OUTPUT | ENABLE_VIRTUAL_TERMINAL_PROCESSING) == FALSE {
+                let err = std::io::Error::last_os_error();
+                return Err(error::Error::with_cause("enabling escape sequences of the console", Box::new(err)));
+            }
+
+            // Key events are read with ReadConsoleInputW, see
+            // input::read_available(). Ctrl+C stays processed, so it ends the
+            // program normally and the modes are restored.
+            let input = console_mode(STD_INPUT_HANDLE);
+            if let Some((handle, mode)) = input {
+                let mut new_mode = (mode & !(ENABLE_ECHO_INPUT | ENABLE_LINE_INPUT)) | ENABLE_PROCESSED_INPUT;
+                if mouse {
+                    // quick edit mode would select text instead
+                    new_mode = (new_mode & !ENABLE_QUICK_EDIT_MODE) | ENABLE_MOUSE_INPUT | ENABLE_EXTENDED_FLAGS;
+                }
+                SetConsoleMode(handle, new_mode);
+            }
+
+            ConsoleModes {
+                input: input.map(|(_, mode)| mode),
+                output: output_mode,
+            }
+        };
 
         // CSI ? 25 l     Hide cursor (DECTCEM), VT220
         // CSI ?  7 l     No Auto-Wrap Mode (DECAWM), VT100.
+        // CSI ? 2004 h   Enable bracketed paste mode
         // CSI 2 J        Clear entire screen
-        print!("\x1B[?25l\x1B[?7l\x1B[2J");
+        print!("\x1B[?25l\x1B[?7l\x1B[?2004h\x1B[2J");
+
+        if mouse {
+            // CSI ? 1000 h   Send mouse button presses, including the wheel
+            // CSI ? 1006 h   Report them as SGR sequences
+            print!("\x1B[?1000h\x1B[?1006h");
+        }
 
-        Ok(Self)
+        Ok(Self {
+            mouse,
+            #[cfg(windows)]
+            console_modes,
+        })
     }
 }
 
@@ -124,84 +291,32 @@ impl Drop for NBTerm {
             }
         }
 
-//        #[cfg(windows)]
-//        unsafe {
-//            use winapi::shared::minwindef::{DWORD, FALSE};
-//            let handle = winapi::um::processenv::GetStdHandle(winapi::um::winbase::STD_INPUT_HANDLE);
-//            if handle != winapi::um::handleapi::INVALID_HANDLE_VALUE {
-//                let mut mode: DWORD = 0;
-//
-//                if winapi::um::consoleapi::GetConsoleMode(handle, &mut mode as *mut DWORD) != FALSE {
-//                    winapi::um::consoleapi::SetConsoleMode(handle, mode | winapi::um::wincon::ENABLE_ECHO_INPUT | winapi::um::wincon::ENABLE_LINE_INPUT);
-//                }
-//            }
-//        }
+        if self.mouse {
+            // CSI ? 1006 l   Back to the default mouse encoding
+            // CSI ? 1000 l   Stop sending mouse button presses
+            print!("\x1B[?1006l\x1B[?1000l");
+        }
 
         // CSI 0 m        Reset or normal, all attributes become turned off
         // CSI ? 25 h     Show cursor (DECTCEM), VT220
         // CSI ?  7 h     Auto-Wrap Mode (DECAWM), VT100
-        println!("\x1B[0m\x1B[?25h\x1B[?7h");
-    }
-}
-
-fn interruptable_sleep(duration: Duration) -> bool {
-    #[cfg(unix)]
-    {
-        let req = libc::timespec {
-            tv_sec:  duration.as_secs() as libc::time_t,
-            tv_nsec: duration.subsec_nanos() as i64,
-        };
-        let ret = unsafe { libc::nanosleep(&req, std::ptr::null_mut()) };
-        ret == 0
-    }
-
-    #[cfg(not(unix))]
-    {
-        std::thread::sleep(duration);
-        true
-    }
-}
-
-#[cfg(windows)]
-extern {
-    fn _getch() -> core::ffi::c_char;
-    fn _kbhit() -> core::ffi::c_int;
-}
-
-#[cfg(windows)]
-fn nb_read_byte(mut _reader: impl Read) -> std::io::Result<Option<u8>> {
-    unsafe {
-        if _kbhit() == 0 {
-            return Ok(None);
-        }
-
-        let ch = _getch();
-        Ok(Some(ch as u8))
-    }
-}
-
-#[cfg(not(windows))]
-fn nb_read_byte(mut reader: impl Read) -> std::io::Result<Option<u8>> {
-    let mut buf = [0u8];
-    loop {
-        return match reader.read(&mut buf) {
-            Err(err) => {
-                match err.kind() {
-                    std::io::ErrorKind::WouldBlock => Ok(None),
+        // CSI ? 2004 l   Disable bracketed paste mode
+        println!("\x1B[0m\x1B[?25h\x1B[?7h\x1B[?2004l");
 
-                    #[cfg(not(windows))]
-                    std::io::ErrorKind::Other if err.raw_os_error() == Some(libc::EAGAIN) => Ok(None),
+        // after the escape sequences above, which need VT processing
+        #[cfg(windows)]
+        unsafe {
+            use winapi::um::consoleapi::SetConsoleMode;
+            use winapi::um::winbase::{STD_INPUT_HANDLE, STD_OUTPUT_HANDLE};
 
-                    std::io::ErrorKind::Interrupted => continue,
-                    _ => Err(err)
-                }
+            let _ = std::io::stdout().flush();
+            if let Some(mode) = self.console_modes.input && let Some((handle, _)) = console_mode(STD_INPUT_HANDLE) {
+                SetConsoleMode(handle, mode);
             }
-            Ok(count) => if count == 0 {
-                Ok(None)
-            } else {
-                Ok(Some(buf[0]))
+            if let Some((handle, _)) = console_mode(STD_OUTPUT_HANDLE) {
+                SetConsoleMode(handle, self.console_modes.output);
             }
-        };
+        }
     }
 }
 
@@ -209,14 +324,19 @@ fn nb_read_byte(mut reader: impl Read) -> std::io::Result<Option<u8>> {
 #[command(version, about, long_about = None, after_help = "\
 color-cycle  Copyright (C) 2025  Mathias Panzenböck
 License: GPL-3.0
-Bugs: https://github.com/panzi/rust-color-cycle/issues"
+Bugs: https://github.com/panzi/rust-color-cycle/issues",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
 )]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Frames per second.
     /// 
     /// Attempt to render in this number of frames per second.
     /// Actual FPS might be lower.
-    #[arg(short, long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..MAX_FPS as i64))]
+    #[arg(short, long, default_value_t = DEFAULT_FPS, value_parser = clap::value_parser!(u32).range(1..MAX_FPS as i64))]
     pub fps: u32,
 
     /// Enable blend mode.
@@ -225,12 +345,205 @@ pub struct Args {
     #[arg(short, long, default_value_t = false)]
     pub blend: bool,
 
+    /// How colors fade into each other in blend mode.
+    /// 
+    /// Changes the look of waterfalls and fire quite a bit. Can be cycled
+    /// through with Shift+B.
+    #[arg(long, value_enum, default_value_t = BlendCurve::Linear)]
+    pub blend_curve: BlendCurve,
+
+    /// Start in performance mode.
+    /// 
+    /// Number keys speed up single color cycles while held and the keys A
+    /// to L flash the palette, for playing a scene live. Toggled with
+    /// Shift+V.
+    #[arg(long, default_value_t = false)]
+    pub perform: bool,
+
+    /// Interpolate the color cycles when the FPS can't be kept up.
+    /// 
+    /// Frames that come late show the colors half way between the previous
+    /// and the current state of the cycles, so the animation stays smooth
+    /// on slow terminals. Works with and without --blend.
+    #[arg(long, default_value_t = false)]
+    pub smooth: bool,
+
     /// Enable On Screen Display.
     /// 
-    /// Displays messages when changing things like blend mode or FPS.{n}
+    /// Displays messages when changing things like blend mode or FPS. The
+    /// text is black or white, whichever is easier to read over the image
+    /// under it.{n}
     #[arg(short, long, default_value_t = false)]
     pub osd: bool,
 
+    /// Where to show On Screen Display messages.
+    /// 
+    /// `image` shows them just below the image, or on its last line if the
+    /// image fills the whole height of the terminal.
+    #[arg(long, value_enum, default_value_t = OsdAnchor::Terminal)]
+    pub osd_anchor: OsdAnchor,
+
+    /// Where in the area of --osd-anchor to show On Screen Display messages.
+    /// 
+    /// `top` and `bottom` center the messages, the corners align them to
+    /// the left or right edge. With `--osd-anchor image` the top messages
+    /// are shown just above the image, or on its first line if there is no
+    /// room above it.
+    #[arg(long, value_enum, default_value_t = OsdPosition::Bottom)]
+    pub osd_position: OsdPosition,
+
+    /// Text color of On Screen Display messages, e.g. #FFCC00.
+    /// 
+    /// Defaults to black or white, whichever is easier to read over the
+    /// background.
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    pub osd_color: Option<color::Rgb>,
+
+    /// Background color of On Screen Display messages, e.g. #202020.
+    /// 
+    /// Defaults to the image under the message, blended towards black or
+    /// white.
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    pub osd_background: Option<color::Rgb>,
+
+    /// Spaces on either side of On Screen Display messages.
+    #[arg(long, value_name = "CELLS", default_value_t = 1, value_parser = clap::value_parser!(u32).range(..=MAX_OSD_PADDING as i64))]
+    pub osd_padding: u32,
+
+    /// Milliseconds On Screen Display messages are shown.
+    #[arg(long, value_name = "MS", default_value_t = 3000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub osd_duration: u64,
+
+    /// Milliseconds it takes to move the view-port to a new position.
+    /// 
+    /// Applies to jumps with Home, End, Page Up, and Page Down. 0 disables the
+    /// animation.
+    #[arg(long, value_name = "MS", default_value_t = 200)]
+    pub pan_transition: u64,
+
+    /// Change the colors of the terminal to match the scene.
+    /// 
+    /// Sets the 16 colors, background, and foreground of the terminal to the
+    /// most used colors of the current palette, updated at most every 5
+    /// seconds. The original colors are restored on exit.
+    #[arg(long, default_value_t = false)]
+    pub sync_terminal_colors: bool,
+
+    /// Format of palettes exported with E.
+    #[arg(long, value_enum, default_value_t = ThemeFormat::Css)]
+    pub theme_format: ThemeFormat,
+
+    /// Number of the most used colors exported with E. 0 exports all used
+    /// colors.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub theme_colors: usize,
+
+    /// Notify when the time of day reaches a timeline event.
+    /// 
+    /// Living Worlds files change the palette at certain times of the day,
+    /// like sunrise or dusk.
+    #[arg(long, value_enum, value_name = "KIND")]
+    pub timeline_alert: Option<TimelineAlert>,
+
+    /// What to do when trying to move the view-port past an edge of the image.
+    #[arg(long, value_enum, default_value_t = EdgeFeedback::None)]
+    pub edge_feedback: EdgeFeedback,
+
+    /// Ignore all keys that move the view-port.
+    /// 
+    /// Prevents accidental changes during long unattended sessions. Can be
+    /// toggled with L.
+    #[arg(long, default_value_t = false)]
+    pub pan_lock: bool,
+
+    /// Show every image pixel as this many pixels in both directions.
+    /// 
+    /// `auto` picks the biggest factor at which the whole image still fits
+    /// the terminal, so small images fill more of big terminals. Can be
+    /// changed with Z and Shift+Z.
+    #[arg(long, visible_alias = "scale", default_value = "1", value_name = "FACTOR|auto", value_parser = parse_zoom)]
+    pub zoom: Zoom,
+
+    /// Width to height ratio of the character cells of the terminal font.
+    /// 
+    /// Every cell shows two pixels on top of each other, which only looks
+    /// right with cells that are exactly twice as high as wide (1:2). For
+    /// other fonts rows or columns of the image are repeated to make up for
+    /// it, so that scenes don't look stretched. E.g. 9:20 for a font of
+    /// 9 x 20 pixels. Only applies to the interactive viewer.
+    #[arg(long, value_name = "W:H", value_parser = parse_cell_aspect)]
+    pub cell_aspect: Option<CellAspect>,
+
+    /// Enable mouse reporting for the scroll wheel.
+    /// 
+    /// The wheel pans the view-port vertically, with Shift horizontally.
+    /// With Ctrl it zooms, or changes the time of day of scenes with a
+    /// timeline.
+    /// Selecting text with the mouse then usually needs Shift held down.
+    #[arg(long, default_value_t = false)]
+    pub mouse: bool,
+
+    /// Show a grid overlay with a ruler of image coordinates.
+    /// 
+    /// Helps to locate cycle regions and coordinates in the original image.
+    /// Can be toggled with G.
+    #[arg(long, default_value_t = false)]
+    pub grid: bool,
+
+    /// Distance of the grid lines in image pixels.
+    #[arg(long, value_name = "PIXELS", default_value_t = 16, value_parser = clap::value_parser!(u32).range(2..))]
+    pub grid_size: u32,
+
+    /// Show a bar of the time of day along the bottom in scenes with a
+    /// timeline.
+    /// 
+    /// Ticks mark the times of the timeline palettes, and the part of the
+    /// day between the two palettes that are blended right now is drawn
+    /// heavier. Can be toggled with Shift+T.
+    #[arg(long, default_value_t = false)]
+    pub time_bar: bool,
+
+    /// Draw procedural weather over the image.
+    /// 
+    /// Overrides the weather of the file (the `weather` field of JSON files).
+    #[arg(long, value_name = "KIND")]
+    pub weather: Option<Weather>,
+
+    /// Don't draw the weather of the file.
+    #[arg(long, default_value_t = false, conflicts_with = "weather")]
+    pub no_weather: bool,
+
+    /// Phase offset of the color cycles.
+    /// 
+    /// A fraction from 0 to 1 of the duration of the longest color cycle of
+    /// the image. Use this to make exports or side-by-side comparisons start
+    /// at the same point of the cycle. Can be adjusted with [ and ].
+    #[arg(long, value_name = "PHASE", default_value_t = 0.0, value_parser = parse_phase)]
+    pub cycle_phase: f64,
+
+    /// Speed factor of the color cycling, from 0.25 to 8.
+    /// 
+    /// Independent of --fps and of fast forwarding the time of day. Slow
+    /// cycles like waterfalls are easier to inspect at lower speeds. Can be
+    /// halved and doubled with < and >.
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_cycle_speed)]
+    pub cycle_speed: f64,
+
+    /// Restart the animation and go back to the current time of day (ending
+    /// fast forward) whenever another file is opened.
+    /// 
+    /// By default the animation clock, a time of day selected with A, D, or
+    /// time scrub mode, and fast forward carry over to the next file.
+    #[arg(long, default_value_t = false)]
+    pub reset_clock: bool,
+
+    /// Line the animation clock up with the wall clock.
+    /// 
+    /// Instances started at different times (e.g. side by side in tmux, see
+    /// `color-cycle panes`) then show the color cycles in step.
+    #[arg(long, default_value_t = false)]
+    pub sync_clock: bool,
+
     /// Swap direction of 8 pixel columns.
     /// 
     /// The current implementation of ILBM files is broken for some files and
@@ -240,226 +553,2197 @@ pub struct Args {
     #[arg(long, default_value_t = false)]
     pub ilbm_column_swap: bool,
 
-    /// Show list of hotkeys.
+    /// Highlight the cells that are re-drawn each frame.
+    /// 
+    /// Debug mode for the differential renderer. Cells that changed since the
+    /// last frame are drawn in magenta.
     #[arg(long, default_value_t = false)]
-    pub help_hotkeys: bool,
+    pub debug_diff: bool,
 
-    /// Path to a Canvas Cycle JSON file.
-    #[arg(required = true)]
-    pub paths: Vec<PathBuf>,
-}
+    /// Always redraw the whole image instead of only the changed cells.
+    /// 
+    /// Uses more bandwidth, but works around terminals that have problems
+    /// with the cursor movements of the differential renderer. Can be
+    /// toggled with Shift+R.
+    #[arg(long, default_value_t = false)]
+    pub no_diff: bool,
 
-struct GlobalState {
-    running: Arc<AtomicBool>,
-    current_time: Option<u64>,
-    time_speed: u64,
-    stdin: StdinLock<'static>,
-    stdout: StdoutLock<'static>,
-}
+    /// Write a profile of the rendering to this file.
+    /// 
+    /// The profile is written in the Chrome trace event format. Open it with
+    /// chrome://tracing, Perfetto, or Speedscope.
+    #[cfg(feature = "profile")]
+    #[arg(long, value_name = "PATH")]
+    pub profile: Option<PathBuf>,
 
-fn main() {
-    let mut args = Args::parse();
+    /// Print a single frame of each file and exit.
+    /// 
+    /// Lines are separated by newlines, so the output can be viewed with
+    /// e.g. `cat` or `less -R`. Used when stdout is not a terminal.
+    #[arg(long, default_value_t = false, conflicts_with = "stream_ansi")]
+    pub single_frame: bool,
 
-    if args.help_hotkeys {
-        println!("\
-Hotkeys
-=======
-B              Toggle blend mode
-Q or Escape    Quit program
-O              Toggle On Screen Display
-N              Open next file
-P              Open previous file
-1 to 9         Open file by index
-0              Open last file
-+              Increase frames per second by 1
--              Decrease frames per second by 1
-W              Toogle fast forward ({FAST_FORWARD_SPEED}x speed)
-A              Go back in time by 5 minutes
-Shift+A        Go back in time by 1 minute
-D              Go forward in time by 5 minutes
-Shift+D        Go forward in time by 1 minute
-S              Go to current time and continue normal progression
-I              Reverse pixels in columns of 8.
-               This is a hack fix for images that appear to be
-               broken like that.
-Cursor Up      Move view-port up by 1 pixel
-Cursor Down    Move view-port down by 1 pixel
-Cursor Left    Move view-port left by 1 pixel
-Cursor Right   Move view-port right by 1 pixel
-Home           Move view-port to left edge
-End            Move view-port to right edge
-Ctrl+Home      Move view-port to top
-Ctrl+End       Move view-port to bottom
-Page Up        Move view-port up by half a screen
-Page Down      Move view-port down by half a screen
-Alt+Page Up    Move view-port left by half a screen
-Alt+Page Down  Move view-port right by half a screen");
-        return;
-    }
+    /// Stream the animation of the first file as ANSI escape sequences.
+    /// 
+    /// Doesn't read any hotkeys. Used when stdout is not a terminal.
+    #[arg(long, default_value_t = false)]
+    pub stream_ansi: bool,
 
-    let mut state = GlobalState {
-        running: Arc::new(AtomicBool::new(true)),
-        stdin: std::io::stdin().lock(),
-        stdout: std::io::stdout().lock(),
-        current_time: None,
-        time_speed: 1,
-    };
+    /// Write the timing of the --stream-ansi output to this file.
+    /// 
+    /// When the stream is recorded into a file, this allows to play it back
+    /// at the right speed with `color-cycle play-ansi`.
+    #[arg(long, value_name = "FILE", requires = "stream_ansi")]
+    pub record_timing: Option<PathBuf>,
 
-    {
-        let running = state.running.clone();
-        let _ = ctrlc::set_handler(move || {
-            running.store(false, Ordering::Relaxed);
-        });
-    }
+    /// Write the animation as raw video instead of showing it.
+    /// 
+    /// `y4m` can be piped into ffmpeg as is, e.g.
+    /// `color-cycle --video y4m FILE | ffmpeg -i - out.mp4`, `rgb24` needs
+    /// `-f rawvideo -pix_fmt rgb24 -s WxH -r FPS` to be passed to ffmpeg.
+    /// Frames are rendered as fast as they are read, --fps frames per second
+    /// of animation time. Each file is shown for --video-seconds, or only the
+    /// first file until interrupted. Doesn't read any hotkeys.
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with_all = ["single_frame", "stream_ansi", "describe"])]
+    pub video: Option<VideoFormat>,
+
+    /// Size of the --video frames as WIDTHxHEIGHT.
+    /// 
+    /// Images are scaled to fit, keeping their aspect ratio.
+    #[arg(long, value_name = "WxH", default_value = "640x480", value_parser = parse_size)]
+    pub video_size: (u32, u32),
 
-    let mut file_index = 0;
+    /// Write the --video to this file or named pipe instead of stdout.
+    #[arg(long, value_name = "PATH", requires = "video")]
+    pub video_output: Option<PathBuf>,
 
-    let res = match NBTerm::new() {
-        Err(err) => Err(err),
-        Ok(_nbterm) => {
-            loop {
-                match show_image(&mut args, &mut state, file_index) {
-                    Ok(Action::Goto(index)) => {
-                        file_index = index;
-                    }
-                    Ok(Action::Quit) => {
-                        break Ok(());
-                    }
-                    Err(err) => {
-                        break Err(err);
-                    }
-                }
-            }
-        }
-    };
+    /// Seconds of --video per file.
+    #[arg(long, value_name = "SECONDS", requires = "video")]
+    pub video_seconds: Option<f64>,
 
-    if let Err(err) = res {
-        eprintln!("{}: {}", args.paths[file_index].to_string_lossy(), err);
-        std::process::exit(1);
-    }
-}
+    /// Describe the first file in words instead of showing it.
+    /// 
+    /// Prints the name of the scene, the time of day, the overall colors,
+    /// and when the palette changes, for screen readers. `status` keeps
+    /// rewriting a single short line instead, for braille displays. Doesn't
+    /// read any hotkeys.
+    #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, require_equals = true, default_missing_value = "lines", conflicts_with_all = ["single_frame", "stream_ansi"])]
+    pub describe: Option<DescribeMode>,
+
+    /// Seconds between two descriptions with --describe.
+    /// 
+    /// Timeline events are described when they happen regardless.
+    #[arg(long, value_name = "SECONDS", default_value_t = describe::DEFAULT_DESCRIBE_INTERVAL, value_parser = clap::value_parser!(u64).range(1..))]
+    pub describe_interval: u64,
 
-enum Action {
-    Goto(usize),
-    Quit,
-}
+    /// Snap the displayed colors to the gamut of historic hardware.
+    /// 
+    /// The cycled palette is snapped every frame, to preview how the art
+    /// would look on the real hardware or just for the look of it.
+    #[arg(long, value_enum, conflicts_with = "mono")]
+    pub gamut: Option<Gamut>,
 
-fn get_time_of_day_msec(time_speed: u64) -> u64 {
-    #[cfg(not(windows))]
-    unsafe {
-        let mut tod = MaybeUninit::<libc::timespec>::zeroed();
-        if libc::clock_gettime(libc::CLOCK_REALTIME, tod.as_mut_ptr()) != 0 {
-            return 0;
-        }
-        let tod = tod.assume_init_ref();
-        let mut tm = MaybeUninit::<libc::tm>::zeroed();
-        if libc::localtime_r(&tod.tv_sec, tm.as_mut_ptr()).is_null() {
-            return 0;
-        }
-        let tm = tm.assume_init_ref();
-        let mut now = Duration::new(tod.tv_sec as u64, tod.tv_nsec as u32);
+    /// Render in grayscale for monochrome displays.
+    /// 
+    /// `shades` maps the luminance of the colors onto 16 shades of gray,
+    /// `blocks` onto the characters " ░▒▓█" without using any colors.
+    #[arg(long, value_enum, value_name = "RAMP")]
+    pub mono: Option<MonoRamp>,
 
-        if tm.tm_gmtoff > 0 {
-            now += Duration::from_secs(tm.tm_gmtoff as u64);
-        } else {
-            now -= Duration::from_secs((-tm.tm_gmtoff) as u64);
-        }
+    /// Gamma of the --mono ramp.
+    /// 
+    /// Values above 1 brighten dark colors, values below 1 darken bright colors.
+    #[arg(long, value_name = "GAMMA", default_value_t = 1.0, value_parser = parse_gamma)]
+    pub mono_gamma: f64,
 
-        ((now.as_millis() * time_speed as u128) % DAY_DURATION as u128) as u64
-    }
+    /// E-ink friendly mode.
+    /// 
+    /// Renders at 1 FPS (unless --fps is given) and redraws the whole image
+    /// instead of only the changed cells, which avoids ghosting artifacts.
+    /// Frames that didn't change are not drawn at all.
+    #[arg(long, default_value_t = false)]
+    pub eink: bool,
 
-    #[cfg(windows)]
-    unsafe {
-        let mut tm = MaybeUninit::<winapi::um::minwinbase::SYSTEMTIME>::zeroed();
-        winapi::um::sysinfoapi::GetLocalTime(tm.as_mut_ptr());
-        let tm = tm.assume_init_ref();
+    /// Quantize each color channel to this many levels in e-ink mode.
+    /// 
+    /// 2 gives 8 colors. Combine with --mono for gray levels.
+    #[arg(long, value_name = "LEVELS", requires = "eink", value_parser = clap::value_parser!(u32).range(2..=256))]
+    pub eink_levels: Option<u32>,
 
-        (
-            tm.wHour as u64 * 60 * 60 * 1000 +
-            tm.wMinute as u64 * 60 * 1000 +
-            tm.wSecond as u64 * 1000 +
-            tm.wMilliseconds as u64
-        ) * time_speed % DAY_DURATION
-    }
-}
+    /// Milliseconds of day time per cached palette of Living Worlds files.
+    /// 
+    /// The blended time of day palettes are computed once per this much day
+    /// time and reused, so that only one palette needs to be cycled per
+    /// frame. This saves CPU time (and battery) when running all day. 0
+    /// disables the cache.
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    pub palette_cache: u64,
+
+    /// Maximum number of cached palettes (768 bytes each).
+    /// 
+    /// The cache is cleared when it is full.
+    #[arg(long, value_name = "N", default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..))]
+    pub palette_cache_size: u64,
 
-fn get_hours_mins(time_of_day: u64) -> (u32, u32) {
-    let mins = (time_of_day / (60 * 1000)) as u32;
-    let hours = mins / 60;
-    (hours, mins - hours * 60)
-}
+    /// Maximum number of pixels of an image.
+    /// 
+    /// Files declaring bigger images are rejected instead of trying to
+    /// allocate the memory for them.
+    #[arg(long, value_name = "N", default_value_t = image::DEFAULT_MAX_PIXELS)]
+    pub max_pixels: u64,
 
-const MESSAGE_DISPLAY_DURATION: Duration = Duration::from_secs(3);
-const ERROR_MESSAGE_DISPLAY_DURATION: Duration = Duration::from_secs(1000 * 365 * 24 * 60 * 60);
+    /// Forbid any network access.
+    /// 
+    /// URLs in the arguments or in pasted text fail to load instead, so the
+    /// viewer can be pointed at untrusted playlists.
+    #[arg(long, default_value_t = false)]
+    pub offline: bool,
 
-fn show_image(args: &mut Args, state: &mut GlobalState, file_index: usize) -> Result<Action, error::Error> {
-    let path = &args.paths[file_index];
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
+    /// Maximum size of a downloaded file in megabytes.
+    #[arg(long, value_name = "MB", default_value_t = source::DEFAULT_MAX_DOWNLOAD_SIZE / (1024 * 1024))]
+    pub max_download_size: u64,
 
-    let living_world: Result<LivingWorld, error::Error> = match ilbm::ILBM::read(&mut reader) {
-        Ok(ilbm) => {
-            let res: Result<CycleImage, _> = ilbm.try_into();
-            match res {
-                Ok(image) => Ok(image.into()),
-                Err(err) => Err(err.into())
-            }
-        }
-        Err(err) => {
-            if err.kind() != ilbm::ErrorKind::UnsupportedFileFormat {
-                Err(err.into())
-            } else if let Err(err) = reader.seek(std::io::SeekFrom::Start(0)) {
-                Err(err.into())
-            } else {
-                match serde_json::from_reader(&mut reader) {
-                    Ok(image) => Ok(image),
-                    Err(err) => Err(err.into())
-                }
-            }
-        }
-    };
-    drop(reader);
+    /// Seconds a download may take in total, including redirects.
+    #[arg(long, value_name = "SECONDS", default_value_t = source::DEFAULT_NETWORK_TIMEOUT.as_secs(), value_parser = clap::value_parser!(u64).range(1..))]
+    pub network_timeout: u64,
 
-    let filename = path.file_name().map(|f| f.to_string_lossy()).unwrap_or_else(|| path.to_string_lossy());
-    let mut message = String::new();
-    let mut message_end_ts = Instant::now();
-    let mut living_world = match living_world {
-        Ok(living_world) => {
-            use std::fmt::Write;
-
-            if living_world.base().width() == 0 || living_world.base().height() == 0 {
-                message_end_ts += ERROR_MESSAGE_DISPLAY_DURATION;
-                let _ = write!(message, " {filename}: image of size {} x {} ",
-                    living_world.base().width(),
-                    living_world.base().height());
-                CycleImage::new(None, IndexedImage::new(80, 25, Palette::default()), Box::new([])).into()
-            } else {
-                if args.osd {
-                    if let Some(name) = living_world.name() {
-                        let _ = write!(message, " {name} ({filename}) ");
-                    } else {
-                        let _ = write!(message, " {filename} ");
-                    }
-                    message_end_ts += MESSAGE_DISPLAY_DURATION
-                }
+    /// Milliseconds to wait for the rest of an escape sequence.
+    /// 
+    /// A lone Escape key press is only detected after this timeout. Increase
+    /// this on slow connections if cursor keys are misinterpreted.
+    #[arg(long, value_name = "MS", default_value_t = input::DEFAULT_ESCAPE_TIMEOUT.as_millis() as u64)]
+    pub escape_timeout: u64,
 
-                living_world
-            }
-        },
-        Err(err) => {
-            use std::fmt::Write;
-            message_end_ts += ERROR_MESSAGE_DISPLAY_DURATION;
-            let _ = write!(message, " {filename}: {err} ");
-            CycleImage::new(None, IndexedImage::new(80, 25, Palette::default()), Box::new([])).into()
-        }
-    };
-    // TODO: implement full worlds demo support
+    /// Wrap each frame in synchronized output (mode 2026).
+    /// 
+    /// Terminals that support it show a frame only once it arrived
+    /// completely, which avoids tearing over slow connections. Enabled
+    /// automatically when the terminal is far away, see --no-latency-probe.
+    #[arg(long, default_value_t = false)]
+    pub sync_output: bool,
+
+    /// Limit the output to this many KiB per second.
+    /// 
+    /// Frames are delayed so that the terminal connection is not saturated,
+    /// which would make hotkeys lag behind. Set automatically over very slow
+    /// connections, see --no-latency-probe.
+    #[arg(long, value_name = "KIB", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_bandwidth: Option<u32>,
+
+    /// Don't measure the latency of the terminal.
+    /// 
+    /// By default the round-trip time of a cursor position query is measured
+    /// at startup and every few seconds. Over slow connections it lowers the
+    /// default of --fps and enables --sync-output and --max-bandwidth, unless
+    /// they are given explicitly.
+    #[arg(long, default_value_t = false)]
+    pub no_latency_probe: bool,
+
+    /// Milliseconds writing a frame may block.
+    /// 
+    /// When the terminal doesn't accept output for longer, e.g. because a
+    /// tmux client detached or an ssh connection hangs, frames are skipped
+    /// until it does again and the frame rate is halved. 0 waits forever.
+    #[arg(long, value_name = "MS", default_value_t = watchdog::DEFAULT_OUTPUT_TIMEOUT.as_millis() as u64)]
+    pub output_timeout: u64,
+
+    /// Advance to the next file after this many seconds.
+    /// 
+    /// After the last file it starts over with the first one. Files that
+    /// can't be loaded are skipped after the same time. The slideshow waits
+    /// while the color cycling is paused.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub slideshow: Option<u64>,
+
+    /// Play the files in random order.
+    /// 
+    /// Applies to N, P, and --slideshow. Every file is shown once before the
+    /// order is shuffled again. Toggled with Shift+S.
+    #[arg(long, default_value_t = false)]
+    pub shuffle: bool,
+
+    /// Reload the shown file when it changes.
+    /// 
+    /// The view-port position and the time of day are kept, so the viewer
+    /// can be used as a live preview while editing a scene. Only works for
+    /// local files.
+    #[arg(long, default_value_t = false)]
+    pub watch: bool,
+
+    /// Keep the playback state in this file and resume from it on start.
+    /// 
+    /// The current file, time of day, view position and settings are
+    /// written to the journal while running, so that after a crash or a
+    /// power loss the playback continues where it left off. For unattended
+    /// installations. A corrupted journal falls back to the previous one.
+    #[arg(long, value_name = "PATH")]
+    pub resume_journal: Option<PathBuf>,
+
+    /// Seconds between two writes of the --resume-journal.
+    #[arg(long, value_name = "SECONDS", default_value_t = journal::DEFAULT_JOURNAL_INTERVAL, value_parser = clap::value_parser!(u64).range(1..))]
+    pub journal_interval: u64,
+
+    /// Key that starts a two-key chord.
+    /// 
+    /// Press it followed by T to go to a time of day or F to go to a file by
+    /// number, then type the time (HHMM) or number and press Enter. This key
+    /// loses its normal function.
+    #[arg(long, value_name = "KEY", default_value_t = chord::DEFAULT_LEADER)]
+    pub leader_key: char,
+
+    /// Write the output of the next frames to this directory.
+    /// 
+    /// For each frame the exact escape sequences written to the terminal
+    /// (frame-NNNNNN.ansi) and the previous and current frame as PPM images
+    /// (frame-NNNNNN-prev.ppm, frame-NNNNNN.ppm) are written. Useful for
+    /// reporting rendering bugs.
+    #[arg(long, value_name = "DIR")]
+    pub dump_frames: Option<PathBuf>,
+
+    /// Number of frames to write with --dump-frames.
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "dump_frames")]
+    pub dump_count: u32,
+
+    /// Feed the frames to this shell command, e.g. an encoder.
+    /// 
+    /// Each frame is written to the standard input of the command as a line
+    /// `RGBA WIDTH HEIGHT FPS` followed by WIDTH × HEIGHT × 4 bytes of
+    /// pixels, row by row. A command that reads slower than --fps slows the
+    /// animation down. The output of the command is discarded.
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["single_frame", "video", "describe"])]
+    pub pipe_frames: Option<String>,
+
+    /// Seed of the random number generator.
+    /// 
+    /// Everything random uses this seed, so passing the same value again
+    /// reproduces the same behavior. The seed in use is shown in the file
+    /// information panel (Shift+I). Defaults to a seed based on the current
+    /// time.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Show list of hotkeys.
+    #[arg(long, default_value_t = false)]
+    pub help_hotkeys: bool,
+
+    /// Explain an error code, like E0003, with possible causes and
+    /// workarounds. Lists all codes if none is given.
+    #[arg(long, value_name = "CODE", num_args = 0..=1, exclusive = true)]
+    pub explain: Option<Option<String>>,
+
+    /// Language of the on screen display and the hotkey help, e.g. de_AT.
+    /// 
+    /// Defaults to the locale of LC_ALL, LC_MESSAGES or LANG. Built in
+    /// languages are English and German (de).
+    #[arg(long, value_name = "LOCALE")]
+    pub lang: Option<String>,
+
+    /// Load translations of the on screen display and the hotkey help
+    /// from FILE.
+    /// 
+    /// Each line has the form `key = text`, where `{}` in the text is
+    /// replaced by the value shown. Keys that aren't in the file keep
+    /// the text of --lang. See src/i18n.rs for all keys.
+    #[arg(long, value_name = "FILE")]
+    pub messages: Option<PathBuf>,
+
+    /// Change key bindings with the ones in FILE.
+    /// 
+    /// Each line has the form `key = command`, like `X = quit` or
+    /// `Shift+Q = quit`, with keys written like in --help-hotkeys. The
+    /// command `none` unbinds a key. See src/keymap.rs for all commands.
+    #[arg(long, value_name = "FILE")]
+    pub keys: Option<PathBuf>,
+
+    /// Play the demo images embedded into the binary.
+    #[cfg(feature = "embed-demo")]
+    #[arg(long, default_value_t = false)]
+    pub demo: bool,
+
+    /// Paths of Canvas Cycle JSON, Living Worlds, or ILBM files.
+    /// 
+    /// Directories are searched recursively for *.json, *.lbm, and *.ilbm
+    /// files, which are shown sorted by path. Glob patterns the shell didn't
+    /// expand (like on Windows) are expanded. URLs are loaded over HTTP.
+    /// `-` reads a file piped into stdin.
+    #[cfg_attr(feature = "embed-demo", arg(required_unless_present = "demo"))]
+    #[cfg_attr(not(feature = "embed-demo"), arg(required = true))]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Probe the terminal for supported features.
+    /// 
+    /// Prints a report of what will and won't work in this terminal.
+    Doctor,
+
+    /// Print the palette of a file as a color theme.
+    /// 
+    /// Exports the colors of the palette at the given time of day, most used
+    /// colors first, e.g. to theme a desktop after a favorite scene.
+    ExportPalette {
+        /// Format of the theme.
+        #[arg(long, value_enum, default_value_t = ThemeFormat::Css)]
+        format: ThemeFormat,
+
+        /// Only export this many of the most used colors. 0 exports all used
+        /// colors.
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        colors: usize,
+
+        /// Time of day of the palette as HH:MM. Defaults to the current time.
+        #[arg(long, value_parser = parse_time_of_day)]
+        time: Option<u64>,
+
+        /// Blend the color cycles of the palette.
+        #[arg(short, long, default_value_t = false)]
+        blend: bool,
+
+        /// Path or URL of the file.
+        path: PathBuf,
+    },
+
+    /// Export the indexed image of a file and its palette.
+    /// 
+    /// Writes the palette indices of the pixels, with the parallax layers and
+    /// sprites as they are at the start of the animation, and the palette at
+    /// the given time of day before color cycling, so that other tools can
+    /// analyze the art or re-implement the effects.
+    Export {
+        /// Format of the export.
+        #[arg(long, value_enum, default_value_t = IndexedFormat::IndexedPng)]
+        format: IndexedFormat,
+
+        /// Time of day of the palette as HH:MM. Defaults to the current time.
+        #[arg(long, value_parser = parse_time_of_day)]
+        time: Option<u64>,
+
+        /// File to write to. For the raw format the palette is written next
+        /// to it with the extension .json.
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Path or URL of the file.
+        path: PathBuf,
+    },
+
+    /// Convert ILBM files to Canvas Cycle JSON files and back.
+    /// 
+    /// Writes a .json (or .lbm) file next to each file, or into
+    /// --output-dir, with the image, the palette and the color cycles.
+    /// Files that can't be converted are reported and skipped.
+    Convert {
+        /// Format to convert to.
+        #[arg(long, value_enum, default_value_t)]
+        to: ConvertFormat,
+
+        /// Directory to write the converted files to.
+        #[arg(short, long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+
+        /// Paths or URLs of the files.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Save the palettes of a file for palette editors.
+    /// 
+    /// Writes all 256 entries of the base palette in their original order,
+    /// so that they can be edited in external tools and put back in place.
+    Palette {
+        /// Format of the palette file. Defaults to the one of the extension
+        /// of the output file (.pal, .gpl or .act).
+        #[arg(long, value_enum)]
+        format: Option<PaletteFormat>,
+
+        /// Also write each palette of the timeline, numbered from 1, e.g.
+        /// scene-1.gpl next to scene.gpl.
+        #[arg(long, default_value_t = false)]
+        timeline: bool,
+
+        /// File to write to.
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Path or URL of the file.
+        path: PathBuf,
+    },
+
+    /// Print statistics about the color cycles of files.
+    /// 
+    /// Shows how many pixels each cycle covers, how often it steps, the
+    /// entropy of the palette, and the frames per second above which no
+    /// further change is visible, to help choosing --fps and tuning cycles.
+    Stats {
+        /// Time of day of the palette as HH:MM. Defaults to the current time.
+        #[arg(long, value_parser = parse_time_of_day)]
+        time: Option<u64>,
+
+        /// Paths or URLs of the files.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Check the palettes of files for wasted colors.
+    /// 
+    /// Lists palette entries that no pixel uses, not even through a cycle,
+    /// and pairs of used entries whose colors are too close to tell apart,
+    /// so they can be merged to make room for other colors.
+    Lint {
+        /// ΔE (CIE76) below which two colors count as indistinguishable.
+        #[arg(long, default_value_t = lint::DEFAULT_THRESHOLD)]
+        threshold: f64,
+
+        /// Time of day of the palette as HH:MM. Defaults to the current time.
+        #[arg(long, value_parser = parse_time_of_day)]
+        time: Option<u64>,
+
+        /// Paths or URLs of the files.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Generate a montage tour of some files.
+    /// 
+    /// Shows a title card and then a few seconds of each file at an
+    /// interesting time of day with a slow pan, joined by crossfades. The
+    /// reel is either encoded into a video file using ffmpeg or played on
+    /// the terminal.
+    Reel {
+        /// Write the reel to this video file. Needs ffmpeg.
+        #[arg(short, long, value_name = "FILE", required_unless_present = "play", conflicts_with = "play")]
+        output: Option<PathBuf>,
+
+        /// Play the reel on the terminal instead.
+        #[arg(long, default_value_t = false)]
+        play: bool,
+
+        /// Size of the video as WIDTHxHEIGHT. Defaults to 640x480 for a
+        /// video file and to the size of the terminal for --play.
+        #[arg(long, value_name = "WxH", value_parser = parse_size)]
+        size: Option<(u32, u32)>,
+
+        /// Frames per second.
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+        fps: u32,
+
+        /// Seconds each file is shown.
+        #[arg(long, default_value_t = 6.0)]
+        seconds: f64,
+
+        /// Seconds each title card is shown.
+        #[arg(long, default_value_t = 2.0)]
+        title_seconds: f64,
+
+        /// Seconds of crossfade between title cards and files.
+        #[arg(long, default_value_t = 1.0)]
+        crossfade: f64,
+
+        /// Blend the animated color palettes.
+        #[arg(short, long, default_value_t = false)]
+        blend: bool,
+
+        /// How colors fade into each other with --blend.
+        #[arg(long, value_enum, default_value_t = BlendCurve::Linear)]
+        blend_curve: BlendCurve,
+
+        /// Cache the rendered frames in this directory.
+        /// 
+        /// Exporting the same files again (e.g. to try another video format)
+        /// reuses the cached frames instead of rendering them again. Frames
+        /// of a file are dropped when it changes.
+        #[arg(long, value_name = "DIR")]
+        frame_cache: Option<PathBuf>,
+
+        /// Size limit of the frame cache in megabytes.
+        /// 
+        /// The least recently used frames are deleted when it is exceeded.
+        #[arg(long, value_name = "MB", default_value_t = 1024, requires = "frame_cache")]
+        frame_cache_size: u64,
+
+        /// Files or directories of files to show.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Play back a recording of --stream-ansi.
+    /// 
+    /// Writes the recording to the terminal with the delays of the timing
+    /// file written by --record-timing.
+    PlayAnsi {
+        /// Timing file. Defaults to the recording with .timing appended.
+        #[arg(short, long, value_name = "FILE")]
+        timing: Option<PathBuf>,
+
+        /// Playback speed factor.
+        #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+        speed: f64,
+
+        /// The recorded ANSI stream.
+        recording: PathBuf,
+    },
+
+    /// Show files side by side in tmux panes.
+    /// 
+    /// Opens a tmux window with one pane per file, each running an instance
+    /// with --sync-clock. Keys are sent to all panes at once. Outside of
+    /// tmux a new session is started and attached.
+    Panes {
+        /// tmux layout of the panes, e.g. even-horizontal or main-vertical.
+        #[arg(long, default_value = panes::DEFAULT_LAYOUT)]
+        layout: String,
+
+        /// Paths or URLs of the files.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Options passed on to every instance, after --.
+        #[arg(last = true, value_name = "OPTIONS")]
+        options: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexedFormat {
+    /// PNG with all 256 palette entries in their original order
+    IndexedPng,
+    /// One byte per pixel, row by row, and a JSON file with the size, the
+    /// palette, and the color cycles
+    Raw,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsdAnchor {
+    /// Bottom or top line of the terminal.
+    Terminal,
+    /// Just below or above the image.
+    Image,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsdPosition {
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OsdPosition {
+    #[inline]
+    fn is_top(self) -> bool {
+        matches!(self, OsdPosition::Top | OsdPosition::TopLeft | OsdPosition::TopRight)
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescribeMode {
+    /// Print a new line for every description
+    Lines,
+    /// Rewrite a single status line
+    Status,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineAlert {
+    /// Ring the terminal bell
+    Bell,
+    /// Show the time and palette in the On Screen Display, even if it is disabled
+    Flash,
+    /// Both
+    Both,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeFeedback {
+    /// Do nothing
+    None,
+    /// Show an On Screen Display message
+    Hint,
+    /// Briefly bounce the image back from the edge
+    Bounce,
+}
+
+/// Zoom factor of the image, see `--zoom`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zoom {
+    Auto,
+    Factor(u32),
+}
+
+impl Zoom {
+    /// The factor for an image of `image_size` shown in an area of
+    /// `area_size` pixels.
+    fn factor(self, image_size: (u32, u32), area_size: (u32, u32)) -> u32 {
+        match self {
+            Zoom::Factor(factor) => factor,
+            Zoom::Auto => {
+                let (image_width, image_height) = image_size;
+                let (area_width, area_height) = area_size;
+                (area_width / image_width.max(1)).min(area_height / image_height.max(1)).clamp(1, MAX_ZOOM)
+            }
+        }
+    }
+}
+
+/// Width to height ratio of a terminal cell, see `--cell-aspect`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellAspect {
+    width: u32,
+    height: u32,
+}
+
+impl CellAspect {
+    /// The stretch of the pixels of a view-port that makes them square.
+    fn stretch(self) -> (Scale, Scale) {
+        // a pixel is half a cell high
+        if self.height >= 2 * self.width {
+            (Scale::new(self.height, 2 * self.width), Scale::ONE)
+        } else {
+            (Scale::ONE, Scale::new(2 * self.width, self.height))
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonoRamp {
+    /// Shades of gray.
+    Shades,
+    /// Block characters without colors.
+    Blocks,
+}
+
+/// Parses HH:MM into milliseconds since midnight.
+fn parse_time_of_day(value: &str) -> Result<u64, String> {
+    let Some((hours, mins)) = value.split_once(':') else {
+        return Err("time of day needs to be in the format HH:MM".to_owned());
+    };
+    let hours: u64 = hours.parse().map_err(|err: std::num::ParseIntError| err.to_string())?;
+    let mins: u64 = mins.parse().map_err(|err: std::num::ParseIntError| err.to_string())?;
+    if hours > 23 || mins > 59 {
+        return Err("time of day needs to be from 00:00 to 23:59".to_owned());
+    }
+    Ok((hours * 60 + mins) * 60 * 1000)
+}
+
+/// The playlist of `paths`, with scene indexes expanded to their scenes.
+fn playlist(paths: &[PathBuf]) -> Result<Vec<Source>, error::Error> {
+    let mut sources = Vec::with_capacity(paths.len());
+    let mut stdin: Option<Source> = None;
+    for path in paths {
+        if path.as_os_str() == "-" {
+            let source = match &stdin {
+                Some(source) => source.clone(),
+                None => {
+                    let source = Source::from_stdin().map_err(|err| error::Error::with_cause("-", Box::new(err)))?;
+                    // hotkeys are read from stdin, which is at its end now
+                    let _ = input::reopen_terminal();
+                    stdin.insert(source).clone()
+                }
+            };
+            sources.push(source);
+            continue;
+        }
+
+        if let Some(url) = path.to_str() && source::is_url(url) {
+            source::check_url(url)?;
+        }
+
+        let files = expand::expand(path).map_err(|err| error::Error::with_cause(path.to_string_lossy(), Box::new(err)))?;
+        let start = sources.len();
+        for file in &files {
+            match manifest::load(file).map_err(|err| error::Error::with_cause(file.to_string_lossy(), Box::new(err)))? {
+                Some(scenes) => sources.extend(scenes),
+                None => sources.push(file.as_path().into()),
+            }
+        }
+
+        if files.len() > 1 {
+            // files of a directory that a scene index in it already lists
+            let mut expanded = sources.split_off(start);
+            let scenes: Vec<Source> = expanded.iter()
+                .filter_map(|source| match source {
+                    Source::Scene { source, .. } => Some(source.as_ref().clone()),
+                    _ => None,
+                })
+                .collect();
+            expanded.retain(|source| !scenes.contains(source));
+            sources.extend(expanded);
+        }
+    }
+    Ok(sources)
+}
+
+/// Loads the files for `color-cycle reel` and encodes the reel to `output`,
+/// or plays it on the terminal if there is none. Files that can't be loaded
+/// are skipped with a warning.
+fn make_reel(options: reel::ReelOptions, paths: &[PathBuf], output: Option<&Path>, frame_cache: Option<FrameCache>) -> Result<(), error::Error> {
+    let mut reel = reel::Reel::new(options);
+    reel.set_frame_cache(frame_cache);
+    for source in playlist(paths)? {
+        match source.load() {
+            Ok(living_world) => {
+                let title = living_world.name().map(str::to_owned).unwrap_or_else(|| source.name().into_owned());
+                reel.add_title(title);
+                reel.add_scene(living_world, source.path());
+            }
+            Err(err) => diagnostics::report(&error::Error::with_cause(source.name(), Box::new(err))),
+        }
+    }
+
+    if reel.frame_count() == 0 {
+        return Err(error::Error::new("no files to make a reel of"));
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        let _ = ctrlc::set_handler(move || {
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    match output {
+        Some(output) => reel::encode(&mut reel, output, &running)
+            .map_err(|err| error::Error::with_cause(output.to_string_lossy(), Box::new(err))),
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            match reel::play(&mut reel, &mut stdout, &running) {
+                Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Err(err.into()),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+/// Plays back a recording of --stream-ansi for `color-cycle play-ansi`.
+fn play_ansi(recording: &Path, timing: &Path, speed: f64) -> Result<(), error::Error> {
+    fn with_path(path: &Path) -> impl Fn(std::io::Error) -> error::Error + '_ {
+        move |err| error::Error::with_cause(path.to_string_lossy(), Box::new(err))
+    }
+    let timing_file = File::open(timing).map(std::io::BufReader::new).map_err(with_path(timing))?;
+    let mut recording_file = File::open(recording).map(std::io::BufReader::new).map_err(with_path(recording))?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        let _ = ctrlc::set_handler(move || {
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    match recording::play(&mut recording_file, timing_file, &mut stdout, speed, &running) {
+        Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Err(with_path(recording)(err)),
+        _ => Ok(()),
+    }
+}
+
+/// Writes the indexed image of `living_world` and its palette at
+/// `time_of_day` for `color-cycle export`.
+fn write_indexed_export(living_world: &LivingWorld, time_of_day: u64, format: IndexedFormat, output: &Path) -> Result<(), error::Error> {
+    let clock = CycleClock::at_time_of_day(time_of_day);
+    let mut animator = Animator::new(living_world);
+    let palette = animator.palette(living_world, clock, false).clone();
+    let mut buf = IndexedImage::new(0, 0, Palette::default());
+    let image = animator.compose(living_world, living_world.base().indexed_image(), 0, 0, clock, &mut buf);
+    fn with_path(path: &Path) -> impl Fn(std::io::Error) -> error::Error + '_ {
+        move |err| error::Error::with_cause(path.to_string_lossy(), Box::new(err))
+    }
+
+    match format {
+        IndexedFormat::IndexedPng => {
+            let mut writer = BufWriter::new(File::create(output).map_err(with_path(output))?);
+            png::write_indexed_png(&mut writer, image, &palette)
+                .and_then(|_| writer.flush())
+                .map_err(with_path(output))
+        }
+        IndexedFormat::Raw => {
+            let json_path = output.with_extension("json");
+            if json_path == output {
+                return Err(error::Error::new(format!("{}: the raw indices would overwrite the palette JSON file", output.to_string_lossy())));
+            }
+            std::fs::write(output, image.data()).map_err(with_path(output))?;
+
+            let cycles: Vec<_> = animator::active_cycles(living_world, time_of_day).iter()
+                .map(|cycle| serde_json::json!({
+                    "low": cycle.low(),
+                    "high": cycle.high(),
+                    "rate": cycle.rate(),
+                    "reverse": cycle.reverse(),
+                }))
+                .collect();
+            let json = serde_json::json!({
+                "width": image.width(),
+                "height": image.height(),
+                "palette": palette.0.iter().map(|color| color.0).collect::<Vec<_>>(),
+                "cycles": cycles,
+            });
+            let mut writer = BufWriter::new(File::create(&json_path).map_err(with_path(&json_path))?);
+            serde_json::to_writer(&mut writer, &json).map_err(std::io::Error::from)
+                .and_then(|_| writeln!(writer))
+                .and_then(|_| writer.flush())
+                .map_err(with_path(&json_path))
+        }
+    }
+}
+
+/// Writes the base palette of `living_world` to `output` for
+/// `color-cycle palette`, and with `timeline` each palette of the timeline
+/// next to it.
+fn write_palette_files(living_world: &LivingWorld, format: Option<PaletteFormat>, timeline: bool, output: &Path) -> Result<(), error::Error> {
+    let Some(format) = format.or_else(|| PaletteFormat::from_path(output)) else {
+        return Err(error::Error::new(format!("{}: unknown palette format, use --format", output.to_string_lossy())));
+    };
+    let stem = output.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = output.extension().map(|extension| extension.to_string_lossy().into_owned())
+        .unwrap_or_else(|| format.extension().to_owned());
+    let name = living_world.name().unwrap_or(&stem);
+
+    let write = |path: &Path, image: &image::CycleImage, name: &str| {
+        let mut writer = BufWriter::new(File::create(path)?);
+        palette_file::write_palette(&mut writer, image.palette(), image.cycles(), name, format)?;
+        writer.flush()
+    };
+
+    write(output, living_world.base(), name)
+        .map_err(|err| error::Error::with_cause(output.to_string_lossy(), Box::new(err)))?;
+
+    if timeline {
+        for (index, image) in living_world.palettes().iter().enumerate() {
+            let path = output.with_file_name(format!("{stem}-{}.{extension}", index + 1));
+            write(&path, image, image.filename().unwrap_or(name))
+                .map_err(|err| error::Error::with_cause(path.to_string_lossy(), Box::new(err)))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the palette of `living_world` at `time_of_day` as a color theme.
+fn write_palette_theme(out: &mut impl Write, living_world: &LivingWorld, time_of_day: u64, blend: bool, colors: usize, format: ThemeFormat) -> std::io::Result<()> {
+    let mut animator = Animator::new(living_world);
+    let palette = animator.palette(living_world, CycleClock::at_time_of_day(time_of_day), blend);
+    let colors = theme::dominant_colors(palette, living_world.base().indexed_image(), colors);
+    theme::write_theme(out, &colors, format)
+}
+
+/// Parses WIDTHxHEIGHT.
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let Some((width, height)) = value.split_once(['x', 'X']) else {
+        return Err("size needs to be in the format WIDTHxHEIGHT".to_owned());
+    };
+    let width: u32 = width.parse().map_err(|err: std::num::ParseIntError| err.to_string())?;
+    let height: u32 = height.parse().map_err(|err: std::num::ParseIntError| err.to_string())?;
+    if width == 0 || height == 0 {
+        return Err("size needs to be at least 1x1".to_owned());
+    }
+    Ok((width, height))
+}
+
+/// Parses #RRGGBB or #RGB, the # being optional.
+fn parse_color(value: &str) -> Result<color::Rgb, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let digits = if hex.len() == 3 { hex.chars().flat_map(|ch| [ch, ch]).collect() } else { hex.to_owned() };
+    if digits.len() != 6 || !digits.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return Err("color needs to be in the format #RRGGBB or #RGB".to_owned());
+    }
+    let value = u32::from_str_radix(&digits, 16).map_err(|err| err.to_string())?;
+    Ok(color::Rgb([(value >> 16) as u8, (value >> 8) as u8, value as u8]))
+}
+
+fn parse_phase(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(phase) if (0.0..=1.0).contains(&phase) => Ok(phase),
+        Ok(_) => Err("phase needs to be a number from 0 to 1".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_zoom(value: &str) -> Result<Zoom, String> {
+    if value.eq_ignore_ascii_case("auto") {
+        return Ok(Zoom::Auto);
+    }
+    match value.parse::<u32>() {
+        Ok(factor) if (1..=MAX_ZOOM).contains(&factor) => Ok(Zoom::Factor(factor)),
+        Ok(_) => Err(format!("zoom needs to be auto or from 1 to {MAX_ZOOM}")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_cell_aspect(value: &str) -> Result<CellAspect, String> {
+    let Some((width, height)) = value.split_once(':') else {
+        return Err("cell aspect needs to be of the form W:H".to_owned());
+    };
+    let width = width.trim().parse::<u32>().map_err(|err| err.to_string())?;
+    let height = height.trim().parse::<u32>().map_err(|err| err.to_string())?;
+    if width == 0 || height == 0 || width > MAX_CELL_SIZE || height > MAX_CELL_SIZE {
+        return Err(format!("cell width and height need to be from 1 to {MAX_CELL_SIZE}"));
+    }
+    // stretching by more than MAX_CELL_STRETCH is surely a typo
+    if height > 2 * width * MAX_CELL_STRETCH || 2 * width > height * MAX_CELL_STRETCH {
+        return Err(format!("cell aspect needs to be between 1:{} and {}:1", 2 * MAX_CELL_STRETCH, MAX_CELL_STRETCH / 2));
+    }
+    Ok(CellAspect { width, height })
+}
+
+fn parse_speed(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        Ok(_) => Err("speed needs to be a positive number".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_cycle_speed(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if (MIN_CYCLE_SPEED..=MAX_CYCLE_SPEED).contains(&speed) => Ok(speed),
+        Ok(_) => Err(format!("cycle speed needs to be from {MIN_CYCLE_SPEED} to {MAX_CYCLE_SPEED}")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_gamma(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(gamma) if gamma.is_finite() && gamma > 0.0 => Ok(gamma),
+        Ok(_) => Err("gamma needs to be a positive number".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Draws `image` with `palette` into `frame`. `frame_palette` is the palette
+/// `frame` was last drawn with, so only the pixels whose color changed are
+/// updated. It has to be `None` if `frame` doesn't hold `image` drawn with
+/// that palette (e.g. the image changed or overlays were drawn over it).
+fn apply_palette(image: &IndexedImage, palette: &Palette, frame: &mut RgbImage, frame_palette: &mut Option<Palette>) {
+    match frame_palette {
+        Some(old_palette) => {
+            if image.apply_palette_changes(frame, palette, old_palette) {
+                old_palette.clone_from(palette);
+            }
+        }
+        None => {
+            image.apply_with_palette(frame, palette);
+            *frame_palette = Some(palette.clone());
+        }
+    }
+}
+
+/// Translated "Enabled" or "Disabled".
+fn enabled(value: bool) -> &'static str {
+    i18n::tr(if value { Msg::Enabled } else { Msg::Disabled })
+}
+
+/// Translated name of `edge`.
+fn edge_name(edge: Edge) -> &'static str {
+    i18n::tr(match edge {
+        Edge::Top    => Msg::EdgeTop,
+        Edge::Bottom => Msg::EdgeBottom,
+        Edge::Left   => Msg::EdgeLeft,
+        Edge::Right  => Msg::EdgeRight,
+    })
+}
+
+/// Replaces everything on screen by a notice that the terminal is too
+/// small, cut to `columns`.
+fn write_too_small(out: &mut impl Write, columns: u32) {
+    let msg: String = i18n::tr(Msg::TooSmall).chars().take(columns as usize).collect();
+    // CSI 0 m        Reset or normal, all attributes become turned off
+    // CSI 2 J        Clear entire screen
+    let _ = write!(out, "\x1B[0m\x1B[2J\x1B[1;1H{msg}");
+}
+
+/// The palette cache configured by --palette-cache and --palette-cache-size.
+fn palette_cache(args: &Args) -> Option<PaletteCache> {
+    (args.palette_cache > 0).then(|| PaletteCache::new(args.palette_cache, args.palette_cache_size as usize))
+}
+
+/// Seconds per frame for [`Animator::set_smoothing()`] if --smooth is used.
+fn smoothing(args: &Args) -> Option<f64> {
+    args.smooth.then(|| 1.0 / args.fps as f64)
+}
+
+/// The weather of --weather or the file, unless --no-weather is used.
+fn weather_effect(args: &Args, living_world: &LivingWorld, rng: &mut Rng) -> Option<WeatherEffect> {
+    if args.no_weather {
+        return None;
+    }
+
+    args.weather.or(living_world.weather()).map(|weather| WeatherEffect::new(weather, Rng::new(rng.next_u64())))
+}
+
+/// Applies the defaults for `link` to the settings that follow the latency.
+fn adapt_to_link(args: &mut Args, adaptive: &latency::Adaptive, link: Link) {
+    if adaptive.fps {
+        args.fps = link.fps().unwrap_or(DEFAULT_FPS);
+    }
+    if adaptive.sync_output {
+        args.sync_output = link.sync_output();
+    }
+    if adaptive.max_bandwidth {
+        args.max_bandwidth = link.max_bandwidth();
+    }
+}
+
+/// Returns the palette to render with, which is `palette` snapped to
+/// --gamut, converted to grayscale if --mono is used and quantized if
+/// --eink-levels is used.
+fn display_palette<'a>(args: &Args, palette: &'a Palette, output: &'a mut Palette) -> &'a Palette {
+    if args.gamut.is_none() && args.mono.is_none() && args.eink_levels.is_none() {
+        return palette;
+    }
+
+    if let Some(ramp) = args.mono {
+        let levels = match ramp {
+            MonoRamp::Shades => MONO_SHADES,
+            MonoRamp::Blocks => 256,
+        };
+        palette::grayscale(palette, args.mono_gamma, levels, output);
+    } else {
+        output.clone_from(palette);
+    }
+
+    if let Some(gamut) = args.gamut {
+        palette::snap_to_gamut(output, gamut);
+    }
+
+    if let Some(levels) = args.eink_levels {
+        palette::posterize(output, levels);
+    }
+
+    output
+}
+
+/// Milliseconds since midnight (UTC) with --sync-clock, so that the
+/// animation clocks of all instances line up, or else 0.
+fn clock_offset(args: &Args) -> u64 {
+    if !args.sync_clock {
+        return 0;
+    }
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|time| (time.as_millis() % DAY_DURATION as u128) as u64)
+        .unwrap_or(0)
+}
+
+/// The animation clock of the frame rendered at `now`, which stands still
+/// while paused.
+fn cycle_clock<W: Write>(state: &GlobalState<W>, now: Instant, time_of_day: u64) -> CycleClock {
+    let now = state.paused_at.unwrap_or(now);
+    let elapsed = now.saturating_duration_since(state.clock_start).as_secs_f64() * state.cycle_speed;
+    CycleClock::new((elapsed * 1000.0) as u64 + state.clock_offset, time_of_day)
+}
+
+/// Changes the speed of the animation clock, continuing from where it is
+/// at `now`.
+fn set_cycle_speed<W: Write>(state: &mut GlobalState<W>, speed: f64, now: Instant) {
+    let now = state.paused_at.unwrap_or(now);
+    state.clock_offset = cycle_clock(state, now, 0).elapsed;
+    state.clock_start = now;
+    state.cycle_speed = speed;
+}
+
+/// State shared by all files. `W` is where frames are written to, which is
+/// the terminal except when rendering into something else.
+struct GlobalState<W: Write = StdoutLock<'static>> {
+    running: Arc<AtomicBool>,
+    sources: Vec<Source>,
+    /// order in which N, P, and --slideshow go through `sources`
+    order: PlayOrder,
+    prefetcher: Prefetcher,
+    input: InputParser,
+    keymap: KeyMap,
+    rng: Rng,
+    /// start of the animation clock that drives the color cycles
+    clock_start: Instant,
+    /// milliseconds added to the animation clock, see [`clock_offset()`]
+    clock_offset: u64,
+    /// when the animation clock was stopped with Space
+    paused_at: Option<Instant>,
+    /// speed factor of the animation clock, see `--cycle-speed`
+    cycle_speed: f64,
+    /// time of day selected by the user, None to follow the clock
+    current_time: Option<u64>,
+    /// fast forward factor of the time of day
+    time_speed: u64,
+    stdin: StdinLock<'static>,
+    stdout: Tee<Watchdog<W>>,
+    frame_dump: Option<FrameDump>,
+    frame_pipe: Option<FramePipe>,
+    color_sync: Option<ColorSync>,
+    overlays: Overlays,
+    latency: Option<LatencyProbe>,
+    journal: Option<Journal>,
+    /// view position restored from the journal, applied to the next file
+    resume_position: Option<(u32, u32)>,
+    /// the next file is the current one again, reloaded by --watch
+    reloading: bool,
+}
+
+fn main() {
+    let matches = Args::command().get_matches();
+    let mut args = match Args::from_arg_matches(&matches) {
+        Ok(args) => args,
+        Err(err) => err.exit(),
+    };
+
+    if args.eink && matches.value_source("fps") != Some(ValueSource::CommandLine) {
+        args.fps = EINK_FPS;
+    }
+
+    if let Some(code) = &args.explain {
+        let mut stdout = std::io::stdout().lock();
+        let res = match code.as_deref().map(|code| (code, diagnostics::Code::parse(code))) {
+            None => diagnostics::list(&mut stdout),
+            Some((_, Some(code))) => diagnostics::explain(&mut stdout, code),
+            Some((code, None)) => {
+                eprintln!("unknown error code: {code}");
+                std::process::exit(1);
+            }
+        };
+        if let Err(err) = res && err.kind() != std::io::ErrorKind::BrokenPipe {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(command) = &args.command {
+        let res = match command {
+            Command::Doctor => doctor::run(),
+            Command::ExportPalette { format, colors, time, blend, path } => {
+                let source: Source = path.as_path().into();
+                source.load()
+                    .and_then(|living_world| {
+                        let time_of_day = time.unwrap_or_else(|| get_time_of_day_msec(1));
+                        let mut stdout = std::io::stdout().lock();
+                        match write_palette_theme(&mut stdout, &living_world, time_of_day, *blend, *colors, *format) {
+                            Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Err(err.into()),
+                            _ => Ok(()),
+                        }
+                    })
+                    .map_err(|err| error::Error::with_cause(source.name(), Box::new(err)))
+            }
+            Command::Export { format, time, output, path } => {
+                let source: Source = path.as_path().into();
+                source.load()
+                    .map_err(|err| error::Error::with_cause(source.name(), Box::new(err)))
+                    .and_then(|living_world| {
+                        let time_of_day = time.unwrap_or_else(|| get_time_of_day_msec(1));
+                        write_indexed_export(&living_world, time_of_day, *format, output)
+                    })
+            }
+            Command::Convert { to, output_dir, paths } => {
+                let mut failed = 0;
+                for path in paths {
+                    let source: Source = path.as_path().into();
+                    let output = convert::output_path(path, output_dir.as_deref(), *to);
+                    let res = source.read()
+                        .and_then(|data| {
+                            let filename = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+                            to.decode(&data, &filename)
+                        })
+                        .map_err(|err| error::Error::with_cause(source.name(), Box::new(err)))
+                        .and_then(|image| {
+                            File::create(&output)
+                                .map(BufWriter::new)
+                                .map_err(error::Error::from)
+                                .and_then(|mut writer| {
+                                    to.write(&mut writer, &image)?;
+                                    Ok(writer.flush()?)
+                                })
+                                .map_err(|err| error::Error::with_cause(output.to_string_lossy(), Box::new(err)))
+                        });
+                    if let Err(err) = res {
+                        diagnostics::report(&err);
+                        failed += 1;
+                    }
+                }
+                if failed > 0 {
+                    Err(error::Error::new(format!("{failed} of {} files could not be converted", paths.len())))
+                } else {
+                    Ok(())
+                }
+            }
+            Command::Palette { format, timeline, output, path } => {
+                let source: Source = path.as_path().into();
+                source.load()
+                    .map_err(|err| error::Error::with_cause(source.name(), Box::new(err)))
+                    .and_then(|living_world| write_palette_files(&living_world, *format, *timeline, output))
+            }
+            Command::Stats { time, paths } => {
+                let time_of_day = time.unwrap_or_else(|| get_time_of_day_msec(1));
+                let mut stdout = std::io::stdout().lock();
+                let mut res = Ok(());
+                for (index, path) in paths.iter().enumerate() {
+                    let source: Source = path.as_path().into();
+                    let living_world = match source.load() {
+                        Ok(living_world) => living_world,
+                        Err(err) => {
+                            res = Err(error::Error::with_cause(source.name(), Box::new(err)));
+                            break;
+                        }
+                    };
+                    let mut animator = Animator::new(&living_world);
+                    let palette = animator.palette(&living_world, CycleClock::at_time_of_day(time_of_day), false);
+                    let cycles = animator::active_cycles(&living_world, time_of_day);
+                    let scene_stats = stats::SceneStats::new(living_world.base().indexed_image(), palette, cycles);
+                    let written = if index > 0 { writeln!(stdout) } else { Ok(()) }
+                        .and_then(|_| stats::write_stats(&mut stdout, &source.name(), &scene_stats));
+                    if let Err(err) = written {
+                        if err.kind() != std::io::ErrorKind::BrokenPipe {
+                            res = Err(err.into());
+                        }
+                        break;
+                    }
+                }
+                res
+            }
+            Command::Lint { threshold, time, paths } => {
+                let time_of_day = time.unwrap_or_else(|| get_time_of_day_msec(1));
+                let mut stdout = std::io::stdout().lock();
+                let mut res = Ok(());
+                for (index, path) in paths.iter().enumerate() {
+                    let source: Source = path.as_path().into();
+                    let living_world = match source.load() {
+                        Ok(living_world) => living_world,
+                        Err(err) => {
+                            res = Err(error::Error::with_cause(source.name(), Box::new(err)));
+                            break;
+                        }
+                    };
+                    let mut animator = Animator::new(&living_world);
+                    let palette = animator.palette(&living_world, CycleClock::at_time_of_day(time_of_day), false);
+                    let cycles = animator::active_cycles(&living_world, time_of_day);
+                    let usage = living_world.base().indexed_image().index_usage();
+                    let palette_lint = lint::PaletteLint::new(&usage, palette, cycles, *threshold);
+                    let written = if index > 0 { writeln!(stdout) } else { Ok(()) }
+                        .and_then(|_| lint::write_lint(&mut stdout, &source.name(), palette, &palette_lint));
+                    if let Err(err) = written {
+                        if err.kind() != std::io::ErrorKind::BrokenPipe {
+                            res = Err(err.into());
+                        }
+                        break;
+                    }
+                }
+                res
+            }
+            Command::Reel { output, play, size, fps, seconds, title_seconds, crossfade, blend, blend_curve, frame_cache, frame_cache_size, paths } => {
+                let (width, height) = size.unwrap_or_else(|| if *play {
+                    let (columns, rows) = term_size::dimensions().unwrap_or((80, 25));
+                    (columns as u32, rows.saturating_sub(1).max(1) as u32 * 2)
+                } else {
+                    (640, 480)
+                });
+                let options = reel::ReelOptions {
+                    width,
+                    height,
+                    fps: *fps,
+                    scene_duration: seconds.max(0.0),
+                    title_duration: title_seconds.max(0.0),
+                    crossfade: crossfade.max(0.0),
+                    blend: *blend,
+                    blend_curve: *blend_curve,
+                };
+                match frame_cache {
+                    Some(dir) => FrameCache::open(dir, frame_cache_size.saturating_mul(1024 * 1024))
+                        .map_err(|err| error::Error::with_cause(dir.to_string_lossy(), Box::new(err)))
+                        .and_then(|frame_cache| make_reel(options, paths, output.as_deref(), Some(frame_cache))),
+                    None => make_reel(options, paths, output.as_deref(), None),
+                }
+            }
+            Command::PlayAnsi { timing, speed, recording } => {
+                let timing = timing.clone().unwrap_or_else(|| recording::default_timing_path(recording));
+                play_ansi(recording, &timing, *speed)
+            }
+            Command::Panes { layout, paths, options } => {
+                panes::open_panes(paths, options, layout)
+            }
+        };
+
+        if let Err(err) = res {
+            diagnostics::report(&err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut catalog = match &args.lang {
+        Some(locale) => Catalog::for_locale(locale),
+        None => Catalog::from_env(),
+    };
+    if let Some(path) = &args.messages && let Err(err) = catalog.load(path) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+    i18n::init(catalog);
+
+    let mut keymap = KeyMap::default();
+    if let Some(path) = &args.keys && let Err(err) = keymap.load(path) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+
+    if args.help_hotkeys {
+        println!("{}", i18n::catalog().hotkey_help(&keymap, FAST_FORWARD_SPEED));
+        return;
+    }
+
+    image::set_max_pixels(args.max_pixels);
+    source::set_network_policy(NetworkPolicy {
+        offline: args.offline,
+        max_download_size: args.max_download_size.saturating_mul(1024 * 1024),
+        timeout: Duration::from_secs(args.network_timeout),
+    });
+
+    let sources = match playlist(&args.paths) {
+        Ok(sources) => sources,
+        Err(err) => {
+            diagnostics::report(&err);
+            std::process::exit(1);
+        }
+    };
+
+    #[cfg(feature = "embed-demo")]
+    let sources = if args.demo {
+        demo::DEMOS.iter().map(|&(name, data)| Source::Embedded { name, data }).collect()
+    } else {
+        sources
+    };
+
+    let mut state = GlobalState {
+        running: Arc::new(AtomicBool::new(true)),
+        order: PlayOrder::new(sources.len()),
+        sources,
+        prefetcher: Prefetcher::new(),
+        input: InputParser::new(Duration::from_millis(args.escape_timeout)),
+        keymap,
+        rng: args.seed.map(Rng::new).unwrap_or_else(Rng::from_time),
+        stdin: std::io::stdin().lock(),
+        stdout: Tee::new(Watchdog::new(std::io::stdout().lock())),
+        frame_dump: None,
+        frame_pipe: None,
+        color_sync: None,
+        clock_start: Instant::now(),
+        clock_offset: clock_offset(&args),
+        paused_at: None,
+        cycle_speed: args.cycle_speed,
+        current_time: None,
+        time_speed: 1,
+        overlays: Overlays::new(),
+        latency: None,
+        journal: None,
+        resume_position: None,
+        reloading: false,
+    };
+    state.overlays.set(Overlay::Osd, args.osd);
+    state.overlays.set(Overlay::Grid, args.grid);
+    state.overlays.set(Overlay::TimeBar, args.time_bar);
+    state.overlays.set(Overlay::DebugDiff, args.debug_diff);
+
+    if let Some(dir) = &args.dump_frames {
+        match FrameDump::new(dir, args.dump_count) {
+            Ok(frame_dump) => state.frame_dump = Some(frame_dump),
+            Err(err) => {
+                eprintln!("{}: {}", dir.to_string_lossy(), err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(command) = &args.pipe_frames {
+        match FramePipe::spawn(command) {
+            Ok(frame_pipe) => state.frame_pipe = Some(frame_pipe),
+            Err(err) => {
+                eprintln!("{command}: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    {
+        let running = state.running.clone();
+        let _ = ctrlc::set_handler(move || {
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    #[cfg(feature = "profile")]
+    if args.profile.is_some() {
+        profile::start();
+    }
+
+    if let Some(mode) = args.describe {
+        if let Err(err) = describe_first_file(&args, &mut state, mode) {
+            diagnostics::report(&err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(format) = args.video {
+        let res = render_video(&args, &mut state, format);
+
+        #[cfg(feature = "profile")]
+        if let Some(path) = &args.profile && let Err(err) = profile::finish(path) {
+            eprintln!("{}: {}", path.to_string_lossy(), err);
+        }
+
+        if let Err(err) = res {
+            diagnostics::report(&err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.stream_ansi && args.record_timing.is_none() && stdout_is_file() {
+        eprintln!("Warning: recording --stream-ansi into a file without timing.\n\
+            Use --record-timing to be able to play it back at the right speed\n\
+            with `color-cycle play-ansi`.");
+    }
+
+    if args.single_frame || args.stream_ansi {
+        let res = render_non_interactive(&args, &mut state);
+
+        if let Some(frame_pipe) = state.frame_pipe.take() {
+            let _ = frame_pipe.finish();
+        }
+
+        #[cfg(feature = "profile")]
+        if let Some(path) = &args.profile && let Err(err) = profile::finish(path) {
+            eprintln!("{}: {}", path.to_string_lossy(), err);
+        }
+
+        if let Err(err) = res {
+            diagnostics::report(&err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if !std::io::stdout().is_terminal() {
+        eprintln!("stdout is not a terminal.\n\
+            Use --single-frame to print a single frame of each file or\n\
+            --stream-ansi to stream the animation as ANSI escape sequences.");
+        std::process::exit(1);
+    }
+
+    let mut file_index = 0;
+
+    if let Some(path) = &args.resume_journal {
+        let mut journal = Journal::new(path, Duration::from_secs(args.journal_interval));
+        if let Some(snapshot) = journal.recover() {
+            file_index = resume_index(&state.sources, &snapshot);
+            state.current_time = snapshot.current_time;
+            state.time_speed = snapshot.time_speed;
+            state.rng = Rng::new(snapshot.seed);
+            state.resume_position = snapshot.position;
+            args.fps = snapshot.fps;
+            args.blend = snapshot.blend;
+            args.cycle_phase = snapshot.cycle_phase;
+        }
+        state.journal = Some(journal);
+    }
+
+    if args.shuffle {
+        state.order.shuffle(file_index, &mut state.rng);
+    }
+
+    let res = match NBTerm::new(args.mouse) {
+        Err(err) => Err(err),
+        Ok(_nbterm) => {
+            if args.sync_terminal_colors && let Ok(saved) = SavedColors::query(&mut state.stdin, &mut state.stdout) {
+                state.color_sync = Some(ColorSync::new(saved));
+            }
+
+            if !args.no_latency_probe && let Ok(Some(rtt)) = latency::measure(&mut state.stdin, &mut state.stdout) {
+                let adaptive = latency::Adaptive {
+                    fps: !args.eink && matches.value_source("fps") != Some(ValueSource::CommandLine),
+                    sync_output: matches.value_source("sync_output") != Some(ValueSource::CommandLine),
+                    max_bandwidth: args.max_bandwidth.is_none(),
+                };
+                let probe = LatencyProbe::new(rtt, adaptive, Instant::now());
+                adapt_to_link(&mut args, &probe.adaptive, probe.link());
+                state.latency = Some(probe);
+            }
+
+            if args.output_timeout > 0 {
+                state.stdout.get_mut().watch(Duration::from_millis(args.output_timeout));
+            }
+
+            let res = loop {
+                let scene_time = state.sources[file_index].scene_time();
+                let saved_clock = (state.current_time, state.time_speed);
+                let res = show_image(&mut args, &mut state, file_index);
+                if scene_time.is_some() {
+                    // the time of day of the scene index only applies to its scene
+                    (state.current_time, state.time_speed) = saved_clock;
+                }
+                match res {
+                    Ok(Action::Goto(index)) => {
+                        file_index = index;
+                    }
+                    Ok(Action::Quit) => {
+                        break Ok(());
+                    }
+                    Err(err) => {
+                        break Err(err);
+                    }
+                }
+            };
+
+            let _ = state.stdout.get_mut().finish();
+
+            if let Some(frame_pipe) = state.frame_pipe.take() {
+                let _ = frame_pipe.finish();
+            }
+
+            if let Some(color_sync) = &state.color_sync {
+                let _ = color_sync.restore(&mut state.stdout);
+            }
+
+            res
+        }
+    };
+
+    #[cfg(feature = "profile")]
+    if let Some(path) = &args.profile && let Err(err) = profile::finish(path) {
+        eprintln!("{}: {}", path.to_string_lossy(), err);
+    }
+
+    if let Err(err) = res {
+        diagnostics::report(&error::Error::with_cause(state.sources[file_index].name(), Box::new(err)));
+        std::process::exit(1);
+    }
+}
+
+enum Action {
+    Goto(usize),
+    Quit,
+}
+
+/// Index of the journaled file in `sources`. Falls back to the first file
+/// if the playlist changed and the file isn't in it anymore.
+fn resume_index(sources: &[Source], snapshot: &journal::Snapshot) -> usize {
+    if sources.get(snapshot.file_index).is_some_and(|source| source.name() == snapshot.source) {
+        return snapshot.file_index;
+    }
+    sources.iter()
+        .position(|source| source.name() == snapshot.source)
+        .unwrap_or(0)
+}
+
+/// Whether stdout is redirected into a regular file (not a terminal or pipe).
+fn stdout_is_file() -> bool {
+    #[cfg(not(windows))]
+    let handle = {
+        use std::os::fd::AsFd;
+        std::io::stdout().as_fd().try_clone_to_owned()
+    };
+    #[cfg(windows)]
+    let handle = {
+        use std::os::windows::io::AsHandle;
+        std::io::stdout().as_handle().try_clone_to_owned()
+    };
+
+    handle.map(File::from)
+        .and_then(|file| file.metadata())
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+fn get_time_of_day_msec(time_speed: u64) -> u64 {
+    #[cfg(not(windows))]
+    unsafe {
+        let mut tod = MaybeUninit::<libc::timespec>::zeroed();
+        if libc::clock_gettime(libc::CLOCK_REALTIME, tod.as_mut_ptr()) != 0 {
+            return 0;
+        }
+        let tod = tod.assume_init_ref();
+        let mut tm = MaybeUninit::<libc::tm>::zeroed();
+        if libc::localtime_r(&tod.tv_sec, tm.as_mut_ptr()).is_null() {
+            return 0;
+        }
+        let tm = tm.assume_init_ref();
+        let mut now = Duration::new(tod.tv_sec as u64, tod.tv_nsec as u32);
+
+        if tm.tm_gmtoff > 0 {
+            now += Duration::from_secs(tm.tm_gmtoff as u64);
+        } else {
+            now -= Duration::from_secs((-tm.tm_gmtoff) as u64);
+        }
+
+        ((now.as_millis() * time_speed as u128) % DAY_DURATION as u128) as u64
+    }
+
+    #[cfg(windows)]
+    unsafe {
+        let mut tm = MaybeUninit::<winapi::um::minwinbase::SYSTEMTIME>::zeroed();
+        winapi::um::sysinfoapi::GetLocalTime(tm.as_mut_ptr());
+        let tm = tm.assume_init_ref();
+
+        (
+            tm.wHour as u64 * 60 * 60 * 1000 +
+            tm.wMinute as u64 * 60 * 1000 +
+            tm.wSecond as u64 * 1000 +
+            tm.wMilliseconds as u64
+        ) * time_speed % DAY_DURATION
+    }
+}
+
+/// The time of day shifted by the `time_offset` of a scene index entry.
+fn shifted_time_of_day(time_speed: u64, offset: i64) -> u64 {
+    (get_time_of_day_msec(time_speed) as i64 + offset).rem_euclid(DAY_DURATION as i64) as u64
+}
+
+/// Prints descriptions of the first file until interrupted, see
+/// `--describe`.
+fn describe_first_file<W: Write>(args: &Args, state: &mut GlobalState<W>, mode: DescribeMode) -> Result<(), error::Error> {
+    let (living_world, file_info) = state.prefetcher.load(&state.sources, 0)
+        .map_err(|err| error::Error::with_cause(state.sources[0].name(), Box::new(err)))?;
+    let name = state.sources[0].name();
+    let title = living_world.name().or(file_info.title.as_deref()).unwrap_or(&name);
+    let usage = living_world.base().indexed_image().index_usage();
+    let mut animator = Animator::new(&living_world);
+    let interval = Duration::from_secs(args.describe_interval);
+    let ignore_broken_pipe = |res: std::io::Result<()>| match res {
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        res => res,
+    };
+
+    let mut last_time_of_day = None;
+    let mut next_description_ts = Instant::now();
+    while state.running.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        let time_of_day = state.current_time.unwrap_or_else(|| get_time_of_day_msec(state.time_speed));
+        let palette = animator.palette(&living_world, CycleClock::at_time_of_day(time_of_day), false);
+        let colors = describe::Colors::new(&usage, palette);
+        let next_event = living_world.next_event(time_of_day).map(|event| event.time_of_day());
+        let event = last_time_of_day.and_then(|last_time_of_day| living_world.crossed_event(last_time_of_day, time_of_day));
+
+        let text = match mode {
+            DescribeMode::Status => (event.is_some() || now >= next_description_ts).then(||
+                format!("\r\x1B[K{}", describe::status_line(title, time_of_day, &colors))),
+            DescribeMode::Lines => if last_time_of_day.is_none() {
+                Some(describe::describe_scene(title, file_info.author.as_deref(), file_info.month.as_deref(), time_of_day, &colors, next_event) + "\n")
+            } else if let Some(event) = event {
+                Some(describe::describe_change(event.time_of_day(), &colors) + "\n")
+            } else if now >= next_description_ts {
+                Some(describe::describe_update(time_of_day, &colors, next_event) + "\n")
+            } else {
+                None
+            },
+        };
+
+        if let Some(text) = text {
+            match write!(state.stdout, "{text}").and_then(|_| state.stdout.flush()) {
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                res => res?,
+            }
+            next_description_ts = now + interval;
+        }
+        last_time_of_day = Some(time_of_day);
+
+        if !pacing::interruptable_sleep(Duration::from_secs(1)) {
+            break;
+        }
+    }
+
+    if mode == DescribeMode::Status {
+        ignore_broken_pipe(writeln!(state.stdout))?;
+    }
+
+    Ok(())
+}
+
+fn render_non_interactive<W: Write>(args: &Args, state: &mut GlobalState<W>) -> Result<(), error::Error> {
+    let load = |state: &mut GlobalState<W>, file_index: usize| {
+        let living_world = state.prefetcher.load(&state.sources, file_index);
+        state.prefetcher.prefetch(&state.sources, file_index + 1);
+        living_world.map(|(living_world, _)| living_world).map_err(|err| error::Error::with_cause(state.sources[file_index].name(), Box::new(err)))
+    };
+    let ignore_broken_pipe = |res: std::io::Result<()>| match res {
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        res => res,
+    };
+    let mut linebuf = String::new();
+    let mut display_palette_buf = Palette::default();
+    let mut sprite_buf = IndexedImage::new(0, 0, Palette::default());
+
+    if args.single_frame {
+        let time_of_day = state.current_time.unwrap_or_else(|| get_time_of_day_msec(state.time_speed));
+
+        for file_index in 0..state.sources.len() {
+            if !state.running.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let living_world = load(state, file_index)?;
+            let mut animator = Animator::new(&living_world);
+            animator.set_phase(args.cycle_phase);
+            animator.set_blend_curve(args.blend_curve);
+            let clock = CycleClock::at_time_of_day(time_of_day);
+            let indexed_image = animator.compose(&living_world, living_world.base().indexed_image(), 0, 0, clock, &mut sprite_buf);
+            let palette = animator.palette(&living_world, clock, args.blend);
+            let palette = display_palette(args, palette, &mut display_palette_buf);
+            let mut frame = RgbImage::new(indexed_image.width(), indexed_image.height());
+
+            indexed_image.apply_with_palette(&mut frame, palette);
+            if args.mono == Some(MonoRamp::Blocks) {
+                blocks_image_to_text_into(&frame, &mut linebuf);
+            } else {
+                image_to_ansi_text_into(&frame, &mut linebuf);
+            }
+
+            ignore_broken_pipe(writeln!(state.stdout, "{linebuf}"))?;
+        }
+
+        ignore_broken_pipe(state.stdout.flush())?;
+        return Ok(());
+    }
+
+    let living_world = load(state, 0)?;
+    let mut animator = Animator::new(&living_world);
+    animator.set_phase(args.cycle_phase);
+    animator.set_blend_curve(args.blend_curve);
+    animator.set_palette_cache(palette_cache(args));
+    animator.set_smoothing(smoothing(args));
+    let mut weather = weather_effect(args, &living_world, &mut state.rng);
+    let indexed_image = living_world.base().indexed_image();
+    let mut frame = RgbImage::new(indexed_image.width(), indexed_image.height());
+    let mut prev_frame = RgbImage::new(indexed_image.width(), indexed_image.height());
+    let mut frame_palette = None;
+    let mut prev_frame_palette = None;
+    let loop_start_ts = Instant::now();
+    let mut pacer = FramePacer::new(Duration::from_secs_f64(1.0 / (args.fps as f64)), loop_start_ts);
+
+    let timing_error = |err: std::io::Error| error::Error::with_cause(
+        args.record_timing.as_deref().unwrap_or(Path::new("")).to_string_lossy(), Box::new(err));
+    let mut timing = args.record_timing.as_deref()
+        .map(TimingWriter::create)
+        .transpose()
+        .map_err(timing_error)?;
+
+    // CSI ? 25 l     Hide cursor (DECTCEM), VT220
+    // CSI 2 J        Clear entire screen
+    const START: &str = "\x1B[?25l\x1B[2J";
+    let res = write!(state.stdout, "{START}");
+    ignore_broken_pipe(res)?;
+    if let Some(timing) = &mut timing {
+        timing.record(START.len()).map_err(timing_error)?;
+    }
+
+    let mut first_frame = true;
+    let res = loop {
+        if !state.running.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+
+        let frame_start_ts = Instant::now();
+        if state.frame_dump.is_some() {
+            state.stdout.start_capture();
+        }
+
+        let time_of_day = state.current_time.unwrap_or_else(|| get_time_of_day_msec(state.time_speed));
+        let clock = cycle_clock(state, frame_start_ts, time_of_day);
+
+        let composed = animator.compose(&living_world, indexed_image, 0, 0, clock, &mut sprite_buf);
+        let palette = animator.palette(&living_world, clock, args.blend);
+        let palette = display_palette(args, palette, &mut display_palette_buf);
+        if !living_world.layers().is_empty() || !living_world.sprites().is_empty() {
+            frame_palette = None;
+        }
+        apply_palette(composed, palette, &mut frame, &mut frame_palette);
+        if let Some(weather) = &mut weather {
+            weather.draw(&mut frame, time_of_day, frame_start_ts);
+            frame_palette = None;
+        }
+
+        if let Some(frame_pipe) = &mut state.frame_pipe && let Err(err) = frame_pipe.write_frame(&frame, args.fps) {
+            eprintln!("{}: {}", args.pipe_frames.as_deref().unwrap_or_default(), err);
+            state.frame_pipe = None;
+        }
+
+        let redraw_all = first_frame || args.eink || args.no_diff;
+        if args.mono == Some(MonoRamp::Blocks) {
+            blocks_image_to_ansi_into((!redraw_all).then_some(&prev_frame), &frame, false, 0, &mut linebuf);
+            first_frame = false;
+        } else if redraw_all {
+            simple_image_to_ansi_into(&frame, &mut linebuf);
+            first_frame = false;
+        } else {
+            image_to_ansi_into(&prev_frame, &frame, false, &mut linebuf);
+        }
+        std::mem::swap(&mut frame, &mut prev_frame);
+        std::mem::swap(&mut frame_palette, &mut prev_frame_palette);
+
+        if let Err(err) = write!(state.stdout, "\x1B[1;1H{linebuf}").and_then(|_| state.stdout.flush()) {
+            break Err(err);
+        }
+        // the length of "\x1B[1;1H" and the frame
+        if let Some(timing) = &mut timing && let Err(err) = timing.record(6 + linebuf.len()) {
+            break Err(err);
+        }
+
+        if let Some(frame_dump) = &mut state.frame_dump && let Some(payload) = state.stdout.take_capture() {
+            if let Err(err) = frame_dump.write(&payload, &frame, &prev_frame) {
+                eprintln!("{}: {}", frame_dump.dir().to_string_lossy(), err);
+                state.frame_dump = None;
+            } else if frame_dump.is_done() {
+                state.frame_dump = None;
+            }
+        }
+
+        if !pacer.wait(false) {
+            break Ok(());
+        }
+    };
+
+    // CSI 0 m        Reset or normal, all attributes become turned off
+    // CSI ? 25 h     Show cursor (DECTCEM), VT220
+    const END: &str = "\x1B[0m\x1B[?25h\n";
+    let _ = write!(state.stdout, "{END}");
+    let _ = state.stdout.flush();
+
+    ignore_broken_pipe(res)?;
+    if let Some(mut timing) = timing {
+        timing.record(END.len())
+            .and_then(|_| timing.finish())
+            .map_err(timing_error)?;
+    }
+    Ok(())
+}
+
+/// Renders the files for `--video` with a virtual clock, so no frame is
+/// dropped no matter how long the consumer takes.
+fn render_video<W: Write>(args: &Args, state: &mut GlobalState<W>, format: VideoFormat) -> Result<(), error::Error> {
+    let (width, height) = args.video_size;
+    image::check_dimensions(width, height)
+        .map_err(|err| error::Error::with_cause("--video-size", Box::new(err)).with_code(diagnostics::Code::ImageTooLarge))?;
+
+    let out: Box<dyn Write> = match &args.video_output {
+        Some(path) => Box::new(File::create(path)
+            .map_err(|err| error::Error::with_cause(path.to_string_lossy(), Box::new(err)))?),
+        None if std::io::stdout().is_terminal() => {
+            return Err(error::Error::new("refusing to write video to a terminal, pipe it into ffmpeg instead"));
+        }
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut video = VideoWriter::new(BufWriter::new(out), format, width, height, args.fps);
+
+    let frame_duration = 1.0 / args.fps as f64;
+    let start_clock = CycleClock::at_time_of_day(state.current_time.unwrap_or_else(|| get_time_of_day_msec(state.time_speed)));
+    let mut display_palette_buf = Palette::default();
+    let mut sprite_buf = IndexedImage::new(0, 0, Palette::default());
+    // animation time of the current frame across all files
+    let mut time = 0.0;
+
+    let file_count = if args.video_seconds.is_some() { state.sources.len() } else { 1 };
+    for file_index in 0..file_count {
+        let living_world = state.prefetcher.load(&state.sources, file_index)
+            .map(|(living_world, _)| living_world)
+            .map_err(|err| error::Error::with_cause(state.sources[file_index].name(), Box::new(err)))?;
+        state.prefetcher.prefetch(&state.sources, file_index + 1);
+
+        let mut animator = Animator::new(&living_world);
+        animator.set_phase(args.cycle_phase);
+        animator.set_blend_curve(args.blend_curve);
+        animator.set_smoothing(smoothing(args));
+        let mut weather = weather_effect(args, &living_world, &mut state.rng);
+        let indexed_image = living_world.base().indexed_image();
+        let mut frame = RgbImage::new(indexed_image.width(), indexed_image.height());
+        let mut frame_palette = None;
+        let frame_count = args.video_seconds.map(|seconds| (seconds.max(0.0) * args.fps as f64).round() as u64);
+        let start_ts = Instant::now();
+
+        let mut index = 0;
+        while frame_count.is_none_or(|frame_count| index < frame_count) && state.running.load(Ordering::Relaxed) {
+            let blend_cycle = index as f64 * frame_duration;
+            let time_of_day = match state.current_time {
+                Some(time_of_day) => time_of_day,
+                None => start_clock.advance((time * 1000.0) as u64, state.time_speed).time_of_day,
+            };
+
+            let clock = CycleClock::from_secs(blend_cycle * args.cycle_speed, time_of_day);
+            let composed = animator.compose(&living_world, indexed_image, 0, 0, clock, &mut sprite_buf);
+            let palette = animator.palette(&living_world, clock, args.blend);
+            let palette = display_palette(args, palette, &mut display_palette_buf);
+            if !living_world.layers().is_empty() || !living_world.sprites().is_empty() {
+                frame_palette = None;
+            }
+            apply_palette(composed, palette, &mut frame, &mut frame_palette);
+            if let Some(weather) = &mut weather {
+                weather.draw(&mut frame, time_of_day, start_ts + Duration::from_secs_f64(blend_cycle));
+                frame_palette = None;
+            }
+
+            match video.write_frame(&frame) {
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                res => res?,
+            }
+
+            index += 1;
+            time += frame_duration;
+        }
+    }
+
+    match video.flush() {
+        Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Formats label and value pairs as aligned lines for [`write_panel()`].
+fn panel_lines(fields: &[(String, String)]) -> Vec<String> {
+    let label_width = fields.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0) + 1;
+    fields.iter()
+        .map(|(label, value)| format!(" {:<label_width$} {value} ", format!("{label}:")))
+        .collect()
+}
+
+/// Draws `lines` as a box with its top left corner at `row`/`column`.
+fn write_panel(out: &mut impl Write, lines: &[String], row: u32, column: u32, max_width: u32, max_rows: u32) {
+    let width = lines.iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(max_width as usize);
+
+    let _ = write!(out, "\x1B[38;2;255;255;255m\x1B[48;2;0;0;0m");
+    for (index, line) in lines.iter().take(max_rows as usize).enumerate() {
+        let line: String = line.chars().take(width).collect();
+        let _ = write!(out, "\x1B[{};{}H{line:<width$}", row + index as u32, column);
+    }
+}
+
+/// Draws a bar of the given width showing the time of day and its position
+/// within the day. Ticks mark 6:00, 12:00, and 18:00, and diamonds the times
+/// of the timeline palettes in `keyframes` (sorted, in milliseconds). The
+/// part between the two keyframes that are blended at `time_of_day` is drawn
+/// with heavy lines.
+fn write_timeline_bar(out: &mut impl Write, time_of_day: u64, keyframes: &[u64], row: u32, column: u32, width: u32) {
+    let (hours, mins) = get_hours_mins(time_of_day);
+    let mut bar = format!(" {hours:2}:{mins:02} ");
+    let label_len = bar.len() as u64;
+    let track_len = (width as u64).saturating_sub(label_len + 1);
+
+    if track_len > 1 {
+        let position = |time: u64| time.min(DAY_DURATION - 1) * track_len / DAY_DURATION;
+        let marker = position(time_of_day);
+        let span = match timeline_span(keyframes, time_of_day, |&time| time) {
+            Some((from, to, _)) if from != to => Some((position(keyframes[from]), position(keyframes[to]))),
+            _ => None,
+        };
+        let in_span = |index: u64| match span {
+            Some((from, to)) if from <= to => index > from && index < to,
+            // over midnight
+            Some((from, to)) => index > from || index < to,
+            None => false,
+        };
+
+        for index in 0..track_len {
+            let ch = if index == marker {
+                '█'
+            } else if keyframes.iter().any(|&time| index == position(time)) {
+                '◆'
+            } else if (1..4).any(|quarter| index == quarter * track_len / 4) {
+                if in_span(index) { '╋' } else { '┼' }
+            } else if in_span(index) {
+                '━'
+            } else {
+                '─'
+            };
+            bar.push(ch);
+        }
+        bar.push(' ');
+    }
+
+    let _ = write!(out, "\x1B[{row};{column}H\x1B[38;2;255;255;255m\x1B[48;2;0;0;0m{bar}");
+}
+
+fn get_hours_mins(time_of_day: u64) -> (u32, u32) {
+    let mins = (time_of_day / (60 * 1000)) as u32;
+    let hours = mins / 60;
+    (hours, mins - hours * 60)
+}
+
+#[inline]
+fn slideshow_duration(args: &Args) -> Option<Duration> {
+    args.slideshow.map(Duration::from_secs)
+}
+
+/// Watches the file of `source` with --watch, if it is a local file.
+fn file_watcher(args: &Args, source: &Source, now: Instant) -> Option<FileWatcher> {
+    let path = source.path().filter(|_| args.watch)?;
+    Some(FileWatcher::new(path, now))
+}
+
+/// How often the error screen checks for a resized terminal.
+const ERROR_SCREEN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Shows why `sources[file_index]` couldn't be loaded until the user
+/// retries, skips the file, or quits, until the --slideshow moves on, or
+/// until the file changes with --watch.
+fn show_error<W: Write>(state: &mut GlobalState<W>, file_index: usize, err: &error::Error, args: &Args) -> Result<Action, error::Error> {
+    let name = state.sources[file_index].name().into_owned();
+    let (error, hint) = match err.code() {
+        Some(code) => (format!("{code}: {err}"), i18n::format(Msg::ExplainHint, &[&code])),
+        None => (err.to_string(), String::new()),
+    };
+    let mut status = None;
+    let mut shown = None;
+    let start_ts = Instant::now();
+    let mut pacer = FramePacer::new(ERROR_SCREEN_POLL_INTERVAL, start_ts);
+    let wake_on_input = std::io::stdin().is_terminal();
+    let mut watcher = file_watcher(args, &state.sources[file_index], start_ts);
+
+    while state.running.load(Ordering::Relaxed) {
+        let now = Instant::now();
+
+        if let Some(slideshow) = slideshow_duration(args) && now.saturating_duration_since(start_ts) >= slideshow {
+            return Ok(Action::Goto(state.order.next_wrapping(file_index, &mut state.rng)));
+        }
+
+        if let Some(watcher) = &mut watcher && watcher.changed(now) {
+            state.reloading = true;
+            return Ok(Action::Goto(file_index));
+        }
+
+        input::read_available(&mut state.stdin, &mut state.input)?;
+        while let Some(event) = state.input.next_event(now) {
+            let (key, modifiers) = match event {
+                Event::Key(KeyEvent { key, modifiers }) => (key, modifiers),
+                Event::Paste(_) | Event::CursorPosition { .. } | Event::Wheel { .. } => continue,
+            };
+            match (key, state.keymap.get(key, modifiers)) {
+                (_, Some(KeyCommand::Quit)) => return Ok(Action::Quit),
+                (Key::Char('r'), _) if modifiers == Modifiers::NONE => return Ok(Action::Goto(file_index)),
+                (_, Some(KeyCommand::NextFile)) => {
+                    if let Some(next) = state.order.step(file_index, 1) {
+                        return Ok(Action::Goto(next));
+                    }
+                    status = Some(Msg::AtLastFile);
+                    shown = None;
+                }
+                (_, Some(KeyCommand::PrevFile)) => {
+                    if let Some(prev) = state.order.step(file_index, -1) {
+                        return Ok(Action::Goto(prev));
+                    }
+                    status = Some(Msg::AtFirstFile);
+                    shown = None;
+                }
+                _ => {}
+            }
+        }
+
+        let size = term_size::dimensions().unwrap_or((80, 25));
+        if shown != Some(size) {
+            write_error_screen(&mut state.stdout, size.0, &name, &error, &hint, status);
+            let _ = state.stdout.flush();
+            shown = Some(size);
+        }
+
+        if !pacer.wait(wake_on_input) {
+            break;
+        }
+    }
+
+    Ok(Action::Quit)
+}
+
+/// Replaces everything on screen by the error screen of [`show_error`],
+/// with lines wrapped at `columns`.
+fn write_error_screen(out: &mut impl Write, columns: usize, name: &str, error: &str, hint: &str, status: Option<Msg>) {
+    let catalog = i18n::catalog();
+    let lines = [
+        catalog.get(Msg::LoadError),
+        "",
+        name,
+        error,
+        hint,
+        "",
+        catalog.get(Msg::LoadErrorHotkeys),
+        status.map_or("", |status| catalog.get(status)),
+    ];
+
+    // CSI 0 m        Reset or normal, all attributes become turned off
+    // CSI 2 J        Clear entire screen
+    let _ = write!(out, "\x1B[0m\x1B[2J\x1B[1;1H");
+    let width = columns.saturating_sub(2).max(1);
+    let mut row = 1;
+    for line in lines {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            row += 1;
+            continue;
+        }
+        for chunk in chars.chunks(width) {
+            let chunk: String = chunk.iter().collect();
+            let _ = write!(out, "\x1B[{row};2H{chunk}");
+            row += 1;
+        }
+    }
+}
+
+// hotkeys that only act under a condition keep it in their body, so the
+// key doesn't fall through to the arms below
+#[allow(clippy::collapsible_match)]
+fn show_image<W: Write>(args: &mut Args, state: &mut GlobalState<W>, file_index: usize) -> Result<Action, error::Error> {
+    let living_world = state.prefetcher.load(&state.sources, file_index);
+    if let Some(next) = state.order.step(file_index, 1) {
+        state.prefetcher.prefetch(&state.sources, next);
+    }
+
+    let (mut living_world, file_info) = match living_world {
+        Ok((living_world, _)) if living_world.base().width() == 0 || living_world.base().height() == 0 => {
+            let err = error::Error::new(format!("image of size {} x {}",
+                living_world.base().width(),
+                living_world.base().height()));
+            return show_error(state, file_index, &err, args);
+        }
+        Ok(loaded) => loaded,
+        Err(err) => return show_error(state, file_index, &err, args),
+    };
+
+    if args.reset_clock && !std::mem::take(&mut state.reloading) {
+        state.clock_start = Instant::now();
+        state.clock_offset = clock_offset(args);
+        if state.paused_at.is_some() {
+            state.paused_at = Some(state.clock_start);
+        }
+        state.current_time = None;
+        state.time_speed = 1;
+    }
+
+    let time_offset = match state.sources[file_index].scene_time() {
+        Some(SceneTime::At(time)) => {
+            state.current_time = Some(time);
+            state.time_speed = 1;
+            0
+        }
+        Some(SceneTime::Offset(offset)) => offset,
+        None => 0,
+    };
+
+    let filename = state.sources[file_index].file_name().into_owned();
+    let mut message = String::new();
+    if state.overlays.is_shown(Overlay::Osd) {
+        use std::fmt::Write;
+        if let Some(name) = living_world.name() {
+            let _ = write!(message, " {name} ({filename}) ");
+        } else {
+            let _ = write!(message, " {filename} ");
+        }
+    }
     let cycle_image = living_world.base();
-    let mut blended_palette = cycle_image.palette().clone();
-    let mut cycled_palette1 = blended_palette.clone();
-    let mut cycled_palette2 = blended_palette.clone();
+    let mut animator = Animator::new(&living_world);
+    animator.set_phase(args.cycle_phase);
+    animator.set_blend_curve(args.blend_curve);
+    animator.set_palette_cache(palette_cache(args));
+    animator.set_smoothing(smoothing(args));
+    let mut display_palette_buf = Palette::default();
+    let mut sprite_buf = IndexedImage::new(0, 0, Palette::default());
 
-    let mut frame_duration = Duration::from_secs_f64(1.0 / (args.fps as f64));
     let mut linebuf = String::new();
 
     let img_width = cycle_image.width();
@@ -477,43 +2761,99 @@ fn show_image(args: &mut Args, state: &mut GlobalState, file_index: usize) -> Re
     let _ = write!(state.stdout, "\x1B[1;1H\x1B[38;2;0;0;0m\x1B[48;2;0;0;0m\x1B[2J");
     let _ = state.stdout.flush();
 
-    let mut x = 0;
-    let mut y = 0;
-
-    if img_width > term_width {
-        x = (img_width - term_width) / 2;
+    let mut view = Viewport::new(img_width, img_height, term_width, term_height);
+    if let Some(cell_aspect) = args.cell_aspect {
+        view.set_stretch(cell_aspect.stretch());
     }
-
-    if img_height > term_height {
-        y = (img_height - term_height) / 2;
+    view.set_zoom(args.zoom.factor(view.stretched_image_size(), (term_width, term_height)));
+    if let Some((x, y)) = state.resume_position.take() {
+        view.set_position(x, y);
     }
+    let mut viewport = view.rect(cycle_image);
+    // the view-port scaled up by the zoom factor and stretched
+    let mut zoomed = IndexedImage::new(0, 0, Palette::default());
 
-    let mut viewport = cycle_image.get_rect(
-        x, y,
-        img_width.min(term_width),
-        img_height.min(term_height));
+    let (shown_width, shown_height) = view.shown_size();
+    let mut frame = RgbImage::new(shown_width, shown_height);
+    let mut prev_frame = RgbImage::new(shown_width, shown_height);
+    // palettes the frames were drawn with, None if they need a full redraw
+    let mut frame_palette = None;
+    let mut prev_frame_palette = None;
 
-    let mut frame = RgbImage::new(viewport.width(), viewport.height());
-    let mut prev_frame = RgbImage::new(viewport.width(), viewport.height());
+    // what is actually on screen when debug_diff is enabled
+    let mut shown_frame = RgbImage::new(shown_width, shown_height);
+    let mut debug_frame = RgbImage::new(shown_width, shown_height);
 
     let mut old_term_width = term_width;
     let mut old_term_height = term_height;
+    let mut old_zoom = view.zoom();
 
-    let mut message_shown = args.osd;
+    let mut message_shown = state.overlays.is_shown(Overlay::Osd);
+    // pixels per palette index for the palette inspector
+    let mut index_usage = None;
+    let mut info_fields = i18n::catalog().file_info_fields(&file_info);
+    info_fields.push((i18n::tr(Msg::InfoSeed).to_owned(), state.rng.seed().to_string()));
 
     let loop_start_ts = Instant::now();
-    let mut message_end_ts = if args.osd {
-        loop_start_ts + MESSAGE_DISPLAY_DURATION
+    let mut pacer = FramePacer::new(Duration::from_secs_f64(1.0 / (args.fps as f64)), loop_start_ts);
+    let wake_on_input = std::io::stdin().is_terminal();
+    let message_duration = Duration::from_millis(args.osd_duration);
+    let osd_colors = OsdColors { text: args.osd_color, background: args.osd_background };
+    let mut message_end_ts = if state.overlays.is_shown(Overlay::Osd) {
+        loop_start_ts + message_duration
     } else {
         loop_start_ts
     };
 
+    let mut dump_message: Option<String> = None;
+    let mut performance = Performance::new();
+    let mut mixer = CycleMixer::new();
+    let mut flash_palette_buf = Palette::default();
+    let mut stalled_fps: Option<u32> = None;
+    let mut chords = Chords::new(args.leader_key);
+    let mut count = CountPrefix::new();
+    let mut pan_animation: Option<PanAnimation> = None;
+    let mut weather = weather_effect(args, &living_world, &mut state.rng);
+    let mut follow_camera = !living_world.camera().is_empty();
+    // times of the timeline palettes, ticks of the time bar
+    let keyframes: Vec<u64> = living_world.timeline().iter().map(|event| event.time_of_day() as u64 * 1000).collect();
+    let mut last_time_of_day = None;
+    let mut scrubbing = false;
+    // how long the file was shown without being paused, for --slideshow
+    let mut slideshow_shown = Duration::ZERO;
+    let mut slideshow_ts = loop_start_ts;
+    let mut watcher = file_watcher(args, &state.sources[file_index], loop_start_ts);
+    let mut too_small_shown = None;
+    let mut scrub_repeat = 0;
+    let mut scrub_forward = false;
+    let mut last_scrub_ts = loop_start_ts;
     while state.running.load(Ordering::Relaxed) {
         let frame_start_ts = Instant::now();
+
+        if state.frame_dump.is_some() {
+            state.stdout.start_capture();
+        }
+
+        if let Some(slideshow) = slideshow_duration(args) {
+            if state.paused_at.is_none() {
+                slideshow_shown += frame_start_ts.saturating_duration_since(slideshow_ts);
+            }
+            slideshow_ts = frame_start_ts;
+            if slideshow_shown >= slideshow {
+                return Ok(Action::Goto(state.order.next_wrapping(file_index, &mut state.rng)));
+            }
+        }
+
+        if let Some(watcher) = &mut watcher && watcher.changed(frame_start_ts) {
+            state.resume_position = Some(view.position());
+            state.reloading = true;
+            return Ok(Action::Goto(file_index));
+        }
+
         let mut time_of_day = if let Some(current_time) = state.current_time {
             current_time
         } else {
-            get_time_of_day_msec(state.time_speed)
+            shifted_time_of_day(state.time_speed, time_offset)
         };
 
         // process input
@@ -524,361 +2864,732 @@ fn show_image(args: &mut Args, state: &mut GlobalState, file_index: usize) -> Re
             (img_width, img_height)
         };
 
-        let old_message_len = message.len();
-        let old_x = x;
-        let old_y = y;
+        let old_message_len = message.chars().count();
+        let old_position = view.position();
 
-        let mut viewport_x = 0;
-        let mut viewport_y = 0;
-
-        if img_width <= term_width {
-            x = 0;
-            viewport_x = (term_width - img_width) / 2;
-        } else if x > img_width - term_width {
-            x = img_width - term_width;
-        }
-
-        if img_height <= term_height {
-            y = 0;
-            viewport_y = (term_height - img_height) / 2;
-        } else if y > img_height - term_height {
-            y = img_height - term_height;
-        }
+        view.resize(term_width, term_height);
+        view.set_zoom(args.zoom.factor(view.stretched_image_size(), (term_width, term_height)));
+        let (viewport_x, viewport_y) = view.offset();
 
         let mut updated_message = false;
         macro_rules! show_message {
-            ($($args:expr),+) => {
-                if args.osd {
-                    message_end_ts = frame_start_ts + MESSAGE_DISPLAY_DURATION;
-                    message.clear();
-                    use std::fmt::Write;
-                    message.push_str(" ");
-                    let _ = write!(&mut message, $($args),+);
-                    message.push_str(" ");
-                    updated_message = true;
+            (@text $text:expr) => {
+                message_end_ts = frame_start_ts + message_duration;
+                message.clear();
+                message.extend(std::iter::repeat_n(' ', args.osd_padding as usize));
+                message.push_str(&$text);
+                message.extend(std::iter::repeat_n(' ', args.osd_padding as usize));
+                updated_message = true;
+            };
+            (@always $msg:expr $(, $args:expr)*) => {
+                show_message!(@text i18n::format($msg, &[$(&$args),*]));
+            };
+            ($msg:expr $(, $args:expr)*) => {
+                if state.overlays.is_shown(Overlay::Osd) {
+                    show_message!(@always $msg $(, $args)*);
                 }
             };
         }
 
-        loop {
-            // TODO: Windows support, maybe with ReadConsoleInput()?
-            let Some(byte) = nb_read_byte(&mut state.stdin)? else {
-                break;
+        if let Some(msg) = dump_message.take() {
+            show_message!(@text msg);
+        }
+
+        if let Some(fps) = stalled_fps.take() {
+            show_message!(@always Msg::OutputStalled, fps);
+        }
+
+        // jumps are relative to where an ongoing pan animation ends
+        let shown_position = view.position();
+        if let Some(animation) = &pan_animation {
+            let (x, y) = animation.target();
+            view.set_position(x, y);
+        }
+        let input_position = view.position();
+        let mut jumped = false;
+        let mut hit_edge = None;
+
+        input::read_available(&mut state.stdin, &mut state.input)?;
+        while let Some(event) = state.input.next_event(frame_start_ts) {
+            let (key, modifiers) = match event {
+                Event::Key(KeyEvent { key, modifiers }) => (key, modifiers),
+                Event::CursorPosition { .. } => {
+                    if let Some(latency) = &mut state.latency && let Some(link) = latency.answer(frame_start_ts) {
+                        let adaptive = latency.adaptive;
+                        adapt_to_link(args, &adaptive, link);
+                        pacer.set_frame_duration(Duration::from_secs_f64(1.0 / args.fps as f64));
+                        animator.set_smoothing(smoothing(args));
+                    }
+                    continue;
+                }
+                Event::Paste(text) => {
+                    // pasted text is a path or URL to open
+                    let Some(source) = Source::from_pasted(&text) else {
+                        continue;
+                    };
+                    let index = if let Some(index) = state.sources.iter().position(|other| *other == source) {
+                        index
+                    } else {
+                        state.sources.push(source);
+                        state.order.push(state.sources.len() - 1, file_index);
+                        state.sources.len() - 1
+                    };
+                    return Ok(Action::Goto(index));
+                }
+                Event::Wheel { direction, modifiers } if modifiers.contains(Modifiers::CTRL) && living_world.timeline().is_empty() => {
+                    let zoom = match direction {
+                        WheelDirection::Up   => view.zoom() + 1,
+                        WheelDirection::Down => view.zoom() - 1,
+                        WheelDirection::Left | WheelDirection::Right => continue,
+                    };
+                    if (1..=MAX_ZOOM).contains(&zoom) {
+                        args.zoom = Zoom::Factor(zoom);
+                        show_message!(Msg::Zoom, zoom);
+                    }
+                    continue;
+                }
+                Event::Wheel { direction, modifiers } if modifiers.contains(Modifiers::CTRL) => {
+                    // scrub through the time of day of scenes with a timeline
+                    time_of_day = match direction {
+                        WheelDirection::Up   => (time_of_day + SMALL_TIME_STEP) % DAY_DURATION,
+                        WheelDirection::Down => (time_of_day + DAY_DURATION - SMALL_TIME_STEP) % DAY_DURATION,
+                        WheelDirection::Left | WheelDirection::Right => continue,
+                    };
+                    state.time_speed = 1;
+                    state.current_time = Some(time_of_day);
+                    let (hours, mins) = get_hours_mins(time_of_day);
+                    show_message!(Msg::Clock, hours, format!("{mins:02}"));
+                    continue;
+                }
+                Event::Wheel { .. } if args.pan_lock => {
+                    show_message!(Msg::PanLocked);
+                    continue;
+                }
+                Event::Wheel { direction, modifiers } => {
+                    // Shift turns the vertical wheel into a horizontal one
+                    let horizontal = modifiers.contains(Modifiers::SHIFT);
+                    let (dx, dy, edge) = match direction {
+                        WheelDirection::Up    if horizontal => (-WHEEL_PAN_STEP, 0, Edge::Left),
+                        WheelDirection::Down  if horizontal => (WHEEL_PAN_STEP, 0, Edge::Right),
+                        WheelDirection::Up    => (0, -WHEEL_PAN_STEP, Edge::Top),
+                        WheelDirection::Down  => (0, WHEEL_PAN_STEP, Edge::Bottom),
+                        WheelDirection::Left  => (-WHEEL_PAN_STEP, 0, Edge::Left),
+                        WheelDirection::Right => (WHEEL_PAN_STEP, 0, Edge::Right),
+                    };
+                    if !view.pan(dx, dy) {
+                        hit_edge = Some(edge);
+                    }
+                    continue;
+                }
             };
-            match byte {
-                b'q' => return Ok(Action::Quit),
-                b'b' => {
+            match chords.handle_key(key, modifiers) {
+                ChordEvent::Ignored => {}
+                ChordEvent::Pending => {
+                    count.take();
+                    if let Some(hint) = chords.hint() {
+                        show_message!(@text hint);
+                    }
+                    continue;
+                }
+                ChordEvent::Cancelled => {
+                    message.clear();
+                    message_end_ts = loop_start_ts;
+                    continue;
+                }
+                ChordEvent::Complete(ChordAction::GotoTime, number) => {
+                    message.clear();
+                    message_end_ts = loop_start_ts;
+                    if let Some(new_time) = chord::parse_time(&number) {
+                        time_of_day = new_time;
+                        state.time_speed = 1;
+                        state.current_time = Some(time_of_day);
+                        let (hours, mins) = get_hours_mins(time_of_day);
+                        show_message!(Msg::Clock, hours, format!("{mins:02}"));
+                    } else {
+                        show_message!(@always Msg::InvalidTime, number);
+                    }
+                    continue;
+                }
+                ChordEvent::Complete(ChordAction::GotoFile, number) => {
+                    match number.parse::<usize>() {
+                        Ok(index) if index >= 1 && index <= state.sources.len() => {
+                            return Ok(Action::Goto(index - 1));
+                        }
+                        _ => {
+                            show_message!(@always Msg::OnlyFilesOpened, state.sources.len());
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            if args.perform && modifiers == Modifiers::NONE && let Key::Char(ch) = key {
+                if let Some(digit) = ch.to_digit(10) {
+                    // 1 to 9, then 0 for the tenth cycle
+                    performance.boost((digit as usize + 9) % 10, frame_start_ts);
+                    continue;
+                }
+                if let Some(flash) = Flash::from_key(ch) {
+                    performance.flash(flash, frame_start_ts);
+                    continue;
+                }
+            }
+
+            if modifiers == Modifiers::NONE && let Key::Char(ch @ '0'..='9') = key && (ch != '0' || count.is_pending()) {
+                count.push_digit(ch as u32 - '0' as u32, frame_start_ts);
+                if let Some(number) = count.pending() {
+                    show_message!(@text format!("{number}_"));
+                }
+                continue;
+            }
+
+            // a number typed before the command repeats it or scales it
+            let repeat = count.take();
+            if repeat.is_some() {
+                message.clear();
+                message_end_ts = loop_start_ts;
+            }
+            let times = repeat.unwrap_or(1);
+
+            // modes that take over some keys come first, then the key map
+            let command = state.keymap.get(key, modifiers);
+            match (key, command) {
+                (Key::Escape, _) if repeat.is_some() => {}
+                (Key::Enter, _) | (_, Some(KeyCommand::Grid)) if let Some(number) = repeat => {
+                    if number >= 1 && number as usize <= state.sources.len() {
+                        return Ok(Action::Goto(number as usize - 1));
+                    }
+                    show_message!(@always Msg::OnlyFilesOpened, state.sources.len());
+                }
+                (_, Some(KeyCommand::Scrub)) | (Key::Escape | Key::Enter, _) if scrubbing => {
+                    scrubbing = false;
+                    // full redraw next frame by faking old term size of 0x0
+                    old_term_width  = 0;
+                    old_term_height = 0;
+                }
+                (_, Some(KeyCommand::Scrub)) => {
+                    scrubbing = true;
+                    scrub_repeat = 0;
+                    state.time_speed = 1;
+                    state.current_time = Some(time_of_day);
+                }
+                (_, Some(KeyCommand::TimeBar)) => {
+                    let time_bar = state.overlays.toggle(Overlay::TimeBar);
+                    if !time_bar {
+                        // full redraw next frame by faking old term size of 0x0
+                        old_term_width  = 0;
+                        old_term_height = 0;
+                    }
+
+                    show_message!(Msg::TimeBar, enabled(time_bar));
+                }
+                (Key::Left | Key::Right, _) if scrubbing => {
+                    // accelerate while the key is held down
+                    let forward = key == Key::Right;
+                    if scrub_forward == forward && frame_start_ts.duration_since(last_scrub_ts) < SCRUB_REPEAT_INTERVAL {
+                        scrub_repeat += 1;
+                    } else {
+                        scrub_repeat = 0;
+                    }
+                    scrub_forward = forward;
+                    last_scrub_ts = frame_start_ts;
+
+                    let mut step = SCRUB_STEP * (1 + scrub_repeat / 4).min(MAX_SCRUB_ACCELERATION);
+                    if modifiers.contains(Modifiers::SHIFT) {
+                        step *= 10;
+                    }
+                    let step = step % DAY_DURATION;
+
+                    time_of_day = if forward {
+                        (time_of_day + step) % DAY_DURATION
+                    } else {
+                        (time_of_day + DAY_DURATION - step) % DAY_DURATION
+                    };
+                    state.time_speed = 1;
+                    state.current_time = Some(time_of_day);
+                }
+                (Key::Escape, _) if state.overlays.is_shown(Overlay::Cycles) => {
+                    state.overlays.set(Overlay::Cycles, false);
+                    // full redraw next frame by faking old term size of 0x0
+                    old_term_width  = 0;
+                    old_term_height = 0;
+                }
+                (Key::Up | Key::Down | Key::Char('m' | 's'), _) if modifiers == Modifiers::NONE && state.overlays.is_shown(Overlay::Cycles) => {
+                    let count = animator::active_cycles(&living_world, time_of_day).len();
+                    if count == 0 {
+                        show_message!(Msg::NoCycles);
+                        continue;
+                    }
+                    match key {
+                        Key::Up   => mixer.select_prev(count),
+                        Key::Down => mixer.select_next(count),
+                        Key::Char('m') => {
+                            let muted = mixer.toggle_mute();
+                            show_message!(if muted { Msg::CycleMuted } else { Msg::CycleUnmuted }, mixer.selected() + 1);
+                        }
+                        _ => {
+                            if mixer.toggle_solo() {
+                                show_message!(Msg::CycleSolo, mixer.selected() + 1);
+                            } else {
+                                show_message!(Msg::CycleSoloOff);
+                            }
+                        }
+                    }
+                }
+                (Key::Escape, _) if args.perform => {
+                    args.perform = false;
+                    show_message!(Msg::PerformOff);
+                }
+                (_, Some(KeyCommand::Quit)) => return Ok(Action::Quit),
+                (_, Some(KeyCommand::Pause)) => {
+                    if let Some(paused_at) = state.paused_at.take() {
+                        // continue where it stopped
+                        state.clock_start += frame_start_ts.saturating_duration_since(paused_at);
+                        show_message!(Msg::Resumed);
+                    } else {
+                        state.paused_at = Some(frame_start_ts);
+                        show_message!(Msg::Paused);
+                    }
+                }
+                (_, Some(KeyCommand::Blend)) => {
                     args.blend = !args.blend;
 
-                    show_message!("Blend Mode: {}", if args.blend { "Enabled" } else { "Disabled" });
+                    show_message!(Msg::BlendMode, enabled(args.blend));
                 }
-                b'o' => {
-                    if args.osd {
-                        show_message!("OSD: Disabled");
-                        args.osd = false;
+                (_, Some(KeyCommand::BlendCurve)) => {
+                    args.blend_curve = match args.blend_curve {
+                        BlendCurve::Linear    => BlendCurve::Cosine,
+                        BlendCurve::Cosine    => BlendCurve::ShiftFade,
+                        BlendCurve::ShiftFade => BlendCurve::Linear,
+                    };
+                    animator.set_blend_curve(args.blend_curve);
+                    // the curve is only visible when blending
+                    args.blend = true;
+
+                    let name = args.blend_curve.to_possible_value().map(|value| value.get_name().to_owned()).unwrap_or_default();
+                    show_message!(Msg::BlendCurve, name);
+                }
+                (_, Some(KeyCommand::Osd)) => {
+                    if state.overlays.is_shown(Overlay::Osd) {
+                        show_message!(Msg::OsdDisabled);
+                        state.overlays.set(Overlay::Osd, false);
                     } else {
-                        args.osd = true;
-                        show_message!("OSD: Enabled");
+                        state.overlays.set(Overlay::Osd, true);
+                        show_message!(Msg::OsdEnabled);
+                    }
+                }
+                (_, Some(KeyCommand::HideAll)) => {
+                    if state.overlays.toggle_all() {
+                        message.clear();
+                        message_end_ts = loop_start_ts;
                     }
+                    // full redraw next frame by faking old term size of 0x0
+                    old_term_width  = 0;
+                    old_term_height = 0;
                 }
-                b'+' => {
+                (_, Some(KeyCommand::FpsUp)) => {
                     if args.fps < MAX_FPS {
-                        args.fps += 1;
-                        frame_duration = Duration::from_secs_f64(1.0 / args.fps as f64);
+                        args.fps = (args.fps + times).min(MAX_FPS);
+                        if let Some(latency) = &mut state.latency {
+                            latency.adaptive.fps = false;
+                        }
+                        pacer.set_frame_duration(Duration::from_secs_f64(1.0 / args.fps as f64));
+                        animator.set_smoothing(smoothing(args));
 
-                        show_message!("FPS: {}", args.fps);
+                        show_message!(Msg::Fps, args.fps);
                     }
                 }
-                b'-' => {
+                (_, Some(KeyCommand::FpsDown)) => {
                     if args.fps > 1 {
-                        args.fps -= 1;
-                        frame_duration = Duration::from_secs_f64(1.0 / args.fps as f64);
+                        args.fps = args.fps.saturating_sub(times).max(1);
+                        if let Some(latency) = &mut state.latency {
+                            latency.adaptive.fps = false;
+                        }
+                        pacer.set_frame_duration(Duration::from_secs_f64(1.0 / args.fps as f64));
+                        animator.set_smoothing(smoothing(args));
 
-                        show_message!("FPS: {}", args.fps);
+                        show_message!(Msg::Fps, args.fps);
                     }
                 }
-                b'n' => {
-                    let new_index = file_index + 1;
-                    if new_index >= args.paths.len() {
-                        show_message!("Already at last file.");
-                    } else {
-                        return Ok(Action::Goto(new_index));
+                (_, Some(KeyCommand::NextFile)) => {
+                    if let Some(next) = state.order.step(file_index, times as isize) {
+                        return Ok(Action::Goto(next));
+                    }
+                    show_message!(Msg::AtLastFile);
+                }
+                (_, Some(KeyCommand::PrevFile)) => {
+                    if let Some(prev) = state.order.step(file_index, -(times as isize)) {
+                        return Ok(Action::Goto(prev));
                     }
+                    show_message!(Msg::AtFirstFile);
                 }
-                b'p' => {
-                    if file_index == 0 {
-                        show_message!("Already at first file.");
+                (_, Some(KeyCommand::Shuffle)) => {
+                    if state.order.is_shuffled() {
+                        state.order.unshuffle();
                     } else {
-                        return Ok(Action::Goto(file_index - 1));
+                        state.order.shuffle(file_index, &mut state.rng);
                     }
+                    show_message!(Msg::Shuffle, enabled(state.order.is_shuffled()));
                 }
-                b'a' | b'A' => {
-                    let time_step = if byte.is_ascii_uppercase() { SMALL_TIME_STEP } else { TIME_STEP };
-                    let rem = time_of_day % time_step;
-                    let new_time = time_of_day - rem;
-                    if new_time == time_of_day {
-                        if new_time < time_step {
-                            time_of_day = DAY_DURATION - time_step;
+                (_, Some(command @ (KeyCommand::Back | KeyCommand::BackMinute))) => {
+                    let time_step = if command == KeyCommand::BackMinute { SMALL_TIME_STEP } else { TIME_STEP };
+                    for _ in 0..times {
+                        let rem = time_of_day % time_step;
+                        let new_time = time_of_day - rem;
+                        if new_time == time_of_day {
+                            if new_time < time_step {
+                                time_of_day = DAY_DURATION - time_step;
+                            } else {
+                                time_of_day = new_time - time_step;
+                            }
                         } else {
-                            time_of_day = new_time - time_step;
+                            time_of_day = new_time;
                         }
-                    } else {
-                        time_of_day = new_time;
                     }
                     state.time_speed = 1;
                     state.current_time = Some(time_of_day);
                     let (hours, mins) = get_hours_mins(time_of_day);
-                    show_message!("{hours}:{mins:02}");
-                }
-                b'd' | b'D' => {
-                    let time_step = if byte.is_ascii_uppercase() { SMALL_TIME_STEP } else { TIME_STEP };
-                    let rem = time_of_day % time_step;
-                    let new_time = time_of_day - rem + time_step;
-                    if new_time >= DAY_DURATION {
-                        time_of_day = 0;
-                    } else {
-                        time_of_day = new_time;
+                    show_message!(Msg::Clock, hours, format!("{mins:02}"));
+                }
+                (_, Some(command @ (KeyCommand::Forward | KeyCommand::ForwardMinute))) => {
+                    let time_step = if command == KeyCommand::ForwardMinute { SMALL_TIME_STEP } else { TIME_STEP };
+                    for _ in 0..times {
+                        let rem = time_of_day % time_step;
+                        let new_time = time_of_day - rem + time_step;
+                        if new_time >= DAY_DURATION {
+                            time_of_day = 0;
+                        } else {
+                            time_of_day = new_time;
+                        }
                     }
                     state.time_speed = 1;
                     state.current_time = Some(time_of_day);
                     let (hours, mins) = get_hours_mins(time_of_day);
-                    show_message!("{hours}:{mins:02}");
+                    show_message!(Msg::Clock, hours, format!("{mins:02}"));
+                }
+                (_, Some(command @ (KeyCommand::PhaseDown | KeyCommand::PhaseUp | KeyCommand::BigPhaseDown | KeyCommand::BigPhaseUp))) => {
+                    let step = match command {
+                        KeyCommand::PhaseDown    => -PHASE_STEP,
+                        KeyCommand::PhaseUp      => PHASE_STEP,
+                        KeyCommand::BigPhaseDown => -BIG_PHASE_STEP,
+                        _                        => BIG_PHASE_STEP,
+                    };
+                    animator.set_phase(animator.phase() + step * times as f64);
+                    args.cycle_phase = animator.phase();
+
+                    show_message!(Msg::CyclePhase, format!("{:.2}", args.cycle_phase));
+                }
+                (_, Some(command @ (KeyCommand::CycleSlower | KeyCommand::CycleFaster))) => {
+                    let factor = 2f64.powi(times.min(8) as i32);
+                    let speed = if command == KeyCommand::CycleFaster { state.cycle_speed * factor } else { state.cycle_speed / factor };
+                    let speed = speed.clamp(MIN_CYCLE_SPEED, MAX_CYCLE_SPEED);
+                    set_cycle_speed(state, speed, frame_start_ts);
+
+                    show_message!(Msg::CycleSpeed, speed);
+                }
+                (_, Some(command @ (KeyCommand::ZoomIn | KeyCommand::ZoomOut))) => {
+                    // from the factor auto zoom picked, which it then stops
+                    let zoom = if command == KeyCommand::ZoomIn {
+                        (view.zoom() + times).min(MAX_ZOOM)
+                    } else {
+                        view.zoom().saturating_sub(times).max(1)
+                    };
+                    args.zoom = Zoom::Factor(zoom);
+
+                    show_message!(Msg::Zoom, zoom);
+                }
+                (_, Some(KeyCommand::PanLock)) => {
+                    args.pan_lock = !args.pan_lock;
+
+                    show_message!(Msg::PanLock, enabled(args.pan_lock));
+                }
+                (_, Some(KeyCommand::Camera)) => {
+                    if living_world.camera().is_empty() {
+                        show_message!(Msg::NoCameraPath);
+                    } else {
+                        follow_camera = !follow_camera;
+
+                        show_message!(if follow_camera { Msg::CameraFollow } else { Msg::CameraManual });
+                    }
+                }
+                (_, Some(KeyCommand::Export)) => {
+                    let (hours, mins) = get_hours_mins(time_of_day);
+                    let stem = Path::new(filename.as_str()).file_stem().map(|stem| stem.to_string_lossy().into_owned());
+                    let path = format!("{}-{hours:02}{mins:02}.{}", stem.as_deref().unwrap_or("palette"), args.theme_format.extension());
+                    let res = std::fs::File::create(&path).and_then(|file| {
+                        let mut writer = std::io::BufWriter::new(file);
+                        write_palette_theme(&mut writer, &living_world, time_of_day, args.blend, args.theme_colors, args.theme_format)?;
+                        writer.flush()
+                    });
+
+                    match res {
+                        Ok(()) => show_message!(Msg::ExportedPalette, path),
+                        Err(err) => show_message!(Msg::ExportError, path, err),
+                    }
+                }
+                (_, Some(KeyCommand::Grid)) => {
+                    state.overlays.toggle(Overlay::Grid);
+                    // full redraw next frame by faking old term size of 0x0
+                    old_term_width  = 0;
+                    old_term_height = 0;
                 }
-                b's' => {
+                (_, Some(KeyCommand::Now)) => {
                     state.time_speed = 1;
                     state.current_time = None;
-                    time_of_day = get_time_of_day_msec(state.time_speed);
+                    time_of_day = shifted_time_of_day(state.time_speed, time_offset);
                     let (hours, mins) = get_hours_mins(time_of_day);
-                    show_message!("{hours}:{mins:02}");
+                    show_message!(Msg::Clock, hours, format!("{mins:02}"));
                 }
-                b'w' => {
+                (_, Some(KeyCommand::FastForward)) => {
                     if state.time_speed == 1 {
                         state.time_speed = FAST_FORWARD_SPEED;
                         state.current_time = None;
-                        time_of_day = get_time_of_day_msec(state.time_speed);
-                        show_message!("Fast Forward: ON");
+                        time_of_day = shifted_time_of_day(state.time_speed, time_offset);
+                        show_message!(Msg::FastForwardOn);
                     } else {
                         state.time_speed = 1;
                         state.current_time = Some(time_of_day);
-                        show_message!("Fast Forward: OFF");
+                        show_message!(Msg::FastForwardOff);
+                    }
+                }
+                (_, Some(KeyCommand::FileInfo)) => {
+                    if !state.overlays.toggle(Overlay::Info) {
+                        // full redraw next frame by faking old term size of 0x0
+                        old_term_width  = 0;
+                        old_term_height = 0;
+                    }
+                }
+                (_, Some(KeyCommand::Perform)) => {
+                    args.perform = !args.perform;
+                    show_message!(if args.perform { Msg::PerformOn } else { Msg::PerformOff });
+                }
+                (_, Some(KeyCommand::Palette)) => {
+                    if !state.overlays.toggle(Overlay::Palette) {
+                        // full redraw next frame by faking old term size of 0x0
+                        old_term_width  = 0;
+                        old_term_height = 0;
+                    }
+                }
+                (_, Some(KeyCommand::CycleList)) => {
+                    if !state.overlays.toggle(Overlay::Cycles) {
+                        // full redraw next frame by faking old term size of 0x0
+                        old_term_width  = 0;
+                        old_term_height = 0;
                     }
                 }
-                b'i' => {
+                (_, Some(KeyCommand::DiffDebug)) => {
+                    let debug_diff = state.overlays.toggle(Overlay::DebugDiff);
+                    // full redraw next frame by faking old term size of 0x0
+                    old_term_width  = 0;
+                    old_term_height = 0;
+
+                    show_message!(Msg::DiffDebug, enabled(debug_diff));
+                }
+                (_, Some(KeyCommand::Renderer)) => {
+                    args.no_diff = !args.no_diff;
+
+                    show_message!(if args.no_diff { Msg::RendererFull } else { Msg::RendererDiff });
+                }
+                (_, Some(KeyCommand::ReverseColumns)) => {
                     living_world.column_swap();
-                    viewport.get_rect_from(x, y, term_width, term_height, living_world.base());
-                }
-                0x1b => {
-                    match nb_read_byte(&mut state.stdin)? {
-                        Option::None => return Ok(Action::Quit),
-                        Some(0x1b) => return Ok(Action::Quit),
-                        Some(b'[') => {
-                            match nb_read_byte(&mut state.stdin)? {
-                                Option::None => break,
-                                Some(b'A') => {
-                                    // Up
-                                    if img_height > term_height && y > 0 {
-                                        y -= 1;
-                                    }
-                                }
-                                Some(b'B') => {
-                                    // Down
-                                    if img_height > term_height && y < (img_height - term_height) {
-                                        y += 1;
-                                    }
-                                }
-                                Some(b'C') => {
-                                    // Right
-                                    if img_width > term_width && x < (img_width - term_width) {
-                                        x += 1;
-                                    }
-                                }
-                                Some(b'D') => {
-                                    // Left
-                                    if img_width > term_width && x > 0 {
-                                        x -= 1;
-                                    }
-                                }
-                                Some(b'H') => {
-                                    // Home
-                                    if img_width > term_width {
-                                        x = 0;
-                                    }
-                                }
-                                Some(b'F') => {
-                                    // End
-                                    if img_width > term_width {
-                                        x = img_width - term_width;
-                                    }
-                                }
-                                Some(b'1') => {
-                                    match nb_read_byte(&mut state.stdin)? {
-                                        Option::None => break,
-                                        Some(b';') => {
-                                            match nb_read_byte(&mut state.stdin)? {
-                                                None => break,
-                                                Some(b'5') => {
-                                                    match nb_read_byte(&mut state.stdin)? {
-                                                        None => break,
-                                                        Some(b'H') => {
-                                                            // Ctrl+Home
-                                                            if img_height > term_height {
-                                                                y = 0;
-                                                            }
-                                                        }
-                                                        Some(b'F') => {
-                                                            // Ctrl+End
-                                                            if img_height > term_height {
-                                                                y = img_height - term_height;
-                                                            }
-                                                        }
-                                                        _ => break,
-                                                    }
-                                                }
-                                                _ => break,
-                                            }
-                                        }
-                                        _ => break,
-                                    }
-                                }
-                                Some(b'5') => {
-                                    match nb_read_byte(&mut state.stdin)? {
-                                        Option::None => break,
-                                        Some(b'~') => {
-                                            // Page Up
-                                            if img_height > term_height {
-                                                let half = term_height / 2;
-                                                if y > half {
-                                                    y -= half;
-                                                } else {
-                                                    y = 0;
-                                                }
-                                            }
-                                        }
-                                        Some(b';') => {
-                                            match nb_read_byte(&mut state.stdin)? {
-                                                Option::None => break,
-                                                Some(b'3') => {
-                                                    match nb_read_byte(&mut state.stdin)? {
-                                                        Option::None => break,
-                                                        Some(b'~') => {
-                                                            // Alt+Page Up
-                                                            if img_width > term_width {
-                                                                let half = term_width / 2;
-                                                                if x > half {
-                                                                    x -= half;
-                                                                } else {
-                                                                    x = 0;
-                                                                }
-                                                            }
-                                                        }
-                                                        _ => {}
-                                                    }
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                                Some(b'6') => {
-                                    match nb_read_byte(&mut state.stdin)? {
-                                        Option::None => break,
-                                        Some(b'~') => {
-                                            // Page Down
-                                            if img_height > term_height {
-                                                let half = term_height / 2;
-                                                let max_y = img_height - term_height;
-                                                y += half;
-                                                if y > max_y {
-                                                    y = max_y;
-                                                }
-                                            }
-                                        }
-                                        Some(b';') => {
-                                            match nb_read_byte(&mut state.stdin)? {
-                                                Option::None => break,
-                                                Some(b'3') => {
-                                                    match nb_read_byte(&mut state.stdin)? {
-                                                        Option::None => break,
-                                                        Some(b'~') => {
-                                                            // Alt+Page Down
-                                                            if img_width > term_width {
-                                                                let half = term_width / 2;
-                                                                let max_x = img_width - term_width;
-                                                                x += half;
-                                                                if x > max_x {
-                                                                    x = max_x;
-                                                                }
-                                                            }
-                                                        }
-                                                        _ => {}
-                                                    }
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                                Some(byte) => {
-                                    if byte.is_ascii_digit() || byte == b';' {
-                                        // eat whole unsupported escape input sequence
-                                        loop {
-                                            let Some(byte) = nb_read_byte(&mut state.stdin)? else {
-                                                break;
-                                            };
-
-                                            if !byte.is_ascii_digit() && byte != b';' {
-                                                break;
-                                            }
-                                        }
-                                    }
-                                    break;
-                                }
-                            }
-                        }
-                        _ => {}
+                    view.rect_into(living_world.base(), &mut viewport);
+                    frame_palette = None;
+                    prev_frame_palette = None;
+                }
+                (_, Some(command)) if command.is_pan() && args.pan_lock => {
+                    show_message!(Msg::PanLocked);
+                }
+                (_, Some(KeyCommand::Up)) => {
+                    if !view.pan(0, -(times as i64)) {
+                        hit_edge = Some(Edge::Top);
+                    }
+                }
+                (_, Some(KeyCommand::Down)) => {
+                    if !view.pan(0, times as i64) {
+                        hit_edge = Some(Edge::Bottom);
                     }
                 }
-                b'0' => {
-                    return Ok(Action::Goto(args.paths.len() - 1));
+                (_, Some(KeyCommand::Right)) => {
+                    if !view.pan(times as i64, 0) {
+                        hit_edge = Some(Edge::Right);
+                    }
                 }
-                b'1' => {
-                    return Ok(Action::Goto(0));
+                (_, Some(KeyCommand::Left)) => {
+                    if !view.pan(-(times as i64), 0) {
+                        hit_edge = Some(Edge::Left);
+                    }
                 }
-                _ if byte >= b'2' && byte <= b'9' => {
-                    let index = (byte - b'1') as usize;
-                    if index >= args.paths.len() {
-                        show_message!("Only {} files opened!", args.paths.len());
-                    } else {
-                        return Ok(Action::Goto(index));
+                (_, Some(KeyCommand::LeftEdge)) => {
+                    jumped = true;
+                    if !view.set_position(0, view.y()) {
+                        hit_edge = Some(Edge::Left);
                     }
                 }
+                (_, Some(KeyCommand::RightEdge)) => {
+                    jumped = true;
+                    if !view.set_position(view.max_x(), view.y()) {
+                        hit_edge = Some(Edge::Right);
+                    }
+                }
+                (_, Some(KeyCommand::Top)) => {
+                    jumped = true;
+                    if !view.set_position(view.x(), 0) {
+                        hit_edge = Some(Edge::Top);
+                    }
+                }
+                (_, Some(KeyCommand::Bottom)) => {
+                    jumped = true;
+                    if !view.set_position(view.x(), view.max_y()) {
+                        hit_edge = Some(Edge::Bottom);
+                    }
+                }
+                (_, Some(KeyCommand::PageUp)) => {
+                    jumped = true;
+                    if !view.pan(0, -(view.view_size().1 as i64 / 2) * times as i64) {
+                        hit_edge = Some(Edge::Top);
+                    }
+                }
+                (_, Some(KeyCommand::PageDown)) => {
+                    jumped = true;
+                    if !view.pan(0, view.view_size().1 as i64 / 2 * times as i64) {
+                        hit_edge = Some(Edge::Bottom);
+                    }
+                }
+                (_, Some(KeyCommand::PageLeft)) => {
+                    jumped = true;
+                    if !view.pan(-(view.view_size().0 as i64 / 2) * times as i64, 0) {
+                        hit_edge = Some(Edge::Left);
+                    }
+                }
+                (_, Some(KeyCommand::PageRight)) => {
+                    jumped = true;
+                    if !view.pan(view.view_size().0 as i64 / 2 * times as i64, 0) {
+                        hit_edge = Some(Edge::Right);
+                    }
+                }
+                (_, Some(KeyCommand::LastFile)) => {
+                    return Ok(Action::Goto(state.sources.len() - 1));
+                }
                 _ => {}
             }
         }
 
+        if let Some(number) = count.take_expired(frame_start_ts, COUNT_TIMEOUT) {
+            // a number without a command opens that file
+            message.clear();
+            message_end_ts = loop_start_ts;
+            if number >= 1 && number as usize <= state.sources.len() {
+                return Ok(Action::Goto(number as usize - 1));
+            }
+            show_message!(Msg::OnlyFilesOpened, state.sources.len());
+        }
+
+        if follow_camera && (view.position() != input_position || jumped || hit_edge.is_some()) {
+            // manual input overrides the camera path
+            follow_camera = false;
+            show_message!(Msg::CameraManualHint);
+        }
+
+        if follow_camera && let Some((center_x, center_y)) = living_world.camera_position(time_of_day) {
+            view.center_on(center_x, center_y);
+        }
+
+        if jumped && args.pan_transition > 0 {
+            let duration = Duration::from_millis(args.pan_transition);
+            pan_animation = Some(PanAnimation::new(shown_position, view.position(), frame_start_ts, duration));
+        } else if let Some(animation) = &pan_animation && (jumped || animation.target() != view.position()) {
+            // moved by other means, so the animation is over
+            pan_animation = None;
+        }
+
+        if let Some(edge) = hit_edge {
+            let (dx, dy) = edge.inward();
+            let (range_x, range_y) = (view.max_x(), view.max_y());
+            let can_bounce = if dx != 0 { range_x > 0 } else { range_y > 0 };
+
+            match args.edge_feedback {
+                EdgeFeedback::None => {}
+                EdgeFeedback::Bounce if can_bounce => {
+                    let from = (
+                        view.x().saturating_add_signed(dx * BOUNCE_DISTANCE.min(range_x) as i32),
+                        view.y().saturating_add_signed(dy * BOUNCE_DISTANCE.min(range_y) as i32),
+                    );
+                    pan_animation = Some(PanAnimation::new(from, view.position(), frame_start_ts, BOUNCE_DURATION));
+                }
+                EdgeFeedback::Hint | EdgeFeedback::Bounce => {
+                    show_message!(Msg::ReachedEdge, edge_name(edge));
+                }
+            }
+        }
+
+        if let Some(animation) = &pan_animation {
+            let (x, y) = animation.position(frame_start_ts);
+            view.set_position(x, y);
+            if animation.is_finished(frame_start_ts) {
+                pan_animation = None;
+            }
+        }
+        let (x, y) = view.position();
+
+        if state.stdout.get_mut().retry()? {
+            // skip frames until the terminal caught up, then redraw everything
+            old_term_width  = 0;
+            old_term_height = 0;
+
+            if !pacer.wait(wake_on_input) {
+                return Ok(Action::Quit);
+            }
+            continue;
+        }
+
+        if term_width < MIN_TERM_COLUMNS || term_height < MIN_TERM_ROWS * 2 {
+            if too_small_shown != Some((term_width, term_height)) {
+                write_too_small(&mut state.stdout, term_width);
+                let _ = state.stdout.flush();
+                too_small_shown = Some((term_width, term_height));
+            }
+
+            // full redraw once the terminal is big enough again
+            old_term_width  = 0;
+            old_term_height = 0;
+
+            if !pacer.wait(wake_on_input) {
+                return Ok(Action::Quit);
+            }
+            continue;
+        }
+        too_small_shown = None;
+
         // render frame
+        if args.sync_output {
+            let _ = write!(state.stdout, "{BEGIN_SYNCHRONIZED_UPDATE}");
+        }
         let mut full_redraw = false;
         let viewport_row = viewport_y / 2 + 1;
+        // odd vertical offsets start the image in the lower half of a cell
+        let phase = viewport_y % 2;
         let viewport_column = viewport_x + 1;
-        if old_x != x || old_y != y || old_term_width != term_width || old_term_height != term_height {
-            viewport.get_rect_from(x, y, term_width, term_height, living_world.base());
-            frame = RgbImage::new(viewport.width(), viewport.height());
-
-            if old_term_width != term_width || old_term_height != term_height {
-                prev_frame = RgbImage::new(viewport.width(), viewport.height());
+        let (shown_width, shown_height) = view.shown_size();
+        if old_position != (x, y) || old_term_width != term_width || old_term_height != term_height || old_zoom != view.zoom() {
+            view.rect_into(living_world.base(), &mut viewport);
+            frame = RgbImage::new(shown_width, shown_height);
+            frame_palette = None;
+            prev_frame_palette = None;
+
+            if old_term_width != term_width || old_term_height != term_height || old_zoom != view.zoom() {
+                prev_frame = RgbImage::new(shown_width, shown_height);
+                shown_frame = RgbImage::new(shown_width, shown_height);
                 full_redraw = true;
 
                 //let _ = write!(state.stdout, "\x1B[38;2;0;0;0m\x1B[48;2;0;0;0m\x1B[2J");
-                if viewport.width() < term_width || viewport.height() < term_height {
+                if shown_width < term_width || shown_height < term_height {
                     let _ = write!(state.stdout, "\x1B[38;2;0;0;0m\x1B[48;2;0;0;0m");
 
                     if viewport_y > 0 {
                         let _ = write!(state.stdout, "\x1B[{};1H\x1B[1J", viewport_row);
                     }
 
-                    let viewport_rows = viewport.height().div_ceil(2);
+                    let viewport_rows = (shown_height + phase).div_ceil(2);
                     let viewport_end_row = viewport_row + viewport_rows;
                     if viewport_x > 0 {
                         let column = viewport_column - 1;
@@ -887,102 +3598,231 @@ fn show_image(args: &mut Args, state: &mut GlobalState, file_index: usize) -> Re
                         }
                     }
 
-                    if viewport_x + viewport.width() < term_width {
-                        let viewport_end_column = viewport_column + viewport.width();
+                    if viewport_x + shown_width < term_width {
+                        let viewport_end_column = viewport_column + shown_width;
                         for row in viewport_row..viewport_end_row {
                             let _ = write!(state.stdout, "\x1B[{};{}H\x1B[0K", row, viewport_end_column);
                         }
                     }
 
-                    if (viewport_y + viewport.height()).div_ceil(2) < term_height / 2 {
+                    if (viewport_y + shown_height).div_ceil(2) < term_height / 2 {
                         let _ = write!(state.stdout, "\x1B[{};1H\x1B[0J", viewport_end_row);
                     }
                 }
             }
         }
 
-        let blend_cycle = (frame_start_ts - loop_start_ts).as_secs_f64();
-        if !living_world.timeline().is_empty() {
-            let mut palette1 = &living_world.palettes()[living_world.timeline().last().unwrap().palette_index()];
-            let mut palette2 = palette1;
-            let mut prev_time_of_day = 0;
-            let mut next_time_of_day = 0;
+        if let Some(alert) = args.timeline_alert
+            && state.current_time.is_none()
+            && state.time_speed == 1
+            && let Some(last_time_of_day) = last_time_of_day
+            && let Some(event) = living_world.crossed_event(last_time_of_day, time_of_day)
+        {
+            if alert != TimelineAlert::Flash {
+                let _ = write!(state.stdout, "\x07");
+            }
 
-            // TODO: binary search?
-            let mut found = false;
-            for event in living_world.timeline() {
-                prev_time_of_day = next_time_of_day;
-                next_time_of_day = event.time_of_day() as u64 * 1000;
-                palette1 = palette2;
-                palette2 = &living_world.palettes()[event.palette_index()];
-                if next_time_of_day > time_of_day {
-                    found = true;
-                    break;
+            if alert != TimelineAlert::Bell && !state.overlays.all_hidden() {
+                let (hours, mins) = get_hours_mins(event.time_of_day() as u64 * 1000);
+                let palette = &living_world.palettes()[event.palette_index()];
+                if let Some(name) = palette.filename() {
+                    show_message!(@always Msg::ClockEvent, hours, format!("{mins:02}"), name);
+                } else {
+                    show_message!(@always Msg::Clock, hours, format!("{mins:02}"));
                 }
             }
+        }
+        last_time_of_day = Some(time_of_day);
+
+        let clock = cycle_clock(state, frame_start_ts, time_of_day);
+        let mut composed = animator.compose(&living_world, viewport.indexed_image(), x, y, clock, &mut sprite_buf);
+        if view.scale() != (Scale::ONE, Scale::ONE) {
+            profile_scope!("zoom");
+            composed.scale_into(view.scale(), &mut zoomed);
+            composed = &zoomed;
+        }
+        let palette = {
+            profile_scope!("palette");
+            animator.set_cycle_offsets(performance.update(frame_start_ts));
+            animator.set_cycle_mask(mixer.mask(animator::active_cycles(&living_world, time_of_day).len()));
+            let palette = animator.palette(&living_world, clock, args.blend);
+            let palette = if performance.apply_flash(palette, frame_start_ts, &mut flash_palette_buf) {
+                &flash_palette_buf
+            } else {
+                palette
+            };
+            display_palette(args, palette, &mut display_palette_buf)
+        };
+
+        if let Some(color_sync) = &mut state.color_sync {
+            let _ = color_sync.update(&mut state.stdout, palette, living_world.base().indexed_image(), frame_start_ts);
+        }
 
-            if !found {
-                prev_time_of_day = next_time_of_day;
-                next_time_of_day = DAY_DURATION;
-                palette1 = palette2;
-                palette2 = &living_world.palettes()[living_world.timeline().first().unwrap().palette_index()];
+        {
+            profile_scope!("apply_with_palette");
+            if !living_world.layers().is_empty() || !living_world.sprites().is_empty() {
+                frame_palette = None;
             }
+            apply_palette(composed, palette, &mut frame, &mut frame_palette);
+        }
 
-            let current_span = next_time_of_day - prev_time_of_day;
-            let time_in_span = time_of_day - prev_time_of_day;
-            let blend_palettes = time_in_span as f64 / current_span as f64;
+        if let Some(weather) = &mut weather {
+            weather.draw(&mut frame, time_of_day, frame_start_ts);
+            frame_palette = None;
+        }
 
-            cycled_palette1.apply_cycles_from(palette1.palette(), palette1.cycles(), blend_cycle, args.blend);
-            cycled_palette2.apply_cycles_from(palette2.palette(), palette2.cycles(), blend_cycle, args.blend);
+        if let Some(frame_pipe) = &mut state.frame_pipe {
+            profile_scope!("pipe_frames");
+            if let Err(err) = frame_pipe.write_frame(&frame, args.fps) {
+                let reason = match state.frame_pipe.take().map(FramePipe::finish) {
+                    Some(Ok(status)) if !status.success() => status.to_string(),
+                    _ => err.to_string(),
+                };
+                show_message!(@always Msg::FramePipeClosed, reason);
+            }
+        }
 
-            crate::palette::blend(&cycled_palette1, &cycled_palette2, blend_palettes, &mut blended_palette);
+        let grid = state.overlays.is_shown(Overlay::Grid);
+        let debug_diff = state.overlays.is_shown(Overlay::DebugDiff);
+        if grid {
+            overlay::draw_grid(&mut frame, x, y, args.grid_size, view.scale());
+            frame_palette = None;
 
-            viewport.indexed_image().apply_with_palette(&mut frame, &blended_palette);
-        } else {
-            cycled_palette1.apply_cycles_from(&blended_palette, living_world.base().cycles(), blend_cycle, args.blend);
-            viewport.indexed_image().apply_with_palette(&mut frame, &cycled_palette1);
+            if old_position != (x, y) {
+                // the ruler labels move with the image
+                full_redraw = true;
+            }
         }
 
-        let full_width = viewport.width() >= term_width;
-        if full_redraw {
-            simple_image_to_ansi_into(&frame, &mut linebuf);
-        } else {
-            image_to_ansi_into(&prev_frame, &frame, full_width, &mut linebuf);
+        let full_width = shown_width >= term_width;
+        let redraw_all = full_redraw || args.eink || args.no_diff;
+        {
+            profile_scope!("encode");
+            if args.eink && !full_redraw && frame == prev_frame {
+                // don't refresh the e-ink display if nothing changed
+                linebuf.clear();
+            } else if args.mono == Some(MonoRamp::Blocks) {
+                let prev_frame = (!redraw_all).then_some(&prev_frame);
+                blocks_image_to_ansi_into(prev_frame, &frame, full_width, phase, &mut linebuf);
+            } else if redraw_all {
+                simple_image_to_ansi_phased_into(&frame, phase, &mut linebuf);
+                if debug_diff {
+                    shown_frame.clone_from(&frame);
+                }
+            } else if debug_diff {
+                highlight_changes(&prev_frame, &frame, phase, DEBUG_DIFF_COLOR, &mut debug_frame);
+                image_to_ansi_phased_into(&shown_frame, &debug_frame, full_width, phase, &mut linebuf);
+                std::mem::swap(&mut debug_frame, &mut shown_frame);
+            } else {
+                image_to_ansi_phased_into(&prev_frame, &frame, full_width, phase, &mut linebuf);
+            }
         }
 
         std::mem::swap(&mut frame, &mut prev_frame);
+        std::mem::swap(&mut frame_palette, &mut prev_frame_palette);
 
-        let _ = write!(state.stdout, "\x1B[{};{}H{linebuf}", viewport_row, viewport_column);
+        {
+            profile_scope!("write");
+            let _ = write!(state.stdout, "\x1B[{};{}H{linebuf}", viewport_row, viewport_column);
+        }
 
         old_term_width  = term_width;
         old_term_height = term_height;
+        old_zoom = view.zoom();
+
+        if grid {
+            overlay::write_ruler(&mut state.stdout, (x, y), viewport.size(), args.grid_size, view.scale(), (viewport_row, viewport_column), phase);
+        }
+
+        if state.overlays.is_shown(Overlay::Info) {
+            let max_width = term_width + 1 - viewport_column;
+            let max_rows = term_height / 2 + 1 - viewport_row;
+            let mut fields = info_fields.clone();
+            if let Some(latency) = &state.latency {
+                fields.push((i18n::tr(Msg::InfoLatency).to_owned(), i18n::format(Msg::InfoLatencyText, &[&latency.rtt().as_millis(), &latency.link()])));
+            }
+            write_panel(&mut state.stdout, &panel_lines(&fields), viewport_row, viewport_column, max_width, max_rows);
+        }
+
+        if state.overlays.is_shown(Overlay::Palette) {
+            let usage = index_usage.get_or_insert_with(|| living_world.base().indexed_image().index_usage());
+            let cycles = animator::active_cycles(&living_world, time_of_day);
+            let palette_lint = lint::PaletteLint::new(usage, palette, cycles, lint::DEFAULT_THRESHOLD);
+            let column = (term_width + 1).saturating_sub(lint::INSPECTOR_WIDTH).max(1);
+            lint::write_inspector(&mut state.stdout, palette, &palette_lint.markers(), 1, column);
+            let legend = i18n::format(Msg::PaletteLegend, &[&palette_lint.similar.len(), &palette_lint.unused.len()]);
+            write_panel(&mut state.stdout, &[legend], 17, column, lint::INSPECTOR_WIDTH, 1);
+        }
+
+        if state.overlays.is_shown(Overlay::Cycles) {
+            let cycles = animator::active_cycles(&living_world, time_of_day);
+            let mut lines = vec![i18n::format(Msg::CycleListTitle, &[])];
+            if cycles.is_empty() {
+                lines.push(format!(" {}", i18n::format(Msg::NoCycles, &[])));
+            } else {
+                lines.extend(mixer.lines(cycles));
+            }
+            let row = if state.overlays.is_shown(Overlay::Info) { viewport_row + info_fields.len() as u32 + 1 } else { viewport_row };
+            let max_width = term_width + 1 - viewport_column;
+            let max_rows = (term_height / 2 + 1).saturating_sub(row);
+            write_panel(&mut state.stdout, &lines, row, viewport_column, max_width, max_rows);
+        }
+
+        if scrubbing || (state.overlays.is_shown(Overlay::TimeBar) && !keyframes.is_empty()) {
+            write_timeline_bar(&mut state.stdout, time_of_day, &keyframes, term_height / 2, 1, term_width);
+        }
 
         if state.time_speed != 1 && message.is_empty() {
             let (hours, mins) = get_hours_mins(time_of_day);
-            show_message!("{hours}:{mins:02}");
+            show_message!(Msg::Clock, hours, format!("{mins:02}"));
         }
 
-        if message_end_ts >= frame_start_ts {
-            if updated_message && old_message_len > message.len() {
+        if chords.is_pending() || count.is_pending() {
+            // keep the hint until the chord or command is complete
+            message_end_ts = frame_start_ts + message_duration;
+        }
+
+        if message_end_ts >= frame_start_ts && !state.overlays.all_hidden() {
+            if updated_message && old_message_len > message.chars().count() {
                 // full redraw next frame by faking old term size of 0x0
                 old_term_width  = 0;
                 old_term_height = 0;
             } else {
-                let msg_len = message.len();
+                let msg_len = message.chars().count();
+
+                let top = args.osd_position.is_top();
+                let (row, area_column, area_width) = match args.osd_anchor {
+                    OsdAnchor::Terminal => (if top { 1 } else { term_height / 2 }, 1, term_width),
+                    OsdAnchor::Image if top => {
+                        let row = if viewport_row > 1 { viewport_row - 1 } else { viewport_row };
+                        (row, viewport_column, shown_width)
+                    }
+                    OsdAnchor::Image => {
+                        let viewport_end_row = viewport_row + (shown_height + phase).div_ceil(2);
+                        let row = if viewport_end_row <= term_height / 2 {
+                            viewport_end_row
+                        } else {
+                            viewport_end_row - 1
+                        };
+                        (row, viewport_column, shown_width)
+                    }
+                };
 
-                let column = if msg_len < term_width as usize {
-                    (term_width as usize - msg_len) / 2 + 1
-                } else { 1 };
+                let free = (area_width as usize).saturating_sub(msg_len);
+                let column = area_column as usize + match args.osd_position {
+                    OsdPosition::TopLeft  | OsdPosition::BottomLeft  => 0,
+                    OsdPosition::TopRight | OsdPosition::BottomRight => free,
+                    OsdPosition::Top      | OsdPosition::Bottom      => free / 2,
+                };
 
-                let message = if msg_len > term_width as usize {
-                    &message[..term_width as usize]
+                let message = if msg_len > area_width as usize {
+                    &message[..message.char_indices().nth(area_width as usize).map_or(message.len(), |(index, _)| index)]
                 } else {
                     &message
                 };
 
-                let _ = write!(state.stdout,
-                    "\x1B[{};{}H\x1B[38;2;255;255;255m\x1B[48;2;0;0;0m{}",
-                    term_height, column, message);
+                // frames are already swapped
+                overlay::write_osd_text(&mut state.stdout, message, (row.max(1), column as u32), &prev_frame, (viewport_row, viewport_column), phase, osd_colors);
                 message_shown = true;
             }
         } else if message_shown {
@@ -992,11 +3832,73 @@ fn show_image(args: &mut Args, state: &mut GlobalState, file_index: usize) -> Re
             message_shown = false;
         }
 
-        let _ = state.stdout.flush();
+        if args.sync_output {
+            let _ = write!(state.stdout, "{END_SYNCHRONIZED_UPDATE}");
+        }
+
+        if let Some(latency) = &mut state.latency {
+            latency.poll(&mut state.stdout, frame_start_ts);
+        }
+
+        {
+            profile_scope!("flush");
+            let _ = state.stdout.flush();
+        }
+
+        if state.stdout.get_mut().is_stalled() && args.fps > 1 {
+            args.fps = (args.fps / 2).max(1);
+            if let Some(latency) = &mut state.latency {
+                latency.adaptive.fps = false;
+            }
+            pacer.set_frame_duration(Duration::from_secs_f64(1.0 / args.fps as f64));
+            animator.set_smoothing(smoothing(args));
+
+            // shown once the terminal caught up
+            stalled_fps = Some(args.fps);
+        }
+
+        if let Some(max_bandwidth) = args.max_bandwidth {
+            // don't send the next frame before this one went through
+            let transfer = Duration::from_secs_f64(linebuf.len() as f64 / (max_bandwidth as f64 * 1024.0));
+            pacer.hold_off(frame_start_ts + transfer);
+        }
+
+        if let Some(frame_dump) = &mut state.frame_dump && let Some(payload) = state.stdout.take_capture() {
+            // frames are already swapped
+            // shown next frame
+            match frame_dump.write(&payload, &frame, &prev_frame) {
+                Ok(()) => if frame_dump.is_done() {
+                    dump_message = Some(i18n::format(Msg::DumpedFrames, &[&frame_dump.written(), &frame_dump.dir().to_string_lossy()]));
+                }
+                Err(err) => {
+                    dump_message = Some(i18n::format(Msg::DumpError, &[&err]));
+                }
+            }
+            if dump_message.is_some() {
+                state.frame_dump = None;
+            }
+        }
+
+        if let Some(journal) = &mut state.journal && journal.is_due(frame_start_ts) {
+            let snapshot = journal::Snapshot {
+                file_index,
+                source: state.sources[file_index].name().into_owned(),
+                current_time: state.current_time,
+                time_speed: state.time_speed,
+                fps: args.fps,
+                blend: args.blend,
+                cycle_phase: args.cycle_phase,
+                position: if follow_camera { None } else { Some(view.position()) },
+                seed: state.rng.seed(),
+            };
+            if let Err(err) = journal.update(snapshot, frame_start_ts) {
+                // shown next frame
+                dump_message = Some(i18n::format(Msg::JournalError, &[&err]));
+            }
+        }
 
-        // sleep for rest of frame
-        let elapsed = frame_start_ts.elapsed();
-        if frame_duration > elapsed && !interruptable_sleep(frame_duration - elapsed) {
+        // sleep until the next frame is due or a key is pressed
+        if !pacer.wait(wake_on_input) {
             return Ok(Action::Quit);
         }
     }
@@ -0,0 +1,148 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Scene index files like the one of the original HTML5 Canvas Cycle demo,
+//! which list the scenes of a collection with their display names, months,
+//! and sounds:
+//!
+//! ```json
+//! [
+//!     { "name": "V26SNOW", "title": "Winter Forest - Snow", "month": "January", "sound": "wind" },
+//!     { "name": "V08AM", "title": "Jungle Waterfall - Morning", "month": "February", "sound": "rain" }
+//! ]
+//! ```
+//!
+//! The list may also be in the `scenes` field of an object. The file of a
+//! scene is looked up by its name next to the index and in an `images`
+//! directory, with the extensions `.json`, `.LBM.json`, `.LBM`, or `.lbm`,
+//! unless it is given explicitly as `file`.
+//!
+//! A scene can also be shown at a fixed `time` of day (`"18:30"`) or with
+//! the clock shifted by a `time_offset` (`"-2:00"`).
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::source::{SceneInfo, SceneTime, Source};
+
+/// Index files are small, bigger JSON files aren't even checked.
+const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+
+/// Where to look for scene files, relative to the index.
+const SCENE_DIRS: &[&str] = &["", "images"];
+const SCENE_EXTENSIONS: &[&str] = &["", ".json", ".LBM.json", ".lbm.json", ".LBM", ".lbm"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct SceneEntry {
+    name: String,
+    title: Option<String>,
+    month: Option<String>,
+    sound: Option<String>,
+    file: Option<String>,
+    time: Option<String>,
+    time_offset: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+enum Manifest {
+    List(Vec<SceneEntry>),
+    Object { scenes: Vec<SceneEntry> },
+}
+
+/// Reads the scene index at `path`. Returns `Ok(None)` if the file isn't a
+/// scene index, but e.g. an image.
+pub fn load(path: &Path) -> Result<Option<Vec<Source>>, Error> {
+    if path.extension().is_none_or(|ext| !ext.eq_ignore_ascii_case("json")) {
+        return Ok(None);
+    }
+
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Ok(None);
+    };
+    if !metadata.is_file() || metadata.len() > MAX_MANIFEST_SIZE {
+        return Ok(None);
+    }
+
+    let data = std::fs::read(path)?;
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&data);
+    let Ok(manifest) = serde_json::from_slice::<Manifest>(data) else {
+        return Ok(None);
+    };
+
+    let scenes = match manifest {
+        Manifest::List(scenes) | Manifest::Object { scenes } => scenes,
+    };
+    if scenes.is_empty() {
+        return Err(Error::new("scene index without scenes"));
+    }
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let sources = scenes.into_iter().map(|scene| {
+        let time = scene_time(&scene)
+            .map_err(|err| Error::new(format!("scene {}: {err}", scene.name)))?;
+        let file = match &scene.file {
+            Some(file) => dir.join(file),
+            None => find_scene_file(dir, &scene.name),
+        };
+        let info = SceneInfo {
+            title: scene.title.unwrap_or(scene.name),
+            month: scene.month,
+            sound: scene.sound,
+            time,
+        };
+        Ok(Source::Scene {
+            info: Box::new(info),
+            source: Box::new(file.into()),
+        })
+    }).collect::<Result<_, Error>>()?;
+
+    Ok(Some(sources))
+}
+
+/// The fixed time (`HH:MM`) or the time offset (`+HH:MM` or `-HH:MM`) of a
+/// scene. Only one of them may be given.
+fn scene_time(scene: &SceneEntry) -> Result<Option<SceneTime>, String> {
+    match (&scene.time, &scene.time_offset) {
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err("only one of time and time_offset may be given".to_owned()),
+        (Some(time), None) => Ok(Some(SceneTime::At(crate::parse_time_of_day(time)?))),
+        (None, Some(offset)) => {
+            let (sign, time) = match offset.strip_prefix('-') {
+                Some(time) => (-1, time),
+                None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+            };
+            Ok(Some(SceneTime::Offset(sign * crate::parse_time_of_day(time)? as i64)))
+        }
+    }
+}
+
+/// The first existing file for the scene `name`, or `<name>.json` next to
+/// the index if there is none, so the error names a sensible path.
+fn find_scene_file(dir: &Path, name: &str) -> PathBuf {
+    for subdir in SCENE_DIRS {
+        for ext in SCENE_EXTENSIONS {
+            let path = dir.join(subdir).join(format!("{name}{ext}"));
+            if path.is_file() {
+                return path;
+            }
+        }
+    }
+    dir.join(format!("{name}.json"))
+}
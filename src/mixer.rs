@@ -0,0 +1,127 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Mute and solo single color cycles, to see which palette ranges drive
+//! which effects of a scene.
+
+use crate::palette::Cycle;
+
+/// Which cycles are muted or solo'd, by index into the cycles of the scene,
+/// and which one is selected in the cycle list.
+#[derive(Debug, Clone, Default)]
+pub struct CycleMixer {
+    selected: usize,
+    muted: Vec<bool>,
+    solo: Option<usize>,
+    mask: Vec<bool>,
+}
+
+impl CycleMixer {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Moves the selection up by one, `count` is the number of cycles.
+    pub fn select_prev(&mut self, count: usize) {
+        self.selected = self.selected.min(count.saturating_sub(1)).saturating_sub(1);
+    }
+
+    /// Moves the selection down by one, `count` is the number of cycles.
+    pub fn select_next(&mut self, count: usize) {
+        self.selected = (self.selected + 1).min(count.saturating_sub(1));
+    }
+
+    #[inline]
+    pub fn is_muted(&self, cycle: usize) -> bool {
+        self.muted.get(cycle).copied().unwrap_or(false)
+    }
+
+    #[inline]
+    pub fn solo(&self) -> Option<usize> {
+        self.solo
+    }
+
+    /// Whether `cycle` is applied: it is the solo'd cycle, or nothing is
+    /// solo'd and it isn't muted.
+    pub fn is_enabled(&self, cycle: usize) -> bool {
+        match self.solo {
+            Some(solo) => solo == cycle,
+            None => !self.is_muted(cycle),
+        }
+    }
+
+    /// Returns whether the selected cycle is muted now.
+    pub fn toggle_mute(&mut self) -> bool {
+        if self.muted.len() <= self.selected {
+            self.muted.resize(self.selected + 1, false);
+        }
+        let muted = &mut self.muted[self.selected];
+        *muted = !*muted;
+        *muted
+    }
+
+    /// Solos the selected cycle, or ends the solo if it already is solo'd.
+    /// Returns whether it is solo'd now.
+    pub fn toggle_solo(&mut self) -> bool {
+        if self.solo == Some(self.selected) {
+            self.solo = None;
+        } else {
+            self.solo = Some(self.selected);
+        }
+        self.solo.is_some()
+    }
+
+    /// Whether all cycles are applied, i.e. nothing is muted or solo'd.
+    pub fn is_neutral(&self) -> bool {
+        self.solo.is_none() && !self.muted.contains(&true)
+    }
+
+    /// The mask for [`crate::animator::Animator::set_cycle_mask()`] for
+    /// `count` cycles.
+    pub fn mask(&mut self, count: usize) -> &[bool] {
+        let mut mask = std::mem::take(&mut self.mask);
+        mask.clear();
+        if !self.is_neutral() {
+            mask.extend((0..count).map(|cycle| self.is_enabled(cycle)));
+        }
+        self.mask = mask;
+        &self.mask
+    }
+
+    /// One line per cycle for the cycle list: number, index range, rate,
+    /// and whether it is reversed, muted, or solo'd.
+    pub fn lines(&self, cycles: &[Cycle]) -> Vec<String> {
+        cycles.iter().enumerate().map(|(index, cycle)| {
+            let marker = if index == self.selected { '▶' } else { ' ' };
+            let direction = if cycle.reverse() { '←' } else { '→' };
+            let state = if self.solo == Some(index) {
+                "solo"
+            } else if !self.is_enabled(index) {
+                "mute"
+            } else {
+                ""
+            };
+            format!("{marker}{:>3} {:>3}-{:<3} {direction} {:>5} {state:<4} ", index + 1, cycle.low(), cycle.high(), cycle.rate())
+        }).collect()
+    }
+}
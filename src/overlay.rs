@@ -0,0 +1,244 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::Write;
+
+use crate::color::{blend, blend_linear, Rgb};
+use crate::image::{RgbImage, Scale};
+
+/// Something drawn on top of the image that can be toggled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    /// On Screen Display messages
+    Osd,
+    /// grid and ruler
+    Grid,
+    /// file information panel
+    Info,
+    /// highlighting of re-drawn cells
+    DebugDiff,
+    /// palette inspector
+    Palette,
+    /// list of the color cycles, to mute and solo them
+    Cycles,
+    /// time of day bar with the keyframes of the timeline
+    TimeBar,
+}
+
+impl Overlay {
+    #[inline]
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The set of overlays that are shown. All of them can be hidden at once
+/// and the previous set restored again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Overlays {
+    shown: u8,
+    /// the set from before everything was hidden
+    saved: Option<u8>,
+}
+
+impl Overlays {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn is_shown(&self, overlay: Overlay) -> bool {
+        self.shown & overlay.bit() != 0
+    }
+
+    /// Shows or hides `overlay`. If everything was hidden, the saved set is
+    /// discarded, so only `overlay` is shown.
+    pub fn set(&mut self, overlay: Overlay, shown: bool) {
+        self.saved = None;
+        if shown {
+            self.shown |= overlay.bit();
+        } else {
+            self.shown &= !overlay.bit();
+        }
+    }
+
+    /// Returns whether `overlay` is shown now.
+    pub fn toggle(&mut self, overlay: Overlay) -> bool {
+        let shown = !self.is_shown(overlay);
+        self.set(overlay, shown);
+        shown
+    }
+
+    #[inline]
+    pub fn all_hidden(&self) -> bool {
+        self.saved.is_some()
+    }
+
+    /// Hides everything, or restores what was shown before everything was
+    /// hidden. Returns whether everything is hidden now.
+    pub fn toggle_all(&mut self) -> bool {
+        if let Some(saved) = self.saved.take() {
+            self.shown = saved;
+            false
+        } else {
+            self.saved = Some(self.shown);
+            self.shown = 0;
+            true
+        }
+    }
+}
+
+const GRID_BLEND: f64 = 0.5;
+const MIN_LABEL_SPACING: u32 = 6;
+/// How far the background of OSD messages is blended from the image under
+/// it towards black or white.
+const OSD_BACKGROUND_BLEND: f64 = 0.65;
+
+/// Draws grid lines every `size` image pixels into `frame`. `origin_x` and
+/// `origin_y` are the image coordinates of the top left pixel of `frame`, so
+/// the grid stays aligned to the image when panning. `scale` is how many
+/// pixels of `frame` an image pixel is wide and high, the lines stay 1 pixel
+/// wide. Lines are blended towards black or white, whichever contrasts more.
+pub fn draw_grid(frame: &mut RgbImage, origin_x: u32, origin_y: u32, size: u32, scale: (Scale, Scale)) {
+    if size == 0 {
+        return;
+    }
+
+    let (scale_x, scale_y) = scale;
+    let on_line = |origin: u32, pos: u32, scale: Scale| scale.is_start(pos) && (origin + scale.to_image(pos)).is_multiple_of(size);
+    let (width, height) = frame.size();
+    for y in 0..height {
+        let on_row = on_line(origin_y, y, scale_y);
+        for x in 0..width {
+            if on_row || on_line(origin_x, x, scale_x) {
+                let color = frame.get_pixel(x, y);
+                frame.set_pixel(x, y, contrast_blend(color));
+            }
+        }
+    }
+}
+
+#[inline]
+fn contrast_blend(color: Rgb) -> Rgb {
+    blend(color, color.contrasting(), GRID_BLEND)
+}
+
+/// Writes the image coordinates of the grid lines along the top and left
+/// edge of the viewport. The viewport starts at the terminal cell
+/// `cell` (row and column) and shows `size` (width and height) image pixels starting
+/// at `origin`, each `scale` pixels wide and high. `phase` is 1 if the image
+/// starts in the lower half of the first row.
+pub fn write_ruler(out: &mut impl Write, origin: (u32, u32), size: (u32, u32), grid_size: u32, scale: (Scale, Scale), cell: (u32, u32), phase: u32) {
+    let (origin_x, origin_y) = origin;
+    let (row, column) = cell;
+    let (width, height) = size;
+    let (scale_x, scale_y) = scale;
+    if grid_size == 0 || width == 0 || height == 0 {
+        return;
+    }
+
+    // don't let labels overlap
+    let label_step = grid_size * (MIN_LABEL_SPACING * scale_x.den()).div_ceil(grid_size * scale_x.num());
+
+    let _ = write!(out, "\x1B[38;2;255;255;255m\x1B[48;2;0;0;0m");
+
+    let end_x = origin_x + width;
+    let mut x = origin_x.next_multiple_of(label_step);
+    while x < end_x {
+        let label = x.to_string();
+        let label_column = scale_x.start_of(x - origin_x);
+        let max_len = scale_x.to_area(width).saturating_sub(label_column) as usize;
+        if label.len() <= max_len {
+            let _ = write!(out, "\x1B[{};{}H{label}", row, column + label_column);
+        }
+        x += label_step;
+    }
+
+    // rows are 2 pixels high, so the vertical labels need less spacing
+    let label_step = grid_size * (2 * scale_y.den()).div_ceil(grid_size * scale_y.num());
+    let end_y = origin_y + height;
+    let mut y = origin_y.next_multiple_of(label_step);
+    while y < end_y {
+        let label_row = row + (scale_y.start_of(y - origin_y) + phase) / 2;
+        if label_row != row {
+            let label: String = y.to_string().chars().take(scale_x.to_area(width) as usize).collect();
+            let _ = write!(out, "\x1B[{};{}H{label}", label_row, column);
+        }
+        y += label_step;
+    }
+}
+
+/// Colors of OSD messages. `None` picks the color by the image under the
+/// message, see [`write_osd_text()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OsdColors {
+    pub text: Option<Rgb>,
+    pub background: Option<Rgb>,
+}
+
+/// Writes `text` at the terminal cell `cell` (row and column) in black or
+/// white, whichever is more readable over the part of `frame` it covers. The
+/// background is the image under each character blended towards the other
+/// one, so the image still shows through. The top left pixel of `frame` is
+/// in the terminal cell `frame_cell`, in its lower half if `phase` is 1.
+/// Cells outside of `frame` count as black. Colors set in `colors` are used
+/// instead, and the text color of a solid background is picked by it.
+pub fn write_osd_text(out: &mut impl Write, text: &str, cell: (u32, u32), frame: &RgbImage, frame_cell: (u32, u32), phase: u32, colors: OsdColors) {
+    let (row, column) = cell;
+    let backgrounds: Vec<Rgb> = (column..).take(text.chars().count())
+        .map(|column| cell_color(frame, (row, column), frame_cell, phase))
+        .collect();
+
+    let text_color = match colors {
+        OsdColors { text: Some(text), .. } => text,
+        OsdColors { background: Some(background), .. } => background.contrasting(),
+        _ => {
+            let luminance = backgrounds.iter().map(Rgb::luminance).sum::<f64>() / backgrounds.len().max(1) as f64;
+            if luminance > 0.5 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) }
+        }
+    };
+    let backdrop = text_color.contrasting();
+
+    let Rgb([r, g, b]) = text_color;
+    let _ = write!(out, "\x1B[{row};{column}H\x1B[38;2;{r};{g};{b}m");
+    let mut prev_background = None;
+    for (ch, background) in text.chars().zip(backgrounds) {
+        let background = colors.background.unwrap_or_else(|| blend(background, backdrop, OSD_BACKGROUND_BLEND));
+        if prev_background != Some(background) {
+            let Rgb([r, g, b]) = background;
+            let _ = write!(out, "\x1B[48;2;{r};{g};{b}m");
+            prev_background = Some(background);
+        }
+        let _ = write!(out, "{ch}");
+    }
+}
+
+/// Average color of the two pixels of `frame` shown in the terminal cell
+/// `cell`, see [`write_osd_text()`].
+fn cell_color(frame: &RgbImage, cell: (u32, u32), frame_cell: (u32, u32), phase: u32) -> Rgb {
+    let (row, column) = cell;
+    let (frame_row, frame_column) = frame_cell;
+    let (width, height) = frame.size();
+    let x = column as i64 - frame_column as i64;
+    let top = (row as i64 - frame_row as i64) * 2 - phase as i64;
+    let pixel = |y: i64| if x >= 0 && y >= 0 && x < width as i64 && y < height as i64 {
+        frame.get_pixel(x as u32, y as u32)
+    } else {
+        Rgb([0, 0, 0])
+    };
+    blend_linear(pixel(top), pixel(top + 1), 0.5)
+}
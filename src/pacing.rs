@@ -0,0 +1,138 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use std::time::{Duration, Instant};
+
+/// Schedules frames at absolute deadlines (`next_frame_ts += frame_duration`)
+/// instead of sleeping for the rest of each frame, so that rounding and
+/// oversleeping don't add up over long runs.
+#[derive(Debug, Clone)]
+pub struct FramePacer {
+    frame_duration: Duration,
+    next_frame_ts: Instant,
+}
+
+impl FramePacer {
+    /// The first frame is due at `start_ts + frame_duration`.
+    #[inline]
+    pub fn new(frame_duration: Duration, start_ts: Instant) -> Self {
+        Self {
+            frame_duration,
+            next_frame_ts: start_ts + frame_duration,
+        }
+    }
+
+    #[inline]
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+
+    /// Changes the frame rate. The next deadline moves accordingly.
+    pub fn set_frame_duration(&mut self, frame_duration: Duration) {
+        let prev_frame_ts = self.next_frame_ts.checked_sub(self.frame_duration).unwrap_or(self.next_frame_ts);
+        self.frame_duration = frame_duration;
+        self.next_frame_ts = prev_frame_ts + frame_duration;
+    }
+
+    /// Moves the next frame to `ts` if it would be due earlier, e.g. to
+    /// stay within a bandwidth limit. The schedule continues from there.
+    pub fn hold_off(&mut self, ts: Instant) {
+        if ts > self.next_frame_ts {
+            self.next_frame_ts = ts;
+        }
+    }
+
+    #[inline]
+    pub fn next_frame_ts(&self) -> Instant {
+        self.next_frame_ts
+    }
+
+    /// Waits until the next frame is due. If `wake_on_input` is `true` it
+    /// returns early when there is input on stdin, without moving the
+    /// deadline. Returns `false` if the wait was interrupted by a signal.
+    pub fn wait(&mut self, wake_on_input: bool) -> bool {
+        let now = Instant::now();
+        if now < self.next_frame_ts && !wait_for(self.next_frame_ts - now, wake_on_input) {
+            return false;
+        }
+
+        let now = Instant::now();
+        if now >= self.next_frame_ts {
+            // When too slow (or overslept) skip the missed frames instead of
+            // rendering them in a burst, but stay on the same schedule.
+            let frame_nanos = self.frame_duration.as_nanos().max(1);
+            let behind = (now - self.next_frame_ts).as_nanos() / frame_nanos;
+            let skip = (frame_nanos * (behind + 1)).min(u64::MAX as u128) as u64;
+            self.next_frame_ts += Duration::from_nanos(skip);
+        }
+
+        true
+    }
+}
+
+/// Sleeps for `duration`. Returns `false` if interrupted by a signal.
+pub fn interruptable_sleep(duration: Duration) -> bool {
+    #[cfg(unix)]
+    {
+        let req = libc::timespec {
+            tv_sec:  duration.as_secs() as libc::time_t,
+            tv_nsec: duration.subsec_nanos() as i64,
+        };
+        let ret = unsafe { libc::nanosleep(&req, std::ptr::null_mut()) };
+        ret == 0
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::thread::sleep(duration);
+        true
+    }
+}
+
+/// Sleeps for `duration`, or until there is input on stdin if
+/// `wake_on_input` is `true`. Returns `false` if interrupted by a signal.
+#[cfg(unix)]
+fn wait_for(duration: Duration, wake_on_input: bool) -> bool {
+    if !wake_on_input {
+        return interruptable_sleep(duration);
+    }
+
+    let mut pollfd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // round up, waking up too early would just mean waiting again
+    let timeout = duration.as_micros().div_ceil(1000).min(libc::c_int::MAX as u128) as libc::c_int;
+    let ret = unsafe { libc::poll(&mut pollfd, 1, timeout) };
+    if ret < 0 {
+        return false;
+    }
+
+    if ret > 0 && pollfd.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0 {
+        // stdin is gone, don't spin on it
+        return interruptable_sleep(duration);
+    }
+
+    true
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn wait_for(duration: Duration, _wake_on_input: bool) -> bool {
+    interruptable_sleep(duration)
+}
@@ -0,0 +1,88 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Writes palettes in the file formats of common palette editors.
+
+use std::io::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::palette::{Cycle, Palette};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteFormat {
+    /// JASC-PAL, read by Paint Shop Pro, Aseprite and many more (.pal)
+    Jasc,
+    /// GIMP palette, also read by Inkscape and Krita (.gpl)
+    Gpl,
+    /// Adobe Color Table, read by Photoshop (.act)
+    Act,
+}
+
+impl PaletteFormat {
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PaletteFormat::Jasc => "pal",
+            PaletteFormat::Gpl  => "gpl",
+            PaletteFormat::Act  => "act",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        [PaletteFormat::Jasc, PaletteFormat::Gpl, PaletteFormat::Act].into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+/// Writes all 256 entries of `palette` in their original order. GIMP
+/// palettes also get `name` and the color cycles as comments, the other
+/// formats have no place for them.
+pub fn write_palette(out: &mut impl Write, palette: &Palette, cycles: &[Cycle], name: &str, format: PaletteFormat) -> std::io::Result<()> {
+    match format {
+        PaletteFormat::Jasc => {
+            write!(out, "JASC-PAL\r\n0100\r\n{}\r\n", palette.0.len())?;
+            for color in palette.0.iter() {
+                let [r, g, b] = color.0;
+                write!(out, "{r} {g} {b}\r\n")?;
+            }
+        }
+        PaletteFormat::Gpl => {
+            writeln!(out, "GIMP Palette")?;
+            // newlines would end the header early
+            writeln!(out, "Name: {}", name.replace(['\r', '\n'], " "))?;
+            writeln!(out, "Columns: 16")?;
+            writeln!(out, "#")?;
+            for cycle in cycles {
+                writeln!(out, "# cycle {}-{} rate {}{}", cycle.low(), cycle.high(), cycle.rate(),
+                    if cycle.reverse() { " reverse" } else { "" })?;
+            }
+            for (index, color) in palette.0.iter().enumerate() {
+                let [r, g, b] = color.0;
+                writeln!(out, "{r:>3} {g:>3} {b:>3}\tIndex {index}")?;
+            }
+        }
+        PaletteFormat::Act => {
+            for color in palette.0.iter() {
+                out.write_all(&color.0)?;
+            }
+        }
+    }
+    Ok(())
+}
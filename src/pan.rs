@@ -0,0 +1,95 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+/// Edge of the image the viewport can't move past.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Edge {
+    /// Direction pointing away from the edge into the image.
+    #[inline]
+    pub fn inward(&self) -> (i32, i32) {
+        match self {
+            Edge::Top    => (0, 1),
+            Edge::Bottom => (0, -1),
+            Edge::Left   => (1, 0),
+            Edge::Right  => (-1, 0),
+        }
+    }
+}
+
+impl Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Edge::Top    => "top".fmt(f),
+            Edge::Bottom => "bottom".fmt(f),
+            Edge::Left   => "left".fmt(f),
+            Edge::Right  => "right".fmt(f),
+        }
+    }
+}
+
+/// Smooth movement of the viewport from one position to another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanAnimation {
+    from: (u32, u32),
+    to: (u32, u32),
+    start: Instant,
+    duration: Duration,
+}
+
+impl PanAnimation {
+    #[inline]
+    pub fn new(from: (u32, u32), to: (u32, u32), start: Instant, duration: Duration) -> Self {
+        Self { from, to, start, duration }
+    }
+
+    /// Position the animation ends at.
+    #[inline]
+    pub fn target(&self) -> (u32, u32) {
+        self.to
+    }
+
+    #[inline]
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+
+    /// Position of the viewport at the given time, easing in and out.
+    pub fn position(&self, now: Instant) -> (u32, u32) {
+        if self.is_finished(now) {
+            return self.to;
+        }
+
+        let t = now.saturating_duration_since(self.start).as_secs_f64() / self.duration.as_secs_f64();
+        let t = t * t * (3.0 - 2.0 * t);
+
+        (lerp(self.from.0, self.to.0, t), lerp(self.from.1, self.to.1, t))
+    }
+}
+
+#[inline]
+fn lerp(from: u32, to: u32, t: f64) -> u32 {
+    (from as f64 + (to as f64 - from as f64) * t).round() as u32
+}
@@ -0,0 +1,107 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Opens a tmux window with one pane per file, see `color-cycle panes`.
+//!
+//! Every pane runs its own instance with `--sync-clock`, so the color
+//! cycles of all panes run in step, and the keys typed into the window are
+//! sent to all panes at once (tmux' `synchronize-panes`).
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::error::Error;
+
+pub const DEFAULT_LAYOUT: &str = "tiled";
+
+/// Opens the panes in a new window of the current tmux session, or in a
+/// new session that is then attached when not running inside of tmux.
+/// `options` are passed on to every instance.
+pub fn open_panes(paths: &[PathBuf], options: &[String], layout: &str) -> Result<(), Error> {
+    let exe = std::env::current_exe()
+        .map_err(|err| Error::with_cause("locating the color-cycle binary", Box::new(err)))?;
+
+    let commands: Vec<String> = paths.iter().map(|path| {
+        let mut command = shell_quote(exe.as_os_str());
+        command.push_str(" --sync-clock");
+        for option in options {
+            command.push(' ');
+            command.push_str(&shell_quote(option.as_ref()));
+        }
+        command.push_str(" -- ");
+        command.push_str(&shell_quote(path.as_os_str()));
+        command
+    }).collect();
+    let Some((first, rest)) = commands.split_first() else {
+        return Ok(());
+    };
+
+    let nested = std::env::var_os("TMUX").is_some();
+    let target = if nested {
+        tmux(&["new-window", "-P", "-F", "#{session_id}:#{window_id}", first])?
+    } else {
+        tmux(&["new-session", "-d", "-P", "-F", "#{session_id}:#{window_id}", first])?
+    };
+    let target = target.trim();
+
+    for command in rest {
+        tmux(&["split-window", "-t", target, command])?;
+        // re-layout after every split, else tmux runs out of room for new panes
+        tmux(&["select-layout", "-t", target, layout])?;
+    }
+    tmux(&["select-layout", "-t", target, layout])?;
+    tmux(&["set-option", "-w", "-t", target, "synchronize-panes", "on"])?;
+
+    if !nested {
+        let session = target.split(':').next().unwrap_or(target);
+        let status = Command::new("tmux")
+            .args(["attach-session", "-t", session])
+            .status()
+            .map_err(|err| Error::with_cause("starting tmux", Box::new(err)))?;
+        if !status.success() {
+            return Err(Error::new(format!("tmux attach-session failed: {status}")));
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a tmux command and returns what it printed.
+fn tmux(args: &[&str]) -> Result<String, Error> {
+    let output = Command::new("tmux")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| Error::with_cause("starting tmux", Box::new(err)))?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(format!("tmux {} failed: {}", args[0], message.trim())));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Quotes `arg` for the shell that tmux runs the commands of the panes with.
+fn shell_quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    if !arg.is_empty() && arg.bytes().all(|byte| byte.is_ascii_alphanumeric() || b"-_./=:,+@%".contains(&byte)) {
+        return arg.into_owned();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
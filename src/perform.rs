@@ -0,0 +1,150 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Performance mode, for "playing" a scene live: number keys speed up
+//! single color cycles for a moment and letter keys flash the palette.
+//!
+//! Terminals don't report key releases, so a key press boosts a cycle for a
+//! short time and the key repeat of a held key keeps it boosted.
+
+use std::time::{Duration, Instant};
+
+use crate::color::{blend_linear, Rgb};
+use crate::palette::Palette;
+
+/// How long a key press boosts a cycle.
+pub const BOOST_DURATION: Duration = Duration::from_millis(400);
+
+/// Rate of a boosted cycle relative to its normal rate.
+pub const BOOST_FACTOR: f64 = 4.0;
+
+/// How long a flash takes to fade out.
+pub const FLASH_DURATION: Duration = Duration::from_millis(600);
+
+/// Keys of the flashes, in the order of [`Flash::ALL`].
+pub const FLASH_KEYS: &str = "asdfghjkl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flash {
+    White,
+    Blackout,
+    Invert,
+    Red,
+    Green,
+    Blue,
+    Gold,
+    Purple,
+    Gray,
+}
+
+impl Flash {
+    pub const ALL: [Flash; 9] = [
+        Flash::White, Flash::Blackout, Flash::Invert,
+        Flash::Red, Flash::Green, Flash::Blue,
+        Flash::Gold, Flash::Purple, Flash::Gray,
+    ];
+
+    pub fn from_key(key: char) -> Option<Flash> {
+        FLASH_KEYS.chars().position(|flash_key| flash_key == key).map(|index| Flash::ALL[index])
+    }
+
+    /// What `color` turns into at the peak of the flash.
+    fn target(self, color: Rgb) -> Rgb {
+        match self {
+            Flash::White    => Rgb([255, 255, 255]),
+            Flash::Blackout => Rgb([0, 0, 0]),
+            Flash::Invert   => Rgb(color.0.map(|value| 255 - value)),
+            Flash::Red      => Rgb([255, 32, 16]),
+            Flash::Green    => Rgb([32, 255, 64]),
+            Flash::Blue     => Rgb([32, 64, 255]),
+            Flash::Gold     => Rgb([255, 200, 40]),
+            Flash::Purple   => Rgb([180, 40, 255]),
+            Flash::Gray     => {
+                let value = (color.luminance() * 255.0).round() as u8;
+                Rgb([value, value, value])
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Performance {
+    /// until when each cycle is boosted, by index
+    boosts: Vec<Option<Instant>>,
+    /// extra cycle time each cycle gained from boosts, in seconds
+    offsets: Vec<f64>,
+    last_update: Option<Instant>,
+    flash: Option<(Flash, Instant)>,
+}
+
+impl Performance {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn boost(&mut self, cycle: usize, now: Instant) {
+        if self.boosts.len() <= cycle {
+            self.boosts.resize(cycle + 1, None);
+            self.offsets.resize(cycle + 1, 0.0);
+        }
+        self.boosts[cycle] = Some(now + BOOST_DURATION);
+    }
+
+    #[inline]
+    pub fn flash(&mut self, flash: Flash, now: Instant) {
+        self.flash = Some((flash, now));
+    }
+
+    /// Advances the boosted cycles to `now` and returns the offsets for
+    /// [`crate::animator::Animator::set_cycle_offsets()`].
+    pub fn update(&mut self, now: Instant) -> &[f64] {
+        let last_update = self.last_update.replace(now).unwrap_or(now);
+        for (until, offset) in self.boosts.iter_mut().zip(self.offsets.iter_mut()) {
+            let Some(boost_end) = *until else {
+                continue;
+            };
+            let boosted = boost_end.min(now).saturating_duration_since(last_update);
+            *offset += boosted.as_secs_f64() * (BOOST_FACTOR - 1.0);
+            if boost_end <= now {
+                *until = None;
+            }
+        }
+        &self.offsets
+    }
+
+    /// Applies the current flash to `palette`. Returns `false` if there is
+    /// none, in which case `output` is left alone.
+    pub fn apply_flash(&mut self, palette: &Palette, now: Instant, output: &mut Palette) -> bool {
+        let Some((flash, start)) = self.flash else {
+            return false;
+        };
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= FLASH_DURATION {
+            self.flash = None;
+            return false;
+        }
+
+        // quick attack, slow release
+        let strength = 1.0 - elapsed.as_secs_f64() / FLASH_DURATION.as_secs_f64();
+        let strength = strength * strength;
+        for (output, &color) in output.0.iter_mut().zip(palette.0.iter()) {
+            *output = blend_linear(color, flash.target(color), strength);
+        }
+        true
+    }
+}
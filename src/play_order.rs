@@ -0,0 +1,94 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The order in which N, P, and `--slideshow` go through the playlist,
+//! which can be shuffled.
+
+use crate::rng::Rng;
+
+/// Indices into the playlist in the order they are played.
+#[derive(Debug, Clone, Default)]
+pub struct PlayOrder {
+    order: Vec<usize>,
+    shuffled: bool,
+}
+
+impl PlayOrder {
+    /// The playlist order of `len` files.
+    pub fn new(len: usize) -> Self {
+        Self { order: (0..len).collect(), shuffled: false }
+    }
+
+    #[inline]
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffled
+    }
+
+    /// Plays the files in random order, starting with `current`. Every file
+    /// is played once before the order is shuffled again.
+    pub fn shuffle(&mut self, current: usize, rng: &mut Rng) {
+        self.order.retain(|&index| index != current);
+        rng.shuffle(&mut self.order);
+        self.order.insert(0, current);
+        self.shuffled = true;
+    }
+
+    /// Back to the playlist order.
+    pub fn unshuffle(&mut self) {
+        self.order.sort_unstable();
+        self.shuffled = false;
+    }
+
+    /// Adds the file at `index` that was added to the end of the playlist.
+    /// When shuffled it is played right after `current`.
+    pub fn push(&mut self, index: usize, current: usize) {
+        match self.position(current) {
+            Some(position) if self.shuffled => self.order.insert(position + 1, index),
+            _ => self.order.push(index),
+        }
+    }
+
+    /// The file `steps` files after `index`, or before it if `steps` is
+    /// negative. `None` past the first or last file.
+    pub fn step(&self, index: usize, steps: isize) -> Option<usize> {
+        let position = self.position(index)?.checked_add_signed(steps)?;
+        self.order.get(position).copied()
+    }
+
+    /// The file after `index`, starting over after the last file. A
+    /// shuffled order is shuffled again then, without playing `index` twice
+    /// in a row.
+    pub fn next_wrapping(&mut self, index: usize, rng: &mut Rng) -> usize {
+        if let Some(next) = self.step(index, 1) {
+            return next;
+        }
+
+        if self.shuffled && self.order.len() > 1 {
+            rng.shuffle(&mut self.order);
+            if self.order[0] == index {
+                let last = self.order.len() - 1;
+                self.order.swap(0, 1 + rng.below(last as u64) as usize);
+            }
+        }
+        self.order.first().copied().unwrap_or(index)
+    }
+
+    #[inline]
+    fn position(&self, index: usize) -> Option<usize> {
+        self.order.iter().position(|&other| other == index)
+    }
+}
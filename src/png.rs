@@ -0,0 +1,130 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Minimal writer of indexed (palette) PNG files. The pixel data is stored
+//! without compression, which keeps the writer small and the palette order
+//! exactly as given.
+
+use std::io::Write;
+
+use crate::image::IndexedImage;
+use crate::palette::Palette;
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1A\n";
+const COLOR_TYPE_INDEXED: u8 = 3;
+
+/// Biggest amount of data a stored (uncompressed) deflate block can hold.
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+/// Writes `image` with all 256 colors of `palette` in order, so the pixel
+/// values of the PNG are the palette indices of the image.
+pub fn write_indexed_png(out: &mut impl Write, image: &IndexedImage, palette: &Palette) -> std::io::Result<()> {
+    let width = image.width();
+    let height = image.height();
+    out.write_all(SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // bit depth, color type, compression, filter, interlace
+    header.extend_from_slice(&[8, COLOR_TYPE_INDEXED, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+
+    let colors: Vec<u8> = palette.0.iter().flat_map(|color| color.0).collect();
+    write_chunk(out, b"PLTE", &colors)?;
+
+    // every row starts with its filter type, 0 meaning none
+    let mut raw = Vec::with_capacity((width as usize + 1) * height as usize);
+    for row in image.data().chunks_exact(width.max(1) as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(crc32(!0, kind), data);
+    out.write_all(&(!crc).to_be_bytes())
+}
+
+/// Wraps `data` into a zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let block_count = data.len().div_ceil(MAX_STORED_BLOCK).max(1);
+    let mut out = Vec::with_capacity(data.len() + block_count * 5 + 6);
+    // deflate with a 32K window, no preset dictionary, fastest compression
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 != 0 { 0xEDB8_8320 ^ (value >> 1) } else { value >> 1 };
+            bit += 1;
+        }
+        table[index] = value;
+        index += 1;
+    }
+    table
+};
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let mut a = 1u32;
+    let mut b = 0u32;
+    // 5552 is the most bytes that can be summed up before b could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,118 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Minimal span profiler writing the Chrome trace event format, which can
+//! be opened in `chrome://tracing`, [Perfetto](https://ui.perfetto.dev/) or
+//! [Speedscope](https://www.speedscope.app/).
+//!
+//! Only compiled with the `profile` feature. Use `profile_scope!()` to mark
+//! spans, it expands to nothing without the feature.
+
+use std::cell::Cell;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+struct Event {
+    name: &'static str,
+    tid: u32,
+    start: u64,
+    duration: u64,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+static NEXT_TID: AtomicU32 = AtomicU32::new(1);
+
+thread_local! {
+    static TID: Cell<u32> = const { Cell::new(0) };
+}
+
+fn thread_id() -> u32 {
+    TID.with(|tid| {
+        if tid.get() == 0 {
+            tid.set(NEXT_TID.fetch_add(1, Ordering::Relaxed));
+        }
+        tid.get()
+    })
+}
+
+/// Starts recording spans.
+pub fn start() {
+    EPOCH.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stops recording and writes all recorded spans to `path`.
+pub fn finish(path: &Path) -> std::io::Result<()> {
+    ENABLED.store(false, Ordering::Relaxed);
+
+    let events = std::mem::take(&mut *EVENTS.lock().unwrap_or_else(|err| err.into_inner()));
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+
+    writer.write_all(b"{\"traceEvents\":[")?;
+    for (index, event) in events.iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        write!(writer,
+            "{{\"name\":{:?},\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{},\"dur\":{}}}",
+            event.name, event.tid, event.start, event.duration)?;
+    }
+    writer.write_all(b"],\"displayTimeUnit\":\"ms\"}\n")?;
+    writer.flush()
+}
+
+/// Records the time from its creation until it is dropped.
+pub struct Span {
+    name: &'static str,
+    start: Option<Instant>,
+}
+
+impl Span {
+    #[inline]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            start: ENABLED.load(Ordering::Relaxed).then(Instant::now),
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(start) = self.start else {
+            return;
+        };
+        let Some(epoch) = EPOCH.get() else {
+            return;
+        };
+
+        let event = Event {
+            name: self.name,
+            tid: thread_id(),
+            start: start.duration_since(*epoch).as_micros() as u64,
+            duration: start.elapsed().as_micros() as u64,
+        };
+
+        if let Ok(mut events) = EVENTS.lock() {
+            events.push(event);
+        }
+    }
+}
@@ -14,9 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{color::Rgb, image::{living_world::TimedEvent, CycleImage, IndexedImage, LivingWorld}, palette::{Cycle, Palette}};
+use crate::{image::{check_dimensions, living_world::TimedEvent, CameraKeyframe, sprite::PathPoint, CycleImage, IndexedImage, Layer, LivingWorld, Sprite, Weather}, palette::{Cycle, Palette}};
 
-use std::{collections::HashMap, convert::TryInto};
+use std::collections::HashMap;
 use serde::{de::{Error, IgnoredAny, Visitor}, Deserializer, Deserialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
@@ -44,7 +44,48 @@ pub struct MagratheaWorldData {
     pub pixels: Box<[u8]>,
 
     // TODO: pub events: Vec<MagratheaWorldEvent>,
-    // TODO: pub modes: Vec<MagratheaWorldMode>,
+    // The modes of Living Worlds Maker scenes aren't supported, the field
+    // is skipped like any other unknown one.
+}
+
+/// A palette of a Living Worlds timeline. Only its colors and cycles are
+/// used, so the pixels may be left out.
+#[derive(Debug, Clone, Deserialize)]
+struct TimelinePalette {
+    filename: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    colors: Palette,
+    cycles: Box<[Cycle]>,
+    pixels: Option<Box<[u8]>>,
+}
+
+impl TimelinePalette {
+    /// Without pixels of its own the palette gets those of `base`.
+    fn into_cycle_image<E: Error>(self, base: &CycleImage) -> Result<CycleImage, E> {
+        let indexed_image = if let Some(pixels) = self.pixels {
+            let Some(width) = self.width else {
+                return Err(Error::missing_field("width"));
+            };
+
+            let Some(height) = self.height else {
+                return Err(Error::missing_field("height"));
+            };
+
+            check_dimensions(width, height).map_err(Error::custom)?;
+
+            let Some(indexed_image) = IndexedImage::from_buffer(width, height, pixels, self.colors) else {
+                return Err(Error::custom("image buffer is too small for given width/height"));
+            };
+            indexed_image
+        } else {
+            let mut indexed_image = base.indexed_image().clone();
+            *indexed_image.palette_mut() = self.colors;
+            indexed_image
+        };
+
+        Ok(CycleImage::new(self.filename, indexed_image, self.cycles))
+    }
 }
 
 struct CycleImageVisitor;
@@ -111,6 +152,8 @@ impl<'de> Visitor<'de> for CycleImageVisitor {
             return Err(Error::missing_field("pixels"));
         };
 
+        check_dimensions(width, height).map_err(Error::custom)?;
+
         let Some(indexed_image) = IndexedImage::from_buffer(width, height, image, palette) else {
             return Err(Error::custom("image buffer is too small for given width/height"));
         };
@@ -130,6 +173,9 @@ impl<'de> serde::de::Deserialize<'de> for CycleImage {
 #[derive(Debug)]
 struct Timeline(pub Vec<(u32, String)>);
 
+/// Times of day in timelines are in seconds since midnight.
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
 struct TimelineVisitor;
 
 impl<'de> Visitor<'de> for TimelineVisitor {
@@ -151,7 +197,7 @@ impl<'de> Visitor<'de> for TimelineVisitor {
             timeline.push(item);
         }
 
-        timeline.sort_by(|a, b| a.0.cmp(&b.0));
+        timeline.sort_by_key(|a| a.0);
 
         Ok(Timeline(timeline))
     }
@@ -173,7 +219,7 @@ impl<'de> Visitor<'de> for TimelineVisitor {
             timeline.push((time_of_day, name));
         }
 
-        timeline.sort_by(|a, b| a.0.cmp(&b.0));
+        timeline.sort_by_key(|a| a.0);
 
         Ok(Timeline(timeline))
     }
@@ -207,8 +253,12 @@ impl<'de> Visitor<'de> for LivingWorldVisitor {
         let mut format: Option<FormatInfo> = None;
         let mut data: Option<MagratheaWorldData> = None;
         let mut base: Option<CycleImage> = None;
-        let mut palettes_map: Option<HashMap<String, CycleImage>> = None;
+        let mut palettes_map: Option<HashMap<String, TimelinePalette>> = None;
         let mut named_timeline: Option<Timeline> = None;
+        let mut layers: Box<[Layer]> = Box::new([]);
+        let mut sprites: Box<[Sprite]> = Box::new([]);
+        let mut camera: Vec<CameraKeyframe> = Vec::new();
+        let mut weather: Option<Weather> = None;
 
         while let Some(key) = map.next_key::<String>()? {
             match key.as_str() {
@@ -242,6 +292,19 @@ impl<'de> Visitor<'de> for LivingWorldVisitor {
                 "timeline" => {
                     named_timeline = Some(map.next_value()?);
                 }
+                "layers" => {
+                    layers = map.next_value()?;
+                }
+                "sprites" => {
+                    sprites = map.next_value()?;
+                }
+                "camera" => {
+                    camera = map.next_value()?;
+                }
+                "weather" => {
+                    let value: Option<String> = map.next_value()?;
+                    weather = value.map(|value| value.parse()).transpose().map_err(Error::custom)?;
+                }
                 "filename" => {
                     filename = Some(map.next_value()?);
                 }
@@ -251,15 +314,18 @@ impl<'de> Visitor<'de> for LivingWorldVisitor {
             }
         }
 
+        camera.sort_by_key(|keyframe| keyframe.time_of_day());
+        let camera = camera.into_boxed_slice();
+
         if let Some(base) = base {
             let palettes_len: usize = if let Some(palettes) = &palettes_map { palettes.len() } else { 0 };
 
             let mut palettes = Vec::with_capacity(palettes_len);
             let mut index_map = HashMap::with_capacity(palettes_len);
             if let Some(palettes_map) = palettes_map {
-                for (index, (key, image)) in palettes_map.into_iter().enumerate() {
+                for (index, (key, palette)) in palettes_map.into_iter().enumerate() {
                     index_map.insert(key, index);
-                    palettes.push(image);
+                    palettes.push(palette.into_cycle_image(&base)?);
                 }
             }
 
@@ -268,18 +334,20 @@ impl<'de> Visitor<'de> for LivingWorldVisitor {
             if let Some(Timeline(named_timeline)) = named_timeline {
                 for (time_of_day, palette_name) in named_timeline {
                     if let Some(palette_index) = index_map.get(&palette_name) {
-                        timeline.push(TimedEvent::new(time_of_day, *palette_index));
+                        // 86400 seconds is the midnight at the end of the day
+                        timeline.push(TimedEvent::new(time_of_day % SECONDS_PER_DAY, *palette_index));
                     } else {
                         return Err(Error::custom(format_args!("missing palette name referenced in timeline: {:?}", palette_name)));
                     }
                 }
             }
+            timeline.sort_by_key(TimedEvent::time_of_day);
 
             return Ok(LivingWorld::new(
                 base.filename().map(|name| name.to_owned()),
                 base,
-                palettes.into_boxed_slice(), timeline.into_boxed_slice()),
-            );
+                palettes.into_boxed_slice(), timeline.into_boxed_slice(),
+            ).with_layers(layers).with_sprites(sprites).with_camera(camera).with_weather(weather));
         }
 
         if let Some(format) = format {
@@ -295,6 +363,8 @@ impl<'de> Visitor<'de> for LivingWorldVisitor {
                 return Err(Error::custom("need at least one palette definition"));
             };
 
+            check_dimensions(data.width, data.height).map_err(Error::custom)?;
+
             let Some(indexed_image) = IndexedImage::from_buffer(data.width, data.height, data.pixels, palette_info.colors) else {
                 return Err(Error::custom("image buffer is too small for given width/height"));
             };
@@ -322,11 +392,13 @@ impl<'de> Visitor<'de> for LivingWorldVisitor {
             return Err(Error::missing_field("pixels"));
         };
 
+        check_dimensions(width, height).map_err(Error::custom)?;
+
         let Some(indexed_image) = IndexedImage::from_buffer(width, height, image, palette) else {
             return Err(Error::custom("image buffer is too small for given width/height"));
         };
 
-        Ok(CycleImage::new(filename, indexed_image, cycles).into())
+        Ok(LivingWorld::from(CycleImage::new(filename, indexed_image, cycles)).with_layers(layers).with_sprites(sprites).with_camera(camera).with_weather(weather))
     }
 }
 
@@ -338,117 +410,265 @@ impl<'de> serde::de::Deserialize<'de> for LivingWorld {
     }
 }
 
-struct RgbVisitor;
+struct CameraKeyframeVisitor;
 
-impl<'de> Visitor<'de> for RgbVisitor {
-    type Value = Rgb;
+impl<'de> Visitor<'de> for CameraKeyframeVisitor {
+    type Value = CameraKeyframe;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("RGB value as list of 3 numbers, each in the range of 0 to 255")
+        formatter.write_str("a camera keyframe")
     }
 
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where A: serde::de::SeqAccess<'de>, {
-        let Some(r) = seq.next_element()? else {
-            return Err(Error::missing_field("r"));
-        };
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: serde::de::MapAccess<'de>, {
+        let mut time_of_day = None;
+        let mut x = None;
+        let mut y = None;
 
-        let Some(g) = seq.next_element()? else {
-            return Err(Error::missing_field("g"));
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "time" => {
+                    time_of_day = Some(map.next_value()?);
+                }
+                "x" => {
+                    x = Some(map.next_value()?);
+                }
+                "y" => {
+                    y = Some(map.next_value()?);
+                }
+                _ => {
+                    let _ = map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let Some(time_of_day) = time_of_day else {
+            return Err(Error::missing_field("time"));
         };
 
-        let Some(b) = seq.next_element()? else {
-            return Err(Error::missing_field("b"));
+        let Some(x) = x else {
+            return Err(Error::missing_field("x"));
         };
 
-        if seq.next_element::<IgnoredAny>()?.is_some() {
-            return Err(Error::custom("superfluous elements in RGB value"));
+        let Some(y) = y else {
+            return Err(Error::missing_field("y"));
         };
 
-        Ok(Rgb([r, g, b]))
+        if time_of_day >= 24 * 60 * 60 {
+            return Err(Error::custom(format_args!("illegal time of day in camera path: {time_of_day}")));
+        }
+
+        Ok(CameraKeyframe::new(time_of_day, x, y))
     }
 }
 
-impl<'de> serde::de::Deserialize<'de> for Rgb {
+impl<'de> serde::de::Deserialize<'de> for CameraKeyframe {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: Deserializer<'de> {
-        deserializer.deserialize_seq(RgbVisitor)
+        deserializer.deserialize_map(CameraKeyframeVisitor)
     }
 }
 
-struct PaletteVisitor;
+struct LayerVisitor;
 
-impl<'de> Visitor<'de> for PaletteVisitor {
-    type Value = Palette;
+impl<'de> Visitor<'de> for LayerVisitor {
+    type Value = Layer;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("a list of 256 RGB values")
+        formatter.write_str("a layer definition")
     }
 
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where A: serde::de::SeqAccess<'de>, {
-        let mut colors = Vec::with_capacity(256);
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: serde::de::MapAccess<'de>, {
+        let mut width = None;
+        let mut height = None;
+        let mut pixels = None;
+        let mut x = 0;
+        let mut y = 0;
+        let mut scroll = 1.0;
+        let mut transparent = None;
 
-        while let Some(rgb) = seq.next_element()? {
-            colors.push(rgb);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "width" => {
+                    width = Some(map.next_value()?);
+                }
+                "height" => {
+                    height = Some(map.next_value()?);
+                }
+                "pixels" => {
+                    pixels = Some(map.next_value()?);
+                }
+                "x" => {
+                    x = map.next_value()?;
+                }
+                "y" => {
+                    y = map.next_value()?;
+                }
+                "scroll" => {
+                    scroll = map.next_value()?;
+                }
+                "transparent" => {
+                    transparent = map.next_value()?;
+                }
+                _ => {
+                    let _ = map.next_value::<IgnoredAny>()?;
+                }
+            }
         }
 
-        let colors: Box<[Rgb; 256]> = match colors.try_into() {
-            Ok(colors) => colors,
-            Err(_) => return Err(Error::custom("the color palette needs to have exactly 256 color values"))
+        let Some(width) = width else {
+            return Err(Error::missing_field("width"));
         };
 
-        Ok(Palette(colors))
+        let Some(height) = height else {
+            return Err(Error::missing_field("height"));
+        };
+
+        let Some(pixels) = pixels else {
+            return Err(Error::missing_field("pixels"));
+        };
+
+        check_dimensions(width, height).map_err(Error::custom)?;
+
+        let Some(layer) = Layer::new(width, height, pixels, x, y, scroll, transparent) else {
+            return Err(Error::custom("layer buffer is too small for given width/height"));
+        };
+
+        Ok(layer)
     }
 }
 
-impl<'de> serde::de::Deserialize<'de> for Palette {
+impl<'de> serde::de::Deserialize<'de> for Layer {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: Deserializer<'de> {
-        deserializer.deserialize_seq(PaletteVisitor)
+        deserializer.deserialize_map(LayerVisitor)
     }
 }
 
-struct CycleVisitor;
+struct SpriteVisitor;
 
-impl<'de> Visitor<'de> for CycleVisitor {
-    type Value = Cycle;
+impl<'de> Visitor<'de> for SpriteVisitor {
+    type Value = Sprite;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("a color cycle definition")
+        formatter.write_str("a sprite definition")
     }
 
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where A: serde::de::MapAccess<'de>, {
-        let mut reverse = false;
-        let mut rate = 0;
-        let mut low = None;
-        let mut high = None;
+        let mut width = None;
+        let mut height = None;
+        let mut frames: Option<Vec<Box<[u8]>>> = None;
+        let mut frame_duration = 0;
+        let mut transparent = None;
+        let mut path: Option<Box<[PathPoint]>> = None;
+        let mut x = 0;
+        let mut y = 0;
 
         while let Some(key) = map.next_key::<String>()? {
             match key.as_str() {
-                "reverse" => {
-                    let value: i32 = map.next_value()?;
-                    if value == 0 {
-                        reverse = false;
-                    } else if value == 2 {
-                        reverse = true;
-                    } else {
-                        return Err(Error::invalid_value(
-                            serde::de::Unexpected::Signed(value as i64),
-                            &"0 or 2"));
-                    }
+                "width" => {
+                    width = Some(map.next_value()?);
+                }
+                "height" => {
+                    height = Some(map.next_value()?);
                 }
-                "rate" => {
-                    rate = map.next_value()?;
+                "frames" => {
+                    frames = Some(map.next_value()?);
+                }
+                "frameDuration" => {
+                    frame_duration = map.next_value()?;
+                }
+                "transparent" => {
+                    transparent = map.next_value()?;
+                }
+                "path" => {
+                    path = Some(map.next_value()?);
+                }
+                "x" => {
+                    x = map.next_value()?;
+                }
+                "y" => {
+                    y = map.next_value()?;
+                }
+                _ => {
+                    let _ = map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let Some(width) = width else {
+            return Err(Error::missing_field("width"));
+        };
+
+        let Some(height) = height else {
+            return Err(Error::missing_field("height"));
+        };
+
+        let Some(frames) = frames else {
+            return Err(Error::missing_field("frames"));
+        };
+
+        let frame_size = check_dimensions(width, height).map_err(Error::custom)?;
+        if frames.is_empty() {
+            return Err(Error::custom("a sprite needs at least one frame"));
+        }
+
+        let mut pixels = Vec::with_capacity(frame_size * frames.len());
+        for frame in &frames {
+            if frame.len() < frame_size {
+                return Err(Error::custom("sprite frame is too small for given width/height"));
+            }
+            pixels.extend_from_slice(&frame[..frame_size]);
+        }
+
+        // a sprite without a path stays at x/y
+        let path = path.unwrap_or_else(|| Box::new([PathPoint::new(0, x, y)]));
+
+        let Some(sprite) = Sprite::new(width, height, pixels.into(), frame_duration, transparent, path) else {
+            return Err(Error::custom("sprite path needs at least one point and has to be sorted by time"));
+        };
+
+        Ok(sprite)
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for Sprite {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        deserializer.deserialize_map(SpriteVisitor)
+    }
+}
+
+struct PathPointVisitor;
+
+impl<'de> Visitor<'de> for PathPointVisitor {
+    type Value = PathPoint;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a sprite path point")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: serde::de::MapAccess<'de>, {
+        let mut time = None;
+        let mut x = None;
+        let mut y = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "time" => {
+                    time = Some(map.next_value()?);
                 }
-                "low" => {
-                    low = Some(map.next_value()?);
+                "x" => {
+                    x = Some(map.next_value()?);
                 }
-                "high" => {
-                    high = Some(map.next_value()?);
+                "y" => {
+                    y = Some(map.next_value()?);
                 }
                 _ => {
                     let _ = map.next_value::<IgnoredAny>()?;
@@ -456,22 +676,26 @@ impl<'de> Visitor<'de> for CycleVisitor {
             }
         }
 
-        let Some(low) = low else {
-            return Err(Error::missing_field("low"));
+        let Some(time) = time else {
+            return Err(Error::missing_field("time"));
+        };
+
+        let Some(x) = x else {
+            return Err(Error::missing_field("x"));
         };
 
-        let Some(high) = high else {
-            return Err(Error::missing_field("high"));
+        let Some(y) = y else {
+            return Err(Error::missing_field("y"));
         };
 
-        Ok(Cycle::new(low, high, rate, reverse))
+        Ok(PathPoint::new(time, x, y))
     }
 }
 
-impl<'de> serde::de::Deserialize<'de> for Cycle {
+impl<'de> serde::de::Deserialize<'de> for PathPoint {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: Deserializer<'de> {
-        deserializer.deserialize_map(CycleVisitor)
+        deserializer.deserialize_map(PathPointVisitor)
     }
 }
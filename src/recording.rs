@@ -0,0 +1,118 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Timing files for recordings made with `--stream-ansi`, so that
+//! `color-cycle play-ansi` can play them back at the right speed.
+//!
+//! Like the timing files of `script -t`, there is a line `DELAY BYTES` for
+//! every chunk of output: the seconds since the previous chunk and the
+//! length of the chunk in bytes. The recording itself stays a plain stream
+//! of ANSI escape sequences that can still be shown with `cat`.
+
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Longest a single sleep of the player takes, so that it notices Ctrl+C.
+const MAX_SLEEP: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub struct TimingWriter {
+    out: BufWriter<File>,
+    last: Instant,
+}
+
+impl TimingWriter {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        Ok(Self {
+            out: BufWriter::new(File::create(path)?),
+            last: Instant::now(),
+        })
+    }
+
+    /// Records that a chunk of `len` bytes was written just now.
+    pub fn record(&mut self, len: usize) -> std::io::Result<()> {
+        let now = Instant::now();
+        writeln!(self.out, "{:.6} {}", (now - self.last).as_secs_f64(), len)?;
+        self.last = now;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// The timing file that goes with `recording` if none is given:
+/// `recording.timing`.
+pub fn default_timing_path(recording: &Path) -> PathBuf {
+    let mut path = recording.as_os_str().to_owned();
+    path.push(".timing");
+    path.into()
+}
+
+/// Writes the chunks of `recording` to `out` with the delays of `timing`,
+/// sped up by `speed`. Bytes after the last chunk are written at the end.
+pub fn play(recording: &mut impl Read, timing: impl BufRead, out: &mut impl Write, speed: f64, running: &AtomicBool) -> std::io::Result<()> {
+    let mut chunk = Vec::new();
+    let mut due = Instant::now();
+
+    for (index, line) in timing.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = line.split_once(' ').and_then(|(delay, len)| {
+            let delay: f64 = delay.parse().ok()?;
+            let len: usize = len.trim().parse().ok()?;
+            (delay.is_finite() && delay >= 0.0).then_some((delay, len))
+        });
+        let Some((delay, len)) = parsed else {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("invalid line {} of the timing file: {line:?}", index + 1)));
+        };
+
+        due += Duration::from_secs_f64(delay / speed);
+        loop {
+            if !running.load(Ordering::Relaxed) {
+                // the recording was cut off, so undo what it might have set
+                // CSI 0 m        Reset or normal, all attributes become turned off
+                // CSI ? 25 h     Show cursor (DECTCEM), VT220
+                writeln!(out, "\x1B[0m\x1B[?25h")?;
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= due {
+                break;
+            }
+            std::thread::sleep((due - now).min(MAX_SLEEP));
+        }
+
+        chunk.resize(len, 0);
+        recording.read_exact(&mut chunk)?;
+        out.write_all(&chunk)?;
+        out.flush()?;
+    }
+
+    std::io::copy(recording, out)?;
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,378 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A demo reel: a title card and a few seconds of each file at an
+//! interesting time of day with a slow pan, joined by crossfades.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::animator::Animator;
+use crate::clock::CycleClock;
+use crate::color::{blend, Rgb};
+use crate::error::Error;
+use crate::font;
+use crate::frame_cache::{self, FrameCache, SourceCache};
+use crate::image_to_ansi::simple_image_to_ansi_into;
+use crate::image::{IndexedImage, LivingWorld, RgbImage, Viewport};
+use crate::pacing::FramePacer;
+use crate::palette::{BlendCurve, Palette};
+
+const TITLE_COLOR: Rgb = Rgb([255, 255, 255]);
+const BACKGROUND: Rgb = Rgb([0, 0, 0]);
+
+/// Share of the frame width a title may take up at most.
+const TITLE_MAX_WIDTH: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReelOptions {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    /// seconds each file is shown
+    pub scene_duration: f64,
+    /// seconds each title card is shown
+    pub title_duration: f64,
+    /// seconds two segments overlap while fading from one to the other
+    pub crossfade: f64,
+    pub blend: bool,
+    pub blend_curve: BlendCurve,
+}
+
+struct Scene {
+    living_world: LivingWorld,
+    animator: Animator,
+    time_of_day: u64,
+    blend: bool,
+    view: Viewport,
+    pan_from: (u32, u32),
+    pan_to: (u32, u32),
+    crop: IndexedImage,
+    sprite_buf: IndexedImage,
+    frame: RgbImage,
+    cache: Option<SourceCache>,
+}
+
+enum Content {
+    Title(String),
+    Scene(Box<Scene>),
+}
+
+struct Segment {
+    content: Content,
+    /// seconds since the start of the reel
+    start: f64,
+    duration: f64,
+}
+
+pub struct Reel {
+    options: ReelOptions,
+    segments: Vec<Segment>,
+    other_frame: RgbImage,
+    frame_cache: Option<FrameCache>,
+}
+
+impl Reel {
+    pub fn new(options: ReelOptions) -> Self {
+        Self {
+            options,
+            segments: Vec::new(),
+            other_frame: RgbImage::new(options.width, options.height),
+            frame_cache: None,
+        }
+    }
+
+    /// Caches the rendered frames of the scenes added after this on disk.
+    #[inline]
+    pub fn set_frame_cache(&mut self, frame_cache: Option<FrameCache>) {
+        self.frame_cache = frame_cache;
+    }
+
+    fn push(&mut self, content: Content, duration: f64) {
+        let start = match self.segments.last() {
+            Some(last) => last.start + last.duration - self.options.crossfade,
+            None => 0.0,
+        };
+        self.segments.push(Segment { content, start, duration });
+    }
+
+    pub fn add_title(&mut self, title: impl Into<String>) {
+        self.push(Content::Title(title.into()), self.options.title_duration);
+    }
+
+    /// Adds `living_world` at [`interesting_time_of_day`], panning along the
+    /// axis the image is larger than the frame. `path` is the file it was
+    /// loaded from, if any, for the frame cache.
+    pub fn add_scene(&mut self, living_world: LivingWorld, path: Option<&Path>) {
+        let (image_width, image_height) = living_world.base().size();
+        let view = Viewport::new(image_width, image_height, self.options.width, self.options.height);
+        let (pan_from, pan_to) = if view.max_x() >= view.max_y() {
+            ((0, view.y()), (view.max_x(), view.y()))
+        } else {
+            ((view.x(), 0), (view.x(), view.max_y()))
+        };
+
+        let mut animator = Animator::new(&living_world);
+        animator.set_blend_curve(self.options.blend_curve);
+
+        let scene = Scene {
+            animator,
+            time_of_day: interesting_time_of_day(&living_world),
+            blend: self.options.blend,
+            living_world,
+            view,
+            pan_from,
+            pan_to,
+            crop: IndexedImage::new(0, 0, Palette::default()),
+            sprite_buf: IndexedImage::new(0, 0, Palette::default()),
+            frame: RgbImage::new(0, 0),
+            cache: path.zip(self.frame_cache.as_mut()).and_then(|(path, frame_cache)| frame_cache.source(path)),
+        };
+        self.push(Content::Scene(Box::new(scene)), self.options.scene_duration);
+    }
+
+    /// Seconds from the start of the first to the end of the last segment.
+    pub fn duration(&self) -> f64 {
+        self.segments.last().map_or(0.0, |last| last.start + last.duration)
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        (self.duration() * self.options.fps as f64).ceil() as u64
+    }
+
+    /// Renders frame number `index` into `out`, which has to be of the size
+    /// of the reel.
+    pub fn render(&mut self, index: u64, out: &mut RgbImage) {
+        let time = index as f64 / self.options.fps as f64;
+        let Some(current) = self.segments.iter().rposition(|segment| segment.start <= time) else {
+            out.fill(BACKGROUND);
+            return;
+        };
+
+        let crossfade = self.options.crossfade;
+        let start = self.segments[current].start;
+        render_segment(&mut self.segments[current], time - start, self.frame_cache.as_mut(), out);
+
+        // fade in from the previous segment while both overlap
+        if current > 0 && crossfade > 0.0 {
+            let previous = &mut self.segments[current - 1];
+            let previous_end = previous.start + previous.duration;
+            if time < previous_end {
+                let start = previous.start;
+                render_segment(previous, time - start, self.frame_cache.as_mut(), &mut self.other_frame);
+                let mid = ((previous_end - time) / crossfade).clamp(0.0, 1.0);
+                crossfade_into(out, &self.other_frame, mid);
+            }
+        }
+    }
+}
+
+fn render_segment(segment: &mut Segment, time: f64, frame_cache: Option<&mut FrameCache>, out: &mut RgbImage) {
+    out.fill(BACKGROUND);
+    match &mut segment.content {
+        Content::Title(title) => render_title(title, out),
+        Content::Scene(scene) => scene.render(time / segment.duration, time, frame_cache, out),
+    }
+}
+
+/// Draws `title` centered, as big as fits.
+fn render_title(title: &str, out: &mut RgbImage) {
+    let unscaled = font::text_width(title, 1).max(1);
+    let max_width = (out.width() as f64 * TITLE_MAX_WIDTH) as u32;
+    let scale = (max_width / unscaled).clamp(1, (out.height() / 4 / font::GLYPH_HEIGHT).max(1));
+    let width = font::text_width(title, scale) as i64;
+    let height = (font::GLYPH_HEIGHT * scale) as i64;
+    let x = (out.width() as i64 - width) / 2;
+    let y = (out.height() as i64 - height) / 2;
+    font::draw_text(out, x, y, title, scale, TITLE_COLOR);
+}
+
+/// Blends `other` over `out`, `mid` being the share of `other`.
+fn crossfade_into(out: &mut RgbImage, other: &RgbImage, mid: f64) {
+    for y in 0..out.height() {
+        for x in 0..out.width() {
+            let color = blend(out.get_pixel(x, y), other.get_pixel(x, y), mid);
+            out.set_pixel(x, y, color);
+        }
+    }
+}
+
+impl Scene {
+    /// `progress` goes from 0 to 1 over the duration of the scene, `time` is
+    /// in seconds since its start.
+    fn render(&mut self, progress: f64, time: f64, frame_cache: Option<&mut FrameCache>, out: &mut RgbImage) {
+        // ease in and out
+        let progress = progress.clamp(0.0, 1.0);
+        let progress = progress * progress * (3.0 - 2.0 * progress);
+        let lerp = |from: u32, to: u32| (from as f64 + (to as f64 - from as f64) * progress).round() as u32;
+        self.view.set_position(lerp(self.pan_from.0, self.pan_to.0), lerp(self.pan_from.1, self.pan_to.1));
+
+        let (width, height) = self.view.visible_size();
+        let clock = CycleClock::from_secs(time, self.time_of_day);
+        match (self.cache.take(), frame_cache) {
+            (Some(source), Some(frame_cache)) => {
+                let key = self.cache_key(clock);
+                if !frame_cache.load(&source, key, &mut self.frame) || self.frame.size() != (width, height) {
+                    self.render_frame(clock);
+                    frame_cache.store(&source, key, &self.frame);
+                }
+                self.cache = Some(source);
+            }
+            (source, _) => {
+                self.cache = source;
+                self.render_frame(clock);
+            }
+        }
+
+        // center images smaller than the reel
+        let offset_x = out.width().saturating_sub(width) / 2;
+        let offset_y = out.height().saturating_sub(height) / 2;
+        for frame_y in 0..height {
+            for frame_x in 0..width {
+                out.set_pixel(offset_x + frame_x, offset_y + frame_y, self.frame.get_pixel(frame_x, frame_y));
+            }
+        }
+    }
+
+    fn render_frame(&mut self, clock: CycleClock) {
+        let (x, y) = self.view.position();
+        let (width, height) = self.view.visible_size();
+        self.crop.get_rect_from(x, y, width, height, self.living_world.base().indexed_image());
+        let composed = self.animator.compose(&self.living_world, &self.crop, x, y, clock, &mut self.sprite_buf);
+        let palette = self.animator.palette(&self.living_world, clock, self.blend);
+
+        if self.frame.size() != (width, height) {
+            self.frame = RgbImage::new(width, height);
+        }
+        composed.apply_with_palette(&mut self.frame, palette);
+    }
+
+    /// Everything the frame at `clock` depends on besides the source file:
+    /// the visible part of the image, the time of day, and the cycle time.
+    fn cache_key(&self, clock: CycleClock) -> u64 {
+        let (x, y) = self.view.position();
+        let (width, height) = self.view.visible_size();
+
+        let mut bytes = Vec::with_capacity(42);
+        for value in [x, y, width, height] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&clock.time_of_day.to_le_bytes());
+        bytes.extend_from_slice(&clock.elapsed.to_le_bytes());
+        bytes.extend_from_slice(&self.animator.phase().to_le_bytes());
+        bytes.push(self.blend as u8);
+        bytes.push(self.animator.blend_curve() as u8);
+        frame_cache::hash(&bytes)
+    }
+}
+
+/// The time of day of the most colorful palette of the timeline, or
+/// midnight if there is no timeline.
+pub fn interesting_time_of_day(living_world: &LivingWorld) -> u64 {
+    let colorfulness = |palette: &Palette| -> u32 {
+        palette.0.iter()
+            .map(|color| {
+                let max = color.0.iter().max().copied().unwrap_or(0);
+                let min = color.0.iter().min().copied().unwrap_or(0);
+                (max - min) as u32
+            })
+            .sum()
+    };
+
+    living_world.timeline().iter()
+        .max_by_key(|event| {
+            living_world.palettes().get(event.palette_index())
+                .map_or(0, |image| colorfulness(image.palette()))
+        })
+        .map_or(0, |event| event.time_of_day() as u64 * 1000)
+}
+
+/// Encodes the reel into a video file at `path` by piping the frames to
+/// ffmpeg, which picks the format by the file extension.
+pub fn encode(reel: &mut Reel, path: &std::path::Path, running: &AtomicBool) -> Result<(), Error> {
+    let ReelOptions { width, height, fps, .. } = reel.options;
+    let mut child = Command::new("ffmpeg")
+        .args(["-loglevel", "error", "-y", "-f", "rawvideo", "-pix_fmt", "rgb24"])
+        .args(["-s", &format!("{width}x{height}"), "-r", &fps.to_string(), "-i", "-"])
+        .args(["-pix_fmt", "yuv420p"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| Error::with_cause("starting ffmpeg", Box::new(err)))?;
+
+    let mut frame = RgbImage::new(width, height);
+    let mut bytes = Vec::with_capacity(width as usize * height as usize * 3);
+    let res = (|| {
+        let Some(stdin) = &mut child.stdin else {
+            return Ok(());
+        };
+        for index in 0..reel.frame_count() {
+            if !running.load(Ordering::Relaxed) {
+                break;
+            }
+            reel.render(index, &mut frame);
+            bytes.clear();
+            for y in 0..height {
+                for x in 0..width {
+                    bytes.extend_from_slice(&frame.get_pixel(x, y).0);
+                }
+            }
+            stdin.write_all(&bytes)?;
+        }
+        Ok::<(), std::io::Error>(())
+    })();
+
+    // closing stdin lets ffmpeg finish the file
+    drop(child.stdin.take());
+    let status = child.wait()?;
+    res?;
+
+    if !status.success() {
+        return Err(Error::new(format!("ffmpeg failed: {status}")));
+    }
+    Ok(())
+}
+
+/// Plays the reel on the terminal, starting in the top left corner.
+pub fn play(reel: &mut Reel, out: &mut impl Write, running: &AtomicBool) -> std::io::Result<()> {
+    let ReelOptions { width, height, fps, .. } = reel.options;
+    let mut frame = RgbImage::new(width, height);
+    let mut lines = String::new();
+    let mut pacer = FramePacer::new(Duration::from_secs_f64(1.0 / fps as f64), Instant::now());
+
+    // CSI ? 25 l     Hide cursor (DECTCEM), VT220
+    // CSI 2 J        Clear entire screen
+    write!(out, "\x1B[?25l\x1B[2J")?;
+
+    let mut res = Ok(());
+    for index in 0..reel.frame_count() {
+        reel.render(index, &mut frame);
+        simple_image_to_ansi_into(&frame, &mut lines);
+        res = write!(out, "\x1B[1;1H{lines}").and_then(|_| out.flush());
+        if res.is_err() || !pacer.wait(false) || !running.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+
+    // CSI 0 m        Reset or normal, all attributes become turned off
+    // CSI ? 25 h     Show cursor (DECTCEM), VT220
+    let _ = writeln!(out, "\x1B[0m\x1B[?25h");
+    res
+}
@@ -0,0 +1,551 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::io::{IsTerminal, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use std::time::{Duration, Instant};
+
+use crate::diagnostics::Code;
+use crate::error::Error;
+use crate::file_info::FileInfo;
+use crate::image::LivingWorld;
+
+const MAX_REDIRECTS: usize = 5;
+
+/// Name of [`Source::Stdin`] in messages.
+const STDIN_NAME: &str = "<stdin>";
+
+/// Most bytes of HTTP headers accepted on top of the download size limit.
+const MAX_HEADER_SIZE: u64 = 64 * 1024;
+
+/// Default for [`NetworkPolicy::max_download_size`], 64 MiB.
+pub const DEFAULT_MAX_DOWNLOAD_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Default for [`NetworkPolicy::timeout`].
+pub const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Content types of responses that are certainly not a file that can be
+/// shown, like the HTML error pages of some servers.
+const REJECTED_CONTENT_TYPES: &[&str] = &["text/html", "application/xhtml+xml"];
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+static MAX_DOWNLOAD_SIZE: AtomicU64 = AtomicU64::new(DEFAULT_MAX_DOWNLOAD_SIZE);
+static NETWORK_TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_NETWORK_TIMEOUT.as_millis() as u64);
+
+/// Limits for loading URLs, so the viewer can be pointed at untrusted
+/// playlists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkPolicy {
+    /// Forbid any network access.
+    pub offline: bool,
+    /// Most bytes of a downloaded file.
+    pub max_download_size: u64,
+    /// Time a download may take in total, including connecting.
+    pub timeout: Duration,
+}
+
+impl Default for NetworkPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            offline: false,
+            max_download_size: DEFAULT_MAX_DOWNLOAD_SIZE,
+            timeout: DEFAULT_NETWORK_TIMEOUT,
+        }
+    }
+}
+
+pub fn network_policy() -> NetworkPolicy {
+    NetworkPolicy {
+        offline: OFFLINE.load(Ordering::Relaxed),
+        max_download_size: MAX_DOWNLOAD_SIZE.load(Ordering::Relaxed),
+        timeout: Duration::from_millis(NETWORK_TIMEOUT_MS.load(Ordering::Relaxed)),
+    }
+}
+
+pub fn set_network_policy(policy: NetworkPolicy) {
+    OFFLINE.store(policy.offline, Ordering::Relaxed);
+    MAX_DOWNLOAD_SIZE.store(policy.max_download_size, Ordering::Relaxed);
+    NETWORK_TIMEOUT_MS.store(policy.timeout.as_millis().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+}
+
+/// Where the data of a playlist entry comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    File(PathBuf),
+    /// Plain `http://` URL. TLS is not supported.
+    Http(String),
+    Embedded {
+        name: &'static str,
+        data: &'static [u8],
+    },
+    /// File piped in on stdin (`-`), read completely at the start.
+    Stdin(Arc<[u8]>),
+    /// Entry of a scene index, see [`crate::manifest`].
+    Scene {
+        info: Box<SceneInfo>,
+        source: Box<Source>,
+    },
+}
+
+/// What a scene index says about a scene.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SceneInfo {
+    /// Display name, used instead of the name in the file.
+    pub title: String,
+    pub month: Option<String>,
+    /// Name of the ambient sound of the scene. Sound isn't supported, this
+    /// is only shown in the file info.
+    pub sound: Option<String>,
+    /// Time of day while the scene is shown.
+    pub time: Option<SceneTime>,
+}
+
+/// Time of day a scene index sets for one of its scenes. It only applies
+/// while the scene is shown, the clock is back to normal afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneTime {
+    /// Stopped at this time, in milliseconds since midnight.
+    At(u64),
+    /// The current time shifted by this many milliseconds.
+    Offset(i64),
+}
+
+impl From<PathBuf> for Source {
+    fn from(path: PathBuf) -> Self {
+        if let Some(url) = path.to_str() && is_url(url) {
+            return Source::Http(url.to_owned());
+        }
+        Source::File(path)
+    }
+}
+
+impl From<&Path> for Source {
+    #[inline]
+    fn from(path: &Path) -> Self {
+        path.to_path_buf().into()
+    }
+}
+
+#[inline]
+pub fn is_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+/// Fails for URLs that can't be loaded at all, so they are rejected before
+/// they end up in the playlist. There is no TLS support, so only plain
+/// http:// URLs work.
+pub fn check_url(url: &str) -> Result<(), Error> {
+    if !url.starts_with("http://") {
+        return Err(Error::new(format!("TLS is not supported, use an http:// URL or download the file: {url}")).with_code(Code::Network));
+    }
+    Ok(())
+}
+
+impl Source {
+    /// Reads the file piped in on stdin. Fails if stdin is a terminal,
+    /// since there is nothing to wait for.
+    pub fn from_stdin() -> Result<Source, Error> {
+        let mut stdin = std::io::stdin().lock();
+        if stdin.is_terminal() {
+            return Err(Error::new("stdin is a terminal, pipe a file into it to read it from -"));
+        }
+        let mut data = Vec::new();
+        stdin.read_to_end(&mut data)?;
+        Ok(Source::Stdin(data.into()))
+    }
+
+    /// Interprets pasted text as a path or URL. Surrounding whitespace and
+    /// quotes, as well as a `file://` prefix added by file managers, are
+    /// removed. Only the first line is used.
+    pub fn from_pasted(text: &str) -> Option<Source> {
+        let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+        let line = ['"', '\'']
+            .iter()
+            .find_map(|&quote| line.strip_prefix(quote)?.strip_suffix(quote))
+            .unwrap_or(line);
+        let line = line.strip_prefix("file://").unwrap_or(line);
+
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(Path::new(line).into())
+    }
+
+    /// Full name of the source for error messages.
+    pub fn name(&self) -> Cow<'_, str> {
+        match self {
+            Source::File(path) => path.to_string_lossy(),
+            Source::Http(url) => Cow::Borrowed(url),
+            Source::Embedded { name, .. } => Cow::Borrowed(name),
+            Source::Stdin(_) => Cow::Borrowed(STDIN_NAME),
+            Source::Scene { source, .. } => source.name(),
+        }
+    }
+
+    /// Path of the local file, if it is one.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Source::File(path) => Some(path),
+            Source::Http(_) | Source::Embedded { .. } | Source::Stdin(_) => None,
+            Source::Scene { source, .. } => source.path(),
+        }
+    }
+
+    /// Short name of the source for the OSD.
+    pub fn file_name(&self) -> Cow<'_, str> {
+        match self {
+            Source::File(path) => path.file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_else(|| path.to_string_lossy()),
+            Source::Http(url) => {
+                let path = url.split(['?', '#']).next().unwrap_or(url);
+                match path.trim_end_matches('/').rsplit_once('/') {
+                    Some((_, name)) if !name.is_empty() => Cow::Borrowed(name),
+                    _ => Cow::Borrowed(url),
+                }
+            }
+            Source::Embedded { name, .. } => Cow::Borrowed(name),
+            Source::Stdin(_) => Cow::Borrowed(STDIN_NAME),
+            Source::Scene { source, .. } => source.file_name(),
+        }
+    }
+
+    pub fn read(&self) -> Result<Cow<'static, [u8]>, Error> {
+        match self {
+            Source::File(path) => Ok(Cow::Owned(std::fs::read(path)?)),
+            Source::Http(url) => Ok(Cow::Owned(http_get(url)?)),
+            Source::Embedded { data, .. } => Ok(Cow::Borrowed(data)),
+            Source::Stdin(data) => Ok(Cow::Owned(data.to_vec())),
+            Source::Scene { source, .. } => source.read(),
+        }
+    }
+
+    /// Time of day the scene index sets for this entry.
+    #[inline]
+    pub fn scene_time(&self) -> Option<SceneTime> {
+        match self {
+            Source::Scene { info, .. } => info.time,
+            _ => None,
+        }
+    }
+
+    pub fn load(&self) -> Result<LivingWorld, Error> {
+        let data = {
+            profile_scope!("read");
+            self.read()?
+        };
+        profile_scope!("decode");
+        let living_world = LivingWorld::from_bytes(&data)?;
+        Ok(match self {
+            Source::Scene { info, .. } => living_world.with_name(Some(info.title.clone())),
+            _ => living_world,
+        })
+    }
+
+    /// Like [`Source::load()`], but also collects information about the file.
+    pub fn load_with_info(&self) -> Result<(LivingWorld, FileInfo), Error> {
+        let start = Instant::now();
+        let data = {
+            profile_scope!("read");
+            self.read()?
+        };
+        profile_scope!("decode");
+        let (mut living_world, mut info) = FileInfo::decode(self.name(), &data)?;
+        if let Source::Scene { info: scene, .. } = self {
+            living_world = living_world.with_name(Some(scene.title.clone()));
+            info.month = scene.month.clone();
+            info.sound = scene.sound.clone();
+        }
+        info.load_time = start.elapsed();
+        Ok((living_world, info))
+    }
+}
+
+type LoadResult = Result<(LivingWorld, FileInfo), Error>;
+
+/// Loads a playlist entry on a background thread, so switching to the
+/// next file doesn't have to wait for slow sources.
+#[derive(Debug, Default)]
+pub struct Prefetcher {
+    pending: Option<(usize, JoinHandle<LoadResult>)>,
+}
+
+impl Prefetcher {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts loading `sources[index]` in the background, replacing any
+    /// previous prefetch.
+    pub fn prefetch(&mut self, sources: &[Source], index: usize) {
+        if let Some((pending_index, _)) = &self.pending && *pending_index == index {
+            return;
+        }
+
+        let Some(source) = sources.get(index) else {
+            self.pending = None;
+            return;
+        };
+
+        let source = source.clone();
+        let handle = std::thread::spawn(move || source.load_with_info());
+        self.pending = Some((index, handle));
+    }
+
+    /// Loads `sources[index]`, using the prefetched result if available.
+    pub fn load(&mut self, sources: &[Source], index: usize) -> LoadResult {
+        if let Some((pending_index, handle)) = self.pending.take() && pending_index == index {
+            return match handle.join() {
+                Ok(result) => result,
+                Err(_) => Err(Error::new("loader thread panicked")),
+            };
+        }
+
+        sources[index].load_with_info()
+    }
+}
+
+fn http_get(url: &str) -> Result<Vec<u8>, Error> {
+    let policy = network_policy();
+    if policy.offline {
+        return Err(Error::new(format!("{url}: network access is disabled by --offline")).with_code(Code::Network));
+    }
+
+    let deadline = Instant::now() + policy.timeout;
+    let mut url = url.to_owned();
+
+    for _ in 0..=MAX_REDIRECTS {
+        // redirects may lead to https://
+        check_url(&url)?;
+        let rest = &url["http://".len()..];
+
+        let (host, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+
+        let addr = if host.contains(':') { Cow::Borrowed(host) } else { Cow::Owned(format!("{host}:80")) };
+        let limit = policy.max_download_size.saturating_add(MAX_HEADER_SIZE);
+        let response = request(addr.as_ref(), host, path, limit, deadline).map_err(|err| timeout_error(&url, err))?;
+        let Some(mut response) = response else {
+            return Err(Error::new(format!("{url}: download exceeds the limit of {} bytes", policy.max_download_size)).with_code(Code::Network));
+        };
+
+        let Some(header_end) = response.windows(4).position(|window| window == b"\r\n\r\n") else {
+            return Err(Error::new(format!("{url}: malformed HTTP response")).with_code(Code::Network));
+        };
+
+        let header = String::from_utf8_lossy(&response[..header_end]);
+        let mut lines = header.split("\r\n");
+        let status_line = lines.next().unwrap_or_default();
+        let status: u32 = status_line.split(' ').nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| Error::new(format!("{url}: malformed HTTP status line: {status_line:?}")).with_code(Code::Network))?;
+
+        match status {
+            200 => {
+                let content_type = lines.clone()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                    .map(|(_, value)| value.trim().to_ascii_lowercase());
+                if let Some(content_type) = content_type
+                    && REJECTED_CONTENT_TYPES.iter().any(|rejected| content_type.starts_with(rejected))
+                {
+                    return Err(Error::new(format!("{url}: unexpected content type: {content_type}")).with_code(Code::Network));
+                }
+
+                response.drain(..header_end + 4);
+                if response.len() as u64 > policy.max_download_size {
+                    return Err(Error::new(format!("{url}: download exceeds the limit of {} bytes", policy.max_download_size)).with_code(Code::Network));
+                }
+                return Ok(response);
+            }
+            301 | 302 | 303 | 307 | 308 => {
+                let location = lines
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+                    .map(|(_, value)| value.trim().to_owned());
+
+                let Some(location) = location else {
+                    return Err(Error::new(format!("{url}: redirect without location")).with_code(Code::Network));
+                };
+
+                url = resolve_location(&url, &location);
+            }
+            _ => {
+                return Err(Error::new(format!("{url}: HTTP error: {status_line}")).with_code(Code::Network));
+            }
+        }
+    }
+
+    Err(Error::new(format!("{url}: too many redirects")).with_code(Code::Network))
+}
+
+/// The URL a redirect from `url` to `location` leads to. Relative locations
+/// are resolved against the directory of `url`.
+fn resolve_location(url: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_owned();
+    }
+    if let Some(rest) = location.strip_prefix("//") {
+        return format!("http://{rest}");
+    }
+
+    let rest = url.strip_prefix("http://").unwrap_or(url);
+    let (host, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let path = &path[..path.find(['?', '#']).unwrap_or(path.len())];
+
+    let path = if location.starts_with('/') {
+        location.to_owned()
+    } else if location.starts_with(['?', '#']) {
+        format!("{path}{location}")
+    } else {
+        let dir = &path[..path.rfind('/').map_or(0, |index| index + 1)];
+        format!("{dir}{location}")
+    };
+
+    format!("http://{host}{}", remove_dot_segments(&path))
+}
+
+/// Resolves `.` and `..` in an absolute URL path.
+fn remove_dot_segments(path: &str) -> String {
+    let (path, query) = path.split_at(path.find(['?', '#']).unwrap_or(path.len()));
+    let parts: Vec<&str> = path.split('/').skip(1).collect();
+    let mut segments = Vec::with_capacity(parts.len());
+    for (index, &part) in parts.iter().enumerate() {
+        let last = index + 1 == parts.len();
+        match part {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => {
+                segments.push(part);
+                continue;
+            }
+        }
+        if last {
+            // keep the trailing slash of a directory
+            segments.push("");
+        }
+    }
+    format!("/{}{query}", segments.join("/"))
+}
+
+/// Time left until `deadline`, or a timeout error if it has passed.
+fn remaining(deadline: Instant) -> std::io::Result<Duration> {
+    let now = Instant::now();
+    if now >= deadline {
+        return Err(std::io::ErrorKind::TimedOut.into());
+    }
+    Ok(deadline - now)
+}
+
+/// Sends a GET request for `path` to `host` and reads the response, or
+/// `None` if it is longer than `limit` bytes.
+fn request(addr: &str, host: &str, path: &str, limit: u64, deadline: Instant) -> std::io::Result<Option<Vec<u8>>> {
+    let mut stream = connect(addr, deadline)?;
+    stream.set_write_timeout(Some(remaining(deadline)?))?;
+
+    write!(stream, "GET {path} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: color-cycle/{}\r\nConnection: close\r\n\r\n",
+        env!("CARGO_PKG_VERSION"))?;
+    stream.flush()?;
+
+    read_limited(&mut stream, limit, deadline)
+}
+
+/// Connects to the first address of `addr` that answers before `deadline`.
+fn connect(addr: &str, deadline: Instant) -> std::io::Result<TcpStream> {
+    let mut last_err = None;
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, remaining(deadline)?) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| std::io::ErrorKind::AddrNotAvailable.into()))
+}
+
+/// Reads everything until `deadline`. Returns `None` if there are more
+/// than `limit` bytes.
+fn read_limited(stream: &mut TcpStream, limit: u64, deadline: Instant) -> std::io::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        stream.set_read_timeout(Some(remaining(deadline)?))?;
+        let count = match stream.read(&mut buf) {
+            Ok(0) => return Ok(Some(data)),
+            Ok(count) => count,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if data.len() as u64 + count as u64 > limit {
+            return Ok(None);
+        }
+        data.extend_from_slice(&buf[..count]);
+    }
+}
+
+fn timeout_error(url: &str, err: std::io::Error) -> Error {
+    if matches!(err.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock) {
+        Error::new(format!("{url}: timed out")).with_code(Code::Network)
+    } else {
+        err.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_absolute_location() {
+        assert_eq!(resolve_location("http://a.example/x/y.lbm", "http://b.example/z.lbm"), "http://b.example/z.lbm");
+        assert_eq!(resolve_location("http://a.example/x/y.lbm", "https://b.example/z.lbm"), "https://b.example/z.lbm");
+        assert_eq!(resolve_location("http://a.example/x/y.lbm", "//b.example/z.lbm"), "http://b.example/z.lbm");
+        assert_eq!(resolve_location("http://a.example:8080/x/y.lbm", "/z.lbm"), "http://a.example:8080/z.lbm");
+    }
+
+    #[test]
+    fn resolve_relative_location() {
+        assert_eq!(resolve_location("http://a.example/x/y.lbm", "img.lbm"), "http://a.example/x/img.lbm");
+        assert_eq!(resolve_location("http://a.example/x/", "img.lbm"), "http://a.example/x/img.lbm");
+        assert_eq!(resolve_location("http://a.example", "img.lbm"), "http://a.example/img.lbm");
+        assert_eq!(resolve_location("http://a.example/x/y.lbm?v=1", "img.lbm"), "http://a.example/x/img.lbm");
+        assert_eq!(resolve_location("http://a.example/x/y.lbm", "?v=2"), "http://a.example/x/y.lbm?v=2");
+    }
+
+    #[test]
+    fn resolve_dot_segments() {
+        assert_eq!(resolve_location("http://a.example/x/y/z.lbm", "../img.lbm"), "http://a.example/x/img.lbm");
+        assert_eq!(resolve_location("http://a.example/x/y/z.lbm", "./img.lbm"), "http://a.example/x/y/img.lbm");
+        assert_eq!(resolve_location("http://a.example/x/y.lbm", "../../img.lbm"), "http://a.example/img.lbm");
+        assert_eq!(resolve_location("http://a.example/x/y/z.lbm", ".."), "http://a.example/x/");
+        assert_eq!(resolve_location("http://a.example/x/y.lbm", "/a/./b/../c.lbm?p=../q"), "http://a.example/a/c.lbm?p=../q");
+    }
+}
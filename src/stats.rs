@@ -0,0 +1,154 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Statistics about the color cycles of a scene for `color-cycle stats`,
+//! to help choosing `--fps` and tuning cycles.
+
+use std::io::Write;
+
+use crate::image::IndexedImage;
+use crate::palette::{Cycle, LBM_CYCLE_RATE_DIVISOR, Palette};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleStats {
+    pub cycle: Cycle,
+    /// Number of pixels whose color is in the range of the cycle.
+    pub pixels: usize,
+    /// Palette steps per second.
+    pub steps_per_second: f64,
+    /// Largest difference of a color channel between two neighboring colors
+    /// of the cycle. This is how many shades a blended color can go
+    /// through during one step.
+    pub max_step: u8,
+}
+
+impl CycleStats {
+    /// Whether the cycle changes anything on screen: it moves, covers some
+    /// pixels, and not all of its colors are the same.
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.pixels > 0 && self.steps_per_second > 0.0 && self.max_step > 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneStats {
+    pub width: u32,
+    pub height: u32,
+    pub colors_used: usize,
+    /// Shannon entropy of the palette indices of the pixels in bits.
+    pub entropy: f64,
+    pub cycles: Vec<CycleStats>,
+}
+
+impl SceneStats {
+    pub fn new(image: &IndexedImage, palette: &Palette, cycles: &[Cycle]) -> Self {
+        let usage = image.index_usage();
+        let pixel_count = image.data().len();
+
+        let entropy = usage.iter()
+            .filter(|&&uses| uses > 0)
+            .map(|&uses| {
+                let share = uses as f64 / pixel_count as f64;
+                -share * share.log2()
+            })
+            .sum::<f64>()
+            .max(0.0);
+
+        let cycles = cycles.iter().map(|&cycle| {
+            let range = cycle.low() as usize..=cycle.high() as usize;
+            let pixels = if cycle.low() <= cycle.high() { usage[range.clone()].iter().sum() } else { 0 };
+            let steps_per_second = if cycle.high() > cycle.low() {
+                cycle.rate() as f64 / LBM_CYCLE_RATE_DIVISOR as f64
+            } else {
+                0.0
+            };
+
+            let colors = if cycle.low() <= cycle.high() { &palette.0[range] } else { &[][..] };
+            let max_step = colors.iter()
+                .zip(colors.iter().cycle().skip(1))
+                .flat_map(|(a, b)| (0..3).map(|channel| a.0[channel].abs_diff(b.0[channel])))
+                .max()
+                .unwrap_or(0);
+
+            CycleStats { cycle, pixels, steps_per_second, max_step }
+        }).collect();
+
+        Self {
+            width: image.width(),
+            height: image.height(),
+            colors_used: usage.iter().filter(|&&uses| uses > 0).count(),
+            entropy,
+            cycles,
+        }
+    }
+
+    #[inline]
+    pub fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Frames per second above which no additional change is visible
+    /// without blending: the image only changes when a cycle steps, which
+    /// happens at most this often.
+    pub fn suggested_fps(&self) -> u32 {
+        let steps: f64 = self.visible_cycles().map(|stats| stats.steps_per_second).sum();
+        steps.ceil().max(1.0) as u32
+    }
+
+    /// Like [`SceneStats::suggested_fps()`], but with blending, where each
+    /// step goes through up to [`CycleStats::max_step`] shades.
+    pub fn suggested_blend_fps(&self) -> u32 {
+        let changes: f64 = self.visible_cycles()
+            .map(|stats| stats.steps_per_second * stats.max_step as f64)
+            .sum();
+        changes.ceil().max(1.0) as u32
+    }
+
+    fn visible_cycles(&self) -> impl Iterator<Item = &CycleStats> {
+        self.cycles.iter().filter(|stats| stats.is_visible())
+    }
+}
+
+pub fn write_stats(out: &mut impl Write, name: &str, stats: &SceneStats) -> std::io::Result<()> {
+    let pixel_count = stats.pixel_count().max(1);
+    let max_entropy = (stats.colors_used.max(1) as f64).log2();
+
+    writeln!(out, "File:            {name}")?;
+    writeln!(out, "Size:            {} x {} ({} pixels)", stats.width, stats.height, stats.pixel_count())?;
+    writeln!(out, "Colors used:     {} of 256", stats.colors_used)?;
+    writeln!(out, "Palette entropy: {:.2} bits (max. {:.2} for {} colors)", stats.entropy, max_entropy, stats.colors_used)?;
+
+    if stats.cycles.is_empty() {
+        writeln!(out, "Cycles:          none")?;
+    } else {
+        writeln!(out, "Cycles:")?;
+        writeln!(out, "  #  Range    Rate     Dir  Pixels   Steps/s  Period")?;
+        for (index, cycle_stats) in stats.cycles.iter().enumerate() {
+            let cycle = &cycle_stats.cycle;
+            let range = format!("{}-{}", cycle.low(), cycle.high());
+            let direction = if cycle.reverse() { "<-" } else { "->" };
+            let coverage = format!("{:.1}%", cycle_stats.pixels as f64 * 100.0 / pixel_count as f64);
+            let period = cycle.period().map_or_else(|| "-".to_owned(), |period| format!("{period:.1}s"));
+            let note = if cycle_stats.is_visible() { "" } else { "  (no visible change)" };
+            writeln!(out, "  {:<2} {range:<8} {:<8} {direction:<4} {coverage:<8} {:<8.2} {period}{note}",
+                index + 1, cycle.rate(), cycle_stats.steps_per_second)?;
+        }
+    }
+
+    writeln!(out, "Suggested FPS:   {} ({} with --blend)", stats.suggested_fps(), stats.suggested_blend_fps())
+}
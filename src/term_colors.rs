@@ -0,0 +1,210 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Changes the colors of the terminal itself via OSC 4/10/11 to match the
+//! shown scene (`--sync-terminal-colors`).
+
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use crate::color::Rgb;
+use crate::doctor::{parse_da1, DA1};
+use crate::image::IndexedImage;
+use crate::palette::Palette;
+use crate::theme::{dominant_colors, TerminalScheme, TERMINAL_COLORS};
+
+const QUERY_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// The terminal colors are changed at most this often.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Colors of the terminal before they were changed. `None` if the terminal
+/// didn't answer the query, in which case the color is reset to the
+/// terminal's default on restore.
+#[derive(Debug, Clone, Default)]
+pub struct SavedColors {
+    pub colors: [Option<Rgb>; TERMINAL_COLORS],
+    pub foreground: Option<Rgb>,
+    pub background: Option<Rgb>,
+}
+
+impl SavedColors {
+    /// Queries the current terminal colors. `reader` needs to be the
+    /// terminal in non-canonical mode without blocking reads.
+    pub fn query(reader: &mut impl Read, writer: &mut impl Write) -> std::io::Result<Self> {
+        for index in 0..TERMINAL_COLORS {
+            write!(writer, "\x1B]4;{index};?\x1B\\")?;
+        }
+        write!(writer, "\x1B]10;?\x1B\\\x1B]11;?\x1B\\{DA1}")?;
+        writer.flush()?;
+
+        let start = Instant::now();
+        let mut answer = Vec::new();
+        let mut buf = [0u8; 256];
+
+        while start.elapsed() < QUERY_TIMEOUT {
+            let count = match reader.read(&mut buf) {
+                Ok(count) => count,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock || err.kind() == std::io::ErrorKind::Interrupted => 0,
+                Err(err) => return Err(err),
+            };
+
+            if count == 0 {
+                std::thread::sleep(Duration::from_millis(5));
+                continue;
+            }
+
+            answer.extend_from_slice(&buf[..count]);
+            if parse_da1(&answer).is_some() {
+                break;
+            }
+        }
+
+        let mut saved = SavedColors::default();
+        for reply in osc_replies(&answer) {
+            let Some((code, rest)) = reply.split_once(';') else {
+                continue;
+            };
+            match code {
+                "4" => if let Some((index, color)) = rest.split_once(';')
+                    && let Ok(index) = index.parse::<usize>()
+                    && index < TERMINAL_COLORS
+                {
+                    saved.colors[index] = parse_color(color);
+                }
+                "10" => saved.foreground = parse_color(rest),
+                "11" => saved.background = parse_color(rest),
+                _ => {}
+            }
+        }
+
+        Ok(saved)
+    }
+
+    pub fn restore(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for (index, color) in self.colors.iter().enumerate() {
+            match color {
+                Some(color) => write!(writer, "\x1B]4;{index};{}\x1B\\", XColor(*color))?,
+                None => write!(writer, "\x1B]104;{index}\x1B\\")?,
+            }
+        }
+
+        match self.foreground {
+            Some(color) => write!(writer, "\x1B]10;{}\x1B\\", XColor(color))?,
+            None => write!(writer, "\x1B]110\x1B\\")?,
+        }
+
+        match self.background {
+            Some(color) => write!(writer, "\x1B]11;{}\x1B\\", XColor(color))?,
+            None => write!(writer, "\x1B]111\x1B\\")?,
+        }
+
+        writer.flush()
+    }
+}
+
+/// Keeps the terminal colors in sync with the scene without sending
+/// updates more often than [`SYNC_INTERVAL`].
+#[derive(Debug)]
+pub struct ColorSync {
+    saved: SavedColors,
+    current: Option<TerminalScheme>,
+    next_update: Option<Instant>,
+}
+
+impl ColorSync {
+    #[inline]
+    pub fn new(saved: SavedColors) -> Self {
+        Self { saved, current: None, next_update: None }
+    }
+
+    /// Sets the terminal colors to the most used colors of `image` with
+    /// `palette`, if the interval passed and the colors changed.
+    pub fn update(&mut self, writer: &mut impl Write, palette: &Palette, image: &IndexedImage, now: Instant) -> std::io::Result<()> {
+        if let Some(next_update) = self.next_update && now < next_update {
+            return Ok(());
+        }
+        self.next_update = Some(now + SYNC_INTERVAL);
+
+        let scheme = TerminalScheme::from_colors(&dominant_colors(palette, image, TERMINAL_COLORS));
+        if self.current == Some(scheme) {
+            return Ok(());
+        }
+
+        for (index, color) in scheme.colors.iter().enumerate() {
+            write!(writer, "\x1B]4;{index};{}\x1B\\", XColor(*color))?;
+        }
+        write!(writer, "\x1B]10;{}\x1B\\\x1B]11;{}\x1B\\", XColor(scheme.foreground), XColor(scheme.background))?;
+        self.current = Some(scheme);
+
+        Ok(())
+    }
+
+    /// Restores the colors the terminal had before.
+    #[inline]
+    pub fn restore(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        self.saved.restore(writer)
+    }
+}
+
+/// Color in the `rgb:RR/GG/BB` notation of XParseColor.
+struct XColor(Rgb);
+
+impl std::fmt::Display for XColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Rgb([r, g, b]) = self.0;
+        write!(f, "rgb:{r:02x}/{g:02x}/{b:02x}")
+    }
+}
+
+/// Returns the contents of all OSC sequences (`ESC ] ... BEL` or
+/// `ESC ] ... ESC \`) in `data`.
+fn osc_replies(data: &[u8]) -> Vec<&str> {
+    let mut replies = Vec::new();
+    let mut rest = data;
+
+    while let Some(start) = rest.windows(2).position(|window| window == b"\x1B]") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.iter().position(|&byte| byte == 0x07 || byte == 0x1B) else {
+            break;
+        };
+        if let Ok(reply) = std::str::from_utf8(&rest[..end]) {
+            replies.push(reply);
+        }
+        rest = &rest[end..];
+    }
+
+    replies
+}
+
+/// Parses `rgb:R/G/B` with 1 to 4 hex digits per component.
+fn parse_color(value: &str) -> Option<Rgb> {
+    let value = value.strip_prefix("rgb:")?;
+    let mut components = value.split('/').map(|component| {
+        if component.is_empty() || component.len() > 4 {
+            return None;
+        }
+        let max = (1u32 << (4 * component.len())) - 1;
+        let value = u32::from_str_radix(component, 16).ok()?;
+        Some((value * 255 / max) as u8)
+    });
+
+    let r = components.next()??;
+    let g = components.next()??;
+    let b = components.next()??;
+
+    Some(Rgb([r, g, b]))
+}
@@ -0,0 +1,135 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Exports palettes as color themes for other programs.
+
+use std::io::Write;
+
+use clap::ValueEnum;
+
+use crate::color::Rgb;
+use crate::image::IndexedImage;
+use crate::palette::Palette;
+
+/// Number of colors of a terminal color scheme.
+pub const TERMINAL_COLORS: usize = 16;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeFormat {
+    /// CSS custom properties
+    Css,
+    /// JSON array of hex colors
+    Json,
+    /// X resources terminal color scheme (16 colors, background, foreground)
+    Xresources,
+}
+
+impl ThemeFormat {
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThemeFormat::Css        => "css",
+            ThemeFormat::Json       => "json",
+            ThemeFormat::Xresources => "Xresources",
+        }
+    }
+}
+
+/// Colors of `palette` that are used by `image`, most used first.
+/// Identical colors at different indices are merged. Returns at most
+/// `count` colors, or all if `count` is 0.
+pub fn dominant_colors(palette: &Palette, image: &IndexedImage, count: usize) -> Vec<Rgb> {
+    let usage = image.index_usage();
+    let mut colors: Vec<(Rgb, usize)> = Vec::new();
+    for (index, &uses) in usage.iter().enumerate() {
+        if uses == 0 {
+            continue;
+        }
+        let color = palette.0[index];
+        if let Some(entry) = colors.iter_mut().find(|(other, _)| *other == color) {
+            entry.1 += uses;
+        } else {
+            colors.push((color, uses));
+        }
+    }
+
+    // stable sort keeps palette order for equally used colors
+    colors.sort_by_key(|&(_, uses)| std::cmp::Reverse(uses));
+
+    if count > 0 {
+        colors.truncate(count);
+    }
+
+    colors.into_iter().map(|(color, _)| color).collect()
+}
+
+/// The 16 colors, background, and foreground of a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalScheme {
+    pub background: Rgb,
+    pub foreground: Rgb,
+    pub colors: [Rgb; TERMINAL_COLORS],
+}
+
+impl TerminalScheme {
+    /// Builds a scheme from the first 16 of `colors` (most used first),
+    /// sorted from dark to bright. The darkest becomes the background and
+    /// the brightest the foreground. Fewer colors are repeated.
+    pub fn from_colors(colors: &[Rgb]) -> Self {
+        let mut colors = colors[..colors.len().min(TERMINAL_COLORS)].to_vec();
+        if colors.is_empty() {
+            colors.push(Rgb([0, 0, 0]));
+        }
+        colors.sort_by(|a, b| a.luminance().total_cmp(&b.luminance()));
+
+        Self {
+            background: colors[0],
+            foreground: colors[colors.len() - 1],
+            colors: std::array::from_fn(|index| colors[index % colors.len()]),
+        }
+    }
+}
+
+pub fn write_theme(out: &mut impl Write, colors: &[Rgb], format: ThemeFormat) -> std::io::Result<()> {
+    match format {
+        ThemeFormat::Css => {
+            writeln!(out, ":root {{")?;
+            for (index, color) in colors.iter().enumerate() {
+                writeln!(out, "  --color-{index}: {color};")?;
+            }
+            writeln!(out, "}}")?;
+        }
+        ThemeFormat::Json => {
+            write!(out, "[")?;
+            for (index, color) in colors.iter().enumerate() {
+                if index > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "\"{color}\"")?;
+            }
+            writeln!(out, "]")?;
+        }
+        ThemeFormat::Xresources => {
+            let scheme = TerminalScheme::from_colors(colors);
+            writeln!(out, "*.background: {}", scheme.background)?;
+            writeln!(out, "*.foreground: {}", scheme.foreground)?;
+            for (index, color) in scheme.colors.iter().enumerate() {
+                writeln!(out, "*.color{index}: {color}")?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Raw video output, so scenes can be piped into ffmpeg and turned into
+//! MP4 or WebM files without going through a terminal.
+
+use std::io::Write;
+
+use clap::ValueEnum;
+
+use crate::color::Rgb;
+use crate::image::RgbImage;
+
+const BACKGROUND: Rgb = Rgb([0, 0, 0]);
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoFormat {
+    /// yuv4mpeg2 with 4:4:4 chroma, read by ffmpeg without further options
+    Y4m,
+    /// Bare RGB frames without any header, 3 bytes per pixel
+    Rgb24,
+}
+
+/// Writes frames of a fixed size. Frames of another size are scaled to fit
+/// and centered on a black background.
+#[derive(Debug)]
+pub struct VideoWriter<W: Write> {
+    out: W,
+    format: VideoFormat,
+    fps: u32,
+    frame: RgbImage,
+    scaled: RgbImage,
+    header_written: bool,
+    bytes: Vec<u8>,
+}
+
+impl<W: Write> VideoWriter<W> {
+    pub fn new(out: W, format: VideoFormat, width: u32, height: u32, fps: u32) -> Self {
+        Self {
+            out,
+            format,
+            fps,
+            frame: RgbImage::from_color(width, height, BACKGROUND),
+            scaled: RgbImage::new(0, 0),
+            header_written: false,
+            bytes: Vec::with_capacity(width as usize * height as usize * 3),
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.frame.size()
+    }
+
+    pub fn write_frame(&mut self, frame: &RgbImage) -> std::io::Result<()> {
+        let frame = if frame.size() == self.frame.size() {
+            frame
+        } else {
+            self.fit(frame);
+            &self.frame
+        };
+
+        let (width, height) = frame.size();
+        self.bytes.clear();
+        match self.format {
+            VideoFormat::Y4m => {
+                if !self.header_written {
+                    // 4:4:4 so the colors of single pixels don't bleed
+                    writeln!(self.out, "YUV4MPEG2 W{width} H{height} F{}:1 Ip A1:1 C444", self.fps)?;
+                    self.header_written = true;
+                }
+                self.bytes.extend_from_slice(b"FRAME\n");
+                let plane_size = width as usize * height as usize;
+                let start = self.bytes.len();
+                self.bytes.resize(start + plane_size * 3, 0);
+                let (luma, chroma) = self.bytes[start..].split_at_mut(plane_size);
+                let (cb, cr) = chroma.split_at_mut(plane_size);
+                let mut index = 0;
+                for y in 0..height {
+                    for x in 0..width {
+                        [luma[index], cb[index], cr[index]] = rgb_to_ycbcr(frame.get_pixel(x, y));
+                        index += 1;
+                    }
+                }
+            }
+            VideoFormat::Rgb24 => {
+                for y in 0..height {
+                    for x in 0..width {
+                        self.bytes.extend_from_slice(&frame.get_pixel(x, y).0);
+                    }
+                }
+            }
+        }
+
+        self.out.write_all(&self.bytes)
+    }
+
+    #[inline]
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Scales `frame` to fit into the video frame, keeping its aspect ratio.
+    fn fit(&mut self, frame: &RgbImage) {
+        let (width, height) = self.frame.size();
+        let (frame_width, frame_height) = frame.size();
+        if frame_width == 0 || frame_height == 0 {
+            self.frame.fill(BACKGROUND);
+            return;
+        }
+
+        let scale = (width as f64 / frame_width as f64).min(height as f64 / frame_height as f64);
+        let scaled_width = ((frame_width as f64 * scale).round() as u32).clamp(1, width);
+        let scaled_height = ((frame_height as f64 * scale).round() as u32).clamp(1, height);
+        if self.scaled.size() != (scaled_width, scaled_height) {
+            self.scaled = RgbImage::new(scaled_width, scaled_height);
+            self.frame.fill(BACKGROUND);
+        }
+        frame.resize_into(&mut self.scaled);
+
+        let x_offset = (width - scaled_width) / 2;
+        let y_offset = (height - scaled_height) / 2;
+        for y in 0..scaled_height {
+            for x in 0..scaled_width {
+                self.frame.set_pixel(x_offset + x, y_offset + y, self.scaled.get_pixel(x, y));
+            }
+        }
+    }
+}
+
+/// Limited range BT.601, what players assume for yuv4mpeg2 without any
+/// color range given.
+fn rgb_to_ycbcr(color: Rgb) -> [u8; 3] {
+    let [r, g, b] = color.0.map(|value| value as f64 / 255.0);
+    let y = 16.0 + 65.481 * r + 128.553 * g + 24.966 * b;
+    let cb = 128.0 - 37.797 * r - 74.203 * g + 112.0 * b;
+    let cr = 128.0 + 112.0 * r - 93.786 * g - 18.214 * b;
+    [y, cb, cr].map(|value| value.round().clamp(0.0, 255.0) as u8)
+}
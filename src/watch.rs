@@ -0,0 +1,81 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Notices when a file changes, for `--watch`.
+//!
+//! The modification time and size of the file are polled, which works the
+//! same everywhere and is cheap enough for a single file.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the file is checked.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What is compared between polls. `None` while the file doesn't exist,
+/// e.g. while an editor replaces it.
+type Stamp = Option<(Option<SystemTime>, u64)>;
+
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    path: PathBuf,
+    stamp: Stamp,
+    /// a change that is only reported once the file stays like that for a
+    /// poll, so that files aren't read while they are still being written
+    pending: Option<Stamp>,
+    next_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path, now: Instant) -> Self {
+        Self {
+            path: path.to_owned(),
+            stamp: stamp(path),
+            pending: None,
+            next_poll: now + POLL_INTERVAL,
+        }
+    }
+
+    /// Whether the file changed since the last time this returned `true`.
+    pub fn changed(&mut self, now: Instant) -> bool {
+        if now < self.next_poll {
+            return false;
+        }
+        self.next_poll = now + POLL_INTERVAL;
+
+        let current = stamp(&self.path);
+        if current == self.stamp {
+            self.pending = None;
+            return false;
+        }
+
+        if self.pending != Some(current) {
+            self.pending = Some(current);
+            return false;
+        }
+
+        self.pending = None;
+        self.stamp = current;
+        // nothing to show while the file is gone
+        current.is_some()
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
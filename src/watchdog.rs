@@ -0,0 +1,177 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Output to the terminal that doesn't block for longer than a timeout.
+//!
+//! When a tmux client detaches or an ssh connection hangs, writing to the
+//! terminal blocks until it drains again. Instead of freezing the animation
+//! and then bursting all frames at once, whatever couldn't be written in
+//! time is kept and sent first once the terminal accepts output again, and
+//! the caller skips frames until then.
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_OUTPUT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Passes everything through to `inner` until [`Watchdog::watch()`] is
+/// called. After that output is collected until [`Write::flush()`] and
+/// then written to stdout directly, giving up after the timeout. The rest is pending until the next flush or
+/// [`Watchdog::retry()`].
+#[derive(Debug)]
+pub struct Watchdog<W: Write> {
+    inner: W,
+    watched: Option<Watched>,
+    pending: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Watched {
+    #[cfg(unix)]
+    fd: libc::c_int,
+    timeout: Duration,
+}
+
+impl<W: Write> Watchdog<W> {
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self { inner, watched: None, pending: Vec::new() }
+    }
+
+    /// Starts writing to stdout directly with `timeout`. `inner` needs to
+    /// be stdout as well. Only supported on Unix.
+    pub fn watch(&mut self, timeout: Duration) {
+        self.watched = Some(Watched {
+            #[cfg(unix)]
+            fd: libc::STDOUT_FILENO,
+            timeout,
+        });
+    }
+
+    /// Whether some output is still waiting for the terminal.
+    #[inline]
+    pub fn is_stalled(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Tries to write pending output without waiting. Returns whether some
+    /// is still pending.
+    pub fn retry(&mut self) -> std::io::Result<bool> {
+        if let Some(watched) = self.watched && !self.pending.is_empty() {
+            self.drain(watched, Instant::now())?;
+        }
+        Ok(self.is_stalled())
+    }
+
+    /// Stops watching and writes all pending output, waiting as long as it
+    /// takes. Call before anything else writes to the terminal.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        self.watched = None;
+        let pending = std::mem::take(&mut self.pending);
+        self.inner.write_all(&pending)?;
+        self.inner.flush()
+    }
+
+    #[cfg(unix)]
+    fn drain(&mut self, watched: Watched, deadline: Instant) -> std::io::Result<()> {
+        // anything that was written before watching comes first
+        self.inner.flush()?;
+
+        let flags = unsafe { libc::fcntl(watched.fd, libc::F_GETFL) };
+        if flags == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // The terminal is usually shared with stdin, so only set this while
+        // writing.
+        if unsafe { libc::fcntl(watched.fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let res = write_until(watched.fd, &self.pending, deadline);
+
+        unsafe { libc::fcntl(watched.fd, libc::F_SETFL, flags); }
+
+        let written = res?;
+        self.pending.drain(..written);
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn drain(&mut self, _watched: Watched, _deadline: Instant) -> std::io::Result<()> {
+        self.finish()
+    }
+}
+
+/// Writes as much of `data` to the non-blocking `fd` as possible until
+/// `deadline`. Returns how many bytes were written.
+#[cfg(unix)]
+fn write_until(fd: libc::c_int, data: &[u8], deadline: Instant) -> std::io::Result<usize> {
+    let mut written = 0;
+    while written < data.len() {
+        let rest = &data[written..];
+        let count = unsafe { libc::write(fd, rest.as_ptr().cast(), rest.len()) };
+        if count >= 0 {
+            written += count as usize;
+            continue;
+        }
+
+        let err = std::io::Error::last_os_error();
+        match err.kind() {
+            std::io::ErrorKind::Interrupted => continue,
+            std::io::ErrorKind::WouldBlock => {}
+            _ => return Err(err),
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLOUT,
+            revents: 0,
+        };
+        let timeout = (deadline - now).as_micros().div_ceil(1000).min(libc::c_int::MAX as u128) as libc::c_int;
+        if unsafe { libc::poll(&mut pollfd, 1, timeout) } < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() != std::io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+impl<W: Write> Write for Watchdog<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.watched.is_some() {
+            self.pending.extend_from_slice(buf);
+            Ok(buf.len())
+        } else {
+            self.inner.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.watched {
+            Some(watched) => self.drain(watched, Instant::now() + watched.timeout),
+            None => self.inner.flush(),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+// color-cycle - render color cycle images on the terminal
+// Copyright (C) 2025  Mathias Panzenböck
+// 
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// 
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// 
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Visual regression runner for a corpus of real-world files. Every file
+// is decoded and rendered at fixed times of day and cycle times, and the
+// hashes of the frames are compared against recorded baselines. Point it
+// at a directory of LBM and JSON files:
+//
+//     COLOR_CYCLE_CORPUS=path/to/files cargo test --test corpus
+//
+// Record new baselines after intended changes (bless them) with:
+//
+//     COLOR_CYCLE_CORPUS=path/to/files COLOR_CYCLE_BLESS=1 cargo test --test corpus
+//
+// The baselines are kept in baselines.txt in the corpus directory. Without
+// COLOR_CYCLE_CORPUS nothing is checked.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use color_cycle::animator::Animator;
+use color_cycle::clock::CycleClock;
+use color_cycle::image::{IndexedImage, LivingWorld, RgbImage};
+use color_cycle::palette::Palette;
+
+const BASELINES_FILE: &str = "baselines.txt";
+const EXTENSIONS: &[&str] = &["json", "lbm", "ilbm", "iff", "bbm"];
+
+/// Times of day to render at, as HH:MM.
+const TIMES_OF_DAY: &[&str] = &["00:00", "06:00", "12:00", "18:00"];
+/// Milliseconds into the color cycling, including a blended in-between step.
+const CYCLE_TIMES: &[u64] = &[0, 1250, 10_000];
+
+/// Hash of the frames of a file at a time of day, or why it couldn't be
+/// decoded, which is just as much part of the baseline.
+type Baselines = BTreeMap<(String, String), String>;
+
+fn main() -> ExitCode {
+    let Some(corpus) = std::env::var_os("COLOR_CYCLE_CORPUS").map(PathBuf::from) else {
+        println!("corpus: COLOR_CYCLE_CORPUS not set, skipping");
+        return ExitCode::SUCCESS;
+    };
+    let bless = std::env::var_os("COLOR_CYCLE_BLESS").is_some_and(|value| !value.is_empty() && value != "0");
+
+    let mut files = Vec::new();
+    if let Err(err) = find_files(&corpus, &mut files) {
+        eprintln!("{}: {err}", corpus.display());
+        return ExitCode::FAILURE;
+    }
+    files.sort();
+
+    let mut results = Baselines::new();
+    for path in &files {
+        let name = path.strip_prefix(&corpus).unwrap_or(path).to_string_lossy().replace('\\', "/");
+        for (time, hash) in render_file(path) {
+            results.insert((name.clone(), time), hash);
+        }
+    }
+
+    let baselines_path = corpus.join(BASELINES_FILE);
+    if bless {
+        if let Err(err) = std::fs::write(&baselines_path, format_baselines(&results)) {
+            eprintln!("{}: {err}", baselines_path.display());
+            return ExitCode::FAILURE;
+        }
+        println!("corpus: blessed {} baselines of {} files", results.len(), files.len());
+        return ExitCode::SUCCESS;
+    }
+
+    let baselines = match std::fs::read_to_string(&baselines_path) {
+        Ok(data) => parse_baselines(&data),
+        Err(err) => {
+            eprintln!("{}: {err}", baselines_path.display());
+            eprintln!("corpus: record the baselines first with COLOR_CYCLE_BLESS=1");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failures = 0;
+    for ((name, time), hash) in &results {
+        match baselines.get(&(name.clone(), time.clone())) {
+            Some(expected) if expected == hash => {}
+            Some(expected) => {
+                println!("CHANGED  {name} {time}: {expected} -> {hash}");
+                failures += 1;
+            }
+            None => {
+                println!("NEW      {name} {time}: {hash}");
+                failures += 1;
+            }
+        }
+    }
+    for (name, time) in baselines.keys() {
+        if !results.contains_key(&(name.clone(), time.clone())) {
+            println!("MISSING  {name} {time}");
+            failures += 1;
+        }
+    }
+
+    println!("corpus: {} files, {} frames checked, {failures} differences", files.len(), results.len());
+    if failures > 0 {
+        println!("corpus: if the changes are intended, bless them with COLOR_CYCLE_BLESS=1");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn find_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_files(&path, files)?;
+        } else if path.extension().and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(ext))) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Hashes of the frames of the file per time of day.
+fn render_file(path: &Path) -> Vec<(String, String)> {
+    let living_world = match std::fs::read(path) {
+        Ok(data) => LivingWorld::from_bytes(&data).map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    let living_world = match living_world {
+        Ok(living_world) => living_world,
+        Err(err) => {
+            // keep the message on one line of the baselines
+            let err = err.split_whitespace().collect::<Vec<_>>().join(" ");
+            return vec![("-".to_owned(), format!("error: {err}"))];
+        }
+    };
+
+    let base = living_world.base();
+    let mut animator = Animator::new(&living_world);
+    let mut sprite_buf = IndexedImage::new(0, 0, Palette::default());
+    let mut frame = RgbImage::new(base.width(), base.height());
+
+    TIMES_OF_DAY.iter().map(|&time| {
+        let (hours, mins) = time.split_once(':').unwrap();
+        let time_of_day = (hours.parse::<u64>().unwrap() * 60 + mins.parse::<u64>().unwrap()) * 60 * 1000;
+
+        let mut hash = Fnv1a::new();
+        for &elapsed in CYCLE_TIMES {
+            let clock = CycleClock::new(elapsed, time_of_day);
+            let palette = animator.palette(&living_world, clock, true).clone();
+            let composed = animator.compose(&living_world, base.indexed_image(), 0, 0, clock, &mut sprite_buf);
+            frame.draw_indexed_image_with_palette(composed, &palette);
+            for y in 0..frame.height() {
+                for x in 0..frame.width() {
+                    hash.write(&frame.get_pixel(x, y).0);
+                }
+            }
+        }
+        (time.to_owned(), format!("{:016x}", hash.finish()))
+    }).collect()
+}
+
+fn format_baselines(baselines: &Baselines) -> String {
+    let mut data = String::from("# path time hash, written by COLOR_CYCLE_BLESS=1 cargo test --test corpus\n");
+    for ((name, time), hash) in baselines {
+        data.push_str(&format!("{name}\t{time}\t{hash}\n"));
+    }
+    data
+}
+
+fn parse_baselines(data: &str) -> Baselines {
+    data.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let name = fields.next()?.to_owned();
+            let time = fields.next()?.to_owned();
+            let hash = fields.next()?.to_owned();
+            Some(((name, time), hash))
+        })
+        .collect()
+}
+
+/// FNV-1a, stable across platforms and Rust versions unlike the hasher of
+/// the standard library.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}